@@ -0,0 +1,226 @@
+use crate::alerts::trend_lines::{check_close, TrendLineTracker};
+use crate::alerts::AlertTracker;
+use crate::calendar::{market_now, TradingCalendar};
+use crate::command::xueqiu_command::{provider_chain, save_snapshot_quietly};
+use crate::db::alert_db::{query_alert_rules, record_alert_event};
+use crate::db::app_config_db::{get_alert_settings, is_offline_mode};
+use crate::db::common::AppDb;
+use crate::db::market_snapshot_db::SNAPSHOT_DETAIL;
+use crate::db::trend_lines_db::{query_alert_trend_lines, record_trend_line_break};
+use crate::requests::kline_cache::load_klines;
+use crate::requests::provider::ProviderChain;
+use crate::structs::alert_structs::AlertSettings;
+use crate::structs::trend_lines_structs::{TrendLine, TrendLineAlertMode, TrendLineBreak};
+use chrono::{Local, NaiveDateTime};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_log::log;
+use tauri_plugin_notification::NotificationExt;
+
+/// 触发提醒时发送给前端的事件名
+pub const ALERT_EVENT: &str = "price-alert";
+
+/// 趋势线突破时发送给前端的事件名
+pub const TREND_LINE_BREAK_EVENT: &str = "trend-line-break";
+
+// 未开启提醒或不在交易时段时，重新检查设置的间隔
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+// 轮询间隔下限，避免请求过于频繁
+const MIN_POLL_INTERVAL_SECS: u64 = 5;
+// 收盘模式的趋势线按 (股票, 周期) 重新获取K线的最短间隔
+const CLOSE_CHECK_INTERVAL_SECS: i64 = 60;
+
+// 跨轮次保存的提醒状态
+#[derive(Default)]
+struct EngineState {
+    rules: AlertTracker,
+    lines: TrendLineTracker,
+    close_checked: HashMap<(String, String), NaiveDateTime>, // (股票, 周期) -> 上次检查时间
+}
+
+/// 启动后台行情轮询：交易时段内按设定间隔获取规则和趋势线中股票的行情，检查提醒规则与趋势线突破
+pub fn start_alert_engine(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut state = EngineState::default();
+        loop {
+            let interval = match poll_once(&app, &mut state).await {
+                Ok(Some(interval)) => interval,
+                Ok(None) => IDLE_INTERVAL,
+                Err(e) => {
+                    log::warn!("价格提醒检查失败: {}", e);
+                    IDLE_INTERVAL
+                }
+            };
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// 轮询的股票：启用规则和开启突破提醒的趋势线中的股票 + 设置中额外指定的股票
+fn watched_symbols(codes: impl Iterator<Item = String>, settings: &AlertSettings) -> Vec<String> {
+    codes
+        .chain(
+            settings
+                .symbols
+                .iter()
+                .map(|symbol| symbol.trim().to_uppercase()),
+        )
+        .filter(|symbol| !symbol.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn notify(app: &AppHandle, settings: &AlertSettings, title: &str, body: &str) {
+    if !settings.notify {
+        return;
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("显示系统通知失败: {}", e);
+    }
+}
+
+// 执行一轮轮询，返回下一轮的等待时间（未开启、离线或不在交易时段时为 None）
+async fn poll_once(app: &AppHandle, state: &mut EngineState) -> Result<Option<Duration>, String> {
+    let Some(db) = app.try_state::<AppDb>() else {
+        return Ok(None);
+    };
+    let (settings, offline, calendar) = db
+        .with_conn(|conn| {
+            Ok((
+                get_alert_settings(conn)?,
+                is_offline_mode(conn)?,
+                TradingCalendar::load(conn)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    // 交易时段按北京时间和交易日历判断，节假日不轮询
+    let trading = calendar.is_trading_time(market_now());
+    if !settings.enabled || offline || (settings.trading_hours_only && !trading) {
+        return Ok(None);
+    }
+    let interval = Duration::from_secs(settings.poll_interval_secs.max(MIN_POLL_INTERVAL_SECS));
+
+    let (rules, lines) = db
+        .with_conn(|conn| {
+            Ok((
+                query_alert_rules(conn, None, true)?,
+                query_alert_trend_lines(conn)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let codes = rules
+        .iter()
+        .map(|rule| rule.code.clone())
+        .chain(lines.iter().map(|line| line.code.clone()));
+    let symbols = watched_symbols(codes, &settings);
+    if symbols.is_empty() {
+        return Ok(Some(interval));
+    }
+
+    // 详情接口包含涨跌停价和量比，逐只获取
+    let chain = provider_chain(&db)?;
+    let mut details = HashMap::new();
+    for symbol in symbols {
+        match chain.fetch_stock_detail(&symbol).await {
+            Ok((Some(detail), _)) => {
+                save_snapshot_quietly(&db, SNAPSHOT_DETAIL, &symbol, &detail);
+                details.insert(symbol, detail);
+            }
+            Ok((None, _)) => {}
+            Err(e) => log::warn!("价格提醒获取 {} 行情失败: {}", symbol, e),
+        }
+    }
+
+    let now = Local::now();
+    let events = state.rules.evaluate(&rules, &details, now.naive_local());
+    for mut event in events {
+        event.id = db
+            .with_conn(|conn| record_alert_event(conn, &event))
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = app.emit(ALERT_EVENT, &event) {
+            log::warn!("发送价格提醒事件失败: {}", e);
+        }
+        notify(app, &settings, "价格提醒", &event.message);
+    }
+
+    let prices: HashMap<String, f64> = details
+        .iter()
+        .filter_map(|(code, detail)| detail.current.map(|price| (code.clone(), price)))
+        .collect();
+    let mut breaks =
+        state
+            .lines
+            .evaluate_live(&lines, &prices, now.timestamp_millis(), now.naive_local());
+    let closed = check_closed_bars(&db, &chain, &lines, state, &calendar, now.naive_local()).await;
+    breaks.extend(closed);
+    for mut record in breaks {
+        let id = db
+            .with_conn(|conn| record_trend_line_break(conn, &record))
+            .map_err(|e| e.to_string())?;
+        // 收盘模式下同一根K线已经提醒过
+        let Some(id) = id else {
+            continue;
+        };
+        record.id = id;
+        if let Err(e) = app.emit(TREND_LINE_BREAK_EVENT, &record) {
+            log::warn!("发送趋势线突破事件失败: {}", e);
+        }
+        notify(app, &settings, "趋势线突破", &record.message);
+    }
+    Ok(Some(interval))
+}
+
+// 收盘模式：按 (股票, 周期) 获取最新K线，去掉交易时段内尚未收盘的最后一根后逐条检查
+async fn check_closed_bars(
+    db: &AppDb,
+    chain: &ProviderChain,
+    lines: &[TrendLine],
+    state: &mut EngineState,
+    calendar: &TradingCalendar,
+    now: NaiveDateTime,
+) -> Vec<TrendLineBreak> {
+    let mut groups: BTreeMap<(String, String), Vec<&TrendLine>> = BTreeMap::new();
+    for line in lines {
+        if TrendLineAlertMode::parse(&line.alert_mode) == Some(TrendLineAlertMode::Close) {
+            groups
+                .entry((line.code.clone(), line.period.clone()))
+                .or_default()
+                .push(line);
+        }
+    }
+    state
+        .close_checked
+        .retain(|key, _| groups.contains_key(key));
+
+    let mut breaks = Vec::new();
+    for (key, group) in groups {
+        let due = state.close_checked.get(&key).map_or(true, |checked| {
+            (now - *checked).num_seconds() >= CLOSE_CHECK_INTERVAL_SECS
+        });
+        if !due {
+            continue;
+        }
+        state.close_checked.insert(key.clone(), now);
+
+        let (code, period) = &key;
+        let now_ms = Local::now().timestamp_millis();
+        let mut bars = match load_klines(db, chain, code, period, None, 3, now_ms).await {
+            Ok(load) => load.items,
+            Err(e) => {
+                log::warn!("趋势线突破检查获取 {} {} K线失败: {}", code, period, e);
+                continue;
+            }
+        };
+        if calendar.is_trading_time(market_now()) {
+            bars.pop();
+        }
+        breaks.extend(
+            group
+                .into_iter()
+                .filter_map(|line| check_close(line, &bars, now)),
+        );
+    }
+    breaks
+}
@@ -0,0 +1,255 @@
+pub mod engine;
+pub mod trend_lines;
+
+use crate::structs::alert_structs::{AlertEvent, AlertKind, AlertRule};
+use crate::structs::xueqiu_structs::StockDetail;
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet};
+
+/// 判断提醒条件是否满足，满足时返回观测值（行情缺少所需字段时视为不满足）
+pub fn check_condition(kind: AlertKind, threshold: f64, detail: &StockDetail) -> Option<f64> {
+    let current = detail.current.filter(|price| *price > 0.0);
+    let (value, met) = match kind {
+        AlertKind::PriceAbove => {
+            let price = current?;
+            (price, price >= threshold)
+        }
+        AlertKind::PriceBelow => {
+            let price = current?;
+            (price, price <= threshold)
+        }
+        AlertKind::PercentAbove => {
+            let percent = detail.percent?;
+            (percent, percent >= threshold)
+        }
+        AlertKind::PercentBelow => {
+            let percent = detail.percent?;
+            (percent, percent <= -threshold)
+        }
+        // 距涨跌停的百分比（按涨跌停价计算）
+        AlertKind::NearLimitUp => {
+            let limit = detail.limit_up.filter(|limit| *limit > 0.0)?;
+            let distance = (limit - current?) / limit * 100.0;
+            (distance, distance <= threshold)
+        }
+        AlertKind::NearLimitDown => {
+            let limit = detail.limit_down.filter(|limit| *limit > 0.0)?;
+            let distance = (current? - limit) / limit * 100.0;
+            (distance, distance <= threshold)
+        }
+        AlertKind::VolumeRatioAbove => {
+            let ratio = detail.volume_ratio?;
+            (ratio, ratio >= threshold)
+        }
+    };
+    met.then_some(value)
+}
+
+/// 提醒内容，如 “浦发银行(SH600000) 价格上穿 10.50，现价 10.62”
+pub fn describe(kind: AlertKind, threshold: f64, value: f64, detail: &StockDetail) -> String {
+    let stock = format!("{}({})", detail.name, detail.symbol);
+    let price = detail
+        .current
+        .map(|price| format!("，现价 {:.2}", price))
+        .unwrap_or_default();
+    match kind {
+        AlertKind::PriceAbove | AlertKind::PriceBelow => {
+            format!("{} {} {:.2}{}", stock, kind.label(), threshold, price)
+        }
+        AlertKind::PercentAbove | AlertKind::PercentBelow => format!(
+            "{} {} {:.2}%，当前涨跌幅 {:.2}%{}",
+            stock,
+            kind.label(),
+            threshold,
+            value,
+            price
+        ),
+        AlertKind::NearLimitUp | AlertKind::NearLimitDown => format!(
+            "{} {}，距{}停价 {:.2}%{}",
+            stock,
+            kind.label(),
+            if kind == AlertKind::NearLimitUp {
+                "涨"
+            } else {
+                "跌"
+            },
+            value,
+            price
+        ),
+        AlertKind::VolumeRatioAbove => format!(
+            "{} {}，量比 {:.2}（阈值 {:.2}）{}",
+            stock,
+            kind.label(),
+            value,
+            threshold,
+            price
+        ),
+    }
+}
+
+/// 跟踪各规则的触发状态：条件持续满足时只提醒一次，条件解除后重新生效；
+/// 同一规则两次提醒至少间隔冷却时间，相同条件的重复规则在同一轮只提醒一次
+#[derive(Debug, Default)]
+pub struct AlertTracker {
+    triggered: HashSet<i32>, // 已提醒且条件仍满足的规则
+}
+
+impl AlertTracker {
+    /// 用最新行情检查规则，返回本轮需要提醒的事件（尚未写入数据库，id 为 0）
+    pub fn evaluate(
+        &mut self,
+        rules: &[AlertRule],
+        details: &HashMap<String, StockDetail>,
+        now: NaiveDateTime,
+    ) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        let mut fired: HashSet<(String, AlertKind, u64)> = HashSet::new();
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            let (Some(kind), Some(detail)) =
+                (AlertKind::parse(&rule.kind), details.get(&rule.code))
+            else {
+                continue;
+            };
+            let Some(value) = check_condition(kind, rule.threshold, detail) else {
+                self.triggered.remove(&rule.id);
+                continue;
+            };
+            if self.triggered.contains(&rule.id) || in_cooldown(rule, now) {
+                continue;
+            }
+            self.triggered.insert(rule.id);
+            if !fired.insert((rule.code.clone(), kind, rule.threshold.to_bits())) {
+                continue;
+            }
+
+            events.push(AlertEvent {
+                id: 0,
+                rule_id: rule.id,
+                code: rule.code.clone(),
+                name: detail.name.clone(),
+                kind: kind.as_str().to_string(),
+                threshold: rule.threshold,
+                value,
+                price: detail.current,
+                message: describe(kind, rule.threshold, value, detail),
+                triggered_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            });
+        }
+        // 已删除的规则不再跟踪
+        let ids: HashSet<i32> = rules.iter().map(|rule| rule.id).collect();
+        self.triggered.retain(|id| ids.contains(id));
+        events
+    }
+}
+
+fn in_cooldown(rule: &AlertRule, now: NaiveDateTime) -> bool {
+    rule.last_triggered_at
+        .as_deref()
+        .and_then(|time| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").ok())
+        .is_some_and(|last| now - last < chrono::Duration::minutes(rule.cooldown_minutes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("2024-03-20 {}", time), "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn detail(current: f64) -> StockDetail {
+        StockDetail {
+            name: "浦发银行".to_string(),
+            symbol: "SH600000".to_string(),
+            current: Some(current),
+            last_close: Some(10.0),
+            open: None,
+            high: None,
+            low: None,
+            limit_up: Some(11.0),
+            limit_down: Some(9.0),
+            pe_lyr: None,
+            pe_ttm: None,
+            pe_forecast: None,
+            percent: Some((current - 10.0) / 10.0 * 100.0),
+            market_capital: None,
+            volume_ratio: Some(2.5),
+            turnover_rate: None,
+            amount: None,
+        }
+    }
+
+    fn rule(id: i32, kind: &str, threshold: f64) -> AlertRule {
+        AlertRule {
+            id,
+            code: "SH600000".to_string(),
+            kind: kind.to_string(),
+            threshold,
+            enabled: true,
+            cooldown_minutes: 30,
+            note: String::new(),
+            created_at: String::new(),
+            last_triggered_at: None,
+        }
+    }
+
+    fn quotes(current: f64) -> HashMap<String, StockDetail> {
+        HashMap::from([("SH600000".to_string(), detail(current))])
+    }
+
+    #[test]
+    fn conditions_use_price_percent_limits_and_volume_ratio() {
+        let quote = detail(10.9);
+        assert_eq!(
+            check_condition(AlertKind::PriceAbove, 10.5, &quote),
+            Some(10.9)
+        );
+        assert_eq!(check_condition(AlertKind::PriceBelow, 10.5, &quote), None);
+        assert!(check_condition(AlertKind::PercentAbove, 9.0, &quote).is_some());
+        assert!(check_condition(AlertKind::PercentBelow, 1.0, &detail(9.8)).is_some());
+        // 距涨停 (11 - 10.9) / 11 ≈ 0.91%
+        assert!(check_condition(AlertKind::NearLimitUp, 1.0, &quote).is_some());
+        assert!(check_condition(AlertKind::NearLimitDown, 1.0, &quote).is_none());
+        assert_eq!(
+            check_condition(AlertKind::VolumeRatioAbove, 2.0, &quote),
+            Some(2.5)
+        );
+
+        let mut missing = detail(10.9);
+        missing.limit_up = None;
+        assert!(check_condition(AlertKind::NearLimitUp, 5.0, &missing).is_none());
+    }
+
+    #[test]
+    fn tracker_deduplicates_and_honours_cooldown() {
+        let mut tracker = AlertTracker::default();
+        let mut rules = vec![
+            rule(1, "price_above", 10.5),
+            rule(2, "price_above", 10.5), // 与规则 1 相同
+        ];
+
+        let events = tracker.evaluate(&rules, &quotes(10.6), at("10:00:00"));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rule_id, 1);
+
+        // 条件持续满足时不重复提醒
+        assert!(tracker
+            .evaluate(&rules, &quotes(10.7), at("10:01:00"))
+            .is_empty());
+
+        // 条件解除后重新满足，但仍在冷却时间内
+        rules[0].last_triggered_at = Some("2024-03-20 10:00:00".to_string());
+        rules[1].last_triggered_at = Some("2024-03-20 10:00:00".to_string());
+        assert!(tracker
+            .evaluate(&rules, &quotes(10.4), at("10:02:00"))
+            .is_empty());
+        assert!(tracker
+            .evaluate(&rules, &quotes(10.6), at("10:10:00"))
+            .is_empty());
+
+        // 冷却结束后再次提醒
+        let events = tracker.evaluate(&rules, &quotes(10.6), at("10:31:00"));
+        assert_eq!(events.len(), 1);
+        assert!(events[0].message.contains("价格上穿 10.50"));
+    }
+}
@@ -0,0 +1,271 @@
+use crate::structs::trend_lines_structs::{TrendLine, TrendLineAlertMode, TrendLineBreak};
+use crate::structs::xueqiu_structs::StockKlineItem;
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet};
+
+// 价格在趋势线的哪一侧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Above,
+    Below,
+}
+
+fn side_of(price: f64, line_price: f64) -> Option<Side> {
+    if price > line_price {
+        Some(Side::Above)
+    } else if price < line_price {
+        Some(Side::Below)
+    } else {
+        None
+    }
+}
+
+fn direction_label(direction: &str) -> &'static str {
+    match direction {
+        "up" => "向上突破",
+        "down" => "向下跌破",
+        _ => "触及",
+    }
+}
+
+fn new_break(
+    line: &TrendLine,
+    mode: TrendLineAlertMode,
+    direction: &str,
+    line_price: f64,
+    price: f64,
+    bar_time: Option<i64>,
+    now: NaiveDateTime,
+) -> TrendLineBreak {
+    TrendLineBreak {
+        id: 0,
+        line_id: line.id,
+        code: line.code.clone(),
+        period: line.period.clone(),
+        mode: mode.as_str().to_string(),
+        direction: direction.to_string(),
+        line_price,
+        price,
+        bar_time,
+        message: format!(
+            "{} {} 趋势线 #{} {}：{} {:.2}，趋势线 {:.2}",
+            line.code,
+            line.period,
+            line.id,
+            direction_label(direction),
+            if bar_time.is_some() {
+                "收盘价"
+            } else {
+                "现价"
+            },
+            price,
+            line_price
+        ),
+        triggered_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+/// 跟踪实时价格相对各趋势线的位置：
+/// 穿越模式在价格从一侧到另一侧时提醒（首次观察只记录位置）；
+/// 触及模式在价格进入容差范围时提醒一次，离开后重新生效
+#[derive(Debug, Default)]
+pub struct TrendLineTracker {
+    sides: HashMap<i32, Side>,
+    touching: HashSet<i32>,
+}
+
+impl TrendLineTracker {
+    /// 用实时价格检查穿越和触及模式的趋势线（收盘模式见 check_close）
+    /// prices：股票代码 -> 现价；now_ms：当前时间（13位时间戳）
+    pub fn evaluate_live(
+        &mut self,
+        lines: &[TrendLine],
+        prices: &HashMap<String, f64>,
+        now_ms: i64,
+        now: NaiveDateTime,
+    ) -> Vec<TrendLineBreak> {
+        let mut breaks = Vec::new();
+        for line in lines.iter().filter(|line| line.alert_enabled) {
+            let mode = TrendLineAlertMode::parse(&line.alert_mode);
+            let (Some(price), Some(line_price)) = (prices.get(&line.code), line.price_at(now_ms))
+            else {
+                continue;
+            };
+            if line_price <= 0.0 {
+                continue;
+            }
+            match mode {
+                Some(TrendLineAlertMode::Cross) => {
+                    let Some(side) = side_of(*price, line_price) else {
+                        continue;
+                    };
+                    if let Some(previous) = self.sides.insert(line.id, side) {
+                        if previous != side {
+                            let direction = if side == Side::Above { "up" } else { "down" };
+                            breaks.push(new_break(
+                                line,
+                                TrendLineAlertMode::Cross,
+                                direction,
+                                line_price,
+                                *price,
+                                None,
+                                now,
+                            ));
+                        }
+                    }
+                }
+                Some(TrendLineAlertMode::Touch) => {
+                    let distance = (price - line_price).abs() / line_price * 100.0;
+                    if distance > line.tolerance {
+                        self.touching.remove(&line.id);
+                    } else if self.touching.insert(line.id) {
+                        breaks.push(new_break(
+                            line,
+                            TrendLineAlertMode::Touch,
+                            "touch",
+                            line_price,
+                            *price,
+                            None,
+                            now,
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        // 已删除或关闭提醒的趋势线不再跟踪
+        let ids: HashSet<i32> = lines.iter().map(|line| line.id).collect();
+        self.sides.retain(|id, _| ids.contains(id));
+        self.touching.retain(|id| ids.contains(id));
+        breaks
+    }
+}
+
+/// 收盘模式：比较最近两根已收盘K线的收盘价在趋势线哪一侧，换边时返回突破记录
+/// bars 按时间升序且只包含已收盘的K线；画线区间内的K线不算突破
+pub fn check_close(
+    line: &TrendLine,
+    bars: &[StockKlineItem],
+    now: NaiveDateTime,
+) -> Option<TrendLineBreak> {
+    let [previous, last] = bars.get(bars.len().checked_sub(2)?..)? else {
+        return None;
+    };
+    if last.date <= line.start_time.max(line.end_time) {
+        return None;
+    }
+    let previous_side = side_of(previous.close, line.price_at(previous.date)?)?;
+    let line_price = line.price_at(last.date)?;
+    let side = side_of(last.close, line_price)?;
+    if side == previous_side {
+        return None;
+    }
+    let direction = if side == Side::Above { "up" } else { "down" };
+    Some(new_break(
+        line,
+        TrendLineAlertMode::Close,
+        direction,
+        line_price,
+        last.close,
+        Some(last.date),
+        now,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: i64 = 86_400_000;
+
+    fn now() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("2024-03-20 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    // 每天上升 0.1 元的趋势线：第 0 天 10 元，第 10 天 11 元
+    fn line(mode: &str) -> TrendLine {
+        TrendLine {
+            id: 1,
+            code: "SH600000".to_string(),
+            period: "day".to_string(),
+            start_time: 0,
+            start_price: 10.0,
+            end_time: 10 * DAY_MS,
+            end_price: 11.0,
+            alert_enabled: true,
+            alert_mode: mode.to_string(),
+            tolerance: 0.5,
+        }
+    }
+
+    fn bar(day: i64, close: f64) -> StockKlineItem {
+        StockKlineItem {
+            date: day * DAY_MS,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 0.0,
+            percent: 0.0,
+            turnoverrate: 0.0,
+        }
+    }
+
+    fn prices(price: f64) -> HashMap<String, f64> {
+        HashMap::from([("SH600000".to_string(), price)])
+    }
+
+    #[test]
+    fn line_is_extrapolated_by_time() {
+        let line = line("cross");
+        assert!((line.price_at(20 * DAY_MS).unwrap() - 12.0).abs() < 1e-9);
+        assert!((line.price_at(5 * DAY_MS).unwrap() - 10.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn live_price_crosses_and_touches() {
+        let mut tracker = TrendLineTracker::default();
+        let lines = vec![line("cross")];
+        let at = 20 * DAY_MS; // 趋势线 12 元
+        assert!(tracker
+            .evaluate_live(&lines, &prices(12.5), at, now())
+            .is_empty());
+        assert!(tracker
+            .evaluate_live(&lines, &prices(12.3), at, now())
+            .is_empty());
+        let breaks = tracker.evaluate_live(&lines, &prices(11.8), at, now());
+        assert_eq!(breaks.len(), 1);
+        assert_eq!(breaks[0].direction, "down");
+
+        let mut tracker = TrendLineTracker::default();
+        let lines = vec![line("touch")];
+        assert!(tracker
+            .evaluate_live(&lines, &prices(12.5), at, now())
+            .is_empty());
+        assert_eq!(
+            tracker
+                .evaluate_live(&lines, &prices(12.05), at, now())
+                .len(),
+            1
+        );
+        // 仍在容差内不重复提醒
+        assert!(tracker
+            .evaluate_live(&lines, &prices(11.98), at, now())
+            .is_empty());
+    }
+
+    #[test]
+    fn close_beyond_line_uses_last_two_closed_bars() {
+        let line = line("close");
+        // 第 19 天收在线上（11.95 > 11.9），第 20 天收在线下（11.9 < 12）
+        let bars = vec![bar(18, 11.9), bar(19, 11.95), bar(20, 11.9)];
+        let record = check_close(&line, &bars, now()).unwrap();
+        assert_eq!(
+            (record.direction.as_str(), record.bar_time),
+            ("down", Some(20 * DAY_MS))
+        );
+        assert!(check_close(&line, &bars[..2], now()).is_none());
+        // 画线区间内的K线不算突破
+        assert!(check_close(&line, &[bar(4, 10.5), bar(5, 10.4)], now()).is_none());
+    }
+}
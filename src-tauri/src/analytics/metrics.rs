@@ -0,0 +1,217 @@
+//! 组合表现的基础算法，输入均按时间升序排列
+
+use chrono::NaiveDate;
+
+/// 每年交易日数，用于日收益率的年化
+pub const TRADING_DAYS: f64 = 252.0;
+
+/// 单日时间加权收益率：买入视为开盘前投入，卖出所得计入收盘后的价值
+/// 当日没有可计算的本金（空仓且未买入）时收益率为 0
+pub fn daily_return(prev_value: f64, value: f64, bought: f64, sold: f64) -> f64 {
+    let base = prev_value + bought;
+    if base <= 1e-9 {
+        return 0.0;
+    }
+    (value + sold - base) / base
+}
+
+/// 多期收益率连乘
+pub fn compound(returns: &[f64]) -> f64 {
+    returns.iter().fold(1.0, |acc, r| acc * (1.0 + r)) - 1.0
+}
+
+/// 将 days 个自然日的总收益率换算为年化收益率
+pub fn annualize(total_return: f64, days: i64) -> Option<f64> {
+    if days <= 0 || total_return <= -1.0 {
+        return None;
+    }
+    Some((1.0 + total_return).powf(365.0 / days as f64) - 1.0)
+}
+
+/// 资金加权收益率（XIRR，年化）：投入为负、取回为正
+/// 现金流方向单一或无法收敛时返回 None
+pub fn xirr(flows: &[(NaiveDate, f64)]) -> Option<f64> {
+    let first = flows.iter().map(|(date, _)| *date).min()?;
+    if !flows.iter().any(|(_, v)| *v > 0.0) || !flows.iter().any(|(_, v)| *v < 0.0) {
+        return None;
+    }
+    let npv = |rate: f64| -> f64 {
+        flows
+            .iter()
+            .map(|(date, v)| {
+                let years = (*date - first).num_days() as f64 / 365.0;
+                v / (1.0 + rate).powf(years)
+            })
+            .sum()
+    };
+
+    // 二分法：先扩大上界直到 NPV 变号
+    let (mut lo, mut hi) = (-0.99, 1.0);
+    let (mut f_lo, mut f_hi) = (npv(lo), npv(hi));
+    while f_lo * f_hi > 0.0 && hi < 1e6 {
+        hi *= 2.0;
+        f_hi = npv(hi);
+    }
+    if f_lo * f_hi > 0.0 || (f_lo * f_hi).is_nan() {
+        return None;
+    }
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        let f_mid = npv(mid);
+        if f_mid.abs() < 1e-9 {
+            return Some(mid);
+        }
+        if f_lo * f_mid < 0.0 {
+            hi = mid;
+        } else {
+            lo = mid;
+            f_lo = f_mid;
+        }
+    }
+    Some((lo + hi) / 2.0)
+}
+
+/// 回撤统计结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drawdown {
+    pub series: Vec<f64>,  // 每个点相对此前最高净值的回撤（<= 0）
+    pub max: f64,          // 最大回撤（正数）
+    pub longest_days: i64, // 从前高到收复前高的最长自然日数（未收复时算到最后一天）
+}
+
+/// 按净值序列计算回撤
+pub fn drawdown(navs: &[(NaiveDate, f64)]) -> Drawdown {
+    let mut series = Vec::with_capacity(navs.len());
+    let mut max = 0.0f64;
+    let mut longest = 0;
+    let mut peak: Option<(NaiveDate, f64)> = None;
+    let mut underwater = false;
+
+    for &(date, nav) in navs {
+        match peak {
+            Some((_, peak_nav)) if nav < peak_nav => {
+                let dd = nav / peak_nav - 1.0;
+                series.push(dd);
+                max = max.max(-dd);
+                underwater = true;
+            }
+            _ => {
+                // 收复前高，回撤结束
+                if let (true, Some((peak_date, _))) = (underwater, peak) {
+                    longest = longest.max((date - peak_date).num_days());
+                }
+                series.push(0.0);
+                peak = Some((date, nav));
+                underwater = false;
+            }
+        }
+    }
+    if let (true, Some((peak_date, _)), Some(&(last, _))) = (underwater, peak, navs.last()) {
+        longest = longest.max((last - peak_date).num_days());
+    }
+
+    Drawdown {
+        series,
+        max,
+        longest_days: longest,
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// 夏普比率（按日收益率计算后年化），样本不足或波动为 0 时返回 None
+pub fn sharpe(returns: &[f64], risk_free_rate: f64) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let rf = risk_free_rate / TRADING_DAYS;
+    let excess: Vec<f64> = returns.iter().map(|r| r - rf).collect();
+    let avg = mean(&excess);
+    let variance =
+        excess.iter().map(|r| (r - avg).powi(2)).sum::<f64>() / (excess.len() - 1) as f64;
+    let std = variance.sqrt();
+    if std <= 1e-12 {
+        return None;
+    }
+    Some(avg / std * TRADING_DAYS.sqrt())
+}
+
+/// 索提诺比率：只用低于无风险收益的部分计算下行波动
+pub fn sortino(returns: &[f64], risk_free_rate: f64) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let rf = risk_free_rate / TRADING_DAYS;
+    let excess: Vec<f64> = returns.iter().map(|r| r - rf).collect();
+    let downside =
+        (excess.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / excess.len() as f64).sqrt();
+    if downside <= 1e-12 {
+        return None;
+    }
+    Some(mean(&excess) / downside * TRADING_DAYS.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn daily_return_handles_flows() {
+        // 开盘前买入 1000，收盘市值 1100
+        assert!(approx(daily_return(0.0, 1100.0, 1000.0, 0.0), 0.1));
+        // 持有 1000，全部以 1100 卖出
+        assert!(approx(daily_return(1000.0, 0.0, 0.0, 1100.0), 0.1));
+        // 空仓
+        assert_eq!(daily_return(0.0, 0.0, 0.0, 0.0), 0.0);
+        assert!(approx(compound(&[0.1, -0.1]), -0.01));
+    }
+
+    #[test]
+    fn xirr_of_one_year_investment() {
+        let flows = [(date("2023-01-01"), -1000.0), (date("2024-01-01"), 1100.0)];
+        assert!((xirr(&flows).unwrap() - 0.1).abs() < 1e-6);
+        assert_eq!(xirr(&[(date("2023-01-01"), -1000.0)]), None);
+        assert!(approx(annualize(0.21, 730).unwrap(), 0.1));
+    }
+
+    #[test]
+    fn drawdown_depth_and_duration() {
+        let navs = [
+            (date("2024-01-01"), 1.0),
+            (date("2024-01-02"), 1.2),
+            (date("2024-01-05"), 0.9),
+            (date("2024-01-10"), 1.3),
+            (date("2024-01-11"), 1.2),
+        ];
+        let dd = drawdown(&navs);
+        assert!(approx(dd.max, 0.25));
+        // 01-02 的高点到 01-10 才收复，最后一天的回撤尚未收复
+        assert_eq!(dd.longest_days, 8);
+        assert!(approx(dd.series[2], -0.25));
+        assert_eq!(dd.series[3], 0.0);
+    }
+
+    #[test]
+    fn sharpe_and_sortino() {
+        assert_eq!(sharpe(&[0.01, 0.01, 0.01], 0.0), None);
+        let returns = [0.02, -0.01, 0.02, -0.01];
+        let s = sharpe(&returns, 0.0).unwrap();
+        let std = (4.0 * 0.015f64.powi(2) / 3.0).sqrt();
+        assert!(approx(s, 0.005 / std * TRADING_DAYS.sqrt()));
+        let downside = (2.0 * 0.01f64.powi(2) / 4.0).sqrt();
+        assert!(approx(
+            sortino(&returns, 0.0).unwrap(),
+            0.005 / downside * TRADING_DAYS.sqrt()
+        ));
+    }
+}
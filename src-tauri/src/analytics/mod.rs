@@ -0,0 +1,759 @@
+pub mod metrics;
+
+use crate::calendar::TradingCalendar;
+use crate::db::position_db::{is_buy_action, is_transfer_action, replay_steps, ReplayEvent};
+use crate::structs::analytics_structs::{
+    EquityPoint, PerformanceSummary, PeriodPerformance, PortfolioAnalytics,
+};
+use crate::structs::corporate_action_structs::{CorporateAction, KlineAdjust};
+use crate::structs::holdings_structs::HoldingExit;
+use crate::structs::orders_structs::Order;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// 每只股票按日期（北京时间）索引的日线收盘价
+pub type DailyCloses = HashMap<String, BTreeMap<NaiveDate, f64>>;
+
+/// 统计结果的分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Group {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "day" => Some(Group::Day),
+            "week" => Some(Group::Week),
+            "month" => Some(Group::Month),
+            "year" => Some(Group::Year),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Group::Day => "day",
+            Group::Week => "week",
+            Group::Month => "month",
+            Group::Year => "year",
+        }
+    }
+
+    // 日期所属分组的标识，周按 ISO 周计算
+    fn key(&self, date: NaiveDate) -> String {
+        match self {
+            Group::Day => date.format("%Y-%m-%d").to_string(),
+            Group::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Group::Month => date.format("%Y-%m").to_string(),
+            Group::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+/// 日线时间戳（毫秒）对应的北京时间日期
+pub fn kline_date(timestamp: i64) -> NaiveDate {
+    epoch() + Duration::days((timestamp + 8 * HOUR_MS).div_euclid(DAY_MS))
+}
+
+/// 北京时间某日零点的时间戳（毫秒）
+pub fn date_timestamp(date: NaiveDate) -> i64 {
+    (date - epoch()).num_days() * DAY_MS - 8 * HOUR_MS
+}
+
+/// 解析 YYYY-MM-DD（带时间的字符串只取日期部分）
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim().get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// 组合回放中的一步：某账户某只股票的一笔委托或一次公司行动，以及之后的持仓数量
+#[derive(Debug, Clone)]
+pub struct LedgerEvent {
+    pub date: NaiveDate,
+    pub account_id: i32,
+    pub code: String,
+    pub kind: LedgerKind,
+    pub held: i32,
+}
+
+#[derive(Debug, Clone)]
+pub enum LedgerKind {
+    Order(Order),
+    // 除权除息：现金分红金额和每股变为多少股
+    Action { dividend: f64, share_factor: f64 },
+}
+
+/// 按账户和股票分别回放委托（含划转）与公司行动，与生成持仓使用同一回放，
+/// 送转和拆合股调整数量、现金分红计入收益，结果按时间排序
+pub fn replay_ledger(
+    orders: &[Order],
+    actions: &[CorporateAction],
+) -> Result<Vec<LedgerEvent>, String> {
+    let mut groups: BTreeMap<(i32, String), Vec<Order>> = BTreeMap::new();
+    for order in orders {
+        groups
+            .entry((order.account_id, order.code.to_uppercase()))
+            .or_default()
+            .push(order.clone());
+    }
+
+    let mut events = Vec::new();
+    for ((account_id, code), orders) in groups {
+        let code_actions: Vec<CorporateAction> = actions
+            .iter()
+            .filter(|action| action.code.eq_ignore_ascii_case(&code))
+            .cloned()
+            .collect();
+        let steps = replay_steps(&orders, &code_actions).map_err(|e| e.to_string())?;
+        for step in steps {
+            let date = parse_date(&step.time)
+                .ok_or_else(|| format!("时间无效（{}）: {}", code, step.time))?;
+            let kind = match step.event {
+                ReplayEvent::Order(order) => LedgerKind::Order(order.clone()),
+                ReplayEvent::Action(action) => LedgerKind::Action {
+                    dividend: step.dividend,
+                    share_factor: action.share_factor(),
+                },
+            };
+            events.push((step.time, account_id, code.clone(), date, kind, step.held));
+        }
+    }
+    // 同一时间的不同股票按账户、代码排序，同一股票保持回放顺序
+    events.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    Ok(events
+        .into_iter()
+        .map(|(_, account_id, code, date, kind, held)| LedgerEvent {
+            date,
+            account_id,
+            code,
+            kind,
+            held,
+        })
+        .collect())
+}
+
+/// 把复权后的日线收盘价还原为实际成交价格，与回放中的实际持仓数量相乘得到市值
+/// - 前复权：除权日之前的价格 = (原价 - 每股派息) / 每股变为的股数，从最近的一次开始逐次还原
+/// - 后复权：除权日及之后的价格 = 原价 × 每股变为的股数 + 每股派息，从最早的一次开始逐次还原
+pub fn unadjust_closes(
+    series: &mut BTreeMap<NaiveDate, f64>,
+    actions: &[CorporateAction],
+    adjust: KlineAdjust,
+) {
+    let mut dated: Vec<(NaiveDate, &CorporateAction)> = actions
+        .iter()
+        .filter(|action| action.share_factor() > 0.0)
+        .filter_map(|action| Some((parse_date(&action.ex_date)?, action)))
+        .collect();
+    dated.sort_by_key(|(date, _)| *date);
+
+    for (date, close) in series.iter_mut() {
+        match adjust {
+            KlineAdjust::None => return,
+            KlineAdjust::Before => {
+                for (_, action) in dated.iter().rev().filter(|(ex, _)| ex > date) {
+                    *close = *close * action.share_factor() + action.cash_dividend;
+                }
+            }
+            KlineAdjust::After => {
+                for (_, action) in dated.iter().filter(|(ex, _)| ex <= date) {
+                    *close = (*close - action.cash_dividend) / action.share_factor();
+                }
+            }
+        }
+    }
+}
+
+// 区间内的一个交易日
+struct Day {
+    date: NaiveDate,
+    market_value: f64,
+    net_invested: f64,
+    pnl: f64,
+    ret: f64,
+}
+
+// 一组卖出记录的盈亏汇总
+#[derive(Default)]
+struct TradeTotals {
+    profit: f64,
+    count: i32,
+    wins: i32,
+}
+
+impl TradeTotals {
+    fn add(&mut self, exit: &HoldingExit) {
+        self.profit += exit.profit;
+        self.count += 1;
+        if exit.profit > 0.0 {
+            self.wins += 1;
+        }
+    }
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+// 卖出记录的汇总指标（胜率、盈亏比、平均持有天数等）
+fn trade_summary(exits: &[&HoldingExit], summary: &mut PerformanceSummary) {
+    let wins: Vec<f64> = exits
+        .iter()
+        .map(|e| e.profit)
+        .filter(|p| *p > 0.0)
+        .collect();
+    let losses: Vec<f64> = exits
+        .iter()
+        .map(|e| e.profit)
+        .filter(|p| *p < 0.0)
+        .collect();
+    let gross_loss = -losses.iter().sum::<f64>();
+    let holding_days: Vec<f64> = exits
+        .iter()
+        .filter_map(|e| Some((parse_date(&e.sell_time)? - parse_date(&e.hold_time)?).num_days()))
+        .map(|days| days as f64)
+        .collect();
+
+    summary.trade_count = exits.len() as i32;
+    summary.realized_profit = exits.iter().map(|e| e.profit).sum();
+    summary.win_rate = if exits.is_empty() {
+        0.0
+    } else {
+        wins.len() as f64 / exits.len() as f64
+    };
+    summary.profit_factor = if gross_loss > 0.0 {
+        Some(wins.iter().sum::<f64>() / gross_loss)
+    } else {
+        None
+    };
+    summary.average_win = average(&wins);
+    summary.average_loss = average(&losses);
+    summary.average_holding_days = average(&holding_days);
+}
+
+/// [start, end] 区间内卖出记录的平均持有交易日数（节假日和周末不计）
+pub fn average_holding_trading_days(
+    exits: &[HoldingExit],
+    start: NaiveDate,
+    end: NaiveDate,
+    calendar: &TradingCalendar,
+) -> Option<f64> {
+    let days: Vec<f64> = exits
+        .iter()
+        .filter_map(|e| Some((parse_date(&e.hold_time)?, parse_date(&e.sell_time)?)))
+        .filter(|(_, sell)| *sell >= start && *sell <= end)
+        .map(|(hold, sell)| calendar.trading_days_between(hold, sell) as f64)
+        .collect();
+    average(&days)
+}
+
+/// 按回放结果和日线收盘价（实际价格）逐日估值，计算 [start, end] 区间内的表现
+/// - 持仓市值按当日收盘价估值，缺少收盘价时用最近一次成交价（并列入 missing_prices）
+/// - 时间加权收益率按日计算后连乘，买入视为开盘前投入、卖出所得和现金分红计入收盘后价值
+/// - 资金加权收益率以区间起点市值、区间内买卖金额、分红和区间终点市值为现金流
+/// - 同一天相互抵消的持仓划转（汇总多个账户时）不计入现金流
+/// - 交易统计来自区间内的卖出记录（已扣除费用）
+/// - start 为空时从第一笔委托开始，end 为空时到最新的收盘价或委托日期
+pub fn analyze(
+    ledger: &[LedgerEvent],
+    exits: &[HoldingExit],
+    closes: &DailyCloses,
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    group: Group,
+    risk_free_rate: f64,
+) -> Result<PortfolioAnalytics, String> {
+    // 1. 回放的时间范围（第一步总是建仓委托）
+    let (first_trade, last_trade) = match (ledger.first(), ledger.last()) {
+        (Some(first), Some(last)) => (first.date, last.date),
+        _ => return Err("没有委托记录，无法计算组合表现".to_string()),
+    };
+    let codes: BTreeSet<String> = ledger.iter().map(|e| e.code.clone()).collect();
+    let last_close = codes
+        .iter()
+        .filter_map(|code| closes.get(code)?.keys().next_back())
+        .max()
+        .copied();
+    let start = start.unwrap_or(first_trade);
+    let end = end.unwrap_or_else(|| last_close.map_or(last_trade, |d| d.max(last_trade)));
+    if start > end {
+        return Err(format!("开始日期 {} 晚于结束日期 {}", start, end));
+    }
+
+    // 2. 时间轴：委托和除权日期 + 所涉股票有收盘价的日期
+    let mut timeline: BTreeSet<NaiveDate> = ledger
+        .iter()
+        .map(|e| e.date)
+        .filter(|d| *d <= end)
+        .collect();
+    for code in &codes {
+        if let Some(series) = closes.get(code) {
+            timeline.extend(series.range(first_trade..=end).map(|(date, _)| *date));
+        }
+    }
+
+    // 3. 逐日按回放的持仓数量估值
+    let mut held: BTreeMap<(i32, String), i64> = BTreeMap::new();
+    let mut last_price: HashMap<String, f64> = HashMap::new();
+    let mut missing_prices = BTreeSet::new();
+    let mut net_invested = 0.0;
+    let (mut prev_value, mut base_pnl) = (0.0, 0.0);
+    let mut days: Vec<Day> = Vec::new();
+    let mut cash_flows: Vec<(NaiveDate, f64)> = Vec::new();
+    let mut next = 0;
+
+    for date in timeline {
+        let (mut bought, mut sold, mut transferred) = (0.0, 0.0, 0.0);
+        while next < ledger.len() && ledger[next].date <= date {
+            let event = &ledger[next];
+            match &event.kind {
+                LedgerKind::Order(order) => {
+                    let amount = order.cost * order.quantity as f64;
+                    if is_transfer_action(&order.action) {
+                        if is_buy_action(&order.action) {
+                            transferred += amount;
+                        } else {
+                            transferred -= amount;
+                        }
+                    } else if is_buy_action(&order.action) {
+                        bought += amount + order.total_fee();
+                    } else {
+                        sold += amount - order.total_fee();
+                    }
+                    last_price.insert(event.code.clone(), order.cost);
+                }
+                LedgerKind::Action {
+                    dividend,
+                    share_factor,
+                } => {
+                    sold += dividend;
+                    if *share_factor > 0.0 {
+                        if let Some(price) = last_price.get_mut(&event.code) {
+                            *price /= share_factor;
+                        }
+                    }
+                }
+            }
+            held.insert((event.account_id, event.code.clone()), event.held as i64);
+            next += 1;
+        }
+        // 单个账户的划转按成本价买入/卖出计入
+        if transferred > 0.0 {
+            bought += transferred;
+        } else {
+            sold -= transferred;
+        }
+        net_invested += bought - sold;
+
+        let mut value = 0.0;
+        for ((_, code), &quantity) in &held {
+            if quantity <= 0 {
+                continue;
+            }
+            let close = closes
+                .get(code)
+                .and_then(|series| series.range(..=date).next_back())
+                .map(|(_, close)| *close);
+            let price = close.unwrap_or_else(|| {
+                missing_prices.insert(code.clone());
+                last_price.get(code).copied().unwrap_or(0.0)
+            });
+            value += quantity as f64 * price;
+        }
+        let pnl = value - net_invested;
+
+        if date < start {
+            prev_value = value;
+            base_pnl = pnl;
+            continue;
+        }
+        if days.is_empty() && prev_value > 0.0 {
+            cash_flows.push((start, -prev_value));
+        }
+        if bought > 0.0 {
+            cash_flows.push((date, -bought));
+        }
+        if sold > 0.0 {
+            cash_flows.push((date, sold));
+        }
+        days.push(Day {
+            date,
+            market_value: value,
+            net_invested,
+            pnl,
+            ret: metrics::daily_return(prev_value, value, bought, sold),
+        });
+        prev_value = value;
+    }
+    if let Some(last) = days.last() {
+        if last.market_value > 0.0 {
+            cash_flows.push((last.date, last.market_value));
+        }
+    }
+
+    // 4. 净值、回撤和风险指标
+    let returns: Vec<f64> = days.iter().map(|d| d.ret).collect();
+    let mut nav = 1.0;
+    let navs: Vec<(NaiveDate, f64)> = days
+        .iter()
+        .map(|d| {
+            nav *= 1.0 + d.ret;
+            (d.date, nav)
+        })
+        .collect();
+    let drawdown = metrics::drawdown(&navs);
+    let total_return = metrics::compound(&returns);
+
+    let mut summary = PerformanceSummary {
+        total_return,
+        annualized_return: days
+            .last()
+            .and_then(|last| metrics::annualize(total_return, (last.date - start).num_days())),
+        money_weighted_return: metrics::xirr(&cash_flows),
+        total_pnl: days.last().map_or(0.0, |last| last.pnl - base_pnl),
+        max_drawdown: drawdown.max,
+        max_drawdown_days: drawdown.longest_days,
+        sharpe_ratio: metrics::sharpe(&returns, risk_free_rate),
+        sortino_ratio: metrics::sortino(&returns, risk_free_rate),
+        ..Default::default()
+    };
+
+    // 5. 区间内的卖出记录
+    let exits: Vec<&HoldingExit> = exits
+        .iter()
+        .filter(|e| parse_date(&e.sell_time).map_or(false, |d| d >= start && d <= end))
+        .collect();
+    trade_summary(&exits, &mut summary);
+    let mut group_trades: HashMap<String, TradeTotals> = HashMap::new();
+    for exit in &exits {
+        if let Some(date) = parse_date(&exit.sell_time) {
+            group_trades.entry(group.key(date)).or_default().add(exit);
+        }
+    }
+    let best_trade = exits
+        .iter()
+        .max_by(|a, b| a.profit.total_cmp(&b.profit))
+        .map(|e| (*e).clone());
+    let worst_trade = exits
+        .iter()
+        .min_by(|a, b| a.profit.total_cmp(&b.profit))
+        .map(|e| (*e).clone());
+
+    // 6. 按分组汇总，净值曲线取每组最后一个交易日
+    let mut equity_curve = Vec::new();
+    let mut periods = Vec::new();
+    let mut pnl_before = base_pnl;
+    let mut i = 0;
+    while i < days.len() {
+        let key = group.key(days[i].date);
+        let mut j = i + 1;
+        while j < days.len() && group.key(days[j].date) == key {
+            j += 1;
+        }
+        let last = &days[j - 1];
+        equity_curve.push(EquityPoint {
+            date: last.date.to_string(),
+            market_value: last.market_value,
+            net_invested: last.net_invested,
+            pnl: last.pnl,
+            nav: navs[j - 1].1,
+            drawdown: drawdown.series[j - 1],
+        });
+        let trades = group_trades.remove(&key).unwrap_or_default();
+        periods.push(PeriodPerformance {
+            period: key,
+            start: days[i].date.to_string(),
+            end: last.date.to_string(),
+            return_rate: metrics::compound(&returns[i..j]),
+            pnl: last.pnl - pnl_before,
+            realized_profit: trades.profit,
+            trade_count: trades.count,
+            win_count: trades.wins,
+        });
+        pnl_before = last.pnl;
+        i = j;
+    }
+
+    Ok(PortfolioAnalytics {
+        start: start.to_string(),
+        end: end.to_string(),
+        group: group.as_str().to_string(),
+        summary,
+        equity_curve,
+        periods,
+        best_trade,
+        worst_trade,
+        missing_prices: missing_prices.into_iter().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn order(id: i32, code: &str, time: &str, action: &str, quantity: i32, cost: f64) -> Order {
+        Order {
+            id,
+            code: code.to_string(),
+            name: code.to_string(),
+            time: time.to_string(),
+            quantity,
+            cost,
+            action: action.to_string(),
+            commission: 0.0,
+            stamp_duty: 0.0,
+            transfer_fee: 0.0,
+            account_id: 1,
+        }
+    }
+
+    fn ledger(orders: &[Order]) -> Vec<LedgerEvent> {
+        replay_ledger(orders, &[]).unwrap()
+    }
+
+    fn exit(hold_time: &str, sell_time: &str, profit: f64) -> HoldingExit {
+        HoldingExit {
+            id: Some(1),
+            holding_id: 1,
+            order_id: Some(2),
+            code: "SH600000".to_string(),
+            name: "浦发银行".to_string(),
+            hold_time: hold_time.to_string(),
+            sell_time: sell_time.to_string(),
+            sell_price: 11.0,
+            quantity: 100,
+            cost: 10.0,
+            profit,
+            closed: true,
+            fee: 0.0,
+            account_id: 1,
+        }
+    }
+
+    // 10 元买入 100 股，涨到 11、跌到 9.9，最后 11 元全部卖出
+    fn round_trip() -> (Vec<Order>, Vec<HoldingExit>, DailyCloses) {
+        let orders = vec![
+            order(1, "SH600000", "2024-01-02 10:00:00", "1", 100, 10.0),
+            order(2, "SH600000", "2024-01-05 10:00:00", "2", 100, 11.0),
+        ];
+        let exits = vec![exit("2024-01-02 10:00:00", "2024-01-05 10:00:00", 100.0)];
+        let series = [
+            ("2024-01-02", 10.0),
+            ("2024-01-03", 11.0),
+            ("2024-01-04", 9.9),
+            ("2024-01-05", 11.0),
+        ]
+        .iter()
+        .map(|(d, c)| (date(d), *c))
+        .collect();
+        let closes = HashMap::from([("SH600000".to_string(), series)]);
+        (orders, exits, closes)
+    }
+
+    #[test]
+    fn round_trip_returns_and_drawdown() {
+        let (orders, exits, closes) = round_trip();
+        let result = analyze(
+            &ledger(&orders),
+            &exits,
+            &closes,
+            None,
+            None,
+            Group::Day,
+            0.0,
+        )
+        .unwrap();
+
+        assert_eq!(result.start, "2024-01-02");
+        assert_eq!(result.end, "2024-01-05");
+        assert_eq!(result.equity_curve.len(), 4);
+        let summary = &result.summary;
+        assert!((summary.total_return - 0.1).abs() < 1e-9);
+        assert!((summary.total_pnl - 100.0).abs() < 1e-9);
+        assert!((summary.max_drawdown - 0.1).abs() < 1e-9);
+        assert_eq!(summary.max_drawdown_days, 2);
+        assert_eq!(summary.trade_count, 1);
+        assert_eq!(summary.win_rate, 1.0);
+        assert_eq!(summary.profit_factor, None);
+        assert_eq!(summary.average_holding_days, Some(3.0));
+        let calendar = TradingCalendar::bundled();
+        assert_eq!(
+            average_holding_trading_days(&exits, date("2024-01-01"), date("2024-01-31"), &calendar),
+            Some(3.0)
+        );
+        assert_eq!(
+            average_holding_trading_days(&exits, date("2024-02-01"), date("2024-02-29"), &calendar),
+            None
+        );
+        assert!(summary.money_weighted_return.unwrap() > 0.0);
+        assert!(result.missing_prices.is_empty());
+        assert_eq!(result.best_trade.unwrap().profit, 100.0);
+    }
+
+    #[test]
+    fn range_and_grouping() {
+        let (orders, exits, closes) = round_trip();
+
+        // 从 01-04 开始：以 01-03 收盘市值为起点，区间内先跌后涨回原值
+        let result = analyze(
+            &ledger(&orders),
+            &exits,
+            &closes,
+            Some(date("2024-01-04")),
+            None,
+            Group::Day,
+            0.0,
+        )
+        .unwrap();
+        assert!(result.summary.total_return.abs() < 1e-9);
+        assert!(result.summary.total_pnl.abs() < 1e-9);
+        assert!(result.summary.money_weighted_return.unwrap().abs() < 1e-6);
+
+        let result = analyze(
+            &ledger(&orders),
+            &exits,
+            &closes,
+            None,
+            None,
+            Group::Month,
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(result.periods.len(), 1);
+        let month = &result.periods[0];
+        assert_eq!(month.period, "2024-01");
+        assert!((month.return_rate - 0.1).abs() < 1e-9);
+        assert!((month.pnl - 100.0).abs() < 1e-9);
+        assert_eq!(month.trade_count, 1);
+        assert_eq!(result.equity_curve[0].date, "2024-01-05");
+
+        assert!(analyze(&ledger(&[]), &[], &closes, None, None, Group::Day, 0.0).is_err());
+        assert_eq!(Group::parse("WEEK"), Some(Group::Week));
+        assert_eq!(Group::Week.key(date("2024-01-01")), "2024-W01");
+    }
+
+    #[test]
+    fn missing_closes_fall_back_to_trade_price() {
+        let orders = vec![order(1, "SZ000001", "2024-01-02 10:00:00", "1", 100, 10.0)];
+        let result = analyze(
+            &ledger(&orders),
+            &[],
+            &DailyCloses::new(),
+            None,
+            None,
+            Group::Day,
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(result.missing_prices, vec!["SZ000001".to_string()]);
+        assert_eq!(result.equity_curve[0].market_value, 1000.0);
+        assert_eq!(
+            kline_date(date_timestamp(date("2024-03-01"))),
+            date("2024-03-01")
+        );
+    }
+
+    fn bonus(ex_date: &str, cash_dividend: f64, bonus_shares: f64) -> CorporateAction {
+        CorporateAction {
+            id: 1,
+            code: "SH600000".to_string(),
+            ex_date: ex_date.to_string(),
+            cash_dividend,
+            bonus_shares,
+            transfer_shares: 0.0,
+            split_ratio: 1.0,
+            source: "manual".to_string(),
+            note: String::new(),
+        }
+    }
+
+    #[test]
+    fn bonus_shares_and_dividends_follow_the_ledger() {
+        // 10 元买入 100 股，01-04 每股派 0.5 元并 10 送 10，之后 5.5 元卖出全部 200 股
+        let orders = vec![
+            order(1, "SH600000", "2024-01-02 10:00:00", "1", 100, 10.0),
+            order(2, "SH600000", "2024-01-05 10:00:00", "0", 200, 5.5),
+        ];
+        let actions = vec![bonus("2024-01-04", 0.5, 1.0)];
+        let ledger = replay_ledger(&orders, &actions).unwrap();
+        assert_eq!(
+            ledger.iter().map(|e| e.held).collect::<Vec<_>>(),
+            vec![100, 200, 0]
+        );
+
+        // 缓存中的前复权收盘价还原为实际价格
+        let raw: BTreeMap<NaiveDate, f64> = [
+            ("2024-01-02", 10.0),
+            ("2024-01-03", 11.0),
+            ("2024-01-04", 5.25),
+            ("2024-01-05", 5.5),
+        ]
+        .iter()
+        .map(|(d, c)| (date(d), *c))
+        .collect();
+        let mut before: BTreeMap<NaiveDate, f64> = raw
+            .iter()
+            .map(|(d, c)| {
+                (
+                    *d,
+                    if *d < date("2024-01-04") {
+                        (c - 0.5) / 2.0
+                    } else {
+                        *c
+                    },
+                )
+            })
+            .collect();
+        unadjust_closes(&mut before, &actions, KlineAdjust::Before);
+        let mut after: BTreeMap<NaiveDate, f64> = raw
+            .iter()
+            .map(|(d, c)| {
+                (
+                    *d,
+                    if *d >= date("2024-01-04") {
+                        c * 2.0 + 0.5
+                    } else {
+                        *c
+                    },
+                )
+            })
+            .collect();
+        unadjust_closes(&mut after, &actions, KlineAdjust::After);
+        for series in [&before, &after] {
+            for (d, c) in series {
+                assert!((c - raw[d]).abs() < 1e-9, "{}: {}", d, c);
+            }
+        }
+
+        let closes = HashMap::from([("SH600000".to_string(), before)]);
+        let result = analyze(&ledger, &[], &closes, None, None, Group::Day, 0.0).unwrap();
+        let values: Vec<f64> = result.equity_curve.iter().map(|p| p.market_value).collect();
+        assert_eq!(values, vec![1000.0, 1100.0, 1050.0, 0.0]);
+        // 卖出 1100 + 分红 50 - 买入 1000
+        assert!((result.summary.total_pnl - 150.0).abs() < 1e-9);
+        // 除权日分红计入当日收益，收盘价随之下调，当日收益为 0
+        assert!((result.summary.total_return - (1.1 * 1100.0 / 1050.0 - 1.0)).abs() < 1e-9);
+        assert!(result.missing_prices.is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+pub mod scheduler;
+
+use crate::db::app_config_db::get_backup_settings;
+use crate::db::backup_db::{export_database_zip, validate_backup_archive};
+use crate::db::common::AppDb;
+use crate::structs::backup_structs::{BackupManifest, BackupSettings, BackupSnapshot};
+use chrono::{Datelike, Local, NaiveDateTime};
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use zip::ZipArchive;
+
+// 快照文件名：backup-YYYYMMDD-HHMMSS.zip
+const SNAPSHOT_PREFIX: &str = "backup-";
+const SNAPSHOT_EXTENSION: &str = ".zip";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 备份目录：设置中指定的目录，未指定时为应用数据目录下的 backups（不存在时创建）
+pub fn backup_directory(app: &AppHandle, settings: &BackupSettings) -> Result<PathBuf, String> {
+    let dir = match settings.directory.as_deref().map(str::trim) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("无法获取应用数据目录: {}", e))?
+            .join("backups"),
+    };
+    std::fs::create_dir_all(&dir).map_err(|e| format!("无法创建备份目录: {}", e))?;
+    Ok(dir)
+}
+
+/// 从快照文件名解析备份时间（不是快照的文件返回 None）
+pub fn snapshot_time(file_name: &str) -> Option<NaiveDateTime> {
+    let stamp = file_name
+        .strip_prefix(SNAPSHOT_PREFIX)?
+        .strip_suffix(SNAPSHOT_EXTENSION)?;
+    NaiveDateTime::parse_from_str(stamp, SNAPSHOT_TIME_FORMAT).ok()
+}
+
+/// 列出备份目录中的快照（最新的在前）
+pub fn list_snapshots(dir: &Path) -> Result<Vec<BackupSnapshot>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("读取备份目录失败: {}", e))?;
+    let mut snapshots = Vec::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(time) = snapshot_time(&file_name) else {
+            continue;
+        };
+        let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+        snapshots.push((time, file_name, size));
+    }
+    snapshots.sort_by(|a, b| b.0.cmp(&a.0));
+
+    Ok(snapshots
+        .into_iter()
+        .map(|(time, file_name, size)| BackupSnapshot {
+            file_name,
+            created_at: time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            size,
+        })
+        .collect())
+}
+
+/// 读取备份目录中的一份快照（只接受快照列表中的文件名，避免访问目录外的文件）
+pub fn read_snapshot(dir: &Path, file_name: &str) -> Result<Vec<u8>, String> {
+    if snapshot_time(file_name).is_none() || file_name.contains(['/', '\\']) {
+        return Err(format!("无效的备份文件: {}", file_name));
+    }
+    std::fs::read(dir.join(file_name))
+        .map_err(|e| format!("读取备份文件 {} 失败: {}", file_name, e))
+}
+
+/// 重新打开快照，按清单校验文件完整性和各表记录数
+pub fn verify_snapshot(data: &[u8]) -> Result<BackupManifest, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(data)).map_err(|e| format!("读取备份压缩包失败: {}", e))?;
+    validate_backup_archive(&mut archive)?.ok_or_else(|| "备份缺少清单文件".to_string())
+}
+
+/// 生成一份快照：先写入临时文件并重新读取校验，通过后再改为正式文件名
+pub fn create_snapshot(
+    db: &AppDb,
+    dir: &Path,
+    now: NaiveDateTime,
+) -> Result<BackupSnapshot, String> {
+    let (data, _) = db
+        .with_conn(|conn| Ok(export_database_zip(conn)))
+        .map_err(|e| e.to_string())??;
+
+    let file_name = format!(
+        "{}{}{}",
+        SNAPSHOT_PREFIX,
+        now.format(SNAPSHOT_TIME_FORMAT),
+        SNAPSHOT_EXTENSION
+    );
+    let temp_path = dir.join(format!("{}.tmp", file_name));
+    std::fs::write(&temp_path, &data).map_err(|e| format!("写入备份文件失败: {}", e))?;
+
+    let verified = std::fs::read(&temp_path)
+        .map_err(|e| format!("读取备份文件失败: {}", e))
+        .and_then(|written| verify_snapshot(&written));
+    if let Err(e) = verified {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(format!("备份校验失败: {}", e));
+    }
+    std::fs::rename(&temp_path, dir.join(&file_name))
+        .map_err(|e| format!("保存备份文件失败: {}", e))?;
+
+    Ok(BackupSnapshot {
+        file_name,
+        created_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        size: data.len() as u64,
+    })
+}
+
+/// 按保留规则挑选要保留的快照：最新的一份，以及最近若干天 / 周 / 月中每个周期最新的一份
+/// 返回：要保留的快照下标
+pub fn select_retained(times: &[NaiveDateTime], settings: &BackupSettings) -> HashSet<usize> {
+    let mut order: Vec<usize> = (0..times.len()).collect();
+    order.sort_by(|a, b| times[*b].cmp(&times[*a]));
+
+    let mut retained: HashSet<usize> = order.first().copied().into_iter().collect();
+    let rules: [(usize, fn(&NaiveDateTime) -> String); 3] = [
+        (settings.keep_daily, |time| {
+            time.format("%Y-%m-%d").to_string()
+        }),
+        (settings.keep_weekly, |time| {
+            let week = time.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }),
+        (settings.keep_monthly, |time| {
+            time.format("%Y-%m").to_string()
+        }),
+    ];
+    for (count, period_of) in rules {
+        let mut periods = HashSet::new();
+        for &index in &order {
+            let period = period_of(&times[index]);
+            if periods.contains(&period) {
+                continue;
+            }
+            if periods.len() >= count {
+                break;
+            }
+            periods.insert(period);
+            retained.insert(index);
+        }
+    }
+    retained
+}
+
+/// 删除保留规则之外的快照
+/// 返回：删除的文件名
+pub fn prune_snapshots(dir: &Path, settings: &BackupSettings) -> Result<Vec<String>, String> {
+    let snapshots = list_snapshots(dir)?;
+    let times: Vec<NaiveDateTime> = snapshots
+        .iter()
+        .filter_map(|snapshot| snapshot_time(&snapshot.file_name))
+        .collect();
+    let retained = select_retained(&times, settings);
+
+    let mut removed = Vec::new();
+    for (index, snapshot) in snapshots.into_iter().enumerate() {
+        if retained.contains(&index) {
+            continue;
+        }
+        std::fs::remove_file(dir.join(&snapshot.file_name))
+            .map_err(|e| format!("删除过期备份 {} 失败: {}", snapshot.file_name, e))?;
+        removed.push(snapshot.file_name);
+    }
+    Ok(removed)
+}
+
+/// 立即备份：生成快照并清理过期快照
+pub fn run_backup(app: &AppHandle, db: &AppDb) -> Result<BackupSnapshot, String> {
+    let settings = db
+        .with_conn(|conn| get_backup_settings(conn))
+        .map_err(|e| e.to_string())?;
+    let dir = backup_directory(app, &settings)?;
+    let snapshot = create_snapshot(db, &dir, Local::now().naive_local())?;
+    prune_snapshots(&dir, &settings)?;
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+    use chrono::NaiveDate;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(20, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn retention_keeps_latest_per_day_week_and_month() {
+        let settings = BackupSettings {
+            keep_daily: 2,
+            keep_weekly: 2,
+            keep_monthly: 2,
+            ..BackupSettings::default()
+        };
+        let times = vec![
+            at("2024-03-20"),                              // 0 最新：日
+            at("2024-03-20") - chrono::Duration::hours(2), // 1 同一天较早的一份
+            at("2024-03-19"),                              // 2 日
+            at("2024-03-18"),                              // 3 同一周（3 月 18 日为周一）
+            at("2024-03-14"),                              // 4 上一周最新
+            at("2024-03-11"),                              // 5
+            at("2024-02-27"),                              // 6 上个月最新
+            at("2024-01-31"),                              // 7 超出保留的月数
+        ];
+        let retained = select_retained(&times, &settings);
+        let mut kept: Vec<usize> = retained.into_iter().collect();
+        kept.sort();
+        assert_eq!(kept, vec![0, 2, 4, 6]);
+    }
+
+    #[test]
+    fn snapshot_is_written_verified_and_listed() {
+        let db = AppDb::from_connection(open_test_connection()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let created = create_snapshot(&db, dir.path(), at("2024-03-20")).unwrap();
+        assert_eq!(created.file_name, "backup-20240320-200000.zip");
+
+        std::fs::write(dir.path().join("notes.txt"), "不是快照").unwrap();
+        let snapshots = list_snapshots(dir.path()).unwrap();
+        assert_eq!(snapshots, vec![created.clone()]);
+
+        let data = read_snapshot(dir.path(), &created.file_name).unwrap();
+        assert!(verify_snapshot(&data).is_ok());
+        assert!(read_snapshot(dir.path(), "../app.db").is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::backup::{backup_directory, list_snapshots, run_backup, snapshot_time};
+use crate::db::app_config_db::get_backup_settings;
+use crate::db::common::AppDb;
+use chrono::Local;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_log::log;
+
+// 检查是否需要自动备份的间隔（修改设置后最迟在此时间内生效）
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// 启动自动备份：启动时备份一次，之后距上次备份超过设定间隔时再备份
+pub fn start_backup_scheduler(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut startup = true;
+        loop {
+            match backup_if_due(&app, startup) {
+                Ok(Some(file_name)) => log::info!("自动备份完成: {}", file_name),
+                Ok(None) => {}
+                Err(e) => log::warn!("自动备份失败: {}", e),
+            }
+            startup = false;
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+// 返回：本次生成的快照文件名（未到备份时间或未开启时为 None）
+fn backup_if_due(app: &AppHandle, startup: bool) -> Result<Option<String>, String> {
+    // 数据库初始化失败时不备份
+    let Some(db) = app.try_state::<AppDb>() else {
+        return Ok(None);
+    };
+    let settings = db
+        .with_conn(|conn| get_backup_settings(conn))
+        .map_err(|e| e.to_string())?;
+    if !settings.enabled {
+        return Ok(None);
+    }
+
+    if !startup {
+        let dir = backup_directory(app, &settings)?;
+        let latest = list_snapshots(&dir)?
+            .first()
+            .and_then(|snapshot| snapshot_time(&snapshot.file_name));
+        if let Some(latest) = latest {
+            let elapsed = Local::now().naive_local() - latest;
+            if elapsed < chrono::Duration::hours(settings.interval_hours as i64) {
+                return Ok(None);
+            }
+        }
+    }
+
+    run_backup(app, &db).map(|snapshot| Some(snapshot.file_name))
+}
@@ -0,0 +1,404 @@
+use crate::db::app_config_db::get_holiday_overrides;
+use crate::structs::StockError;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday,
+};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Mutex, OnceLock};
+use tauri_plugin_log::log;
+
+// 随应用发布的沪深交易所休市安排：年份 -> 工作日休市的日期（周末总是休市，不列出）
+// 更新方法：每年 12 月上交所、深交所公布次年休市安排后，在 holidays.json 中新增该年份，
+// 列出其中落在周一至周五的休市日期（YYYY-MM-DD），随下一个版本发布；
+// 发布前用户也可以通过 set_trading_holidays_cmd 自定义当年的休市日期。
+// 缺少某年的安排时该年只按周末判断，节假日会被当作交易日，启动后会提示
+const BUNDLED_HOLIDAYS: &str = include_str!("holidays.json");
+
+// 北京时间（Asia/Shanghai）相对 UTC 的偏移，中国不实行夏令时
+const MARKET_UTC_OFFSET_SECS: i32 = 8 * 3600;
+
+// 查找前后交易日时最多跨越的天数，避免自定义休市表有误时死循环
+const MAX_SEARCH_DAYS: i64 = 366;
+
+fn at(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+/// 北京时间的时区偏移
+pub fn market_offset() -> FixedOffset {
+    FixedOffset::east_opt(MARKET_UTC_OFFSET_SECS).unwrap()
+}
+
+/// 当前北京时间（与本机时区无关）
+pub fn market_now() -> NaiveDateTime {
+    Utc::now().with_timezone(&market_offset()).naive_local()
+}
+
+/// 13位时间戳对应的北京时间
+pub fn market_time(timestamp_ms: i64) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|time| time.with_timezone(&market_offset()).naive_local())
+}
+
+/// 北京时间对应的13位时间戳
+pub fn market_timestamp_ms(time: NaiveDateTime) -> i64 {
+    (time - Duration::seconds(MARKET_UTC_OFFSET_SECS as i64))
+        .and_utc()
+        .timestamp_millis()
+}
+
+/// A股交易日内的时段（沪深交易所相同）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketSession {
+    Closed,         // 非交易日（周末或节假日）
+    PreOpen,        // 开盘前（9:15 之前）
+    OpeningAuction, // 开盘集合竞价 9:15-9:25
+    AuctionBreak,   // 集合竞价结束，等待连续竞价 9:25-9:30
+    Morning,        // 上午连续竞价 9:30-11:30
+    LunchBreak,     // 午间休市 11:30-13:00
+    Afternoon,      // 下午连续竞价 13:00-14:57
+    ClosingAuction, // 收盘集合竞价 14:57-15:00
+    AfterClose,     // 收盘后
+}
+
+impl MarketSession {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketSession::Closed => "closed",
+            MarketSession::PreOpen => "pre_open",
+            MarketSession::OpeningAuction => "opening_auction",
+            MarketSession::AuctionBreak => "auction_break",
+            MarketSession::Morning => "morning",
+            MarketSession::LunchBreak => "lunch_break",
+            MarketSession::Afternoon => "afternoon",
+            MarketSession::ClosingAuction => "closing_auction",
+            MarketSession::AfterClose => "after_close",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarketSession::Closed => "休市",
+            MarketSession::PreOpen => "开盘前",
+            MarketSession::OpeningAuction => "开盘集合竞价",
+            MarketSession::AuctionBreak => "等待开盘",
+            MarketSession::Morning => "上午交易",
+            MarketSession::LunchBreak => "午间休市",
+            MarketSession::Afternoon => "下午交易",
+            MarketSession::ClosingAuction => "收盘集合竞价",
+            MarketSession::AfterClose => "已收盘",
+        }
+    }
+
+    /// 是否处于交易时段（含集合竞价，行情会变化）
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            MarketSession::OpeningAuction
+                | MarketSession::AuctionBreak
+                | MarketSession::Morning
+                | MarketSession::Afternoon
+                | MarketSession::ClosingAuction
+        )
+    }
+}
+
+fn parse_holidays(
+    years: &BTreeMap<i32, Vec<String>>,
+) -> Result<BTreeMap<i32, BTreeSet<NaiveDate>>, String> {
+    let mut holidays = BTreeMap::new();
+    for (year, dates) in years {
+        let mut set = BTreeSet::new();
+        for date in dates {
+            let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("无效的休市日期: {}", date))?;
+            if date.year() != *year {
+                return Err(format!("休市日期 {} 不属于 {} 年", date, year));
+            }
+            set.insert(date);
+        }
+        holidays.insert(*year, set);
+    }
+    Ok(holidays)
+}
+
+/// 检查自定义的休市日期，返回排序去重后的日期
+pub fn normalize_holidays(year: i32, dates: &[String]) -> Result<Vec<String>, String> {
+    let parsed = parse_holidays(&BTreeMap::from([(year, dates.to_vec())]))?;
+    Ok(parsed[&year].iter().map(|date| date.to_string()).collect())
+}
+
+fn bundled_holidays() -> &'static BTreeMap<i32, BTreeSet<NaiveDate>> {
+    static BUNDLED: OnceLock<BTreeMap<i32, BTreeSet<NaiveDate>>> = OnceLock::new();
+    BUNDLED.get_or_init(|| {
+        let years: BTreeMap<i32, Vec<String>> =
+            serde_json::from_str(BUNDLED_HOLIDAYS).expect("内置休市表格式错误");
+        parse_holidays(&years).expect("内置休市表格式错误")
+    })
+}
+
+/// 交易日历：周末和节假日休市，交易日内按时段区分集合竞价、连续竞价和午间休市
+/// 没有某年休市安排时，该年只按周末判断
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+    holidays: BTreeMap<i32, BTreeSet<NaiveDate>>,
+    customized: BTreeSet<i32>, // 休市日期被用户自定义的年份
+}
+
+impl TradingCalendar {
+    /// 只使用随应用发布的休市表
+    pub fn bundled() -> Self {
+        TradingCalendar {
+            holidays: bundled_holidays().clone(),
+            customized: BTreeSet::new(),
+        }
+    }
+
+    /// 读取数据库中自定义的休市日期，按年份覆盖内置休市表
+    pub fn load(conn: &Connection) -> Result<Self, StockError> {
+        let overrides =
+            parse_holidays(&get_holiday_overrides(conn)?).map_err(StockError::BusinessError)?;
+        let mut calendar = TradingCalendar::bundled();
+        for (year, dates) in overrides {
+            calendar.holidays.insert(year, dates);
+            calendar.customized.insert(year);
+        }
+        calendar.warn_missing_holidays(market_now().year());
+        Ok(calendar)
+    }
+
+    // 没有该年休市安排时记录警告（每年只记录一次）
+    fn warn_missing_holidays(&self, year: i32) {
+        static WARNED: Mutex<BTreeSet<i32>> = Mutex::new(BTreeSet::new());
+        if self.has_holiday_data(year) {
+            return;
+        }
+        if WARNED.lock().is_ok_and(|mut warned| warned.insert(year)) {
+            log::warn!(
+                "没有 {} 年的交易所休市安排，节假日会被当作交易日，请升级应用或自定义该年的休市日期",
+                year
+            );
+        }
+    }
+
+    /// 是否有该年的休市安排
+    pub fn has_holiday_data(&self, year: i32) -> bool {
+        self.holidays.contains_key(&year)
+    }
+
+    pub fn is_customized(&self, year: i32) -> bool {
+        self.customized.contains(&year)
+    }
+
+    /// 该年工作日休市的日期
+    pub fn holidays(&self, year: i32) -> Vec<NaiveDate> {
+        self.holidays
+            .get(&year)
+            .map(|dates| dates.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            && !self
+                .holidays
+                .get(&date.year())
+                .is_some_and(|dates| dates.contains(&date))
+    }
+
+    /// date 之后（不含）的第一个交易日
+    pub fn next_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        (1..=MAX_SEARCH_DAYS)
+            .map(|days| date + Duration::days(days))
+            .find(|day| self.is_trading_day(*day))
+            .unwrap_or(date + Duration::days(1))
+    }
+
+    /// date 之前（不含）的最后一个交易日
+    pub fn previous_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        (1..=MAX_SEARCH_DAYS)
+            .map(|days| date - Duration::days(days))
+            .find(|day| self.is_trading_day(*day))
+            .unwrap_or(date - Duration::days(1))
+    }
+
+    /// (start, end] 之间的交易日数，即 start 买入、end 卖出时持有的交易日数
+    pub fn trading_days_between(&self, start: NaiveDate, end: NaiveDate) -> i64 {
+        start
+            .iter_days()
+            .skip(1)
+            .take_while(|day| *day <= end)
+            .filter(|day| self.is_trading_day(*day))
+            .count() as i64
+    }
+
+    /// 某月的全部交易日，月份无效时返回 None
+    pub fn trading_days_in_month(&self, year: i32, month: u32) -> Option<Vec<NaiveDate>> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        Some(
+            first
+                .iter_days()
+                .take_while(|day| day.month() == month)
+                .filter(|day| self.is_trading_day(*day))
+                .collect(),
+        )
+    }
+
+    /// 北京时间 now 所处的时段
+    pub fn session(&self, now: NaiveDateTime) -> MarketSession {
+        if !self.is_trading_day(now.date()) {
+            return MarketSession::Closed;
+        }
+        let time = now.time();
+        if time < at(9, 15) {
+            MarketSession::PreOpen
+        } else if time < at(9, 25) {
+            MarketSession::OpeningAuction
+        } else if time < at(9, 30) {
+            MarketSession::AuctionBreak
+        } else if time <= at(11, 30) {
+            MarketSession::Morning
+        } else if time < at(13, 0) {
+            MarketSession::LunchBreak
+        } else if time < at(14, 57) {
+            MarketSession::Afternoon
+        } else if time <= at(15, 0) {
+            MarketSession::ClosingAuction
+        } else {
+            MarketSession::AfterClose
+        }
+    }
+
+    /// 是否处于交易时段（含集合竞价）：交易日 9:15-11:30、13:00-15:00（北京时间）
+    pub fn is_trading_time(&self, now: NaiveDateTime) -> bool {
+        self.session(now).is_open()
+    }
+
+    /// 下一次开始交易的时间：开盘集合竞价或午后开盘
+    pub fn next_open(&self, now: NaiveDateTime) -> NaiveDateTime {
+        match self.session(now) {
+            MarketSession::PreOpen => now.date().and_time(at(9, 15)),
+            MarketSession::LunchBreak => now.date().and_time(at(13, 0)),
+            session if session.is_open() => now,
+            _ => self.next_trading_day(now.date()).and_time(at(9, 15)),
+        }
+    }
+
+    /// 最近一个交易日（今天是交易日时为今天）的收盘时间
+    pub fn latest_close(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let date = now.date();
+        let day = if self.is_trading_day(date) {
+            date
+        } else {
+            self.previous_trading_day(date)
+        };
+        day.and_time(at(15, 0))
+    }
+
+    /// 请求最新K线时使用的截止时间（13位时间戳）：不早于当前时间，交易日内为当天收盘，
+    /// 保证尚未收盘的K线也被包含
+    pub fn kline_end_ms(&self, now_ms: i64) -> i64 {
+        market_time(now_ms)
+            .map(|now| market_timestamp_ms(self.latest_close(now)))
+            .map_or(now_ms, |close| close.max(now_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn weekends_and_holidays_are_closed() {
+        let calendar = TradingCalendar::bundled();
+        assert!(calendar.has_holiday_data(2025));
+        assert!(calendar.is_trading_day(date("2025-01-27")));
+        // 春节休市 1 月 28 日至 2 月 4 日
+        assert!(!calendar.is_trading_day(date("2025-01-28")));
+        assert!(!calendar.is_trading_day(date("2025-02-01"))); // 周六
+        assert_eq!(
+            calendar.next_trading_day(date("2025-01-27")),
+            date("2025-02-05")
+        );
+        assert_eq!(
+            calendar.previous_trading_day(date("2025-02-05")),
+            date("2025-01-27")
+        );
+        // 1 月 27 日买入、2 月 6 日卖出：持有 2 个交易日（11 个自然日）
+        assert_eq!(
+            calendar.trading_days_between(date("2025-01-27"), date("2025-02-06")),
+            2
+        );
+        assert_eq!(calendar.trading_days_in_month(2025, 2).unwrap().len(), 18);
+        assert!(calendar.trading_days_in_month(2025, 13).is_none());
+    }
+
+    #[test]
+    fn sessions_follow_auction_and_lunch_break() {
+        let calendar = TradingCalendar::bundled();
+        let session = |value: &str| calendar.session(time(value));
+        assert_eq!(session("2025-03-03 09:00:00"), MarketSession::PreOpen);
+        assert_eq!(
+            session("2025-03-03 09:20:00"),
+            MarketSession::OpeningAuction
+        );
+        assert_eq!(session("2025-03-03 09:30:00"), MarketSession::Morning);
+        assert_eq!(session("2025-03-03 12:00:00"), MarketSession::LunchBreak);
+        assert_eq!(
+            session("2025-03-03 14:59:00"),
+            MarketSession::ClosingAuction
+        );
+        assert_eq!(session("2025-03-03 15:30:00"), MarketSession::AfterClose);
+        assert_eq!(session("2025-10-08 10:00:00"), MarketSession::Closed); // 国庆
+        assert!(calendar.is_trading_time(time("2025-03-03 14:59:00")));
+        assert!(!calendar.is_trading_time(time("2025-03-03 12:00:00")));
+
+        assert_eq!(
+            calendar.next_open(time("2025-03-03 12:00:00")),
+            time("2025-03-03 13:00:00")
+        );
+        assert_eq!(
+            calendar.next_open(time("2025-09-30 15:30:00")),
+            time("2025-10-09 09:15:00")
+        );
+        assert_eq!(
+            calendar.latest_close(time("2025-10-05 10:00:00")),
+            time("2025-09-30 15:00:00")
+        );
+    }
+
+    #[test]
+    fn kline_end_covers_todays_session_in_beijing_time() {
+        let calendar = TradingCalendar::bundled();
+        // 北京时间 2025-03-03 10:00（UTC 02:00）：截止到当天 15:00
+        let now = market_timestamp_ms(time("2025-03-03 10:00:00"));
+        assert_eq!(
+            calendar.kline_end_ms(now),
+            market_timestamp_ms(time("2025-03-03 15:00:00"))
+        );
+        assert_eq!(market_time(now), Some(time("2025-03-03 10:00:00")));
+        // 周六：上一交易日收盘早于当前时间，使用当前时间
+        let saturday = market_timestamp_ms(time("2025-03-08 10:00:00"));
+        assert_eq!(calendar.kline_end_ms(saturday), saturday);
+    }
+
+    #[test]
+    fn custom_holidays_are_validated() {
+        assert_eq!(
+            normalize_holidays(2027, &["2027-02-08".to_string(), "2027-01-01".to_string()])
+                .unwrap(),
+            vec!["2027-01-01", "2027-02-08"]
+        );
+        assert!(normalize_holidays(2027, &["2026-12-31".to_string()]).is_err());
+        assert!(normalize_holidays(2027, &["abc".to_string()]).is_err());
+    }
+}
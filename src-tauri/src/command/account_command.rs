@@ -0,0 +1,181 @@
+use crate::db::account_db::{
+    create_account, query_accounts, query_transfers, rename_account, set_account_archived,
+};
+use crate::db::common::AppDb;
+use crate::db::position_db::{remove_transfer, transfer_position};
+use crate::structs::account_structs::TransferPositionParams;
+use serde_json;
+use tauri::command;
+use tauri::State;
+
+/// 查询账户列表（include_archived 为 true 时包含已归档账户）
+#[command]
+pub fn get_accounts_cmd(
+    db: State<'_, AppDb>,
+    include_archived: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_accounts(conn, include_archived.unwrap_or(false))) {
+        Ok(accounts) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 个账户", accounts.len()),
+            "count": accounts.len(),
+            "data": accounts
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取账户列表失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 新建账户
+#[command]
+pub fn create_account_cmd(db: State<'_, AppDb>, name: String) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| create_account(conn, &name)) {
+        Ok(id) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功创建账户「{}」", name.trim()),
+            "data": id,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("创建账户失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
+/// 重命名账户
+#[command]
+pub fn rename_account_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+    name: String,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| rename_account(conn, id, &name)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("账户已重命名为「{}」", name.trim()),
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("未找到ID为 {} 的账户", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("重命名账户失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 归档或恢复账户（归档后仍可查询，但不能再录入委托和资金流水）
+#[command]
+pub fn archive_account_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+    archived: bool,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| set_account_archived(conn, id, archived)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": if archived { "账户已归档" } else { "账户已恢复" },
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("未找到ID为 {} 的账户", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("更新账户状态失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 按成本价把持仓划转到另一个账户
+#[command]
+pub fn transfer_position_cmd(
+    db: State<'_, AppDb>,
+    params: TransferPositionParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| transfer_position(conn, &params)) {
+        Ok(id) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("已划转 {} 股 {}", params.quantity, params.code.to_uppercase()),
+            "data": id,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("持仓划转失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询持仓划转记录，可按账户和股票代码过滤
+#[command]
+pub fn get_position_transfers_cmd(
+    db: State<'_, AppDb>,
+    account_id: Option<i32>,
+    code: Option<String>,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_transfers(conn, account_id, code.as_deref())) {
+        Ok(transfers) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 条划转记录", transfers.len()),
+            "count": transfers.len(),
+            "data": transfers
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取划转记录失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 撤销持仓划转（两个账户的持仓随之重新生成）
+#[command]
+pub fn delete_position_transfer_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| remove_transfer(conn, id)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": "划转已撤销",
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("未找到ID为 {} 的划转记录", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("撤销划转失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
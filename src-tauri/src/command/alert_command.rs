@@ -0,0 +1,234 @@
+use crate::db::alert_db::{
+    add_alert_rule, clear_alert_events, delete_alert_rule, query_alert_events, query_alert_rules,
+    set_alert_rule_enabled, update_alert_rule,
+};
+use crate::db::app_config_db::{get_alert_settings, set_alert_settings};
+use crate::db::common::AppDb;
+use crate::structs::alert_structs::{AlertRuleParams, AlertSettings};
+use serde_json;
+use tauri::command;
+use tauri::State;
+
+// 默认返回的提醒记录条数
+const DEFAULT_EVENT_LIMIT: i64 = 200;
+
+/// 查询价格提醒规则，可按股票代码过滤
+#[command]
+pub fn get_alert_rules_cmd(
+    db: State<'_, AppDb>,
+    code: Option<String>,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_alert_rules(conn, code.as_deref(), false)) {
+        Ok(rules) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 条提醒规则", rules.len()),
+            "count": rules.len(),
+            "data": rules
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取提醒规则失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 新增价格提醒规则
+#[command]
+pub fn add_alert_rule_cmd(
+    db: State<'_, AppDb>,
+    params: AlertRuleParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| add_alert_rule(conn, &params)) {
+        Ok(id) => Ok(serde_json::json!({
+            "success": true,
+            "message": "提醒规则已添加",
+            "data": id,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("添加提醒规则失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
+/// 修改价格提醒规则
+#[command]
+pub fn update_alert_rule_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+    params: AlertRuleParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| update_alert_rule(conn, id, &params)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": "提醒规则已修改",
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("提醒规则不存在: {}", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("修改提醒规则失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 启用/停用价格提醒规则
+#[command]
+pub fn set_alert_rule_enabled_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+    enabled: bool,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| set_alert_rule_enabled(conn, id, enabled)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": if enabled { "提醒规则已启用" } else { "提醒规则已停用" },
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("提醒规则不存在: {}", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("切换提醒规则失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 删除价格提醒规则（已触发的提醒记录保留）
+#[command]
+pub fn delete_alert_rule_cmd(db: State<'_, AppDb>, id: i32) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| delete_alert_rule(conn, id)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": "提醒规则已删除",
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("提醒规则不存在: {}", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("删除提醒规则失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询提醒记录（最新的在前），可按股票代码过滤
+#[command]
+pub fn get_alert_events_cmd(
+    db: State<'_, AppDb>,
+    code: Option<String>,
+    limit: Option<i64>,
+) -> Result<serde_json::Value, String> {
+    let limit = limit
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_EVENT_LIMIT);
+    match db.with_conn(|conn| query_alert_events(conn, code.as_deref(), limit)) {
+        Ok(events) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 条提醒记录", events.len()),
+            "count": events.len(),
+            "data": events
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取提醒记录失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 清空提醒记录
+#[command]
+pub fn clear_alert_events_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| clear_alert_events(conn)) {
+        Ok(count) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("已清空 {} 条提醒记录", count),
+            "data": true,
+            "count": count
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("清空提醒记录失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询后台行情轮询与提醒设置
+#[command]
+pub fn get_alert_settings_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| get_alert_settings(conn)) {
+        Ok(settings) => Ok(serde_json::json!({
+            "success": true,
+            "message": "获取提醒设置成功",
+            "data": settings,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取提醒设置失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 保存后台行情轮询与提醒设置（下一轮轮询时生效）
+#[command]
+pub fn set_alert_settings_cmd(
+    db: State<'_, AppDb>,
+    settings: AlertSettings,
+) -> Result<serde_json::Value, String> {
+    if settings.poll_interval_secs == 0 {
+        return Ok(serde_json::json!({
+            "success": false,
+            "message": "轮询间隔至少为 1 秒",
+            "data": false,
+            "count": 0
+        }));
+    }
+    match db.with_conn(|conn| set_alert_settings(conn, &settings)) {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": "提醒设置已保存",
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("保存提醒设置失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
@@ -0,0 +1,198 @@
+use crate::analytics::{
+    analyze, average_holding_trading_days, date_timestamp, kline_date, parse_date, replay_ledger,
+    unadjust_closes, DailyCloses, Group,
+};
+use crate::calendar::TradingCalendar;
+use crate::command::xueqiu_command::provider_chain;
+use crate::db::account_db::query_transfers;
+use crate::db::app_config_db::get_kline_adjust;
+use crate::db::common::AppDb;
+use crate::db::corporate_action_db::query_corporate_actions;
+use crate::db::holdings_db::query_all_exits;
+use crate::db::kline_cache_db::query_cached_klines_between;
+use crate::db::orders_db::query_all_orders;
+use crate::db::position_db::transfer_orders;
+use crate::requests::kline_cache::load_klines;
+use crate::structs::analytics_structs::{PortfolioAnalytics, PortfolioAnalyticsParams};
+use chrono::{Local, NaiveDate, Utc};
+use serde_json;
+use tauri::command;
+use tauri::State;
+use tauri_plugin_log::log;
+
+// 每只股票最多补取的日线数量
+const MAX_DAILY_BARS: i32 = 2000;
+
+// 在线时先补齐所涉股票的日线缓存，失败只记录日志，继续使用已有缓存
+async fn refresh_daily_closes(db: &AppDb, codes: &[String], since: NaiveDate) {
+    let chain = match provider_chain(db) {
+        Ok(chain) => chain,
+        Err(e) => {
+            log::info!("组合分析使用本地日线缓存: {}", e);
+            return;
+        }
+    };
+    let days = (Local::now().date_naive() - since).num_days().max(0);
+    let limit = (days * 5 / 7 + 10).min(MAX_DAILY_BARS as i64) as i32;
+    let now = Utc::now().timestamp_millis();
+    for code in codes {
+        if let Err(e) = load_klines(db, &chain, code, "day", None, limit, now).await {
+            log::warn!("补取 {} 的日线失败: {}", code, e);
+        }
+    }
+}
+
+fn parse_param_date(value: &Option<String>) -> Result<Option<NaiveDate>, String> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => parse_date(value)
+            .map(Some)
+            .ok_or_else(|| format!("无效的日期: {}", value)),
+    }
+}
+
+async fn load_portfolio_analytics(
+    db: &AppDb,
+    params: &PortfolioAnalyticsParams,
+) -> Result<PortfolioAnalytics, String> {
+    let group_name = params.group.as_deref().unwrap_or("day");
+    let group =
+        Group::parse(group_name).ok_or_else(|| format!("不支持的分组方式: {}", group_name))?;
+    let start = parse_param_date(&params.start)?;
+    let end = parse_param_date(&params.end)?;
+
+    // 与生成持仓相同：每个账户的委托加上该账户的持仓划转，按公司行动调整数量并计入分红
+    // 单个账户的划转按成本价买入/卖出计入，汇总全部账户时划转相互抵消
+    let (orders, actions) = db
+        .with_conn(|conn| {
+            let mut orders = query_all_orders(conn, params.account_id)?;
+            let transfers = query_transfers(conn, params.account_id, None)?;
+            let mut accounts: Vec<i32> = match params.account_id {
+                Some(account_id) => vec![account_id],
+                None => orders
+                    .iter()
+                    .map(|o| o.account_id)
+                    .chain(
+                        transfers
+                            .iter()
+                            .flat_map(|t| [t.from_account_id, t.to_account_id]),
+                    )
+                    .collect(),
+            };
+            accounts.sort();
+            accounts.dedup();
+            for account_id in accounts {
+                orders.extend(transfer_orders(&transfers, account_id));
+            }
+            Ok((orders, query_corporate_actions(conn, None)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let ledger = replay_ledger(&orders, &actions)?;
+    let mut codes: Vec<String> = ledger.iter().map(|e| e.code.clone()).collect();
+    codes.sort();
+    codes.dedup();
+    let Some(since) = ledger.first().map(|e| e.date) else {
+        return Err("没有委托记录，无法计算组合表现".to_string());
+    };
+
+    refresh_daily_closes(db, &codes, since).await;
+
+    let (closes, exits, calendar) = db
+        .with_conn(|conn| {
+            let adjust = get_kline_adjust(conn)?;
+            let mut closes = DailyCloses::new();
+            for code in &codes {
+                let items = query_cached_klines_between(
+                    conn,
+                    code,
+                    "day",
+                    date_timestamp(since),
+                    i64::MAX,
+                )?;
+                let mut series = items
+                    .iter()
+                    .map(|k| (kline_date(k.date), k.close))
+                    .collect();
+                // 缓存中的日线按设置复权，估值使用实际价格
+                let code_actions: Vec<_> = actions
+                    .iter()
+                    .filter(|action| action.code == *code)
+                    .cloned()
+                    .collect();
+                unadjust_closes(&mut series, &code_actions, adjust);
+                closes.insert(code.clone(), series);
+            }
+            Ok((
+                closes,
+                query_all_exits(conn, params.account_id)?,
+                TradingCalendar::load(conn)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut analytics = analyze(
+        &ledger,
+        &exits,
+        &closes,
+        start,
+        end,
+        group,
+        params.risk_free_rate,
+    )?;
+    // 持有交易日数按交易日历计算，与分析区间使用相同的卖出记录
+    if let (Some(start), Some(end)) = (parse_date(&analytics.start), parse_date(&analytics.end)) {
+        analytics.summary.average_holding_trading_days =
+            average_holding_trading_days(&exits, start, end, &calendar);
+    }
+    Ok(analytics)
+}
+
+/// 组合表现分析：收益率、回撤、夏普/索提诺比率和交易统计，按日/周/月/年分组
+#[command]
+pub async fn get_portfolio_analytics_cmd(
+    db: State<'_, AppDb>,
+    params: PortfolioAnalyticsParams,
+) -> Result<serde_json::Value, String> {
+    match load_portfolio_analytics(&db, &params).await {
+        Ok(analytics) => {
+            let count = analytics.periods.len();
+            Ok(serde_json::json!({
+                "success": true,
+                "message": format!("组合分析完成（{} 至 {}）", analytics.start, analytics.end),
+                "data": analytics,
+                "count": count
+            }))
+        }
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("组合分析失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 组合净值曲线（每个分组取最后一个交易日）
+#[command]
+pub async fn get_equity_curve_cmd(
+    db: State<'_, AppDb>,
+    params: PortfolioAnalyticsParams,
+) -> Result<serde_json::Value, String> {
+    match load_portfolio_analytics(&db, &params).await {
+        Ok(analytics) => {
+            let count = analytics.equity_curve.len();
+            Ok(serde_json::json!({
+                "success": true,
+                "message": format!("成功获取 {} 个净值数据点", count),
+                "data": analytics.equity_curve,
+                "count": count
+            }))
+        }
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取净值曲线失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
@@ -1,27 +1,321 @@
 use crate::db::app_config_db;
-use crate::db::common::init_database;
+use crate::db::common::AppDb;
+use crate::db::position_db::change_cost_method;
+use crate::requests::provider::{get_active_provider, set_active_provider, PROVIDERS};
+use crate::structs::corporate_action_structs::KlineAdjust;
+use crate::structs::holdings_structs::CostMethod;
+use crate::structs::orders_structs::FeeModel;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
 // 保存 Cookie 命令（前端调用）
 #[command]
-pub fn save_xueqiu_cookie(app: AppHandle, cookie: String) -> Result<(), String> {
+pub fn save_xueqiu_cookie(db: State<'_, AppDb>, cookie: String) -> Result<(), String> {
     // 获取数据库连接
-
-    let mut conn =
-        init_database(&app, "app_config").map_err(|e| format!("获取数据库连接失败: {}", e))?;
-    println!("Database connection established.");
-    // 保存到数据库
+    let mut conn = db
+        .conn()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    // 加密后保存到数据库
     app_config_db::save_xueqiu_cookie_to_db(&mut conn, &cookie)
         .map_err(|e| format!("保存 Cookie 失败: {}", e))?;
-    println!("Saved cookie: {}", cookie);
 
     Ok(())
 }
 
-pub fn get_xueqiu_cookie(app: AppHandle) -> Result<Option<String>, String> {
-    let conn =
-        init_database(&app, "app_config").map_err(|e| format!("获取数据库连接失败: {}", e))?;
+pub fn get_xueqiu_cookie(db: &AppDb) -> Result<Option<String>, String> {
+    let conn = db
+        .conn()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
     // 从数据库读取
     app_config_db::get_xueqiu_cookie_from_db(&conn).map_err(|e| format!("读取 Cookie 失败: {}", e))
 }
+
+/// 查询行情数据源（当前选择 + 可选列表）
+#[command]
+pub fn get_market_data_provider_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| get_active_provider(conn)) {
+        Ok(active) => {
+            let providers: Vec<serde_json::Value> = PROVIDERS
+                .iter()
+                .map(|(id, name)| serde_json::json!({ "id": id, "name": name }))
+                .collect();
+            Ok(serde_json::json!({
+                "success": true,
+                "message": "查询行情数据源成功",
+                "data": {
+                    "active": active,
+                    "providers": providers
+                },
+                "count": providers.len()
+            }))
+        }
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("查询行情数据源失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 切换行情数据源
+#[command]
+pub fn set_market_data_provider_cmd(
+    db: State<'_, AppDb>,
+    provider: String,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| set_active_provider(conn, &provider)) {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("已切换行情数据源为 {}", provider),
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("切换行情数据源失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询是否处于离线模式
+#[command]
+pub fn get_offline_mode_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| app_config_db::is_offline_mode(conn)) {
+        Ok(enabled) => Ok(serde_json::json!({
+            "success": true,
+            "message": if enabled { "当前为离线模式" } else { "当前为在线模式" },
+            "data": enabled,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("查询离线模式失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 开启/关闭离线模式（开启后行情命令只读取本地数据）
+#[command]
+pub fn set_offline_mode_cmd(
+    db: State<'_, AppDb>,
+    enabled: bool,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| app_config_db::set_offline_mode(conn, enabled)) {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": if enabled { "已开启离线模式" } else { "已关闭离线模式" },
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("切换离线模式失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询卖出成本计算方式（fifo / average）
+#[command]
+pub fn get_cost_method_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| app_config_db::get_cost_method(conn)) {
+        Ok(method) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("当前成本计算方式: {}", method.as_str()),
+            "data": method.as_str(),
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("查询成本计算方式失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 切换卖出成本计算方式，并按新方式重新生成全部持仓
+#[command]
+pub fn set_cost_method_cmd(
+    db: State<'_, AppDb>,
+    method: String,
+) -> Result<serde_json::Value, String> {
+    let Some(method) = CostMethod::parse(&method) else {
+        return Ok(serde_json::json!({
+            "success": false,
+            "message": format!("不支持的成本计算方式: {}", method),
+            "data": false,
+            "count": 0
+        }));
+    };
+
+    match db.with_conn(|conn| change_cost_method(conn, method)) {
+        Ok(count) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!(
+                "已切换为 {}，重新生成 {} 条持仓记录",
+                method.as_str(),
+                count
+            ),
+            "data": true,
+            "count": count
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("切换成本计算方式失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询交易费用模型（佣金、印花税、过户费费率）
+#[command]
+pub fn get_fee_model_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| app_config_db::get_fee_model(conn)) {
+        Ok(model) => Ok(serde_json::json!({
+            "success": true,
+            "message": "查询交易费用模型成功",
+            "data": model,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("查询交易费用模型失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 保存交易费用模型，只影响之后添加的委托
+#[command]
+pub fn set_fee_model_cmd(
+    db: State<'_, AppDb>,
+    model: FeeModel,
+) -> Result<serde_json::Value, String> {
+    if let Err(e) = model.validate() {
+        return Ok(serde_json::json!({
+            "success": false,
+            "message": e,
+            "data": false,
+            "count": 0
+        }));
+    }
+
+    match db.with_conn(|conn| app_config_db::set_fee_model(conn, &model)) {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": "交易费用模型已保存",
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("保存交易费用模型失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询是否开启资金严格模式
+#[command]
+pub fn get_cash_strict_mode_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| app_config_db::is_cash_strict_mode(conn)) {
+        Ok(enabled) => Ok(serde_json::json!({
+            "success": true,
+            "message": if enabled { "资金严格模式已开启" } else { "资金严格模式未开启" },
+            "data": enabled,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("查询资金严格模式失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 开启/关闭资金严格模式（开启后可用资金不足时拒绝买入和转出）
+#[command]
+pub fn set_cash_strict_mode_cmd(
+    db: State<'_, AppDb>,
+    enabled: bool,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| app_config_db::set_cash_strict_mode(conn, enabled)) {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": if enabled { "已开启资金严格模式" } else { "已关闭资金严格模式" },
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("切换资金严格模式失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询K线复权方式（normal / before / after）
+#[command]
+pub fn get_kline_adjust_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| app_config_db::get_kline_adjust(conn)) {
+        Ok(adjust) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("当前K线复权方式: {}", adjust.as_str()),
+            "data": adjust.as_str(),
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("查询K线复权方式失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 切换K线复权方式，并清空K线缓存以便按新方式重新获取
+#[command]
+pub fn set_kline_adjust_cmd(
+    db: State<'_, AppDb>,
+    adjust: String,
+) -> Result<serde_json::Value, String> {
+    let Some(adjust) = KlineAdjust::parse(&adjust) else {
+        return Ok(serde_json::json!({
+            "success": false,
+            "message": format!("不支持的复权方式: {}", adjust),
+            "data": false,
+            "count": 0
+        }));
+    };
+
+    match db.with_conn(|conn| app_config_db::change_kline_adjust(conn, adjust)) {
+        Ok(cleared) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!(
+                "已切换为 {}，清除 {} 条K线缓存",
+                adjust.as_str(),
+                cleared
+            ),
+            "data": true,
+            "count": cleared
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("切换K线复权方式失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
@@ -0,0 +1,158 @@
+use crate::backup::{backup_directory, list_snapshots, read_snapshot, run_backup};
+use crate::command::import_command::import_backup_zip;
+use crate::db::app_config_db::{get_backup_settings, set_backup_settings};
+use crate::db::common::AppDb;
+use crate::structs::backup_structs::BackupSettings;
+use crate::structs::import_structs::ImportStrategy;
+use serde_json;
+use tauri::command;
+use tauri::{AppHandle, State};
+
+/// 查询自动备份设置
+#[command]
+pub fn get_backup_settings_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| get_backup_settings(conn)) {
+        Ok(settings) => Ok(serde_json::json!({
+            "success": true,
+            "message": "获取自动备份设置成功",
+            "data": settings,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取自动备份设置失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 保存自动备份设置（下一次检查时生效）
+#[command]
+pub fn set_backup_settings_cmd(
+    db: State<'_, AppDb>,
+    settings: BackupSettings,
+) -> Result<serde_json::Value, String> {
+    if settings.interval_hours == 0 || settings.keep_daily == 0 {
+        return Ok(serde_json::json!({
+            "success": false,
+            "message": "备份间隔和每日保留份数至少为 1",
+            "data": false,
+            "count": 0
+        }));
+    }
+    match db.with_conn(|conn| set_backup_settings(conn, &settings)) {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": "自动备份设置已保存",
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("保存自动备份设置失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询备份目录中的快照（最新的在前）
+#[command]
+pub fn list_backup_snapshots_cmd(
+    app: AppHandle,
+    db: State<'_, AppDb>,
+) -> Result<serde_json::Value, String> {
+    let result = db
+        .with_conn(|conn| get_backup_settings(conn))
+        .map_err(|e| e.to_string())
+        .and_then(|settings| backup_directory(&app, &settings))
+        .and_then(|dir| list_snapshots(&dir));
+    match result {
+        Ok(snapshots) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("共 {} 份备份", snapshots.len()),
+            "count": snapshots.len(),
+            "data": snapshots
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取备份列表失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 立即备份一次（同时清理过期快照）
+#[command]
+pub async fn create_backup_snapshot_cmd(
+    app: AppHandle,
+    db: State<'_, AppDb>,
+) -> Result<serde_json::Value, String> {
+    match run_backup(&app, &db) {
+        Ok(snapshot) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("已备份到 {}", snapshot.file_name),
+            "data": snapshot,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("备份失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 从本地快照恢复（与导入备份走同一流程，默认替换现有数据；dry_run 为 true 时只预览）
+#[command]
+pub async fn restore_backup_snapshot_cmd(
+    app: AppHandle,
+    db: State<'_, AppDb>,
+    file_name: String,
+    strategy: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let strategy = match strategy {
+        Some(value) => match ImportStrategy::parse(&value) {
+            Some(strategy) => strategy,
+            None => {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "message": format!("不支持的导入策略: {}", value),
+                    "data": null,
+                    "count": 0
+                }))
+            }
+        },
+        None => ImportStrategy::Replace,
+    };
+    let dry_run = dry_run.unwrap_or(false);
+
+    let result = db
+        .with_conn(|conn| get_backup_settings(conn))
+        .map_err(|e| e.to_string())
+        .and_then(|settings| backup_directory(&app, &settings))
+        .and_then(|dir| read_snapshot(&dir, &file_name))
+        .and_then(|data| import_backup_zip(&db, data, strategy, dry_run));
+    match result {
+        Ok(report) => Ok(serde_json::json!({
+            "success": true,
+            "message": if dry_run {
+                format!("已预览备份 {}", file_name)
+            } else {
+                format!("已从备份 {} 恢复", file_name)
+            },
+            "count": report.tables.len(),
+            "data": report
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("恢复备份失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
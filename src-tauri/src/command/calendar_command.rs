@@ -0,0 +1,198 @@
+use crate::calendar::{market_now, normalize_holidays, TradingCalendar};
+use crate::db::app_config_db::{get_holiday_overrides, set_holiday_overrides};
+use crate::db::common::AppDb;
+use crate::structs::calendar_structs::{MarketStatus, TradingCalendarInfo};
+use crate::structs::StockError;
+use chrono::{Datelike, NaiveDate};
+use serde_json;
+use tauri::command;
+use tauri::State;
+
+fn market_status(calendar: &TradingCalendar) -> MarketStatus {
+    let now = market_now();
+    let today = now.date();
+    let session = calendar.session(now);
+    MarketStatus {
+        now: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        date: today.to_string(),
+        is_trading_day: calendar.is_trading_day(today),
+        session: session.as_str().to_string(),
+        session_label: session.label().to_string(),
+        is_open: session.is_open(),
+        previous_trading_day: calendar.previous_trading_day(today).to_string(),
+        next_trading_day: calendar.next_trading_day(today).to_string(),
+        next_open: calendar
+            .next_open(now)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        holiday_data_available: calendar.has_holiday_data(today.year()),
+    }
+}
+
+fn calendar_info(
+    calendar: &TradingCalendar,
+    year: i32,
+    month: Option<u32>,
+) -> Option<TradingCalendarInfo> {
+    let months = match month {
+        Some(month) => month..=month,
+        None => 1..=12,
+    };
+    let mut trading_days = Vec::new();
+    for month in months {
+        trading_days.extend(calendar.trading_days_in_month(year, month)?);
+    }
+    let holidays = calendar
+        .holidays(year)
+        .into_iter()
+        .filter(|date| month.map_or(true, |month| date.month() == month))
+        .map(|date| date.to_string())
+        .collect();
+    Some(TradingCalendarInfo {
+        year,
+        month,
+        trading_days: trading_days.iter().map(|date| date.to_string()).collect(),
+        holidays,
+        holiday_data_available: calendar.has_holiday_data(year),
+        customized: calendar.is_customized(year),
+    })
+}
+
+/// 当前市场状态：是否交易日、所处时段（集合竞价/连续竞价/午间休市等）和下一次开盘时间（北京时间）
+#[command]
+pub fn get_market_status_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| TradingCalendar::load(conn)) {
+        Ok(calendar) => {
+            let status = market_status(&calendar);
+            Ok(serde_json::json!({
+                "success": true,
+                "message": format!("当前{}", status.session_label),
+                "data": status,
+                "count": 1
+            }))
+        }
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取市场状态失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 某年（或某月）的交易日和休市日期
+#[command]
+pub fn get_trading_calendar_cmd(
+    db: State<'_, AppDb>,
+    year: i32,
+    month: Option<u32>,
+) -> Result<serde_json::Value, String> {
+    let info = db.with_conn(|conn| {
+        calendar_info(&TradingCalendar::load(conn)?, year, month).ok_or_else(|| {
+            StockError::BusinessError(format!("无效的月份: {}-{}", year, month.unwrap_or(0)))
+        })
+    });
+    match info {
+        Ok(info) => {
+            let count = info.trading_days.len();
+            Ok(serde_json::json!({
+                "success": true,
+                "message": format!("共 {} 个交易日", count),
+                "data": info,
+                "count": count
+            }))
+        }
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取交易日历失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 统计 (start, end] 之间的交易日数，日期格式 YYYY-MM-DD
+#[command]
+pub fn count_trading_days_cmd(
+    db: State<'_, AppDb>,
+    start: String,
+    end: String,
+) -> Result<serde_json::Value, String> {
+    let parse = |value: &str| NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok();
+    let (Some(start), Some(end)) = (parse(&start), parse(&end)) else {
+        return Ok(serde_json::json!({
+            "success": false,
+            "message": format!("无效的日期: {} - {}", start, end),
+            "data": 0,
+            "count": 0
+        }));
+    };
+    match db.with_conn(|conn| TradingCalendar::load(conn)) {
+        Ok(calendar) => {
+            let days = calendar.trading_days_between(start, end);
+            Ok(serde_json::json!({
+                "success": true,
+                "message": format!("{} 至 {} 共 {} 个交易日", start, end, days),
+                "data": days,
+                "count": days
+            }))
+        }
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("统计交易日失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
+/// 自定义某年的休市日期（工作日休市的日期，周末无需列出）
+/// dates 为空时删除自定义，恢复随应用发布的休市表
+#[command]
+pub fn set_trading_holidays_cmd(
+    db: State<'_, AppDb>,
+    year: i32,
+    dates: Option<Vec<String>>,
+) -> Result<serde_json::Value, String> {
+    let dates = match dates
+        .map(|dates| normalize_holidays(year, &dates))
+        .transpose()
+    {
+        Ok(dates) => dates,
+        Err(e) => {
+            return Ok(serde_json::json!({
+                "success": false,
+                "message": e,
+                "data": null,
+                "count": 0
+            }))
+        }
+    };
+    let result = db.with_conn(|conn| {
+        let mut overrides = get_holiday_overrides(conn)?;
+        match &dates {
+            Some(dates) => overrides.insert(year, dates.clone()),
+            None => overrides.remove(&year),
+        };
+        set_holiday_overrides(conn, &overrides)?;
+        let calendar = TradingCalendar::load(conn)?;
+        Ok(calendar_info(&calendar, year, None))
+    });
+    match result {
+        Ok(info) => Ok(serde_json::json!({
+            "success": true,
+            "message": match dates {
+                Some(dates) => format!("已设置 {} 年的 {} 个休市日", year, dates.len()),
+                None => format!("已恢复 {} 年的内置休市安排", year),
+            },
+            "data": info,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("保存休市日期失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
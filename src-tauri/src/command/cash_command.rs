@@ -0,0 +1,153 @@
+use crate::command::xueqiu_command::provider_chain;
+use crate::db::app_config_db::is_cash_strict_mode;
+use crate::db::cash_db::{
+    add_cash_entry, delete_cash_entry, query_cash_balance, query_cash_flows, summarize_account,
+};
+use crate::db::common::AppDb;
+use crate::db::holdings_db::query_holdings;
+use crate::db::market_snapshot_db::{load_snapshot, SNAPSHOT_QUOTE};
+use crate::structs::cash_structs::AddCashEntryParams;
+use crate::structs::xueqiu_structs::StockQuote;
+use serde_json;
+use std::collections::HashMap;
+use tauri::command;
+use tauri::State;
+use tauri_plugin_log::log;
+
+/// 添加资金流水（转入/转出/分红/费用）
+#[command]
+pub fn add_cash_entry_cmd(
+    db: State<'_, AppDb>,
+    params: AddCashEntryParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| add_cash_entry(conn, &params)) {
+        Ok(id) => Ok(serde_json::json!({
+            "success": true,
+            "message": "资金流水已记录",
+            "data": id,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("记录资金流水失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 删除手动记录的资金流水
+#[command]
+pub fn delete_cash_entry_cmd(db: State<'_, AppDb>, id: i32) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| delete_cash_entry(conn, id)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": "资金流水已删除",
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("未找到ID为 {} 的资金流水", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("删除资金流水失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询资金流水（含委托产生的买卖流水），account_id 为空时汇总全部账户
+#[command]
+pub fn get_cash_flows_cmd(
+    db: State<'_, AppDb>,
+    account_id: Option<i32>,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_cash_flows(conn, account_id)) {
+        Ok(flows) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 条资金流水", flows.len()),
+            "count": flows.len(),
+            "data": flows
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取资金流水失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+// 获取持仓的最新价：优先在线行情，失败时使用最后一次报价快照
+async fn latest_prices(db: &AppDb, symbols: &[String]) -> HashMap<String, f64> {
+    let mut prices = HashMap::new();
+    if symbols.is_empty() {
+        return prices;
+    }
+
+    match provider_chain(db) {
+        Ok(chain) => match chain.fetch_batch_quote(symbols).await {
+            Ok((quotes, _)) => {
+                for quote in quotes {
+                    prices.insert(quote.symbol.to_uppercase(), quote.current);
+                }
+            }
+            Err(e) => log::warn!("获取持仓报价失败: {}", e),
+        },
+        Err(e) => log::info!("账户总览使用本地报价: {}", e),
+    }
+
+    for symbol in symbols {
+        if prices.contains_key(symbol) {
+            continue;
+        }
+        if let Ok(Some((quote, _))) =
+            db.with_conn(|conn| load_snapshot::<StockQuote>(conn, SNAPSHOT_QUOTE, symbol))
+        {
+            prices.insert(symbol.clone(), quote.current);
+        }
+    }
+    prices
+}
+
+/// 账户总览：现金余额、持仓市值（按最新报价）和总资产，account_id 为空时汇总全部账户
+#[command]
+pub async fn get_account_summary_cmd(
+    db: State<'_, AppDb>,
+    account_id: Option<i32>,
+) -> Result<serde_json::Value, String> {
+    let loaded = db.with_conn(|conn| {
+        Ok((
+            query_cash_balance(conn, account_id)?,
+            query_holdings(conn, account_id)?,
+            is_cash_strict_mode(conn)?,
+        ))
+    });
+    let (cash, holdings, strict_mode) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            return Ok(serde_json::json!({
+                "success": false,
+                "message": format!("获取账户总览失败: {}", e),
+                "data": null,
+                "count": 0
+            }));
+        }
+    };
+
+    let symbols: Vec<String> = holdings.iter().map(|h| h.code.to_uppercase()).collect();
+    let prices = latest_prices(&db, &symbols).await;
+    let summary = summarize_account(cash, &holdings, &prices, strict_mode);
+    let count = summary.positions.len();
+    Ok(serde_json::json!({
+        "success": true,
+        "message": format!("总资产 {:.2} 元", summary.total_equity),
+        "data": summary,
+        "count": count
+    }))
+}
@@ -1,21 +1,7 @@
-use crate::db::init_db::{
-    init_all_stocks_database, init_app_config_database, init_holdings_database,
-    init_market_analysis_database, init_my_selection_database, init_orders_database,
-    init_self_reflect_database, init_stock_review_database, init_trend_lines_database,
-};
+use crate::db::common::AppDb;
+use crate::db::init_db::init_app_database;
 use tauri::AppHandle;
-// Tauri 命令 - 初始化所有数据库
-#[tauri::command]
-pub fn init_all_databases(app: &AppHandle) -> Result<(), String> {
-    init_app_config_database(&app)?;
-    init_all_stocks_database(&app)?;
-    init_my_selection_database(&app)?;
-    init_stock_review_database(&app)?;
-    init_market_analysis_database(&app)?;
-    init_self_reflect_database(app)?;
-    init_holdings_database(app)?;
-    init_orders_database(app)?;
-    init_trend_lines_database(app)?;
-    // 可以添加更多数据库的初始化...
-    Ok(())
+// 初始化应用数据库（迁移所有表结构，合并旧版数据库文件）
+pub fn init_all_databases(app: &AppHandle) -> Result<AppDb, String> {
+    init_app_database(app)
 }
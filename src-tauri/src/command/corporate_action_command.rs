@@ -0,0 +1,166 @@
+use crate::command::xueqiu_command::provider_chain;
+use crate::db::common::AppDb;
+use crate::db::corporate_action_db::query_corporate_actions;
+use crate::db::holdings_db::query_holding_dividends;
+use crate::db::position_db::{
+    import_corporate_actions, record_corporate_action, remove_corporate_action,
+};
+use crate::structs::corporate_action_structs::{
+    AddCorporateActionParams, CorporateAction, MANUAL_SOURCE,
+};
+use serde_json;
+use tauri::command;
+use tauri::State;
+
+/// 查询公司行动（除权除息）记录，可按股票代码过滤
+#[command]
+pub fn get_corporate_actions_cmd(
+    db: State<'_, AppDb>,
+    code: Option<String>,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_corporate_actions(conn, code.as_deref())) {
+        Ok(actions) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 条除权除息记录", actions.len()),
+            "count": actions.len(),
+            "data": actions
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取除权除息记录失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 手动录入公司行动，并按除权结果调整相关持仓
+#[command]
+pub fn add_corporate_action_cmd(
+    db: State<'_, AppDb>,
+    params: AddCorporateActionParams,
+) -> Result<serde_json::Value, String> {
+    let action = CorporateAction {
+        id: 0,
+        code: params.code.trim().to_uppercase(),
+        ex_date: params.ex_date.trim().to_string(),
+        cash_dividend: params.cash_dividend,
+        bonus_shares: params.bonus_shares,
+        transfer_shares: params.transfer_shares,
+        split_ratio: params.split_ratio,
+        source: MANUAL_SOURCE.to_string(),
+        note: params.note.unwrap_or_default(),
+    };
+
+    match db.with_conn(|conn| record_corporate_action(conn, &action)) {
+        Ok(id) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功录入 {} 在 {} 的除权除息", action.code, action.ex_date),
+            "data": id,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("录入除权除息失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
+/// 删除公司行动，并重新生成相关持仓
+#[command]
+pub fn delete_corporate_action_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| remove_corporate_action(conn, id)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": "除权除息记录已删除",
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("未找到ID为 {} 的除权除息记录", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("删除除权除息记录失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 从行情数据源获取分红送转记录并保存（已有同日记录的跳过）
+#[command]
+pub async fn fetch_corporate_actions_cmd(
+    db: State<'_, AppDb>,
+    code: String,
+) -> Result<serde_json::Value, String> {
+    let code = code.trim().to_uppercase();
+    let fetched = match provider_chain(&db) {
+        Ok(chain) => chain
+            .fetch_corporate_actions(&code)
+            .await
+            .map_err(String::from),
+        Err(e) => Err(e),
+    };
+    let (actions, provider) = match fetched {
+        Ok(result) => result,
+        Err(e) => {
+            return Ok(serde_json::json!({
+                "success": false,
+                "message": format!("获取分红送转失败: {}", e),
+                "data": 0,
+                "count": 0
+            }))
+        }
+    };
+
+    match db.with_conn(|conn| import_corporate_actions(conn, &code, &actions)) {
+        Ok(added) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!(
+                "从 {} 获取 {} 条分红送转，新增 {} 条",
+                provider,
+                actions.len(),
+                added
+            ),
+            "data": added,
+            "count": added
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("保存分红送转失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
+/// 查询持仓的现金分红记录
+#[command]
+pub fn get_holding_dividends_cmd(
+    db: State<'_, AppDb>,
+    holding_id: i32,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_holding_dividends(conn, holding_id)) {
+        Ok(dividends) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 条分红记录", dividends.len()),
+            "count": dividends.len(),
+            "data": dividends
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取分红记录失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
@@ -0,0 +1,120 @@
+use crate::db::common::AppDb;
+use crate::db::drawings_db::{add_drawing, delete_drawing, query_drawings, update_drawing};
+use crate::structs::drawings_structs::{DrawingKind, DrawingParams};
+use serde_json;
+use tauri::command;
+use tauri::State;
+
+/// 查询某只股票某个周期的画线，可按类型过滤（如 "horizontal_line"）
+#[command]
+pub fn get_drawings_cmd(
+    db: State<'_, AppDb>,
+    code: String,
+    period: String,
+    kind: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let kind = match kind.as_deref() {
+        Some(value) => match DrawingKind::parse(value) {
+            Some(kind) => Some(kind),
+            None => {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "message": format!("不支持的画线类型: {}", value),
+                    "data": [],
+                    "count": 0
+                }))
+            }
+        },
+        None => None,
+    };
+    match db.with_conn(|conn| query_drawings(conn, &code, Some(&period), kind)) {
+        Ok(drawings) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 个画线", drawings.len()),
+            "count": drawings.len(),
+            "data": drawings
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取画线失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 新增画线，返回画线ID
+#[command]
+pub fn add_drawing_cmd(
+    db: State<'_, AppDb>,
+    params: DrawingParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| add_drawing(conn, &params)) {
+        Ok(id) => Ok(serde_json::json!({
+            "success": true,
+            "message": "画线已添加",
+            "data": id,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("添加画线失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
+/// 修改画线的类型、锚点、样式和标注
+#[command]
+pub fn update_drawing_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+    params: DrawingParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| update_drawing(conn, id, &params)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": "画线已修改",
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("画线不存在: {}", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("修改画线失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 删除画线
+#[command]
+pub fn delete_drawing_cmd(db: State<'_, AppDb>, id: i32) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| delete_drawing(conn, id)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": "画线已删除",
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("画线不存在: {}", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("删除画线失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
@@ -1,20 +1,23 @@
+use crate::db::common::AppDb;
 use crate::db::holdings_db::{
-    add_holding, delete_holding, query_history_holdings, query_holdings,
-    query_latest_holding_by_code, query_monthly_stats, update_holding,
-};
-use crate::structs::holdings_structs::{
-    AddHoldingReq, DeleteHoldingReq, MonthlyStatsParams, QueryHistoryParams, UpdateHoldingReq,
+    add_holding, query_history_holdings, query_holding_lots, query_holdings,
+    query_latest_holding_by_code, query_monthly_stats,
 };
+use crate::db::position_db::rebuild_holdings;
+use crate::structs::holdings_structs::{AddHoldingReq, MonthlyStatsParams, QueryHistoryParams};
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
 
 // --------------------------
-// 1. 获取所有当前持仓 Command
+// 1. 获取所有当前持仓 Command（account_id 为空时汇总全部账户）
 // --------------------------
 #[command]
-pub fn get_all_holdings_cmd(app: AppHandle) -> Result<serde_json::Value, String> {
-    match query_holdings(&app) {
+pub fn get_all_holdings_cmd(
+    db: State<'_, AppDb>,
+    account_id: Option<i32>,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_holdings(conn, account_id)) {
         Ok(holdings) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功获取 {} 条持仓数据", holdings.len()),
@@ -37,10 +40,12 @@ pub fn get_all_holdings_cmd(app: AppHandle) -> Result<serde_json::Value, String>
 // --------------------------
 #[command]
 pub fn get_history_holdings_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     params: QueryHistoryParams,
 ) -> Result<serde_json::Value, String> {
-    match query_history_holdings(&app, params.page, params.page_size) {
+    match db.with_conn(|conn| {
+        query_history_holdings(conn, params.page, params.page_size, params.account_id)
+    }) {
         Ok(paged_result) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功获取历史持仓数据，第 {} 页，共 {} 条", params.page, paged_result.total),
@@ -58,10 +63,12 @@ pub fn get_history_holdings_cmd(
 
 #[tauri::command]
 pub fn get_monthly_stats_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     params: MonthlyStatsParams,
 ) -> Result<serde_json::Value, String> {
-    match query_monthly_stats(&app, params.year, params.month) {
+    match db
+        .with_conn(|conn| query_monthly_stats(conn, params.year, params.month, params.account_id))
+    {
         Ok(stats) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功获取 {} 年 {} 月交易统计", params.year, params.month),
@@ -77,10 +84,14 @@ pub fn get_monthly_stats_cmd(
 
 // --------------------------
 // 3. 添加持仓 Command
+// 持仓由委托推导，手动添加会记录一笔同等的买入委托
 // --------------------------
 #[command]
-pub fn add_holding_cmd(app: AppHandle, params: AddHoldingReq) -> Result<serde_json::Value, String> {
-    match add_holding(&app, &params) {
+pub fn add_holding_cmd(
+    db: State<'_, AppDb>,
+    params: AddHoldingReq,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| add_holding(conn, &params)) {
         Ok(id) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功添加持仓，持仓ID: {}", id),
@@ -96,45 +107,15 @@ pub fn add_holding_cmd(app: AppHandle, params: AddHoldingReq) -> Result<serde_js
     }
 }
 
-// --------------------------
-// 4. 更新持仓 Command
-// --------------------------
-#[command]
-pub fn update_holding_cmd(
-    app: AppHandle,
-    params: UpdateHoldingReq,
-) -> Result<serde_json::Value, String> {
-    match update_holding(&app, &params) {
-        Ok(true) => Ok(serde_json::json!({
-            "success": true,
-            "message": format!("成功更新持仓ID「{}」的数据", params.id),
-            "data": true,
-            "count": 1
-        })),
-        Ok(false) => Ok(serde_json::json!({
-            "success": false,
-            "message": format!("未找到持仓ID「{}」的数据，更新失败", params.id),
-            "data": false,
-            "count": 0
-        })),
-        Err(e) => Ok(serde_json::json!({
-            "success": false,
-            "message": format!("更新持仓失败: {}", e),
-            "data": false,
-            "count": 0
-        })),
-    }
-}
-
 // --------------------------
 // 7. 根据股票代码获取最近持仓数据 Command
 // --------------------------
 #[command]
 pub fn get_latest_holding_by_code_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     code: String,
 ) -> Result<serde_json::Value, String> {
-    match query_latest_holding_by_code(&app, &code) {
+    match db.with_conn(|conn| query_latest_holding_by_code(conn, &code)) {
         Ok(Some(holding)) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功获取股票 {} 的最近持仓数据", code),
@@ -157,29 +138,45 @@ pub fn get_latest_holding_by_code_cmd(
 }
 
 // --------------------------
-// 7. 删除持仓 Command
+// 8. 按委托记录重建持仓 Command
 // --------------------------
 #[command]
-pub fn delete_holding_cmd(app: AppHandle, id: i32) -> Result<serde_json::Value, String> {
-    let req = DeleteHoldingReq { id };
-
-    match delete_holding(&app, &req) {
-        Ok(true) => Ok(serde_json::json!({
+pub fn rebuild_holdings_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| rebuild_holdings(conn)) {
+        Ok(count) => Ok(serde_json::json!({
             "success": true,
-            "message": format!("成功删除持仓ID「{}」", id),
-            "data": true,
-            "count": 1
+            "message": format!("已按委托记录重建 {} 条持仓记录", count),
+            "data": count,
+            "count": count
         })),
-        Ok(false) => Ok(serde_json::json!({
+        Err(e) => Ok(serde_json::json!({
             "success": false,
-            "message": format!("未找到持仓ID「{}」，删除失败", id),
-            "data": false,
+            "message": format!("重建持仓失败: {}", e),
+            "data": 0,
             "count": 0
         })),
+    }
+}
+
+// --------------------------
+// 9. 查询持仓买入批次 Command
+// --------------------------
+#[command]
+pub fn get_holding_lots_cmd(
+    db: State<'_, AppDb>,
+    holding_id: i32,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_holding_lots(conn, holding_id)) {
+        Ok(lots) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 个持仓批次", lots.len()),
+            "data": lots,
+            "count": lots.len()
+        })),
         Err(e) => Ok(serde_json::json!({
             "success": false,
-            "message": format!("删除持仓失败: {}", e),
-            "data": false,
+            "message": format!("获取持仓批次失败: {}", e),
+            "data": [],
             "count": 0
         })),
     }
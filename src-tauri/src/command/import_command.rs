@@ -1,12 +1,54 @@
-use crate::db::import_db::import_single_database;
+use crate::db::backup_db::{export_database_zip, validate_backup_archive};
+use crate::db::common::{AppDb, APP_DB_NAME};
+use crate::db::import_db::{backup_database_names, import_databases, BackupSource};
+use crate::structs::import_structs::{ImportReport, ImportStrategy};
 use rusqlite::Result;
 use std::fs::File;
 use std::io::Read;
-use tauri::AppHandle;
+use tauri::State;
+use tauri_plugin_log::log;
 use zip::ZipArchive;
+
+/// 导出全部数据为 ZIP（数据库快照 + manifest.json），可直接用 import_database 导入
+#[tauri::command]
+pub async fn export_database(db: State<'_, AppDb>) -> Result<Vec<u8>, String> {
+    let (zip_data, manifest) = db
+        .with_conn(|conn| Ok(export_database_zip(conn)))
+        .map_err(|e| e.to_string())??;
+    log::info!(
+        "导出数据库完成: {} 张表, {} 字节",
+        manifest.row_counts.len(),
+        zip_data.len()
+    );
+    Ok(zip_data)
+}
+
 // 添加数据库导入功能
+// strategy 为合并策略（默认 keep_local），dry_run 为 true 时只返回各表将要发生的变更，不写入数据库
 #[tauri::command]
-pub async fn import_database(app: AppHandle, zip_data: Vec<u8>) -> Result<String, String> {
+pub async fn import_database(
+    db: State<'_, AppDb>,
+    zip_data: Vec<u8>,
+    strategy: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<ImportReport, String> {
+    let strategy = match strategy {
+        Some(value) => {
+            ImportStrategy::parse(&value).ok_or_else(|| format!("不支持的导入策略: {}", value))?
+        }
+        None => ImportStrategy::default(),
+    };
+    import_backup_zip(&db, zip_data, strategy, dry_run.unwrap_or(false))
+}
+
+/// 导入备份压缩包（手动导入和从本地快照恢复共用）
+/// 备份中有当前版本无法导入的数据时整体拒绝，本地数据保持不变
+pub fn import_backup_zip(
+    db: &AppDb,
+    zip_data: Vec<u8>,
+    strategy: ImportStrategy,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
     // 创建临时目录
     let temp_dir = tempfile::tempdir().map_err(|e| format!("创建临时目录失败: {}", e))?;
     let zip_path = temp_dir.path().join("backup.zip");
@@ -18,31 +60,40 @@ pub async fn import_database(app: AppHandle, zip_data: Vec<u8>) -> Result<String
     let file = File::open(&zip_path).map_err(|e| format!("打开压缩包失败: {}", e))?;
     let mut archive = ZipArchive::new(file).map_err(|e| format!("读取压缩包失败: {}", e))?;
 
-    // 打印压缩包中的所有文件，用于调试
-    println!("压缩包中的文件列表 (总数: {}):", archive.len());
-    for i in 0..archive.len() {
-        let file = archive.by_index(i).unwrap();
-        println!("- {} (大小: {} 字节)", file.name(), file.size());
-    }
+    // 有清单的备份先整体校验，校验不通过时不导入任何数据
+    let manifest = validate_backup_archive(&mut archive)?;
 
-    // 定义需要导入的数据库文件列表
-    let db_files = [
-        "app_config",
-        "all_stocks",
-        "my_selection",
-        "stock_review",
-        "self_reflect",
-        "market_analysis",
-        "stock_lines",
-        "holdings",
-        "orders",
-    ];
+    // 需要导入的数据库（与初始化共用同一份注册表）
+    let db_files = backup_database_names();
 
-    let mut imported_count = 0;
-    let mut found_files = Vec::new();
+    // 先收集压缩包中的数据库文件，再在同一个事务中统一导入
+    let mut sources: Vec<BackupSource> = Vec::new();
+
+    // 新版备份：单个 app.db 中包含所有表
+    let app_db_paths = [
+        format!("databases/{}.db", APP_DB_NAME),
+        format!("{}.db", APP_DB_NAME),
+    ];
+    for path in &app_db_paths {
+        if let Ok(mut file) = archive.by_name(path) {
+            let mut db_data = Vec::new();
+            file.read_to_end(&mut db_data)
+                .map_err(|e| format!("读取数据库文件 {} 失败: {}", path, e))?;
+            sources.push(BackupSource {
+                path: path.clone(),
+                db_names: db_files.iter().map(|name| name.to_string()).collect(),
+                data: db_data,
+            });
+            break;
+        }
+    }
 
-    // 方法1: 尝试精确匹配文件名
+    // 方法1: 尝试精确匹配文件名（旧版备份：每个功能一个 .db 文件）
+    let has_app_db = !sources.is_empty();
     for db_name in db_files.iter() {
+        if has_app_db {
+            break;
+        }
         // 尝试多种可能的文件路径格式
         let possible_paths = [
             format!("databases/{}.db", db_name), // 子目录中的文件
@@ -51,8 +102,6 @@ pub async fn import_database(app: AppHandle, zip_data: Vec<u8>) -> Result<String
             format!("{}", db_name),              // 根目录中无扩展名
         ];
 
-        let mut found = false;
-
         for path in &possible_paths {
             match archive.by_name(path) {
                 Ok(mut file) => {
@@ -61,24 +110,16 @@ pub async fn import_database(app: AppHandle, zip_data: Vec<u8>) -> Result<String
                         continue;
                     }
 
-                    println!("找到数据库文件: {}", path);
-                    found_files.push(path.clone());
-
                     // 读取数据库文件内容
                     let mut db_data = Vec::new();
                     file.read_to_end(&mut db_data)
                         .map_err(|e| format!("读取数据库文件 {} 失败: {}", path, e))?;
 
-                    // 导入数据到当前数据库
-                    if let Err(e) = import_single_database(&app, db_name, &db_data) {
-                        eprintln!("导入数据库 {} 失败: {}", db_name, e);
-                        // 继续导入其他数据库，不中断整个流程
-                        break;
-                    }
-
-                    imported_count += 1;
-                    println!("成功导入数据库: {}", db_name);
-                    found = true;
+                    sources.push(BackupSource {
+                        path: path.clone(),
+                        db_names: vec![db_name.to_string()],
+                        data: db_data,
+                    });
                     break;
                 }
                 Err(_) => {
@@ -87,16 +128,10 @@ pub async fn import_database(app: AppHandle, zip_data: Vec<u8>) -> Result<String
                 }
             }
         }
-
-        if !found {
-            println!("未找到数据库: {}", db_name);
-        }
     }
 
     // 如果上述方法没有找到文件，尝试遍历所有文件并匹配
-    if imported_count == 0 {
-        println!("尝试遍历所有文件查找数据库...");
-
+    if sources.is_empty() {
         // 首先收集所有匹配的文件索引和对应的数据库名称
         let mut matches = Vec::new();
         for i in 0..archive.len() {
@@ -113,14 +148,13 @@ pub async fn import_database(app: AppHandle, zip_data: Vec<u8>) -> Result<String
                 if file_path.contains(db_name)
                     && (file_path.ends_with(".db") || !file_path.contains('.'))
                 {
-                    println!("匹配到数据库文件: {} -> {}", file_path, db_name);
                     matches.push((i, db_name.to_string(), file_path.clone()));
                     break;
                 }
             }
         }
 
-        // 然后处理所有匹配的文件
+        // 然后读取所有匹配的文件
         for (i, db_name, file_path) in matches {
             // 重新获取文件引用
             let mut file = archive
@@ -132,32 +166,38 @@ pub async fn import_database(app: AppHandle, zip_data: Vec<u8>) -> Result<String
             file.read_to_end(&mut db_data)
                 .map_err(|e| format!("读取数据库文件 {} 失败: {}", file_path, e))?;
 
-            // 导入数据到当前数据库
-            if let Err(e) = import_single_database(&app, &db_name, &db_data) {
-                eprintln!("导入数据库 {} 失败: {}", db_name, e);
-                continue;
-            }
-
-            imported_count += 1;
-            found_files.push(file_path);
-            println!("成功导入数据库: {}", db_name);
+            sources.push(BackupSource {
+                path: file_path,
+                db_names: vec![db_name],
+                data: db_data,
+            });
         }
     }
 
-    // 打印找到的文件列表
-    println!("成功找到的文件: {:?}", found_files);
-
-    if imported_count == 0 {
+    if sources.is_empty() {
         return Err(
             "在压缩包中未找到任何数据库文件。请确保压缩包包含正确的数据库文件。".to_string(),
         );
     }
 
-    Ok(format!(
-        "成功导入 {}/{} 个数据库",
-        imported_count,
-        db_files.len()
-    ))
+    // 所有数据库在同一个事务中导入，失败时本地数据保持不变
+    let tables = db
+        .with_conn(|conn| Ok(import_databases(conn, &sources, strategy, dry_run)))
+        .map_err(|e| e.to_string())??;
+
+    // 备份中没有对应表的数据库
+    let missing = db_files
+        .iter()
+        .filter(|name| !tables.iter().any(|stats| stats.database == **name))
+        .map(|name| name.to_string())
+        .collect();
+
+    Ok(ImportReport {
+        strategy: strategy.as_str().to_string(),
+        dry_run,
+        manifest,
+        files: sources.into_iter().map(|source| source.path).collect(),
+        missing,
+        tables,
+    })
 }
-
-// ... 其他函数保持不变 ...
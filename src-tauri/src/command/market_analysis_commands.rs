@@ -1,16 +1,17 @@
+use crate::db::common::AppDb;
 use crate::db::market_analysis_db::{add_or_update_market_analysis, query_last_10_market_analysis};
 use crate::structs::market_analysis_structs::AddMarketAnalysisReq;
 use chrono::NaiveDate;
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
 
 // --------------------------
 // 1. 新增/更新市场分析 Command（适配 status 字段 + 显式判断日期）
 // --------------------------
 #[command]
 pub fn add_market_analysis_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: AddMarketAnalysisReq,
 ) -> Result<serde_json::Value, String> {
     // 1. 参数校验：必填字段非空（新增 status 字段校验）
@@ -44,7 +45,7 @@ pub fn add_market_analysis_cmd(
     }
 
     // 4. 调用数据库方法（显式判断日期存在性，存在则更新，不存在则新增）
-    match add_or_update_market_analysis(&app, &req) {
+    match db.with_conn(|conn| add_or_update_market_analysis(conn, &req)) {
         Ok((is_update, updated_analysis)) => {
             let operation = if is_update { "更新" } else { "新增" };
             Ok(serde_json::json!({
@@ -67,8 +68,8 @@ pub fn add_market_analysis_cmd(
 // 2. 查询最后10条市场分析 Command（替换原查询最后1条逻辑）
 // --------------------------
 #[command]
-pub fn query_market_analysis_cmd(app: AppHandle) -> Result<serde_json::Value, String> {
-    match query_last_10_market_analysis(&app) {
+pub fn query_market_analysis_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_last_10_market_analysis(conn)) {
         // 修复点1：匹配 Option<Vec> 的 Some 分支，提取内部的 Vec
         Ok(Some(analysis_list)) => {
             let count = analysis_list.len(); // 正确：对 Vec 调用 len()
@@ -1,11 +1,21 @@
+pub mod account_command;
+pub mod alert_command;
+pub mod analytics_command;
 pub mod app_config_command;
+pub mod backup_command;
+pub mod calendar_command;
+pub mod cash_command;
 pub mod common;
+pub mod corporate_action_command;
+pub mod drawings_cmd;
 pub mod holdings_command;
 pub mod import_command;
 pub mod market_analysis_commands;
 pub mod orders_command;
+pub mod secret_command;
 pub mod selection_command;
 pub mod self_reflect_command;
+pub mod statement_command;
 pub mod stock_command;
 pub mod stock_review_command;
 pub mod trend_lines_cmd;
@@ -1,15 +1,17 @@
+use crate::db::common::AppDb;
 use crate::db::orders_db::{add_order, delete_order, query_orders, query_orders_by_code};
+use crate::db::position_db::{record_order, remove_order};
 use crate::structs::orders_structs::{AddOrderParams, Order, QueryOrdersParams};
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
 
 // --------------------------
 // 1. 获取所有委托 Command
 // --------------------------
 #[command]
 pub fn get_all_orders_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     params: QueryOrdersParams,
 ) -> Result<serde_json::Value, String> {
     // 参数验证
@@ -31,7 +33,7 @@ pub fn get_all_orders_cmd(
         }));
     }
 
-    match query_orders(&app, &params) {
+    match db.with_conn(|conn| query_orders(conn, &params)) {
         Ok(paginated_data) => Ok(serde_json::json!({
             "success": true,
             "message": format!(
@@ -56,8 +58,11 @@ pub fn get_all_orders_cmd(
 // 2. 添加委托 Command
 // --------------------------
 #[command]
-pub fn add_order_cmd(app: AppHandle, params: AddOrderParams) -> Result<serde_json::Value, String> {
-    match add_order(&app, &params) {
+pub fn add_order_cmd(
+    db: State<'_, AppDb>,
+    params: AddOrderParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| add_order(conn, &params)) {
         Ok(id) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功添加委托，委托ID: {}", id),
@@ -73,9 +78,31 @@ pub fn add_order_cmd(app: AppHandle, params: AddOrderParams) -> Result<serde_jso
     }
 }
 
+/// 添加委托并同步更新持仓 Command（委托与持仓在同一事务内写入）
+#[command]
+pub fn add_order_with_holding_cmd(
+    db: State<'_, AppDb>,
+    params: AddOrderParams,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| record_order(conn, &params)) {
+        Ok(id) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功添加委托并更新持仓，委托ID: {}", id),
+            "data": id,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("添加委托失败: {}", e),
+            "data": 0,
+            "count": 0
+        })),
+    }
+}
+
 /// 删除委托 Command
 #[command]
-pub fn delete_order_cmd(app: AppHandle, id: i32) -> Result<serde_json::Value, String> {
+pub fn delete_order_cmd(db: State<'_, AppDb>, id: i32) -> Result<serde_json::Value, String> {
     // 参数验证
     if id <= 0 {
         return Ok(serde_json::json!({
@@ -86,7 +113,7 @@ pub fn delete_order_cmd(app: AppHandle, id: i32) -> Result<serde_json::Value, St
         }));
     }
 
-    match delete_order(&app, id) {
+    match db.with_conn(|conn| delete_order(conn, id)) {
         Ok(()) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功删除委托，委托ID: {}", id),
@@ -102,12 +129,53 @@ pub fn delete_order_cmd(app: AppHandle, id: i32) -> Result<serde_json::Value, St
     }
 }
 
+/// 删除委托并回退对应持仓 Command
+#[command]
+pub fn delete_order_with_holding_cmd(
+    db: State<'_, AppDb>,
+    id: i32,
+) -> Result<serde_json::Value, String> {
+    if id <= 0 {
+        return Ok(serde_json::json!({
+            "success": false,
+            "message": "委托ID必须大于0",
+            "data": false,
+            "count": 0
+        }));
+    }
+
+    match db.with_conn(|conn| remove_order(conn, id)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功删除委托并更新持仓，委托ID: {}", id),
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("委托不存在，委托ID: {}", id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("删除委托失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
 // --------------------------
-// 根据股票代码获取所有委托记录 Command
+// 根据股票代码获取所有委托记录 Command（account_id 为空时汇总全部账户）
 // --------------------------
 #[command]
-pub fn get_orders_by_code_cmd(app: AppHandle, code: String) -> Result<serde_json::Value, String> {
-    match query_orders_by_code(&app, &code) {
+pub fn get_orders_by_code_cmd(
+    db: State<'_, AppDb>,
+    code: String,
+    account_id: Option<i32>,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| query_orders_by_code(conn, &code, account_id)) {
         Ok(orders) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功获取股票 {} 的 {} 条委托记录", code, orders.len()),
@@ -0,0 +1,96 @@
+use crate::db::common::AppDb;
+use crate::secrets;
+use serde_json;
+use tauri::command;
+use tauri::{AppHandle, State};
+
+/// 查询敏感配置的加密状态（密钥来源、是否已解锁）
+#[command]
+pub fn get_secret_status_cmd(
+    app: AppHandle,
+    db: State<'_, AppDb>,
+) -> Result<serde_json::Value, String> {
+    let result = db
+        .with_conn(|conn| Ok(secrets::secret_status(&app, conn)))
+        .map_err(|e| e.to_string())
+        .and_then(|status| status);
+    match result {
+        Ok(status) => Ok(serde_json::json!({
+            "success": true,
+            "message": "获取加密状态成功",
+            "data": status,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取加密状态失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 输入密码解锁敏感配置（密码模式下每次启动需要解锁一次）
+#[command]
+pub fn unlock_secrets_cmd(
+    db: State<'_, AppDb>,
+    passphrase: String,
+) -> Result<serde_json::Value, String> {
+    let result = db
+        .with_conn(|conn| Ok(secrets::unlock(conn, &passphrase)))
+        .map_err(|e| e.to_string())
+        .and_then(|unlocked| unlocked);
+    match result {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": "敏感配置已解锁",
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("解锁失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 设置密码（改用密码派生密钥），传入空值时改回本机密钥文件
+/// 已保存的敏感配置会用新密钥重新加密
+#[command]
+pub fn set_secret_passphrase_cmd(
+    app: AppHandle,
+    db: State<'_, AppDb>,
+    passphrase: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let passphrase = passphrase.filter(|value| !value.is_empty());
+    let result = db
+        .with_conn(|conn| {
+            Ok(secrets::change_key_source(
+                &app,
+                conn,
+                passphrase.as_deref(),
+            ))
+        })
+        .map_err(|e| e.to_string())
+        .and_then(|changed| changed);
+    match result {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": if passphrase.is_some() {
+                "已设置密码，之后每次启动需要输入密码解锁"
+            } else {
+                "已改用本机密钥文件"
+            },
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("更换密钥失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
@@ -1,3 +1,4 @@
+use crate::db::common::AppDb;
 use crate::db::selection_db::{
     add_or_update_selection, delete_selection, get_all_selections, get_selection_by_code,
     is_selection_exists, update_selection_sort,
@@ -5,14 +6,14 @@ use crate::db::selection_db::{
 use crate::structs::selection_structs::{Selection, UpdateSelectionSortParams};
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
 
 // --------------------------
 // 1. 获取所有自选股 Command
 // --------------------------
 #[command]
-pub fn get_all_selections_cmd(app: AppHandle) -> Result<serde_json::Value, String> {
-    match get_all_selections(&app) {
+pub fn get_all_selections_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| get_all_selections(conn)) {
         Ok(selections) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功获取 {} 条自选股数据", selections.len()),
@@ -33,10 +34,10 @@ pub fn get_all_selections_cmd(app: AppHandle) -> Result<serde_json::Value, Strin
 // --------------------------
 #[command]
 pub fn get_selection_by_code_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     code: String,
 ) -> Result<serde_json::Value, String> {
-    match get_selection_by_code(&app, &code) {
+    match db.with_conn(|conn| get_selection_by_code(conn, &code)) {
         Ok(Some(selection)) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功获取代码「{}」的自选股数据", code),
@@ -62,8 +63,11 @@ pub fn get_selection_by_code_cmd(
 // 3. 检查自选股是否存在 Command
 // --------------------------
 #[command]
-pub fn is_selection_exists_cmd(app: AppHandle, code: String) -> Result<serde_json::Value, String> {
-    match is_selection_exists(&app, &code) {
+pub fn is_selection_exists_cmd(
+    db: State<'_, AppDb>,
+    code: String,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| is_selection_exists(conn, &code)) {
         Ok(true) => Ok(serde_json::json!({
             "success": true,
             "message": format!("代码「{}」已在自选股中", code),
@@ -90,13 +94,15 @@ pub fn is_selection_exists_cmd(app: AppHandle, code: String) -> Result<serde_jso
 // --------------------------
 #[command]
 pub fn add_or_update_selection_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     selection: Selection,
 ) -> Result<serde_json::Value, String> {
-    match add_or_update_selection(&app, &selection) {
+    match db.with_conn(|conn| add_or_update_selection(conn, &selection)) {
         Ok(_) => {
             // 额外判断是“新增”还是“更新”（提升用户体验）
-            let is_new = !is_selection_exists(&app, &selection.code).unwrap_or(false);
+            let is_new = !db
+                .with_conn(|conn| is_selection_exists(conn, &selection.code))
+                .unwrap_or(false);
             Ok(serde_json::json!({
                 "success": true,
                 "message": if is_new {
@@ -122,7 +128,7 @@ pub fn add_or_update_selection_cmd(
 // --------------------------
 #[command]
 pub fn update_selection_sort_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     params: UpdateSelectionSortParams,
 ) -> Result<serde_json::Value, String> {
     if params.new_order.is_empty() {
@@ -134,7 +140,7 @@ pub fn update_selection_sort_cmd(
         }));
     }
 
-    match update_selection_sort(&app, &params) {
+    match db.with_conn(|conn| update_selection_sort(conn, &params)) {
         Ok(_) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功更新 {} 条自选股的排序顺序", params.new_order.len()),
@@ -154,8 +160,11 @@ pub fn update_selection_sort_cmd(
 // 7. 删除自选股 Command
 // --------------------------
 #[command]
-pub fn delete_selection_cmd(app: AppHandle, code: String) -> Result<serde_json::Value, String> {
-    match delete_selection(&app, &code) {
+pub fn delete_selection_cmd(
+    db: State<'_, AppDb>,
+    code: String,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| delete_selection(conn, &code)) {
         Ok(true) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功删除代码「{}」的自选股", code),
@@ -1,17 +1,18 @@
+use crate::db::common::AppDb;
 use crate::db::self_reflect_db::{
     add_or_edit_self_reflect, delete_self_reflect, get_self_reflect_list, get_single_self_reflect,
 };
 use crate::structs::self_reflect_structs::{AddReflectReq, GetOrDeleteReflectReq};
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
 
 // --------------------------
 // 1. 评论列表查询 Command
 // --------------------------
 #[command]
-pub fn get_self_reflect_list_cmd(app: AppHandle) -> Result<serde_json::Value, String> {
-    match get_self_reflect_list(&app) {
+pub fn get_self_reflect_list_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| get_self_reflect_list(conn)) {
         Ok(reviews) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功查询到 {} 条评论数据", reviews.len()),
@@ -32,7 +33,7 @@ pub fn get_self_reflect_list_cmd(app: AppHandle) -> Result<serde_json::Value, St
 // --------------------------
 #[command]
 pub fn add_self_reflect_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: AddReflectReq, // 接收新增参数（标题、代码、日期等）
 ) -> Result<serde_json::Value, String> {
     // 参数校验：必填字段非空
@@ -51,7 +52,7 @@ pub fn add_self_reflect_cmd(
         }));
     }
 
-    match add_or_edit_self_reflect(&app, &req) {
+    match db.with_conn(|conn| add_or_edit_self_reflect(conn, &req)) {
         Ok(new_review) => Ok(serde_json::json!({
             "success": true,
             "message": format!("评论新增成功（ID：{}）", new_review.id),
@@ -72,7 +73,7 @@ pub fn add_self_reflect_cmd(
 // --------------------------
 #[command]
 pub fn get_single_self_reflect_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: GetOrDeleteReflectReq, // 接收ID参数（i32类型）
 ) -> Result<serde_json::Value, String> {
     // 参数校验：ID需为正整数
@@ -85,7 +86,7 @@ pub fn get_single_self_reflect_cmd(
         }));
     }
 
-    match get_single_self_reflect(&app, &req) {
+    match db.with_conn(|conn| get_single_self_reflect(conn, &req)) {
         Ok(Some(review)) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功查询到ID为 {} 的评论", req.id),
@@ -112,7 +113,7 @@ pub fn get_single_self_reflect_cmd(
 // --------------------------
 #[command]
 pub fn delete_self_reflect_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: GetOrDeleteReflectReq, // 接收ID参数（i32类型）
 ) -> Result<serde_json::Value, String> {
     // 参数校验：ID需为正整数
@@ -125,7 +126,7 @@ pub fn delete_self_reflect_cmd(
         }));
     }
 
-    match delete_self_reflect(&app, &req) {
+    match db.with_conn(|conn| delete_self_reflect(conn, &req)) {
         Ok(true) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功删除ID为 {} 的评论", req.id),
@@ -0,0 +1,145 @@
+use crate::db::app_config_db::{get_statement_columns, set_statement_columns};
+use crate::db::common::AppDb;
+use crate::db::position_db::{import_statement, preview_statement};
+use crate::statement::brokers::{builtin_brokers, builtin_columns, CUSTOM_BROKER};
+use crate::statement::{parse_statement, read_table};
+use crate::structs::statement_structs::{
+    BrokerFormat, SkippedRow, StatementColumns, StatementImportParams, StatementOrder,
+};
+use crate::structs::StockError;
+use rusqlite::Connection;
+use serde_json;
+use tauri::command;
+use tauri::State;
+
+// 按券商格式（或自定义列映射）解析上传的交割单
+fn parse_upload(
+    conn: &Connection,
+    params: &StatementImportParams,
+) -> Result<(Vec<StatementOrder>, Vec<SkippedRow>), StockError> {
+    let columns = if params.broker == CUSTOM_BROKER {
+        match &params.columns {
+            Some(columns) => columns.clone(),
+            None => get_statement_columns(conn)?
+                .ok_or_else(|| StockError::BusinessError("请先设置自定义列映射".to_string()))?,
+        }
+    } else {
+        builtin_columns(&params.broker).ok_or_else(|| {
+            StockError::BusinessError(format!("不支持的券商格式: {}", params.broker))
+        })?
+    };
+    let table = read_table(&params.file_name, &params.data)?;
+    parse_statement(&table, &columns)
+}
+
+/// 查询支持的交割单格式（内置券商 + 已保存的自定义列映射）
+#[command]
+pub fn get_statement_brokers_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| get_statement_columns(conn)) {
+        Ok(custom) => {
+            let mut brokers = builtin_brokers();
+            if let Some(columns) = custom {
+                brokers.push(BrokerFormat {
+                    id: CUSTOM_BROKER.to_string(),
+                    name: "自定义".to_string(),
+                    columns,
+                });
+            }
+            Ok(serde_json::json!({
+                "success": true,
+                "message": format!("共 {} 种交割单格式", brokers.len()),
+                "count": brokers.len(),
+                "data": brokers
+            }))
+        }
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取交割单格式失败: {}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+/// 保存自定义交割单列映射（用于内置格式不支持的券商）
+#[command]
+pub fn save_statement_columns_cmd(
+    db: State<'_, AppDb>,
+    columns: StatementColumns,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| set_statement_columns(conn, &columns)) {
+        Ok(()) => Ok(serde_json::json!({
+            "success": true,
+            "message": "自定义列映射已保存",
+            "data": true,
+            "count": 1
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("保存自定义列映射失败: {}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+/// 预览交割单导入：解析文件并与已有委托比对，不写入数据库
+#[command]
+pub fn preview_statement_import_cmd(
+    db: State<'_, AppDb>,
+    params: StatementImportParams,
+) -> Result<serde_json::Value, String> {
+    let result = db.with_conn(|conn| {
+        let (orders, skipped) = parse_upload(conn, &params)?;
+        preview_statement(conn, params.account_id, orders, skipped)
+    });
+    match result {
+        Ok(preview) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!(
+                "新增 {} 笔，已存在 {} 笔，跳过 {} 行",
+                preview.orders.len(),
+                preview.duplicates.len(),
+                preview.skipped.len()
+            ),
+            "count": preview.orders.len(),
+            "data": preview
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("解析交割单失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
+
+/// 导入交割单：新增委托并重新生成持仓（同一事务，已存在的成交自动跳过）
+#[command]
+pub fn import_statement_cmd(
+    db: State<'_, AppDb>,
+    params: StatementImportParams,
+) -> Result<serde_json::Value, String> {
+    let result = db.with_conn(|conn| {
+        let (orders, skipped) = parse_upload(conn, &params)?;
+        import_statement(conn, params.account_id, orders, skipped)
+    });
+    match result {
+        Ok(imported) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!(
+                "成功导入 {} 笔委托，跳过已存在的 {} 笔",
+                imported.orders.len(),
+                imported.duplicates.len()
+            ),
+            "count": imported.orders.len(),
+            "data": imported
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("导入交割单失败: {}", e),
+            "data": null,
+            "count": 0
+        })),
+    }
+}
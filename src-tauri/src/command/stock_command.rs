@@ -1,13 +1,16 @@
-use crate::db::common::init_database;
+use crate::db::common::AppDb;
 use crate::db::stock_db;
 use crate::requests::get_all_stock;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::{AppHandle, State};
 
 // -------------------------- 2. Tauri 命令：爬取并保存所有股票 --------------------------
 /// 前端调用此命令时，触发爬取 + 存储逻辑
 #[command]
-pub async fn crawl_and_save_stocks(app: AppHandle) -> Result<serde_json::Value, String> {
+pub async fn crawl_and_save_stocks(
+    app: AppHandle,
+    db: State<'_, AppDb>,
+) -> Result<serde_json::Value, String> {
     // 1. 爬取所有股票数据
     let stocks = get_all_stock::crawl_all_stocks(app.clone())
         .await
@@ -20,8 +23,9 @@ pub async fn crawl_and_save_stocks(app: AppHandle) -> Result<serde_json::Value,
     println!("开始爬取数据");
 
     // 2. 获取数据库连接
-    let mut conn =
-        init_database(&app, "all_stocks").map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    let mut conn = db
+        .conn()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
     // 3. 先清空表数据
     let cleared_count =
@@ -45,7 +49,7 @@ pub async fn crawl_and_save_stocks(app: AppHandle) -> Result<serde_json::Value,
 /// 模糊查询股票（仅需关键词，返回所有匹配结果）
 #[command]
 pub fn search_stocks_by_keyword(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     keyword: &str,
 ) -> Result<serde_json::Value, String> {
     // 简单验证关键词（可选，根据需求调整）
@@ -53,8 +57,9 @@ pub fn search_stocks_by_keyword(
         return Err("查询关键词不能为空".to_string());
     }
 
-    let conn =
-        init_database(&app, "all_stocks").map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    let conn = db
+        .conn()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
     // 执行模糊查询
     let stocks =
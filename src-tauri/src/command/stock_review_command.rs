@@ -1,17 +1,18 @@
+use crate::db::common::AppDb;
 use crate::db::stock_review_db::{
     add_or_edit_stock_review, delete_stock_review, get_single_stock_review, get_stock_review_list,
 };
 use crate::structs::stock_review_structs::{AddReviewReq, GetOrDeleteReviewReq, GetReviewListReq};
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
 
 // --------------------------
 // 1. 评论列表查询 Command
 // --------------------------
 #[command]
 pub fn get_stock_review_list_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: GetReviewListReq, // 接收筛选参数（类型+关键字）
 ) -> Result<serde_json::Value, String> {
     // 参数校验：评论类型（type）为必填
@@ -24,7 +25,7 @@ pub fn get_stock_review_list_cmd(
         }));
     }
 
-    match get_stock_review_list(&app, &req) {
+    match db.with_conn(|conn| get_stock_review_list(conn, &req)) {
         Ok(reviews) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功查询到 {} 条评论数据（类型：{}）", reviews.len(), req.r#type),
@@ -45,7 +46,7 @@ pub fn get_stock_review_list_cmd(
 // --------------------------
 #[command]
 pub fn add_stock_review_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: AddReviewReq, // 接收新增参数（标题、代码、日期等）
 ) -> Result<serde_json::Value, String> {
     // 参数校验：必填字段非空
@@ -73,7 +74,7 @@ pub fn add_stock_review_cmd(
         }));
     }
 
-    match add_or_edit_stock_review(&app, &req) {
+    match db.with_conn(|conn| add_or_edit_stock_review(conn, &req)) {
         Ok(new_review) => Ok(serde_json::json!({
             "success": true,
             "message": format!("评论新增成功（ID：{}）", new_review.id),
@@ -94,7 +95,7 @@ pub fn add_stock_review_cmd(
 // --------------------------
 #[command]
 pub fn get_single_stock_review_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: GetOrDeleteReviewReq, // 接收ID参数（i32类型）
 ) -> Result<serde_json::Value, String> {
     // 参数校验：ID需为正整数
@@ -107,7 +108,7 @@ pub fn get_single_stock_review_cmd(
         }));
     }
 
-    match get_single_stock_review(&app, &req) {
+    match db.with_conn(|conn| get_single_stock_review(conn, &req)) {
         Ok(Some(review)) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功查询到ID为 {} 的评论", req.id),
@@ -134,7 +135,7 @@ pub fn get_single_stock_review_cmd(
 // --------------------------
 #[command]
 pub fn delete_stock_review_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: GetOrDeleteReviewReq, // 接收ID参数（i32类型）
 ) -> Result<serde_json::Value, String> {
     // 参数校验：ID需为正整数
@@ -147,7 +148,7 @@ pub fn delete_stock_review_cmd(
         }));
     }
 
-    match delete_stock_review(&app, &req) {
+    match db.with_conn(|conn| delete_stock_review(conn, &req)) {
         Ok(true) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功删除ID为 {} 的评论", req.id),
@@ -1,22 +1,31 @@
-use crate::db::trend_lines_db::{add_trend_line, delete_trend_line, query_trend_lines};
-use crate::structs::trend_lines_structs::{AddTrendLineReq, DeleteTrendLineReq};
+use crate::db::common::AppDb;
+use crate::db::trend_lines_db::{
+    add_trend_line, count_legacy_trend_lines, delete_trend_line, query_projected_trend_lines,
+    query_trend_line_breaks, query_trend_lines, set_trend_line_alert, update_trend_line,
+};
+use crate::structs::trend_lines_structs::{
+    AddTrendLineReq, DeleteTrendLineReq, TrendLineAlertReq, UpdateTrendLineReq,
+};
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::State;
+
+// 默认返回的突破记录条数
+const DEFAULT_BREAK_LIMIT: i64 = 200;
 
 // --------------------------
 // 1. 新增趋势线 Command（支持批量）
 // --------------------------
 #[command]
 pub fn add_trend_lines_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     reqs: Vec<AddTrendLineReq>,
 ) -> Result<serde_json::Value, String> {
     let mut success_ids = Vec::new();
     let mut failed_lines = Vec::new();
 
     for req in reqs {
-        match add_trend_line(&app, &req) {
+        match db.with_conn(|conn| add_trend_line(conn, &req)) {
             Ok(new_id) => {
                 success_ids.push((new_id, req));
             }
@@ -55,12 +64,14 @@ pub fn add_trend_lines_cmd(
 
 // --------------------------
 // 2. 查询趋势线 Command（按 code + period）
+// project 为 true 时同时返回其他周期画的趋势线，起止点投影到当前周期的K线上
 // --------------------------
 #[command]
 pub fn query_trend_lines_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     code: String,
     period: String,
+    project: Option<bool>,
 ) -> Result<serde_json::Value, String> {
     if code.is_empty() || period.is_empty() {
         return Ok(serde_json::json!({
@@ -71,7 +82,12 @@ pub fn query_trend_lines_cmd(
         }));
     }
 
-    match query_trend_lines(&app, &code, &period) {
+    let result = if project.unwrap_or(false) {
+        db.with_conn(|conn| query_projected_trend_lines(conn, &code, &period))
+    } else {
+        db.with_conn(|conn| query_trend_lines(conn, &code, &period))
+    };
+    match result {
         Ok(lines) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功查询到 {} 条趋势线数据（股票：{}，周期：{}）", lines.len(), code, period),
@@ -92,7 +108,7 @@ pub fn query_trend_lines_cmd(
 // --------------------------
 #[command]
 pub fn delete_trend_line_cmd(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     req: DeleteTrendLineReq,
 ) -> Result<serde_json::Value, String> {
     if req.id <= 0 {
@@ -104,7 +120,7 @@ pub fn delete_trend_line_cmd(
         }));
     }
 
-    match delete_trend_line(&app, &req) {
+    match db.with_conn(|conn| delete_trend_line(conn, &req)) {
         Ok(true) => Ok(serde_json::json!({
             "success": true,
             "message": format!("成功删除 ID 为 {} 的趋势线", req.id),
@@ -125,3 +141,115 @@ pub fn delete_trend_line_cmd(
         })),
     }
 }
+
+// --------------------------
+// 3.1 修改趋势线 Command（拖动后保存新的起止点，ID 和提醒设置不变）
+// --------------------------
+#[command]
+pub fn update_trend_line_cmd(
+    db: State<'_, AppDb>,
+    req: UpdateTrendLineReq,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| update_trend_line(conn, &req)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功修改 ID 为 {} 的趋势线", req.id),
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("未找到 ID 为 {} 的趋势线，修改失败", req.id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("趋势线修改失败：{}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+// --------------------------
+// 4. 设置趋势线突破提醒 Command（开关、模式、容差）
+// --------------------------
+#[command]
+pub fn set_trend_line_alert_cmd(
+    db: State<'_, AppDb>,
+    req: TrendLineAlertReq,
+) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| set_trend_line_alert(conn, &req)) {
+        Ok(true) => Ok(serde_json::json!({
+            "success": true,
+            "message": if req.enabled {
+                format!("已开启 ID 为 {} 的趋势线突破提醒", req.id)
+            } else {
+                format!("已关闭 ID 为 {} 的趋势线突破提醒", req.id)
+            },
+            "data": true,
+            "count": 1
+        })),
+        Ok(false) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("未找到 ID 为 {} 的趋势线", req.id),
+            "data": false,
+            "count": 0
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("设置趋势线提醒失败：{}", e),
+            "data": false,
+            "count": 0
+        })),
+    }
+}
+
+// --------------------------
+// 5. 查询趋势线突破记录 Command（最新的在前，可按股票代码或趋势线ID过滤）
+// --------------------------
+#[command]
+pub fn get_trend_line_breaks_cmd(
+    db: State<'_, AppDb>,
+    code: Option<String>,
+    line_id: Option<i32>,
+    limit: Option<i64>,
+) -> Result<serde_json::Value, String> {
+    let limit = limit
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_BREAK_LIMIT);
+    match db.with_conn(|conn| query_trend_line_breaks(conn, code.as_deref(), line_id, limit)) {
+        Ok(records) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("成功获取 {} 条趋势线突破记录", records.len()),
+            "count": records.len(),
+            "data": records
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("获取趋势线突破记录失败：{}", e),
+            "data": [],
+            "count": 0
+        })),
+    }
+}
+
+// --------------------------
+// 6. 查询迁移时未能转换、原样保留的旧版趋势线条数 Command
+// --------------------------
+#[command]
+pub fn get_legacy_trend_line_count_cmd(db: State<'_, AppDb>) -> Result<serde_json::Value, String> {
+    match db.with_conn(|conn| count_legacy_trend_lines(conn)) {
+        Ok(count) => Ok(serde_json::json!({
+            "success": true,
+            "message": format!("有 {} 条旧版趋势线未能转换", count),
+            "data": count
+        })),
+        Err(e) => Ok(serde_json::json!({
+            "success": false,
+            "message": format!("查询旧版趋势线失败：{}", e),
+            "data": 0
+        })),
+    }
+}
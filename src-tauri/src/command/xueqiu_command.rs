@@ -1,23 +1,84 @@
-use crate::requests::xueqiu_request::{
-    add_to_watchlist, fetch_minute_chart, fetch_raw_batch_quote, fetch_raw_kline_data,
-    fetch_raw_stock_detail, remove_from_watchlist,
+use crate::calendar::{market_now, MarketSession, TradingCalendar};
+use crate::db::app_config_db::is_offline_mode;
+use crate::db::common::AppDb;
+use crate::db::kline_cache_db::{query_cache_updated_at, query_cached_klines};
+use crate::db::market_snapshot_db::{
+    load_snapshot, save_snapshot, SNAPSHOT_DETAIL, SNAPSHOT_MINUTE, SNAPSHOT_QUOTE,
 };
+use crate::indicators::{compute_for_window, parse_indicators, warmup_bars};
+use crate::requests::kline_cache::{load_klines, KlineLoad, CACHE_SOURCE};
+use crate::requests::provider::{build_provider_chain, ProviderChain, ProviderError};
+use crate::requests::xueqiu_request::{add_to_watchlist, remove_from_watchlist};
 use crate::structs::xueqiu_structs::{
-    GetMinuteDataParams, GetStockDataParams, MinuteChartResponse, RawBatchQuoteData,
-    RawBatchQuoteItem, RawKlineData, RawStockDetailData, StockKlineItem, StockQuote,
+    GetStockDataParams, MinuteChartItem, StockDetail, StockQuote,
 };
-use chrono::Utc;
+use chrono::{Local, Utc};
+use serde::Serialize;
 use serde_json;
 use tauri::command;
-use tauri::AppHandle;
+use tauri::{AppHandle, State};
+use tauri_plugin_log::log;
+
+// 按当前配置构建行情数据源链（连接锁在返回前释放，不会跨 await 持有）
+// 离线模式下直接返回错误，调用方改用本地数据
+pub(crate) fn provider_chain(db: &AppDb) -> Result<ProviderChain, String> {
+    let conn = db
+        .conn()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    if is_offline_mode(&conn).unwrap_or(false) {
+        return Err("当前处于离线模式".to_string());
+    }
+    build_provider_chain(&conn).map_err(|e| e.to_string())
+}
+
+fn now_str() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// 在线获取失败时的兜底响应：数据来自本地存储，stale 为 true 并附带数据时间
+fn stale_response<T: Serialize>(
+    reason: &str,
+    data: T,
+    count: usize,
+    as_of: &str,
+) -> serde_json::Value {
+    serde_json::json!({
+        "success": true,
+        "message": format!("{}，显示本地数据（截至 {}）", reason, as_of),
+        "data": data,
+        "count": count,
+        "provider": CACHE_SOURCE,
+        "stale": true,
+        "as_of": as_of
+    })
+}
+
+// 保存行情快照失败不影响本次返回
+pub(crate) fn save_snapshot_quietly<T: Serialize>(db: &AppDb, kind: &str, symbol: &str, data: &T) {
+    if let Err(e) = db.with_conn(|conn| save_snapshot(conn, kind, symbol, data)) {
+        log::warn!("保存行情快照失败（{} {}）: {}", kind, symbol, e);
+    }
+}
+
+fn kline_error_message(e: &ProviderError) -> String {
+    match e {
+        ProviderError::Unauthorized(_) => {
+            "Cookie已过期或无效，请重新登录雪球并更新Cookie".to_string()
+        }
+        ProviderError::Status(404) => "请求的股票K线接口不存在（可能是雪球接口更新）".to_string(),
+        ProviderError::Status(500) => "雪球服务器错误，请稍后再试".to_string(),
+        _ => e.to_string(),
+    }
+}
 
 /// Tauri Command：获取股票K线数据并返回JSON格式响应
+/// 离线模式或网络/Cookie不可用时返回本地缓存的K线，并标记 stale
 #[command]
 pub async fn get_kline_data(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     params: GetStockDataParams,
 ) -> Result<serde_json::Value, String> {
-    println!("Received params: {:?}", params);
+    log::debug!("Received params: {:?}", params);
     // 1. 参数校验
     let code = params.code.trim().to_uppercase();
     if code.is_empty() {
@@ -33,113 +94,124 @@ pub async fn get_kline_data(
 
     let limit = params.limit;
 
-    // 2. 处理时间戳
+    // 2. 处理时间戳（为空表示获取最新数据）
     let timestamp = match params.timestamp {
-        Some(ts) if !ts.trim().is_empty() => ts.trim().to_string(),
-        _ => {
-            let one_day_ms = 24 * 60 * 60 * 1000;
-            let current_ts = Utc::now().timestamp_millis() + one_day_ms;
-            current_ts.to_string()
-        }
+        Some(ts) if !ts.trim().is_empty() => match ts.trim().parse::<i64>() {
+            Ok(ts) => Some(ts),
+            Err(_) => {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "message": format!("无效的时间戳: {}", ts),
+                    "data": [],
+                    "count": 0
+                }));
+            }
+        },
+        _ => None,
     };
 
-    println!(
-        "Fetching Kline data for code: {}, period: {}, timestamp: {}, limit: {}",
-        code, period, timestamp, limit
-    );
-
-    // 3. 调用爬取函数获取原始数据
-    let raw_response = match fetch_raw_kline_data(&app, &code, &period, &timestamp, limit).await {
-        Ok(data) => data,
+    // 3. 解析技术指标，多取若干根K线作为指标预热数据，计算后再截取
+    let indicators = match parse_indicators(&params.indicators) {
+        Ok(indicators) => indicators,
         Err(e) => {
-            let msg = if e.contains("401") || e.contains("403") {
-                "Cookie已过期或无效，请重新登录雪球并更新Cookie"
-            } else if e.contains("404") {
-                "请求的股票K线接口不存在（可能是雪球接口更新）"
-            } else if e.contains("500") {
-                "雪球服务器错误，请稍后再试"
-            } else {
-                &e
-            };
             return Ok(serde_json::json!({
                 "success": false,
-                "message": msg,
+                "message": e,
                 "data": [],
                 "count": 0
             }));
         }
     };
+    let fetch_limit = limit.saturating_add(warmup_bars(&indicators) as i32);
+    let window = limit.max(0) as usize;
+
+    log::debug!(
+        "Fetching Kline data for code: {}, period: {}, timestamp: {:?}, limit: {}",
+        code,
+        period,
+        timestamp,
+        limit
+    );
 
-    // 4. 解析原始数据为业务结构体
-    let RawKlineData { column, item } = raw_response.data;
-    if item.is_empty() {
-        return Ok(serde_json::json!({
+    // 4. 先读本地缓存，只向数据源请求缺失部分（当前数据源失败时自动切换备用数据源）
+    let failure = match provider_chain(&db) {
+        Ok(chain) => {
+            let now = Utc::now().timestamp_millis();
+            match load_klines(&db, &chain, &code, &period, timestamp, fetch_limit, now).await {
+                Ok(KlineLoad {
+                    items,
+                    source: provider,
+                }) => {
+                    let (parsed_kline, indicator_data) =
+                        compute_for_window(items, &indicators, &period, window);
+                    // 5. 处理空数据
+                    if parsed_kline.is_empty() {
+                        return Ok(serde_json::json!({
+                            "success": false,
+                            "message": format!("未获取到 {} 的K线数据（可能是股票代码无效）", code),
+                            "data": [],
+                            "count": 0
+                        }));
+                    }
+
+                    // 6. 返回成功JSON响应（匹配你要求的格式）
+                    return Ok(serde_json::json!({
+                        "success": true,
+                        "message": format!(
+                            "成功获取 {} 的 {} 条K线数据（周期：{}）",
+                            code,
+                            parsed_kline.len(),
+                            period
+                        ),
+                        "data": parsed_kline,
+                        "count": parsed_kline.len(),
+                        "indicators": indicator_data,
+                        "provider": provider,
+                        "stale": false,
+                        "as_of": now_str()
+                    }));
+                }
+                Err(e) => kline_error_message(&e),
+            }
+        }
+        Err(e) => e,
+    };
+
+    // 7. 离线兜底：返回本地缓存的K线
+    let end = timestamp.unwrap_or(i64::MAX);
+    let cached = db.with_conn(|conn| {
+        let items = query_cached_klines(conn, &code, &period, end, fetch_limit)?;
+        let as_of = query_cache_updated_at(conn, &code, &period)?;
+        Ok((items, as_of))
+    });
+    match cached {
+        Ok((items, Some(as_of))) if !items.is_empty() => {
+            let (items, indicator_data) = compute_for_window(items, &indicators, &period, window);
+            let count = items.len();
+            let mut response = stale_response(&failure, items, count, &as_of);
+            response["indicators"] = serde_json::json!(indicator_data);
+            Ok(response)
+        }
+        _ => Ok(serde_json::json!({
             "success": false,
-            "message": format!("未获取到 {} 的K线数据（可能是股票代码无效）", code),
+            "message": failure,
             "data": [],
             "count": 0
-        }));
-    }
-
-    let mut parsed_kline = Vec::new();
-    for kline_item in item {
-        // 构建字段映射
-        let data_map: std::collections::HashMap<&str, &serde_json::Value> = column
-            .iter()
-            .zip(kline_item.iter())
-            .map(|(col_name, val)| (col_name.as_str(), val))
-            .collect();
-        // 解析时间戳
-        let timestamp = data_map
-            .get("timestamp")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0);
-
-        // 解析数值字段
-        let parse_num = |key: &str| -> f64 {
-            data_map
-                .get(key)
-                .and_then(|v| match v {
-                    serde_json::Value::Null => None,
-                    serde_json::Value::Number(n) => n.as_f64(),
-                    serde_json::Value::String(s) => s.parse().ok(),
-                    _ => None,
-                })
-                .unwrap_or(0.0)
-        };
-
-        parsed_kline.push(StockKlineItem {
-            date: timestamp,
-            open: parse_num("open"),
-            high: parse_num("high"),
-            low: parse_num("low"),
-            close: parse_num("close"),
-            volume: parse_num("amount"),
-            percent: parse_num("percent"),
-            turnoverrate: parse_num("turnoverrate"),
-        });
+        })),
     }
-
-    // 5. 返回成功JSON响应（匹配你要求的格式）
-    Ok(serde_json::json!({
-        "success": true,
-        "message": format!(
-            "成功获取 {} 的 {} 条K线数据（周期：{}）",
-            code,
-            parsed_kline.len(),
-            period
-        ),
-        "data": parsed_kline,
-        "count": parsed_kline.len()
-    }))
 }
 
 #[command]
 pub async fn get_batch_stock_quote(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     symbols: &str, // 逗号分隔的股票代码（如 "SH600000,SZ000001"）
 ) -> Result<serde_json::Value, String> {
-    if symbols.is_empty() {
+    let symbol_list: Vec<String> = symbols
+        .split(',')
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if symbol_list.is_empty() {
         return Ok(serde_json::json!({
             "success": false,
             "message": "股票代码列表不能为空（如 SH600000,SZ000001）",
@@ -148,39 +220,66 @@ pub async fn get_batch_stock_quote(
         }));
     }
 
-    // 2. 调用爬取函数获取原始数据
-    let raw_response = fetch_raw_batch_quote(&app, &symbols).await?;
+    // 2. 通过数据源链获取报价
+    let failure = match provider_chain(&db) {
+        Ok(chain) => match chain.fetch_batch_quote(&symbol_list).await {
+            Ok((valid_quotes, provider)) if !valid_quotes.is_empty() => {
+                for quote in &valid_quotes {
+                    if !quote.symbol.is_empty() {
+                        save_snapshot_quietly(&db, SNAPSHOT_QUOTE, &quote.symbol, quote);
+                    }
+                }
+
+                // 3. 返回成功 JSON（匹配 Python 响应格式）
+                return Ok(serde_json::json!({
+                    "success": true,
+                    "message": format!("成功获取 {} 只股票的报价数据", valid_quotes.len()),
+                    "data": valid_quotes,
+                    "count": valid_quotes.len(),
+                    "provider": provider,
+                    "stale": false,
+                    "as_of": now_str()
+                }));
+            }
+            // 空数据场景（匹配 Python 的 "cookie已过期" 提示）
+            Ok(_) => "未获取到有效报价数据（Cookie可能已过期或代码无效）".to_string(),
+            Err(e) => e.to_string(),
+        },
+        Err(e) => e,
+    };
 
-    // 3. 解析原始数据（提取有效 Quote，过滤 None）
-    let RawBatchQuoteData { items } = raw_response.data;
-    let valid_quotes: Vec<StockQuote> = items
-        .into_iter()
-        .filter_map(|item: RawBatchQuoteItem| item.quote) // 只保留有数据的 quote
-        .collect();
+    // 4. 离线兜底：逐只读取最后一次报价快照，数据时间取最早的一只
+    let mut quotes: Vec<StockQuote> = Vec::new();
+    let mut oldest_as_of: Option<String> = None;
+    for symbol in &symbol_list {
+        if let Ok(Some((quote, as_of))) =
+            db.with_conn(|conn| load_snapshot::<StockQuote>(conn, SNAPSHOT_QUOTE, symbol))
+        {
+            quotes.push(quote);
+            if oldest_as_of.as_ref().map_or(true, |oldest| as_of < *oldest) {
+                oldest_as_of = Some(as_of);
+            }
+        }
+    }
 
-    // 4. 处理空数据场景（匹配 Python 的 "cookie已过期" 提示）
-    if valid_quotes.is_empty() {
-        return Ok(serde_json::json!({
+    match oldest_as_of {
+        Some(as_of) => {
+            let count = quotes.len();
+            Ok(stale_response(&failure, quotes, count, &as_of))
+        }
+        None => Ok(serde_json::json!({
             "success": false,
-            "message": "未获取到有效报价数据（Cookie可能已过期或代码无效）",
+            "message": failure,
             "data": [],
             "count": 0
-        }));
+        })),
     }
-
-    // 5. 返回成功 JSON（匹配 Python 响应格式）
-    Ok(serde_json::json!({
-        "success": true,
-        "message": format!("成功获取 {} 只股票的报价数据", valid_quotes.len()),
-        "data": valid_quotes,
-        "count": valid_quotes.len()
-    }))
 }
 
 /// Command：获取单只股票详情（对应 Python 的 get_stock_details）
 #[command]
 pub async fn get_single_stock_detail(
-    app: AppHandle,
+    db: State<'_, AppDb>,
     code: &str, // 单个股票代码（如 "SH600000"）
 ) -> Result<serde_json::Value, String> {
     // 1. 参数校验（修剪空格 + 大写转换）
@@ -193,34 +292,63 @@ pub async fn get_single_stock_detail(
         }));
     }
 
-    // 2. 调用爬取函数获取原始数据
-    let raw_response = fetch_raw_stock_detail(&app, &code).await?;
-
-    // 3. 解析原始数据（提取 quote，处理空数据）
-    let RawStockDetailData { quote } = raw_response.data;
-    let stock_detail = match quote {
-        Some(detail) => detail,
-        None => {
-            return Ok(serde_json::json!({
-                "success": false,
-                "message": format!("未获取到 {} 的详情数据（Cookie可能已过期）", code),
-                "data": {},
-                "count": 0
-            }));
-        }
+    // 2. 通过数据源链获取详情
+    let failure = match provider_chain(&db) {
+        Ok(chain) => match chain.fetch_stock_detail(code).await {
+            Ok((Some(stock_detail), provider)) => {
+                save_snapshot_quietly(&db, SNAPSHOT_DETAIL, code, &stock_detail);
+
+                // 3. 返回成功 JSON
+                return Ok(serde_json::json!({
+                    "success": true,
+                    "message": format!("成功获取 {} 的详情数据", code),
+                    "data": stock_detail,
+                    "count": 1, // 单只股票，count 固定为 1
+                    "provider": provider,
+                    "stale": false,
+                    "as_of": now_str()
+                }));
+            }
+            Ok((None, _)) => format!("未获取到 {} 的详情数据（Cookie可能已过期）", code),
+            Err(e) => e.to_string(),
+        },
+        Err(e) => e,
     };
 
-    // 4. 返回成功 JSON
-    Ok(serde_json::json!({
-        "success": true,
-        "message": format!("成功获取 {} 的详情数据", code),
-        "data": stock_detail,
-        "count": 1 // 单只股票，count 固定为 1
-    }))
+    // 4. 离线兜底：最后一次详情快照
+    match db.with_conn(|conn| load_snapshot::<StockDetail>(conn, SNAPSHOT_DETAIL, code)) {
+        Ok(Some((detail, as_of))) => Ok(stale_response(&failure, detail, 1, &as_of)),
+        _ => Ok(serde_json::json!({
+            "success": false,
+            "message": failure,
+            "data": {},
+            "count": 0
+        })),
+    }
+}
+
+// 分时数据为空时按交易日历说明原因：休市、尚未开盘或股票停牌
+fn empty_minute_chart_message(db: &AppDb, code: &str) -> String {
+    let calendar = db
+        .with_conn(|conn| TradingCalendar::load(conn))
+        .unwrap_or_else(|_| TradingCalendar::bundled());
+    let now = market_now();
+    let reason = match calendar.session(now) {
+        MarketSession::Closed => format!(
+            "今日休市，上一交易日为 {}",
+            calendar.previous_trading_day(now.date())
+        ),
+        MarketSession::PreOpen => "尚未开盘".to_string(),
+        _ => "可能股票停牌".to_string(),
+    };
+    format!("未获取到 {} 的分时数据（{}）", code, reason)
 }
 
 #[command]
-pub async fn get_minute_chart(app: AppHandle, code: &str) -> Result<serde_json::Value, String> {
+pub async fn get_minute_chart(
+    db: State<'_, AppDb>,
+    code: &str,
+) -> Result<serde_json::Value, String> {
     if code.is_empty() {
         return Ok(serde_json::json!({
             "success": false,
@@ -230,31 +358,43 @@ pub async fn get_minute_chart(app: AppHandle, code: &str) -> Result<serde_json::
         }));
     }
 
-    // 2. 调用爬取函数获取原始数据
-    // 这里调用的是我们上一步实现的 fetch_minute_chart
-    let raw_response: MinuteChartResponse = fetch_minute_chart(&app, &code).await?;
-
-    // 3. 提取数据并准备返回（处理空数据的情况）
-    let items = raw_response.data.items;
-    let count = items.len();
+    // 2. 通过数据源链获取分时数据
+    let failure = match provider_chain(&db) {
+        Ok(chain) => match chain.fetch_minute_chart(code).await {
+            Ok((items, provider)) if !items.is_empty() => {
+                save_snapshot_quietly(&db, SNAPSHOT_MINUTE, code, &items);
+                let count = items.len();
+
+                // 3. 返回成功 JSON
+                return Ok(serde_json::json!({
+                    "success": true,
+                    "message": format!("成功获取 {} 的分时数据，共 {} 条", code, count),
+                    "data": items, // serde_json 会自动将 Vec 序列化为 JSON 数组
+                    "count": count,
+                    "provider": provider,
+                    "stale": false,
+                    "as_of": now_str()
+                }));
+            }
+            Ok(_) => empty_minute_chart_message(&db, code),
+            Err(e) => e.to_string(),
+        },
+        Err(e) => e,
+    };
 
-    if items.is_empty() {
-        return Ok(serde_json::json!({
+    // 4. 离线兜底：最后一次分时快照
+    match db.with_conn(|conn| load_snapshot::<Vec<MinuteChartItem>>(conn, SNAPSHOT_MINUTE, code)) {
+        Ok(Some((items, as_of))) => {
+            let count = items.len();
+            Ok(stale_response(&failure, items, count, &as_of))
+        }
+        _ => Ok(serde_json::json!({
             "success": false,
-            "message": format!("未获取到 {} 的分时数据（可能非交易日或股票停牌）", code),
+            "message": failure,
             "data": {},
             "count": 0
-        }));
+        })),
     }
-
-    // 4. 返回成功 JSON
-    // 将 items (Vec<MinuteChartItem>) 直接放入 data 字段
-    Ok(serde_json::json!({
-        "success": true,
-        "message": format!("成功获取 {} 的分时数据，共 {} 条", code, count),
-        "data": items, // serde_json 会自动将 Vec 序列化为 JSON 数组
-        "count": count
-    }))
 }
 
 /// 添加股票到雪球自选股（支持多个，逗号分隔）
@@ -0,0 +1,228 @@
+use crate::structs::account_structs::{Account, PositionTransfer, DEFAULT_ACCOUNT_ID};
+use crate::structs::StockError;
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+
+// 账户名称不能为空，且不能与其他账户重名
+fn validate_account_name(
+    conn: &Connection,
+    name: &str,
+    exclude_id: Option<i32>,
+) -> Result<String, StockError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(StockError::BusinessError("账户名称不能为空".to_string()));
+    }
+    let duplicated: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM accounts WHERE name = ?1 AND (?2 IS NULL OR id != ?2)",
+            params![name, exclude_id],
+            |row| Ok(row.get::<_, i32>(0)? > 0),
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    if duplicated {
+        return Err(StockError::BusinessError(format!(
+            "账户名称已存在: {}",
+            name
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// 新建账户，返回账户ID
+pub fn create_account(conn: &Connection, name: &str) -> Result<i32, StockError> {
+    let name = validate_account_name(conn, name, None)?;
+    conn.execute(
+        "INSERT INTO accounts (name, archived, created_at) VALUES (?1, 0, ?2)",
+        params![name, Local::now().format("%Y-%m-%d %H:%M:%S").to_string()],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+/// 重命名账户，账户不存在时返回 false
+pub fn rename_account(conn: &Connection, id: i32, name: &str) -> Result<bool, StockError> {
+    let name = validate_account_name(conn, name, Some(id))?;
+    let affected = conn
+        .execute(
+            "UPDATE accounts SET name = ?1 WHERE id = ?2",
+            params![name, id],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 归档或恢复账户，账户不存在时返回 false
+/// 默认账户用于未指定账户的请求，不能归档
+pub fn set_account_archived(
+    conn: &Connection,
+    id: i32,
+    archived: bool,
+) -> Result<bool, StockError> {
+    if archived && id == DEFAULT_ACCOUNT_ID {
+        return Err(StockError::BusinessError("默认账户不能归档".to_string()));
+    }
+    let affected = conn
+        .execute(
+            "UPDATE accounts SET archived = ?1 WHERE id = ?2",
+            params![archived, id],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 根据ID查询账户
+pub fn query_account(conn: &Connection, id: i32) -> Result<Option<Account>, StockError> {
+    conn.query_row(
+        "SELECT id, name, archived, created_at FROM accounts WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Account {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                archived: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| StockError::DbError(e))
+}
+
+/// 查询账户列表（按创建先后），默认不含已归档账户
+pub fn query_accounts(
+    conn: &Connection,
+    include_archived: bool,
+) -> Result<Vec<Account>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, archived, created_at
+             FROM accounts
+             WHERE ?1 OR archived = 0
+             ORDER BY id ASC",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let accounts = stmt
+        .query_map(params![include_archived], |row| {
+            Ok(Account {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                archived: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<Account>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(accounts)
+}
+
+/// 账户必须存在且未归档，否则返回业务错误（录入委托、持仓和资金流水前调用）
+pub fn ensure_active_account(conn: &Connection, id: i32) -> Result<Account, StockError> {
+    match query_account(conn, id)? {
+        Some(account) if account.archived => Err(StockError::BusinessError(format!(
+            "账户「{}」已归档，不能再录入数据",
+            account.name
+        ))),
+        Some(account) => Ok(account),
+        None => Err(StockError::BusinessError(format!("账户不存在: {}", id))),
+    }
+}
+
+/// 保存一条持仓划转记录，返回记录ID
+pub fn add_transfer(conn: &Connection, transfer: &PositionTransfer) -> Result<i32, StockError> {
+    conn.execute(
+        "INSERT INTO position_transfers
+         (from_account_id, to_account_id, code, name, quantity, cost, time)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            transfer.from_account_id,
+            transfer.to_account_id,
+            transfer.code.to_uppercase(),
+            transfer.name,
+            transfer.quantity,
+            transfer.cost,
+            transfer.time
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+/// 查询持仓划转记录（按时间倒序），可按账户（转入或转出）和股票代码过滤
+pub fn query_transfers(
+    conn: &Connection,
+    account_id: Option<i32>,
+    code: Option<&str>,
+) -> Result<Vec<PositionTransfer>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, from_account_id, to_account_id, code, name, quantity, cost, time
+             FROM position_transfers
+             WHERE (?1 IS NULL OR from_account_id = ?1 OR to_account_id = ?1)
+               AND (?2 IS NULL OR code = ?2)
+             ORDER BY time DESC, id DESC",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let transfers = stmt
+        .query_map(
+            params![account_id, code.map(|code| code.to_uppercase())],
+            |row| {
+                Ok(PositionTransfer {
+                    id: row.get(0)?,
+                    from_account_id: row.get(1)?,
+                    to_account_id: row.get(2)?,
+                    code: row.get(3)?,
+                    name: row.get(4)?,
+                    quantity: row.get(5)?,
+                    cost: row.get(6)?,
+                    time: row.get(7)?,
+                })
+            },
+        )
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<PositionTransfer>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(transfers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    #[test]
+    fn create_rename_and_archive_accounts() {
+        let conn = open_test_connection();
+        // 迁移时自动创建默认账户
+        let accounts = query_accounts(&conn, false).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].id, DEFAULT_ACCOUNT_ID);
+
+        let margin = create_account(&conn, " 融资账户 ").unwrap();
+        assert_eq!(
+            query_account(&conn, margin).unwrap().unwrap().name,
+            "融资账户"
+        );
+        assert!(create_account(&conn, "融资账户").is_err());
+        assert!(create_account(&conn, "  ").is_err());
+
+        assert!(rename_account(&conn, margin, "信用账户").unwrap());
+        assert!(rename_account(&conn, margin, "信用账户").unwrap());
+        assert!(!rename_account(&conn, 99, "不存在").unwrap());
+
+        assert!(set_account_archived(&conn, margin, true).unwrap());
+        assert_eq!(query_accounts(&conn, false).unwrap().len(), 1);
+        assert_eq!(query_accounts(&conn, true).unwrap().len(), 2);
+        assert!(ensure_active_account(&conn, margin).is_err());
+        assert!(ensure_active_account(&conn, 99).is_err());
+        assert!(set_account_archived(&conn, DEFAULT_ACCOUNT_ID, true).is_err());
+
+        assert!(set_account_archived(&conn, margin, false).unwrap());
+        assert!(ensure_active_account(&conn, margin).is_ok());
+    }
+}
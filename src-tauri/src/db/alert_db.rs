@@ -0,0 +1,279 @@
+use crate::structs::alert_structs::{AlertEvent, AlertKind, AlertRule, AlertRuleParams};
+use crate::structs::StockError;
+use chrono::Local;
+use rusqlite::{params, Connection, Row};
+
+const RULE_COLUMNS: &str = "id, code, kind, threshold, enabled, cooldown_minutes, note, \
+                            created_at, last_triggered_at";
+
+// 股票代码不能为空，条件类型必须有效，阈值按类型检查范围
+fn validate_rule(params: &AlertRuleParams) -> Result<(String, AlertKind), StockError> {
+    let code = params.code.trim().to_uppercase();
+    if code.is_empty() {
+        return Err(StockError::BusinessError("股票代码不能为空".to_string()));
+    }
+    let kind = AlertKind::parse(&params.kind)
+        .ok_or_else(|| StockError::BusinessError(format!("不支持的提醒类型: {}", params.kind)))?;
+    let threshold = params.threshold;
+    let valid = threshold.is_finite()
+        && match kind {
+            AlertKind::PriceAbove | AlertKind::PriceBelow | AlertKind::VolumeRatioAbove => {
+                threshold > 0.0
+            }
+            AlertKind::PercentAbove | AlertKind::PercentBelow => threshold >= 0.0,
+            // 距涨跌停的百分比，A股单日涨跌幅最多 30%
+            AlertKind::NearLimitUp | AlertKind::NearLimitDown => (0.0..=30.0).contains(&threshold),
+        };
+    if !valid {
+        return Err(StockError::BusinessError(format!(
+            "{} 的阈值无效: {}",
+            kind.label(),
+            threshold
+        )));
+    }
+    if params.cooldown_minutes < 0 {
+        return Err(StockError::BusinessError("冷却时间不能为负数".to_string()));
+    }
+    Ok((code, kind))
+}
+
+fn row_to_rule(row: &Row) -> rusqlite::Result<AlertRule> {
+    Ok(AlertRule {
+        id: row.get(0)?,
+        code: row.get(1)?,
+        kind: row.get(2)?,
+        threshold: row.get(3)?,
+        enabled: row.get(4)?,
+        cooldown_minutes: row.get(5)?,
+        note: row.get(6)?,
+        created_at: row.get(7)?,
+        last_triggered_at: row.get(8)?,
+    })
+}
+
+/// 新增提醒规则，返回规则ID
+pub fn add_alert_rule(conn: &Connection, params: &AlertRuleParams) -> Result<i32, StockError> {
+    let (code, kind) = validate_rule(params)?;
+    conn.execute(
+        "INSERT INTO alert_rules (code, kind, threshold, enabled, cooldown_minutes, note, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            code,
+            kind.as_str(),
+            params.threshold,
+            params.enabled,
+            params.cooldown_minutes,
+            params.note.clone().unwrap_or_default(),
+            Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+/// 修改提醒规则，规则不存在时返回 false
+/// 修改后清除最近触发时间，新条件满足时可以立即提醒
+pub fn update_alert_rule(
+    conn: &Connection,
+    id: i32,
+    params: &AlertRuleParams,
+) -> Result<bool, StockError> {
+    let (code, kind) = validate_rule(params)?;
+    let affected = conn
+        .execute(
+            "UPDATE alert_rules
+             SET code = ?1, kind = ?2, threshold = ?3, enabled = ?4, cooldown_minutes = ?5,
+                 note = ?6, last_triggered_at = NULL
+             WHERE id = ?7",
+            params![
+                code,
+                kind.as_str(),
+                params.threshold,
+                params.enabled,
+                params.cooldown_minutes,
+                params.note.clone().unwrap_or_default(),
+                id
+            ],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 启用或停用提醒规则，规则不存在时返回 false
+pub fn set_alert_rule_enabled(
+    conn: &Connection,
+    id: i32,
+    enabled: bool,
+) -> Result<bool, StockError> {
+    let affected = conn
+        .execute(
+            "UPDATE alert_rules SET enabled = ?1 WHERE id = ?2",
+            params![enabled, id],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 删除提醒规则（已触发的提醒记录保留），规则不存在时返回 false
+pub fn delete_alert_rule(conn: &Connection, id: i32) -> Result<bool, StockError> {
+    let affected = conn
+        .execute("DELETE FROM alert_rules WHERE id = ?1", params![id])
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 查询提醒规则，可按股票代码过滤，only_enabled 为 true 时只返回启用的规则
+pub fn query_alert_rules(
+    conn: &Connection,
+    code: Option<&str>,
+    only_enabled: bool,
+) -> Result<Vec<AlertRule>, StockError> {
+    let code = code.map(|code| code.trim().to_uppercase());
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM alert_rules
+             WHERE (?1 IS NULL OR code = ?1) AND (?2 = 0 OR enabled = 1)
+             ORDER BY code, id",
+            RULE_COLUMNS
+        ))
+        .map_err(|e| StockError::DbError(e))?;
+    let rules = stmt
+        .query_map(params![code, only_enabled], row_to_rule)
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(rules)
+}
+
+/// 记录一次触发：写入提醒记录并更新规则的最近触发时间，返回记录ID
+pub fn record_alert_event(conn: &Connection, event: &AlertEvent) -> Result<i32, StockError> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| StockError::DbError(e))?;
+    tx.execute(
+        "INSERT INTO alert_events
+         (rule_id, code, name, kind, threshold, value, price, message, triggered_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            event.rule_id,
+            event.code,
+            event.name,
+            event.kind,
+            event.threshold,
+            event.value,
+            event.price,
+            event.message,
+            event.triggered_at
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    let id = tx.last_insert_rowid() as i32;
+    tx.execute(
+        "UPDATE alert_rules SET last_triggered_at = ?1 WHERE id = ?2",
+        params![event.triggered_at, event.rule_id],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(id)
+}
+
+/// 查询提醒记录（最新的在前），可按股票代码过滤
+pub fn query_alert_events(
+    conn: &Connection,
+    code: Option<&str>,
+    limit: i64,
+) -> Result<Vec<AlertEvent>, StockError> {
+    let code = code.map(|code| code.trim().to_uppercase());
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, rule_id, code, name, kind, threshold, value, price, message, triggered_at
+             FROM alert_events
+             WHERE ?1 IS NULL OR code = ?1
+             ORDER BY triggered_at DESC, id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    let events = stmt
+        .query_map(params![code, limit], |row| {
+            Ok(AlertEvent {
+                id: row.get(0)?,
+                rule_id: row.get(1)?,
+                code: row.get(2)?,
+                name: row.get(3)?,
+                kind: row.get(4)?,
+                threshold: row.get(5)?,
+                value: row.get(6)?,
+                price: row.get(7)?,
+                message: row.get(8)?,
+                triggered_at: row.get(9)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(events)
+}
+
+/// 清空提醒记录，返回删除的条数
+pub fn clear_alert_events(conn: &Connection) -> Result<usize, StockError> {
+    conn.execute("DELETE FROM alert_events", [])
+        .map_err(|e| StockError::DbError(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    fn rule_params(kind: &str, threshold: f64) -> AlertRuleParams {
+        AlertRuleParams {
+            code: " sh600000 ".to_string(),
+            kind: kind.to_string(),
+            threshold,
+            cooldown_minutes: 30,
+            enabled: true,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn rules_are_validated_and_events_update_last_trigger() {
+        let conn = open_test_connection();
+        assert!(add_alert_rule(&conn, &rule_params("unknown", 1.0)).is_err());
+        assert!(add_alert_rule(&conn, &rule_params("price_above", -1.0)).is_err());
+        assert!(add_alert_rule(&conn, &rule_params("near_limit_up", 50.0)).is_err());
+
+        let id = add_alert_rule(&conn, &rule_params("price_above", 10.5)).unwrap();
+        let disabled = add_alert_rule(&conn, &rule_params("percent_below", 5.0)).unwrap();
+        assert!(set_alert_rule_enabled(&conn, disabled, false).unwrap());
+        let enabled = query_alert_rules(&conn, Some("SH600000"), true).unwrap();
+        assert_eq!(enabled.len(), 1);
+        assert_eq!(enabled[0].code, "SH600000");
+
+        let event = AlertEvent {
+            id: 0,
+            rule_id: id,
+            code: "SH600000".to_string(),
+            name: "浦发银行".to_string(),
+            kind: "price_above".to_string(),
+            threshold: 10.5,
+            value: 10.6,
+            price: Some(10.6),
+            message: "浦发银行(SH600000) 价格上穿 10.50，现价 10.60".to_string(),
+            triggered_at: "2024-03-20 10:00:00".to_string(),
+        };
+        record_alert_event(&conn, &event).unwrap();
+        let rules = query_alert_rules(&conn, None, false).unwrap();
+        assert_eq!(
+            rules[0].last_triggered_at.as_deref(),
+            Some("2024-03-20 10:00:00")
+        );
+        assert_eq!(query_alert_events(&conn, None, 10).unwrap().len(), 1);
+
+        // 修改规则后可以重新触发
+        assert!(update_alert_rule(&conn, id, &rule_params("price_above", 11.0)).unwrap());
+        let rules = query_alert_rules(&conn, None, true).unwrap();
+        assert_eq!(rules[0].last_triggered_at, None);
+        assert_eq!(clear_alert_events(&conn).unwrap(), 1);
+    }
+}
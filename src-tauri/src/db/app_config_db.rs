@@ -1,25 +1,299 @@
+use crate::db::kline_cache_db::delete_cached_klines;
+use crate::secrets::{self, SecretKey};
+use crate::structs::alert_structs::AlertSettings;
+use crate::structs::backup_structs::BackupSettings;
+use crate::structs::corporate_action_structs::KlineAdjust;
+use crate::structs::holdings_structs::CostMethod;
+use crate::structs::orders_structs::FeeModel;
+use crate::structs::secret_structs::PassphraseParams;
+use crate::structs::statement_structs::StatementColumns;
 use crate::structs::StockError;
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, OptionalExtension, Result};
+use std::collections::BTreeMap;
+// 雪球 Cookie（加密保存）
+const XUEQIU_COOKIE_KEY: &str = "xueqiu_cookie";
+
 // 保存雪球 Cookie 到数据库
 pub fn save_xueqiu_cookie_to_db(conn: &mut Connection, cookie: &str) -> Result<(), StockError> {
-    // 使用 INSERT OR REPLACE：存在则更新，不存在则插入
+    set_secret_value(conn, XUEQIU_COOKIE_KEY, cookie)
+}
+
+// 从数据库读取雪球 Cookie（自动解密）
+pub fn get_xueqiu_cookie_from_db(conn: &Connection) -> Result<Option<String>, StockError> {
+    get_secret_value(conn, XUEQIU_COOKIE_KEY)
+}
+
+// 读取任意配置项（不存在时返回 None）
+pub fn get_config_value(conn: &Connection, key: &str) -> Result<Option<String>, StockError> {
+    conn.query_row(
+        "SELECT value FROM app_config WHERE key = ?1",
+        [key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| StockError::DbError(e))
+}
+
+// 保存任意配置项（存在则更新）
+pub fn set_config_value(conn: &Connection, key: &str, value: &str) -> Result<(), StockError> {
     conn.execute(
         "INSERT OR REPLACE INTO app_config (key, value) VALUES (?1, ?2)",
-        ["xueqiu_cookie", cookie],
+        [key, value],
     )
     .map_err(|e| StockError::DbError(e))?;
-
     Ok(())
 }
 
-// 从数据库读取雪球 Cookie
-pub fn get_xueqiu_cookie_from_db(conn: &Connection) -> Result<Option<String>, StockError> {
-    let mut stmt = conn
-        .prepare("SELECT value FROM app_config WHERE key = 'xueqiu_cookie'")
-        .map_err(|e| StockError::DbError(e))?;
+// 离线模式开关（"1" 表示开启）
+const OFFLINE_MODE_KEY: &str = "offline_mode";
+
+// 是否开启了离线模式
+pub fn is_offline_mode(conn: &Connection) -> Result<bool, StockError> {
+    Ok(get_config_value(conn, OFFLINE_MODE_KEY)?.as_deref() == Some("1"))
+}
+
+// 开启/关闭离线模式
+pub fn set_offline_mode(conn: &Connection, enabled: bool) -> Result<(), StockError> {
+    set_config_value(conn, OFFLINE_MODE_KEY, if enabled { "1" } else { "0" })
+}
+
+// 卖出成本计算方式（fifo / average）
+const COST_METHOD_KEY: &str = "cost_method";
+
+// 读取卖出成本计算方式，未设置时使用平均成本
+pub fn get_cost_method(conn: &Connection) -> Result<CostMethod, StockError> {
+    Ok(get_config_value(conn, COST_METHOD_KEY)?
+        .and_then(|value| CostMethod::parse(&value))
+        .unwrap_or(CostMethod::Average))
+}
+
+// 保存卖出成本计算方式
+pub fn set_cost_method(conn: &Connection, method: CostMethod) -> Result<(), StockError> {
+    set_config_value(conn, COST_METHOD_KEY, method.as_str())
+}
+
+// 交易费用模型（JSON）
+const FEE_MODEL_KEY: &str = "fee_model";
+
+// 读取交易费用模型，未设置时使用默认费率
+pub fn get_fee_model(conn: &Connection) -> Result<FeeModel, StockError> {
+    match get_config_value(conn, FEE_MODEL_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(FeeModel::default()),
+    }
+}
+
+// 保存交易费用模型（只影响之后添加的委托）
+pub fn set_fee_model(conn: &Connection, model: &FeeModel) -> Result<(), StockError> {
+    set_config_value(conn, FEE_MODEL_KEY, &serde_json::to_string(model)?)
+}
+
+// 资金严格模式开关（"1" 表示开启）
+const CASH_STRICT_MODE_KEY: &str = "cash_strict_mode";
+
+// 是否开启资金严格模式（开启后可用资金不足时拒绝买入和取出）
+pub fn is_cash_strict_mode(conn: &Connection) -> Result<bool, StockError> {
+    Ok(get_config_value(conn, CASH_STRICT_MODE_KEY)?.as_deref() == Some("1"))
+}
+
+// 开启/关闭资金严格模式
+pub fn set_cash_strict_mode(conn: &Connection, enabled: bool) -> Result<(), StockError> {
+    set_config_value(conn, CASH_STRICT_MODE_KEY, if enabled { "1" } else { "0" })
+}
+
+// 自动备份设置（JSON）
+const BACKUP_SETTINGS_KEY: &str = "backup_settings";
+
+// 读取自动备份设置，未设置时使用默认值
+pub fn get_backup_settings(conn: &Connection) -> Result<BackupSettings, StockError> {
+    match get_config_value(conn, BACKUP_SETTINGS_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(BackupSettings::default()),
+    }
+}
+
+// 保存自动备份设置
+pub fn set_backup_settings(conn: &Connection, settings: &BackupSettings) -> Result<(), StockError> {
+    set_config_value(conn, BACKUP_SETTINGS_KEY, &serde_json::to_string(settings)?)
+}
+
+// 后台行情轮询与价格提醒设置（JSON）
+const ALERT_SETTINGS_KEY: &str = "alert_settings";
 
-    // 查询结果：如果存在则返回 Some(cookie)，否则返回 None
-    let cookie: Option<String> = stmt.query_row([], |row| row.get(0)).ok(); // 用 ok() 把 Err 转为 None（表示没有记录）
+// 读取价格提醒设置，未设置时使用默认值
+pub fn get_alert_settings(conn: &Connection) -> Result<AlertSettings, StockError> {
+    match get_config_value(conn, ALERT_SETTINGS_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(AlertSettings::default()),
+    }
+}
+
+// 保存价格提醒设置
+pub fn set_alert_settings(conn: &Connection, settings: &AlertSettings) -> Result<(), StockError> {
+    set_config_value(conn, ALERT_SETTINGS_KEY, &serde_json::to_string(settings)?)
+}
+
+// 交易日历中自定义的休市日期（按年份覆盖随应用发布的休市表）
+const TRADING_HOLIDAYS_KEY: &str = "trading_holidays";
+
+// 读取自定义的休市日期：年份 -> 该年工作日休市的日期（YYYY-MM-DD）
+pub fn get_holiday_overrides(conn: &Connection) -> Result<BTreeMap<i32, Vec<String>>, StockError> {
+    match get_config_value(conn, TRADING_HOLIDAYS_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+// 保存自定义的休市日期
+pub fn set_holiday_overrides(
+    conn: &Connection,
+    overrides: &BTreeMap<i32, Vec<String>>,
+) -> Result<(), StockError> {
+    set_config_value(
+        conn,
+        TRADING_HOLIDAYS_KEY,
+        &serde_json::to_string(overrides)?,
+    )
+}
+
+// K线复权方式（normal / before / after）
+const KLINE_ADJUST_KEY: &str = "kline_adjust";
+
+// 读取K线复权方式，未设置时使用前复权
+pub fn get_kline_adjust(conn: &Connection) -> Result<KlineAdjust, StockError> {
+    Ok(get_config_value(conn, KLINE_ADJUST_KEY)?
+        .and_then(|value| KlineAdjust::parse(&value))
+        .unwrap_or(KlineAdjust::Before))
+}
+
+// 保存K线复权方式
+pub fn set_kline_adjust(conn: &Connection, adjust: KlineAdjust) -> Result<(), StockError> {
+    set_config_value(conn, KLINE_ADJUST_KEY, adjust.as_str())
+}
+
+// 切换K线复权方式并清空K线缓存（缓存中是旧复权方式的价格），返回清除的缓存条数
+pub fn change_kline_adjust(
+    conn: &mut Connection,
+    adjust: KlineAdjust,
+) -> Result<usize, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    set_kline_adjust(&tx, adjust)?;
+    let cleared = delete_cached_klines(&tx, None)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(cleared)
+}
+
+// 自定义交割单列映射（JSON），用于内置格式不支持的券商
+const STATEMENT_COLUMNS_KEY: &str = "statement_custom_columns";
+
+// 读取已保存的自定义交割单列映射
+pub fn get_statement_columns(conn: &Connection) -> Result<Option<StatementColumns>, StockError> {
+    match get_config_value(conn, STATEMENT_COLUMNS_KEY)? {
+        Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+        None => Ok(None),
+    }
+}
+
+// 保存自定义交割单列映射
+pub fn set_statement_columns(
+    conn: &Connection,
+    columns: &StatementColumns,
+) -> Result<(), StockError> {
+    set_config_value(
+        conn,
+        STATEMENT_COLUMNS_KEY,
+        &serde_json::to_string(columns)?,
+    )
+}
+
+// 需要加密保存的配置项
+const SECRET_CONFIG_KEYS: &[&str] = &[XUEQIU_COOKIE_KEY];
+
+// 密码模式的密钥派生参数（JSON），未设置时使用本机密钥文件
+const PASSPHRASE_PARAMS_KEY: &str = "secret_passphrase";
+
+/// 只保存在本机的配置项：导出备份时去掉，导入备份时不覆盖
+/// （密文离开本机后无法解密，也不应随备份文件外传）
+pub const LOCAL_ONLY_CONFIG_KEYS: &[&str] = &[XUEQIU_COOKIE_KEY, PASSPHRASE_PARAMS_KEY];
+
+// 读取敏感配置项并解密（旧版明文原样返回）
+pub fn get_secret_value(conn: &Connection, key: &str) -> Result<Option<String>, StockError> {
+    match get_config_value(conn, key)? {
+        Some(value) => Ok(Some(
+            secrets::decrypt(&value).map_err(StockError::BusinessError)?,
+        )),
+        None => Ok(None),
+    }
+}
+
+// 加密后保存敏感配置项（未解锁时返回错误，不会以明文保存）
+pub fn set_secret_value(conn: &Connection, key: &str, value: &str) -> Result<(), StockError> {
+    let encrypted = secrets::encrypt(value).map_err(StockError::BusinessError)?;
+    set_config_value(conn, key, &encrypted)
+}
+
+// 用当前密钥加密旧版以明文保存的敏感配置，返回加密的条数
+pub fn encrypt_plain_secrets(conn: &Connection) -> Result<usize, StockError> {
+    let mut count = 0;
+    for key in SECRET_CONFIG_KEYS {
+        if let Some(value) = get_config_value(conn, key)? {
+            if !secrets::is_encrypted(&value) {
+                set_secret_value(conn, key, &value)?;
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+// 更换密钥：用旧密钥解密后再用新密钥加密全部敏感配置
+pub fn reencrypt_secrets(
+    conn: &Connection,
+    old_key: &SecretKey,
+    new_key: &SecretKey,
+) -> Result<usize, StockError> {
+    let mut count = 0;
+    for key in SECRET_CONFIG_KEYS {
+        let Some(value) = get_config_value(conn, key)? else {
+            continue;
+        };
+        let plain = if secrets::is_encrypted(&value) {
+            secrets::decrypt_with(old_key, &value).map_err(StockError::BusinessError)?
+        } else {
+            value
+        };
+        let encrypted =
+            secrets::encrypt_with(new_key, &plain).map_err(StockError::BusinessError)?;
+        set_config_value(conn, key, &encrypted)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+// 读取密码模式的密钥派生参数（未设置密码时返回 None）
+pub fn get_passphrase_params(conn: &Connection) -> Result<Option<PassphraseParams>, StockError> {
+    match get_config_value(conn, PASSPHRASE_PARAMS_KEY)? {
+        Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+        None => Ok(None),
+    }
+}
 
-    Ok(cookie)
+// 保存密钥派生参数，传入 None 时删除（改回本机密钥文件）
+pub fn set_passphrase_params(
+    conn: &Connection,
+    params: Option<&PassphraseParams>,
+) -> Result<(), StockError> {
+    match params {
+        Some(params) => {
+            set_config_value(conn, PASSPHRASE_PARAMS_KEY, &serde_json::to_string(params)?)
+        }
+        None => {
+            conn.execute(
+                "DELETE FROM app_config WHERE key = ?1",
+                [PASSPHRASE_PARAMS_KEY],
+            )
+            .map_err(|e| StockError::DbError(e))?;
+            Ok(())
+        }
+    }
 }
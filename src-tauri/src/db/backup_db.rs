@@ -0,0 +1,289 @@
+use crate::db::app_config_db::LOCAL_ONLY_CONFIG_KEYS;
+use crate::db::common::APP_DB_NAME;
+use crate::db::init_db::ALL_DATABASES;
+use crate::db::migrations::current_version;
+use crate::structs::backup_structs::{BackupFile, BackupManifest};
+use chrono::Local;
+use rusqlite::backup::Backup;
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Seek, Write};
+use std::time::Duration;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 备份清单在压缩包中的文件名
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// 当前备份清单格式版本
+pub const MANIFEST_FORMAT_VERSION: u32 = 1;
+
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// 数据库快照在压缩包中的路径（与 import_database 读取的路径一致）
+fn database_entry() -> String {
+    format!("databases/{}.db", APP_DB_NAME)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// 各表的记录数（不含 SQLite 内部表）
+fn table_row_counts(conn: &Connection) -> Result<BTreeMap<String, i64>, String> {
+    let tables: Vec<String> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT name FROM sqlite_master
+                 WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+                 ORDER BY name",
+            )
+            .map_err(|e| format!("读取表列表失败: {}", e))?;
+        let names = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("读取表列表失败: {}", e))?;
+        names
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("读取表列表失败: {}", e))?
+    };
+
+    let mut counts = BTreeMap::new();
+    for table in tables {
+        let count: i64 = conn
+            .query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table), [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| format!("统计表 {} 记录数失败: {}", table, e))?;
+        counts.insert(table, count);
+    }
+    Ok(counts)
+}
+
+// 各数据库的结构版本
+fn schema_versions(conn: &Connection) -> Result<BTreeMap<String, i64>, String> {
+    ALL_DATABASES
+        .iter()
+        .map(|schema| Ok((schema.name.to_string(), current_version(conn, schema.name)?)))
+        .collect()
+}
+
+// 从快照中去掉只保存在本机的配置项（加密的 Cookie 等）
+fn remove_local_only_config(snapshot: &Connection) -> Result<(), String> {
+    for key in LOCAL_ONLY_CONFIG_KEYS {
+        snapshot
+            .execute("DELETE FROM app_config WHERE key = ?1", [key])
+            .map_err(|e| format!("清除快照中的敏感配置失败: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 导出全部数据为 ZIP：用 SQLite 在线备份接口生成一致的快照（应用运行中也可安全导出），
+/// 并附带记录应用版本、结构版本、记录数和校验和的清单
+pub fn export_database_zip(conn: &Connection) -> Result<(Vec<u8>, BackupManifest), String> {
+    let temp_dir = tempfile::tempdir().map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let snapshot_path = temp_dir.path().join("snapshot.db");
+
+    let (schemas, row_counts) = {
+        let mut snapshot =
+            Connection::open(&snapshot_path).map_err(|e| format!("创建快照文件失败: {}", e))?;
+        Backup::new(conn, &mut snapshot)
+            .and_then(|backup| backup.run_to_completion(256, Duration::from_millis(10), None))
+            .map_err(|e| format!("生成数据库快照失败: {}", e))?;
+        remove_local_only_config(&snapshot)?;
+        (schema_versions(&snapshot)?, table_row_counts(&snapshot)?)
+    };
+    let db_data = std::fs::read(&snapshot_path).map_err(|e| format!("读取快照文件失败: {}", e))?;
+
+    let entry = database_entry();
+    let manifest = BackupManifest {
+        format_version: MANIFEST_FORMAT_VERSION,
+        app_version: APP_VERSION.to_string(),
+        created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        schemas,
+        row_counts,
+        files: vec![BackupFile {
+            path: entry.clone(),
+            size: db_data.len() as u64,
+            sha256: sha256_hex(&db_data),
+        }],
+    };
+    let manifest_json =
+        serde_json::to_string_pretty(&manifest).map_err(|e| format!("生成清单失败: {}", e))?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let entries = [
+        (MANIFEST_FILE.to_string(), manifest_json.as_bytes()),
+        (entry, db_data.as_slice()),
+    ];
+    for (path, data) in entries {
+        zip.start_file(path.as_str(), options)
+            .and_then(|_| zip.write_all(data).map_err(Into::into))
+            .map_err(|e| format!("写入压缩包文件 {} 失败: {}", path, e))?;
+    }
+    let cursor = zip.finish().map_err(|e| format!("生成压缩包失败: {}", e))?;
+
+    Ok((cursor.into_inner(), manifest))
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str) -> Result<Vec<u8>, String> {
+    let mut file = archive
+        .by_name(path)
+        .map_err(|_| format!("备份中缺少文件 {}", path))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)
+        .map_err(|e| format!("读取备份文件 {} 失败: {}", path, e))?;
+    Ok(data)
+}
+
+// 打开数据库快照，核对结构版本和各表记录数与清单一致
+fn verify_database_contents(data: &[u8], manifest: &BackupManifest) -> Result<(), String> {
+    let temp_dir = tempfile::tempdir().map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let path = temp_dir.path().join("verify.db");
+    std::fs::write(&path, data).map_err(|e| format!("写入临时数据库文件失败: {}", e))?;
+    let conn = Connection::open(&path).map_err(|e| format!("打开备份数据库失败: {}", e))?;
+
+    if schema_versions(&conn)? != manifest.schemas {
+        return Err("备份数据库的结构版本与清单不一致".to_string());
+    }
+    let counts = table_row_counts(&conn)?;
+    for (table, expected) in &manifest.row_counts {
+        let actual = counts.get(table).copied().unwrap_or(0);
+        if actual != *expected {
+            return Err(format!(
+                "备份中表 {} 的记录数（{}）与清单（{}）不一致",
+                table, actual, expected
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 导入前校验备份压缩包：检查清单格式版本、结构版本是否兼容，文件大小和校验和，以及各表记录数
+/// 旧版备份没有清单，返回 None（按原方式导入）
+pub fn validate_backup_archive<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+) -> Result<Option<BackupManifest>, String> {
+    let manifest: BackupManifest = match read_entry(archive, MANIFEST_FILE) {
+        Ok(data) => {
+            serde_json::from_slice(&data).map_err(|e| format!("备份清单无法解析: {}", e))?
+        }
+        Err(_) => return Ok(None),
+    };
+
+    if manifest.format_version > MANIFEST_FORMAT_VERSION {
+        return Err(format!(
+            "备份清单格式版本（{}）高于当前应用支持的版本（{}），请升级应用后再导入",
+            manifest.format_version, MANIFEST_FORMAT_VERSION
+        ));
+    }
+    for (name, version) in &manifest.schemas {
+        let Some(schema) = ALL_DATABASES.iter().find(|schema| schema.name == name) else {
+            return Err(format!(
+                "备份包含当前应用不支持的数据库 {}，请升级应用后再导入",
+                name
+            ));
+        };
+        if *version > schema.latest_version() {
+            return Err(format!(
+                "备份中数据库 {} 的结构版本（{}）高于当前应用支持的版本（{}），请升级应用后再导入",
+                name,
+                version,
+                schema.latest_version()
+            ));
+        }
+    }
+
+    for file in &manifest.files {
+        let data = read_entry(archive, &file.path)?;
+        if data.len() as u64 != file.size || sha256_hex(&data) != file.sha256 {
+            return Err(format!("备份文件 {} 校验失败，压缩包可能已损坏", file.path));
+        }
+        if file.path == database_entry() {
+            verify_database_contents(&data, &manifest)?;
+        }
+    }
+
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    fn open_archive(data: Vec<u8>) -> ZipArchive<Cursor<Vec<u8>>> {
+        ZipArchive::new(Cursor::new(data)).unwrap()
+    }
+
+    // 用给定的清单和数据库内容重新打包
+    fn repack(manifest: &BackupManifest, db_data: &[u8]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(MANIFEST_FILE, FileOptions::default())
+            .unwrap();
+        zip.write_all(serde_json::to_string(manifest).unwrap().as_bytes())
+            .unwrap();
+        zip.start_file(database_entry(), FileOptions::default())
+            .unwrap();
+        zip.write_all(db_data).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn export_round_trips_through_validation() {
+        let conn = open_test_connection();
+        conn.execute(
+            "INSERT INTO my_selection (code, name, sort) VALUES ('SH600000', '浦发银行', 1)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO app_config (key, value) VALUES ('xueqiu_cookie', 'enc:v1:secret'), ('offline_mode', '1')",
+            [],
+        )
+        .unwrap();
+
+        let (data, manifest) = export_database_zip(&conn).unwrap();
+        assert_eq!(manifest.row_counts["my_selection"], 1);
+        // 敏感配置不随备份导出
+        assert_eq!(manifest.row_counts["app_config"], 1);
+        assert_eq!(manifest.schemas["holdings"], 5);
+        assert_eq!(manifest.files[0].path, "databases/app.db");
+
+        let validated = validate_backup_archive(&mut open_archive(data.clone())).unwrap();
+        assert_eq!(validated, Some(manifest.clone()));
+
+        // 记录数与清单不一致
+        let db_data = read_entry(&mut open_archive(data), &database_entry()).unwrap();
+        let mut wrong_count = manifest.clone();
+        wrong_count.row_counts.insert("my_selection".to_string(), 2);
+        let archive = repack(&wrong_count, &db_data);
+        assert!(validate_backup_archive(&mut open_archive(archive)).is_err());
+
+        // 文件内容被修改
+        let mut corrupted = db_data.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xFF;
+        let archive = repack(&manifest, &corrupted);
+        assert!(validate_backup_archive(&mut open_archive(archive)).is_err());
+
+        // 来自更新版本应用的备份
+        let mut newer = manifest;
+        newer.schemas.insert("holdings".to_string(), 99);
+        let archive = repack(&newer, &db_data);
+        assert!(validate_backup_archive(&mut open_archive(archive)).is_err());
+    }
+
+    #[test]
+    fn legacy_backup_without_manifest_is_accepted() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("orders.db", FileOptions::default()).unwrap();
+        zip.write_all(b"legacy").unwrap();
+        let data = zip.finish().unwrap().into_inner();
+        assert_eq!(
+            validate_backup_archive(&mut open_archive(data)).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use crate::db::account_db::ensure_active_account;
+use crate::db::app_config_db::is_cash_strict_mode;
+use crate::db::position_db::normalize_order_time;
+use crate::structs::cash_structs::{
+    AccountSummary, AddCashEntryParams, CashFlow, CashKind, PositionValue,
+};
+use crate::structs::holdings_structs::Holding;
+use crate::structs::StockError;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, HashMap};
+
+/// 当前现金余额（手动流水 + 全部委托的买卖金额），指定账户时只计该账户
+pub fn query_cash_balance(conn: &Connection, account_id: Option<i32>) -> Result<f64, StockError> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(amount), 0) FROM cash_flows WHERE ?1 IS NULL OR account_id = ?1",
+        params![account_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| StockError::DbError(e))
+}
+
+/// 严格模式下账户余额在任何时点都不能为负，否则返回业务错误（在写入后调用，由事务回滚）
+/// 按时间顺序累计资金流水，补录的早期买入即使最终余额足够也会被拒绝
+pub fn ensure_cash_available(conn: &Connection, account_id: i32) -> Result<(), StockError> {
+    if !is_cash_strict_mode(conn)? {
+        return Ok(());
+    }
+    let mut stmt = conn
+        .prepare("SELECT time, amount FROM cash_flows WHERE account_id = ?1")
+        .map_err(|e| StockError::DbError(e))?;
+    let flows = stmt
+        .query_map(params![account_id], |row| {
+            Ok((
+                normalize_order_time(&row.get::<_, String>(0)?),
+                row.get::<_, f64>(1)?,
+            ))
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<(String, f64)>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    // 同一时间的流水先合并再累计，不区分先后
+    let mut by_time: BTreeMap<String, f64> = BTreeMap::new();
+    for (time, amount) in flows {
+        *by_time.entry(time).or_default() += amount;
+    }
+    let mut balance = 0.0;
+    for (time, amount) in by_time {
+        balance += amount;
+        if balance < -1e-6 {
+            return Err(StockError::BusinessError(format!(
+                "可用资金不足：{} 时余额还差 {:.2} 元",
+                time, -balance
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 添加一条手动资金流水，返回新记录ID
+/// 现金分红只能通过公司行动产生，避免与手动记录重复入账
+/// 严格模式下转出和费用超过可用资金时拒绝
+pub fn add_cash_entry(conn: &mut Connection, req: &AddCashEntryParams) -> Result<i32, StockError> {
+    let kind = CashKind::parse(&req.kind)
+        .ok_or_else(|| StockError::BusinessError(format!("不支持的资金类型: {}", req.kind)))?;
+    if kind == CashKind::Dividend {
+        return Err(StockError::BusinessError(
+            "现金分红由公司行动自动计入，请录入公司行动".to_string(),
+        ));
+    }
+    if !req.amount.is_finite() || req.amount <= 0.0 {
+        return Err(StockError::BusinessError(format!(
+            "金额必须大于0: {}",
+            req.amount
+        )));
+    }
+    let code = req
+        .code
+        .as_deref()
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty());
+
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    ensure_active_account(&tx, req.account_id)?;
+    tx.execute(
+        "INSERT INTO cash_ledger (time, kind, amount, code, note, account_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            normalize_order_time(&req.time),
+            kind.as_str(),
+            kind.sign() * req.amount,
+            code,
+            req.note.as_deref().unwrap_or(""),
+            req.account_id
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    let id = tx.last_insert_rowid() as i32;
+    if kind.sign() < 0.0 {
+        ensure_cash_available(&tx, req.account_id)?;
+    }
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(id)
+}
+
+/// 删除手动资金流水（委托产生的流水随委托删除），不存在时返回 false
+/// 严格模式下删除后余额为负（如删除已被买入使用的转入）时拒绝
+pub fn delete_cash_entry(conn: &mut Connection, id: i32) -> Result<bool, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    let account_id: Option<i32> = tx
+        .query_row(
+            "SELECT account_id FROM cash_ledger WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| StockError::DbError(e))?;
+    let Some(account_id) = account_id else {
+        return Ok(false);
+    };
+    tx.execute("DELETE FROM cash_ledger WHERE id = ?1", params![id])
+        .map_err(|e| StockError::DbError(e))?;
+    ensure_cash_available(&tx, account_id)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(true)
+}
+
+/// 查询资金流水（按时间倒序，最新的在前），指定账户时只查该账户
+pub fn query_cash_flows(
+    conn: &Connection,
+    account_id: Option<i32>,
+) -> Result<Vec<CashFlow>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, order_id, time, kind, amount, code, note, account_id
+             FROM cash_flows
+             WHERE ?1 IS NULL OR account_id = ?1",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let mut flows = stmt
+        .query_map(params![account_id], |row| {
+            Ok(CashFlow {
+                id: row.get(0)?,
+                order_id: row.get(1)?,
+                time: normalize_order_time(&row.get::<_, String>(2)?),
+                kind: row.get(3)?,
+                amount: row.get(4)?,
+                code: row.get(5)?,
+                note: row.get(6)?,
+                account_id: row.get(7)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<CashFlow>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    // 委托时间可能是 ISO 格式，统一格式后再排序
+    flows.sort_by(|a, b| b.time.cmp(&a.time).then(b.id.cmp(&a.id)));
+    Ok(flows)
+}
+
+/// 按最新价计算账户总览，prices 中没有的股票按成本价估值
+pub fn summarize_account(
+    cash: f64,
+    holdings: &[Holding],
+    prices: &HashMap<String, f64>,
+    strict_mode: bool,
+) -> AccountSummary {
+    let positions: Vec<PositionValue> = holdings
+        .iter()
+        .map(|holding| {
+            let price = prices.get(&holding.code.to_uppercase()).copied();
+            let current = price.unwrap_or(holding.cost);
+            let market_value = current * holding.quantity as f64;
+            PositionValue {
+                code: holding.code.clone(),
+                name: holding.name.clone(),
+                quantity: holding.quantity,
+                cost: holding.cost,
+                price: current,
+                market_value,
+                unrealized_profit: market_value - holding.cost * holding.quantity as f64,
+                priced: price.is_some(),
+            }
+        })
+        .collect();
+    let market_value: f64 = positions.iter().map(|p| p.market_value).sum();
+
+    AccountSummary {
+        cash,
+        market_value,
+        total_equity: cash + market_value,
+        positions,
+        strict_mode,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::account_db::create_account;
+    use crate::db::app_config_db::{set_cash_strict_mode, set_fee_model};
+    use crate::db::common::open_test_connection;
+    use crate::db::holdings_db::query_holdings;
+    use crate::db::position_db::{
+        record_corporate_action, record_order, remove_corporate_action, remove_order,
+    };
+    use crate::structs::account_structs::DEFAULT_ACCOUNT_ID;
+    use crate::structs::corporate_action_structs::CorporateAction;
+    use crate::structs::orders_structs::{AddOrderParams, FeeModel};
+
+    fn entry(kind: &str, amount: f64) -> AddCashEntryParams {
+        AddCashEntryParams {
+            time: "2024-01-01 09:00:00".to_string(),
+            kind: kind.to_string(),
+            amount,
+            code: None,
+            note: None,
+            account_id: DEFAULT_ACCOUNT_ID,
+        }
+    }
+
+    fn buy(quantity: i32, cost: f64) -> AddOrderParams {
+        AddOrderParams {
+            code: "sz000001".to_string(),
+            name: "平安银行".to_string(),
+            time: "2024-01-02T02:00:00.000Z".to_string(),
+            quantity,
+            cost,
+            action: "1".to_string(),
+            account_id: DEFAULT_ACCOUNT_ID,
+        }
+    }
+
+    fn fee_free_connection() -> Connection {
+        let conn = open_test_connection();
+        let zero = FeeModel {
+            commission_rate: 0.0,
+            min_commission: 0.0,
+            stamp_duty_rate: 0.0,
+            transfer_fee_rate: 0.0,
+        };
+        set_fee_model(&conn, &zero).unwrap();
+        conn
+    }
+
+    #[test]
+    fn orders_debit_and_credit_cash() {
+        let mut conn = fee_free_connection();
+        add_cash_entry(&mut conn, &entry("deposit", 10_000.0)).unwrap();
+        let order_id = record_order(&mut conn, &buy(100, 10.0)).unwrap();
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 9_000.0);
+
+        let flows = query_cash_flows(&conn, None).unwrap();
+        assert_eq!(flows.len(), 2);
+        assert_eq!(flows[0].kind, "buy");
+        assert_eq!(flows[0].order_id, Some(order_id));
+        assert_eq!(flows[0].amount, -1_000.0);
+
+        // 删除委托后资金自动回退
+        remove_order(&mut conn, order_id).unwrap();
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 10_000.0);
+
+        add_cash_entry(&mut conn, &entry("withdraw", 500.0)).unwrap();
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 9_500.0);
+        assert!(add_cash_entry(&mut conn, &entry("bonus", 1.0)).is_err());
+        assert!(add_cash_entry(&mut conn, &entry("deposit", -1.0)).is_err());
+    }
+
+    #[test]
+    fn strict_mode_rejects_overdraft() {
+        let mut conn = fee_free_connection();
+        add_cash_entry(&mut conn, &entry("deposit", 1_000.0)).unwrap();
+
+        // 未开启严格模式时允许透支
+        record_order(&mut conn, &buy(100, 11.0)).unwrap();
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), -100.0);
+
+        set_cash_strict_mode(&conn, true).unwrap();
+        let result = record_order(&mut conn, &buy(100, 1.0));
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+        assert_eq!(query_holdings(&conn, None).unwrap()[0].quantity, 100);
+
+        add_cash_entry(&mut conn, &entry("deposit", 200.0)).unwrap();
+        record_order(&mut conn, &buy(100, 1.0)).unwrap();
+        assert!(add_cash_entry(&mut conn, &entry("withdraw", 1.0)).is_err());
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn strict_mode_rejects_deleting_spent_deposit() {
+        let mut conn = fee_free_connection();
+        let deposit = add_cash_entry(&mut conn, &entry("deposit", 1_000.0)).unwrap();
+        let spare = add_cash_entry(&mut conn, &entry("deposit", 100.0)).unwrap();
+        record_order(&mut conn, &buy(100, 10.0)).unwrap();
+        set_cash_strict_mode(&conn, true).unwrap();
+
+        // 转入已被买入使用，删除后余额为负
+        let result = delete_cash_entry(&mut conn, deposit);
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 100.0);
+
+        assert!(delete_cash_entry(&mut conn, spare).unwrap());
+        assert!(!delete_cash_entry(&mut conn, spare).unwrap());
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn strict_mode_checks_balance_over_time() {
+        let mut conn = fee_free_connection();
+        add_cash_entry(&mut conn, &entry("deposit", 1_000.0)).unwrap();
+        set_cash_strict_mode(&conn, true).unwrap();
+        record_order(&mut conn, &buy(100, 10.0)).unwrap();
+        let mut sell = buy(100, 12.0);
+        sell.action = "2".to_string();
+        sell.time = "2024-01-03 10:00:00".to_string();
+        let sell_id = record_order(&mut conn, &sell).unwrap();
+        let mut rebuy = buy(100, 12.0);
+        rebuy.time = "2024-01-04 10:00:00".to_string();
+        record_order(&mut conn, &rebuy).unwrap();
+
+        // 卖出所得已被之后的买入使用，删除卖出委托会透支
+        let result = remove_order(&mut conn, sell_id);
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 0.0);
+
+        // 最终余额足够，但补录的早期买入发生在转入之前
+        add_cash_entry(&mut conn, &entry("deposit", 500.0)).unwrap();
+        let mut early = buy(10, 10.0);
+        early.time = "2023-12-29 10:00:00".to_string();
+        assert!(record_order(&mut conn, &early).is_err());
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 500.0);
+    }
+
+    #[test]
+    fn strict_mode_rejects_removing_spent_dividend() {
+        let mut conn = fee_free_connection();
+        add_cash_entry(&mut conn, &entry("deposit", 1_000.0)).unwrap();
+        record_order(&mut conn, &buy(100, 10.0)).unwrap();
+        let action = CorporateAction {
+            id: 0,
+            code: "SZ000001".to_string(),
+            ex_date: "2024-01-05".to_string(),
+            cash_dividend: 1.0,
+            bonus_shares: 0.0,
+            transfer_shares: 0.0,
+            split_ratio: 1.0,
+            source: "manual".to_string(),
+            note: String::new(),
+        };
+        let action_id = record_corporate_action(&mut conn, &action).unwrap();
+        set_cash_strict_mode(&conn, true).unwrap();
+        let mut withdraw = entry("withdraw", 100.0);
+        withdraw.time = "2024-01-06 09:00:00".to_string();
+        add_cash_entry(&mut conn, &withdraw).unwrap();
+
+        // 分红已被转出，删除公司行动后余额为负
+        let result = remove_corporate_action(&mut conn, action_id);
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn legacy_manual_dividend_is_not_counted_twice() {
+        let mut conn = fee_free_connection();
+        add_cash_entry(&mut conn, &entry("deposit", 1_000.0)).unwrap();
+        record_order(&mut conn, &buy(100, 10.0)).unwrap();
+        // 旧版本允许手动录入分红
+        conn.execute(
+            "INSERT INTO cash_ledger (time, kind, amount, code, note, account_id)
+             VALUES ('2024-01-08 09:00:00', 'dividend', 100.0, 'sz000001', '', 1)",
+            [],
+        )
+        .unwrap();
+        assert!(add_cash_entry(&mut conn, &entry("dividend", 100.0)).is_err());
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 100.0);
+
+        // 录入对应的公司行动后，手动分红被标记且不再计入余额
+        let action = CorporateAction {
+            id: 0,
+            code: "SZ000001".to_string(),
+            ex_date: "2024-01-05".to_string(),
+            cash_dividend: 1.0,
+            bonus_shares: 0.0,
+            transfer_shares: 0.0,
+            split_ratio: 1.0,
+            source: "manual".to_string(),
+            note: String::new(),
+        };
+        record_corporate_action(&mut conn, &action).unwrap();
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 100.0);
+        let flows = query_cash_flows(&conn, None).unwrap();
+        assert_eq!(flows[0].kind, "duplicate_dividend");
+        assert_eq!(flows[0].amount, 0.0);
+        assert_eq!(flows[1].kind, "dividend");
+    }
+
+    #[test]
+    fn strict_mode_checks_each_account() {
+        let mut conn = fee_free_connection();
+        let family = create_account(&conn, "家庭账户").unwrap();
+        set_cash_strict_mode(&conn, true).unwrap();
+        add_cash_entry(&mut conn, &entry("deposit", 1_000.0)).unwrap();
+
+        // 其他账户的资金不能用于本账户买入
+        let mut order = buy(100, 5.0);
+        order.account_id = family;
+        assert!(record_order(&mut conn, &order).is_err());
+
+        let mut deposit = entry("deposit", 500.0);
+        deposit.account_id = family;
+        add_cash_entry(&mut conn, &deposit).unwrap();
+        record_order(&mut conn, &order).unwrap();
+        assert_eq!(query_cash_balance(&conn, Some(family)).unwrap(), 0.0);
+        assert_eq!(query_cash_balance(&conn, None).unwrap(), 1_000.0);
+        assert_eq!(query_cash_flows(&conn, Some(family)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn account_summary_uses_latest_prices() {
+        let mut conn = fee_free_connection();
+        add_cash_entry(&mut conn, &entry("deposit", 2_000.0)).unwrap();
+        record_order(&mut conn, &buy(100, 10.0)).unwrap();
+
+        let holdings = query_holdings(&conn, None).unwrap();
+        let cash = query_cash_balance(&conn, None).unwrap();
+        let prices = HashMap::from([("SZ000001".to_string(), 12.0)]);
+        let summary = summarize_account(cash, &holdings, &prices, false);
+        assert_eq!(summary.market_value, 1_200.0);
+        assert_eq!(summary.total_equity, 2_200.0);
+        assert_eq!(summary.positions[0].unrealized_profit, 200.0);
+
+        let summary = summarize_account(cash, &holdings, &HashMap::new(), false);
+        assert!(!summary.positions[0].priced);
+        assert_eq!(summary.total_equity, 2_000.0);
+    }
+}
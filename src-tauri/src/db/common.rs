@@ -1,6 +1,9 @@
+use crate::structs::StockError;
 use rusqlite::{Connection, Result};
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 use tauri_plugin_log::log;
 
@@ -24,7 +27,7 @@ where
 }
 
 // 获取数据库目录路径
-fn get_db_directory(app: &AppHandle) -> Result<PathBuf, String> {
+pub fn get_db_directory(app: &AppHandle) -> Result<PathBuf, String> {
     // 获取应用数据目录
     let data_dir = app
         .path()
@@ -43,20 +46,63 @@ fn get_db_directory(app: &AppHandle) -> Result<PathBuf, String> {
 }
 
 // 获取指定名称的数据库文件路径
-fn get_db_path(app: &AppHandle, db_name: &str) -> Result<PathBuf, String> {
+pub fn get_db_path(app: &AppHandle, db_name: &str) -> Result<PathBuf, String> {
     let db_dir = get_db_directory(app)?;
     // 为数据库文件添加.db扩展名
     let db_filename = format!("{}.db", db_name);
     Ok(db_dir.join(db_filename))
 }
 
-// 初始化指定的数据库
-pub fn init_database(app: &AppHandle, db_name: &str) -> Result<Connection, String> {
-    // 获取指定数据库的路径
-    let db_path = get_db_path(app, db_name)?;
+/// 应用唯一的数据库文件名（所有业务表都存放在 app.db 中）
+pub const APP_DB_NAME: &str = "app";
 
-    // 连接数据库（如果不存在会自动创建）
-    let conn = Connection::open(db_path).map_err(|e| format!("无法打开数据库: {}", e))?;
+/// 全局共享的数据库连接（由 Tauri managed state 持有）
+pub struct AppDb {
+    conn: Mutex<Connection>,
+}
+
+impl AppDb {
+    /// 打开数据库文件并开启 WAL 模式与外键约束
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| format!("无法打开数据库: {}", e))?;
+        Self::from_connection(conn)
+    }
+
+    /// 用已有连接构造（统一设置连接参数）
+    pub fn from_connection(conn: Connection) -> Result<Self, String> {
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(|e| format!("无法开启 WAL 模式: {}", e))?;
+        conn.pragma_update(None, "foreign_keys", "ON")
+            .map_err(|e| format!("无法开启外键约束: {}", e))?;
+        conn.busy_timeout(Duration::from_secs(5))
+            .map_err(|e| format!("无法设置繁忙等待时间: {}", e))?;
+
+        Ok(AppDb {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// 获取数据库连接（同一时间只有一个命令持有连接）
+    pub fn conn(&self) -> Result<MutexGuard<'_, Connection>, StockError> {
+        self.conn
+            .lock()
+            .map_err(|e| StockError::BusinessError(format!("获取数据库连接失败: {}", e)))
+    }
+
+    /// 持有连接执行一段数据库操作（命令层统一通过它访问 db 模块）
+    pub fn with_conn<T, F>(&self, f: F) -> Result<T, StockError>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, StockError>,
+    {
+        let mut conn = self.conn()?;
+        f(&mut conn)
+    }
+}
 
-    Ok(conn)
+/// 测试用：创建已执行全部迁移的内存数据库
+#[cfg(test)]
+pub fn open_test_connection() -> Connection {
+    let mut conn = Connection::open_in_memory().expect("无法创建内存数据库");
+    crate::db::init_db::migrate_all(&mut conn).expect("迁移内存数据库失败");
+    conn
 }
@@ -0,0 +1,211 @@
+use crate::structs::corporate_action_structs::CorporateAction;
+use crate::structs::StockError;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+
+// 除权日必须是 YYYY-MM-DD，各项数值不能为负，拆合股比例必须大于0，且至少包含一项权益
+fn validate_action(action: &CorporateAction) -> Result<(), StockError> {
+    if NaiveDate::parse_from_str(action.ex_date.trim(), "%Y-%m-%d").is_err() {
+        return Err(StockError::BusinessError(format!(
+            "除权除息日格式应为 YYYY-MM-DD: {}",
+            action.ex_date
+        )));
+    }
+    let amounts = [
+        action.cash_dividend,
+        action.bonus_shares,
+        action.transfer_shares,
+    ];
+    if amounts
+        .iter()
+        .any(|value| !value.is_finite() || *value < 0.0)
+    {
+        return Err(StockError::BusinessError(
+            "派息、送股和转增不能为负数".to_string(),
+        ));
+    }
+    if !action.split_ratio.is_finite() || action.split_ratio <= 0.0 {
+        return Err(StockError::BusinessError(format!(
+            "拆合股比例必须大于0: {}",
+            action.split_ratio
+        )));
+    }
+    if action.cash_dividend == 0.0 && (action.share_factor() - 1.0).abs() < 1e-9 {
+        return Err(StockError::BusinessError(
+            "公司行动至少需要包含派息、送转或拆合股中的一项".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn insert_action(
+    conn: &Connection,
+    action: &CorporateAction,
+    ignore_existing: bool,
+) -> Result<usize, StockError> {
+    let sql = format!(
+        "INSERT {} INTO corporate_actions
+         (code, ex_date, cash_dividend, bonus_shares, transfer_shares, split_ratio, source, note)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        if ignore_existing { "OR IGNORE" } else { "" }
+    );
+    conn.execute(
+        &sql,
+        params![
+            action.code.trim().to_uppercase(),
+            action.ex_date.trim(),
+            action.cash_dividend,
+            action.bonus_shares,
+            action.transfer_shares,
+            action.split_ratio,
+            action.source,
+            action.note
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))
+}
+
+/// 新增一条公司行动，返回记录ID
+/// 同一股票同一除权日只能有一条记录
+pub fn add_corporate_action(
+    conn: &Connection,
+    action: &CorporateAction,
+) -> Result<i32, StockError> {
+    validate_action(action)?;
+    let code = action.code.trim().to_uppercase();
+    let existed: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM corporate_actions WHERE code = ?1 AND ex_date = ?2",
+            params![code, action.ex_date.trim()],
+            |row| Ok(row.get::<_, i32>(0)? > 0),
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    if existed {
+        return Err(StockError::BusinessError(format!(
+            "{} 在 {} 已有除权除息记录",
+            code, action.ex_date
+        )));
+    }
+    insert_action(conn, action, false)?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+/// 保存数据源获取的公司行动，已存在同一除权日的记录（包括手动录入的）保持不变
+/// 返回新增的记录数，无效的记录直接跳过
+pub fn save_fetched_actions(
+    conn: &Connection,
+    actions: &[CorporateAction],
+) -> Result<usize, StockError> {
+    let mut added = 0;
+    for action in actions {
+        if validate_action(action).is_err() {
+            continue;
+        }
+        added += insert_action(conn, action, true)?;
+    }
+    Ok(added)
+}
+
+/// 删除公司行动，返回被删除记录的股票代码（不存在时为空）
+pub fn delete_corporate_action(conn: &Connection, id: i32) -> Result<Option<String>, StockError> {
+    let code: Option<String> = conn
+        .query_row(
+            "SELECT code FROM corporate_actions WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| StockError::DbError(e))?;
+    if code.is_some() {
+        conn.execute("DELETE FROM corporate_actions WHERE id = ?1", params![id])
+            .map_err(|e| StockError::DbError(e))?;
+    }
+    Ok(code)
+}
+
+/// 查询公司行动（按除权日先后），可按股票代码过滤
+pub fn query_corporate_actions(
+    conn: &Connection,
+    code: Option<&str>,
+) -> Result<Vec<CorporateAction>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, code, ex_date, cash_dividend, bonus_shares, transfer_shares,
+                    split_ratio, source, note
+             FROM corporate_actions
+             WHERE ?1 IS NULL OR code = ?1
+             ORDER BY ex_date ASC, id ASC",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let actions = stmt
+        .query_map(params![code.map(|code| code.to_uppercase())], |row| {
+            Ok(CorporateAction {
+                id: row.get(0)?,
+                code: row.get(1)?,
+                ex_date: row.get(2)?,
+                cash_dividend: row.get(3)?,
+                bonus_shares: row.get(4)?,
+                transfer_shares: row.get(5)?,
+                split_ratio: row.get(6)?,
+                source: row.get(7)?,
+                note: row.get(8)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<CorporateAction>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+    use crate::structs::corporate_action_structs::MANUAL_SOURCE;
+
+    fn action(ex_date: &str, cash_dividend: f64, bonus_shares: f64) -> CorporateAction {
+        CorporateAction {
+            id: 0,
+            code: "sh600000".to_string(),
+            ex_date: ex_date.to_string(),
+            cash_dividend,
+            bonus_shares,
+            transfer_shares: 0.0,
+            split_ratio: 1.0,
+            source: MANUAL_SOURCE.to_string(),
+            note: String::new(),
+        }
+    }
+
+    #[test]
+    fn validates_and_deduplicates_actions() {
+        let conn = open_test_connection();
+        let id = add_corporate_action(&conn, &action("2024-06-20", 0.3, 0.0)).unwrap();
+        assert!(add_corporate_action(&conn, &action("2024-06-20", 0.1, 0.0)).is_err());
+        assert!(add_corporate_action(&conn, &action("2024/06/21", 0.1, 0.0)).is_err());
+        assert!(add_corporate_action(&conn, &action("2024-06-21", -0.1, 0.0)).is_err());
+        assert!(add_corporate_action(&conn, &action("2024-06-21", 0.0, 0.0)).is_err());
+
+        // 数据源的记录不会覆盖手动录入的同日记录
+        let fetched = [
+            action("2024-06-20", 0.5, 0.0),
+            action("2023-06-20", 0.0, 0.3),
+            action("bad", 0.1, 0.0),
+        ];
+        assert_eq!(save_fetched_actions(&conn, &fetched).unwrap(), 1);
+
+        let actions = query_corporate_actions(&conn, Some("SH600000")).unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].ex_date, "2023-06-20");
+        assert_eq!(actions[1].cash_dividend, 0.3);
+        assert_eq!(actions[1].code, "SH600000");
+
+        assert_eq!(
+            delete_corporate_action(&conn, id).unwrap().as_deref(),
+            Some("SH600000")
+        );
+        assert_eq!(delete_corporate_action(&conn, id).unwrap(), None);
+    }
+}
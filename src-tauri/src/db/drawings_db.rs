@@ -0,0 +1,313 @@
+use crate::structs::drawings_structs::{Drawing, DrawingKind, DrawingParams, DrawingPoint};
+use crate::structs::StockError;
+use chrono::Local;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Row};
+
+pub(crate) const DRAWING_COLUMNS: &str = "id, code, period, kind, points, style, label, \
+                                          alert_enabled, alert_mode, tolerance, created_at, \
+                                          updated_at";
+
+// 读取 JSON 文本列
+fn json_column<T: serde::de::DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(index)?;
+    serde_json::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+pub(crate) fn row_to_drawing(row: &Row) -> rusqlite::Result<Drawing> {
+    Ok(Drawing {
+        id: row.get(0)?,
+        code: row.get(1)?,
+        period: row.get(2)?,
+        kind: row.get(3)?,
+        points: json_column::<Vec<DrawingPoint>>(row, 4)?,
+        style: json_column::<serde_json::Value>(row, 5)?,
+        label: row.get(6)?,
+        alert_enabled: row.get(7)?,
+        alert_mode: row.get(8)?,
+        tolerance: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+/// SQL 中可开启突破提醒的画线类型列表，如 'trend_line', 'ray'
+pub(crate) fn alertable_kinds_sql() -> String {
+    DrawingKind::ALERTABLE
+        .iter()
+        .map(|kind| format!("'{}'", kind.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// 锚点数量必须与类型匹配，时间和价格必须为正数
+fn validate_points(kind: DrawingKind, points: &[DrawingPoint]) -> Result<(), StockError> {
+    if points.len() != kind.point_count() {
+        return Err(StockError::BusinessError(format!(
+            "{} 需要 {} 个锚点，实际 {} 个",
+            kind.as_str(),
+            kind.point_count(),
+            points.len()
+        )));
+    }
+    if points
+        .iter()
+        .any(|point| point.time <= 0 || !point.price.is_finite() || point.price <= 0.0)
+    {
+        return Err(StockError::BusinessError(
+            "锚点的时间和价格必须为正数".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// 校验画线参数，返回 (股票代码, 类型, 锚点 JSON, 样式 JSON)
+fn validate_drawing(
+    params: &DrawingParams,
+) -> Result<(String, DrawingKind, String, String), StockError> {
+    let code = params.code.trim().to_uppercase();
+    if code.is_empty() || params.period.trim().is_empty() {
+        return Err(StockError::BusinessError(
+            "股票代码和周期不能为空".to_string(),
+        ));
+    }
+    let kind = DrawingKind::parse(&params.kind)
+        .ok_or_else(|| StockError::BusinessError(format!("不支持的画线类型: {}", params.kind)))?;
+    validate_points(kind, &params.points)?;
+    let style = match &params.style {
+        serde_json::Value::Null => serde_json::json!({}),
+        serde_json::Value::Object(_) => params.style.clone(),
+        _ => {
+            return Err(StockError::BusinessError(
+                "画线样式必须是 JSON 对象".to_string(),
+            ))
+        }
+    };
+    Ok((
+        code,
+        kind,
+        serde_json::to_string(&params.points)?,
+        serde_json::to_string(&style)?,
+    ))
+}
+
+/// 新增画线，返回画线ID
+pub fn add_drawing(conn: &Connection, params: &DrawingParams) -> Result<i32, StockError> {
+    let (code, kind, points, style) = validate_drawing(params)?;
+    let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO drawings (code, period, kind, points, style, label, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        params![
+            code,
+            params.period.trim(),
+            kind.as_str(),
+            points,
+            style,
+            params.label,
+            now
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+/// 修改画线（提醒设置保留；改为不支持提醒的类型时关闭提醒），画线不存在时返回 false
+pub fn update_drawing(
+    conn: &Connection,
+    id: i32,
+    params: &DrawingParams,
+) -> Result<bool, StockError> {
+    let (code, kind, points, style) = validate_drawing(params)?;
+    let affected = conn
+        .execute(
+            "UPDATE drawings
+             SET code = ?1, period = ?2, kind = ?3, points = ?4, style = ?5, label = ?6,
+                 alert_enabled = CASE WHEN ?7 THEN alert_enabled ELSE 0 END, updated_at = ?8
+             WHERE id = ?9",
+            params![
+                code,
+                params.period.trim(),
+                kind.as_str(),
+                points,
+                style,
+                params.label,
+                kind.is_alertable(),
+                Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                id
+            ],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 只修改画线的锚点（拖动画线时使用），画线不存在或类型不符时返回 false
+pub fn update_drawing_points(
+    conn: &Connection,
+    id: i32,
+    kind: DrawingKind,
+    points: &[DrawingPoint],
+) -> Result<bool, StockError> {
+    validate_points(kind, points)?;
+    let affected = conn
+        .execute(
+            "UPDATE drawings SET points = ?1, updated_at = ?2 WHERE id = ?3 AND kind = ?4",
+            params![
+                serde_json::to_string(points)?,
+                Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                id,
+                kind.as_str()
+            ],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 删除画线（突破记录保留），画线不存在时返回 false
+pub fn delete_drawing(conn: &Connection, id: i32) -> Result<bool, StockError> {
+    let affected = conn
+        .execute("DELETE FROM drawings WHERE id = ?1", params![id])
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected > 0)
+}
+
+/// 查询某只股票的画线，可按周期（None 为全部周期）和类型过滤
+pub fn query_drawings(
+    conn: &Connection,
+    code: &str,
+    period: Option<&str>,
+    kind: Option<DrawingKind>,
+) -> Result<Vec<Drawing>, StockError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM drawings
+             WHERE code = ?1 AND (?2 IS NULL OR period = ?2) AND (?3 IS NULL OR kind = ?3)
+             ORDER BY id ASC",
+            DRAWING_COLUMNS
+        ))
+        .map_err(|e| StockError::DbError(e))?;
+    let drawings = stmt
+        .query_map(
+            params![
+                code.trim().to_uppercase(),
+                period,
+                kind.map(|kind| kind.as_str())
+            ],
+            row_to_drawing,
+        )
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(drawings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+    use crate::db::init_db::TREND_LINES_SCHEMA;
+    use crate::db::migrations::{run_migrations, DbSchema};
+    use crate::db::trend_lines_db::count_legacy_trend_lines;
+
+    fn params(kind: &str, points: &[(i64, f64)]) -> DrawingParams {
+        DrawingParams {
+            code: "sh600000".to_string(),
+            period: "day".to_string(),
+            kind: kind.to_string(),
+            points: points
+                .iter()
+                .map(|&(time, price)| DrawingPoint { time, price })
+                .collect(),
+            style: serde_json::Value::Null,
+            label: String::new(),
+        }
+    }
+
+    #[test]
+    fn drawings_are_validated_by_kind() {
+        let conn = open_test_connection();
+        assert!(add_drawing(&conn, &params("circle", &[(1, 10.0)])).is_err());
+        // 平行通道需要 3 个锚点
+        assert!(add_drawing(&conn, &params("parallel_channel", &[(1, 10.0), (2, 11.0)])).is_err());
+        assert!(add_drawing(&conn, &params("horizontal_line", &[(1, -1.0)])).is_err());
+        let mut text = params("text", &[(1, 10.0)]);
+        text.style = serde_json::json!("red");
+        assert!(add_drawing(&conn, &text).is_err());
+
+        text.style = serde_json::json!({ "color": "#f00" });
+        text.label = "放量突破".to_string();
+        let id = add_drawing(&conn, &text).unwrap();
+        let channel = params("parallel_channel", &[(1, 10.0), (3, 11.0), (2, 9.5)]);
+        add_drawing(&conn, &channel).unwrap();
+
+        let drawings = query_drawings(&conn, "SH600000", Some("day"), None).unwrap();
+        assert_eq!(drawings.len(), 2);
+        assert_eq!(drawings[0].style["color"], "#f00");
+        assert_eq!(drawings[1].points.len(), 3);
+        let texts =
+            query_drawings(&conn, "SH600000", Some("day"), Some(DrawingKind::Text)).unwrap();
+        assert_eq!(texts[0].label, "放量突破");
+
+        let mut moved = params("text", &[(5, 12.0)]);
+        moved.label = "回踩".to_string();
+        assert!(update_drawing(&conn, id, &moved).unwrap());
+        assert!(!update_drawing(&conn, 999, &moved).unwrap());
+        let texts =
+            query_drawings(&conn, "SH600000", Some("day"), Some(DrawingKind::Text)).unwrap();
+        assert_eq!(
+            texts[0].points,
+            vec![DrawingPoint {
+                time: 5,
+                price: 12.0
+            }]
+        );
+        assert!(delete_drawing(&conn, id).unwrap());
+    }
+
+    #[test]
+    fn legacy_trend_lines_are_migrated_with_their_ids() {
+        // 先迁移到只有 trend_lines 表的版本 2
+        let v2 = DbSchema {
+            name: TREND_LINES_SCHEMA.name,
+            migrations: &TREND_LINES_SCHEMA.migrations[..2],
+            tables: &[],
+        };
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, &v2).unwrap();
+        conn.execute_batch(
+            "INSERT INTO trend_lines (id, code, period, start_time, start_price, end_time, end_price,
+                alert_enabled, alert_mode)
+             VALUES (7, 'sh600000', 'day', 1704153600000, 10.0, 1704758400000, 11.5, 1, 'close');
+             INSERT INTO trend_lines (id, code, period, start_time, start_price, end_time, end_price)
+             VALUES (3, 'sh600000', 'day', 0, 10.0, 1704758400000, 11.5);",
+        )
+        .unwrap();
+
+        run_migrations(&mut conn, &TREND_LINES_SCHEMA).unwrap();
+        let drawings = query_drawings(&conn, "SH600000", Some("day"), None).unwrap();
+        assert_eq!(drawings.len(), 1);
+        // 时间无效的趋势线不转换，原样保留
+        assert_eq!(count_legacy_trend_lines(&conn).unwrap(), 1);
+        let (id, start_time): (i32, String) = conn
+            .query_row("SELECT id, start_time FROM trend_lines_legacy", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((id, start_time.as_str()), (3, "0"));
+        let line = drawings[0].to_trend_line().unwrap();
+        assert_eq!(
+            (line.id, line.start_time, line.end_price),
+            (7, 1704153600000, 11.5)
+        );
+        assert_eq!(
+            (line.alert_enabled, line.alert_mode.as_str()),
+            (true, "close")
+        );
+        assert_eq!(drawings[0].style, serde_json::json!({}));
+
+        // 自增 ID 从迁移的数据之后继续
+        let id = add_drawing(&conn, &params("ray", &[(1, 10.0), (2, 11.0)])).unwrap();
+        assert_eq!(id, 8);
+    }
+}
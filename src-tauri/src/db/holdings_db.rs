@@ -1,105 +1,75 @@
-use crate::db::common::init_database;
+use crate::analytics::parse_date;
+use crate::calendar::TradingCalendar;
+use crate::db::position_db::{record_order, BUY_ACTION};
 use crate::structs::holdings_structs::{
-    AddHoldingReq, DeleteHoldingReq, Holding, MonthlyStats, PagedResult, UpdateHoldingReq,
+    AddHoldingReq, Holding, HoldingDividend, HoldingExit, HoldingLot, MonthlyStats, PagedResult,
 };
+use crate::structs::orders_structs::AddOrderParams;
 use crate::structs::StockError;
+use chrono::{Datelike, Months, NaiveDate};
 use rusqlite::{params, Connection};
-use tauri::AppHandle;
 
-/// 获取持仓数据库连接
-fn get_holdings_db_conn(app: &AppHandle) -> Result<Connection, StockError> {
-    init_database(app, "holdings")
-        .map_err(|e| StockError::BusinessError(format!("获取持仓数据库连接失败: {}", e)))
-}
-
-/// 1. 添加持仓（必须传入hold_time，新增status字段）
-pub fn add_holding(app: &AppHandle, req: &AddHoldingReq) -> Result<i32, StockError> {
-    let conn = get_holdings_db_conn(app)?;
-
-    // 检查股票是否已存在且为当前持仓
+/// 1. 手动添加持仓：按持仓成本和数量记录一笔买入委托，持仓由委托生成
+/// （重新生成持仓时不会丢失），返回生成的持仓ID
+pub fn add_holding(conn: &mut Connection, req: &AddHoldingReq) -> Result<i32, StockError> {
+    let code = req.code.trim().to_uppercase();
+    // 检查该账户中股票是否已存在且为当前持仓（不同账户可以同时持有同一只股票）
     let exists: bool = conn
         .query_row(
-            "SELECT COUNT(*) FROM holdings WHERE code = ?1 AND status = 1",
-            params![req.code.to_uppercase()],
+            "SELECT COUNT(*) FROM holdings WHERE code = ?1 AND status = 1 AND account_id = ?2",
+            params![code, req.account_id],
             |row| Ok(row.get::<_, i32>(0)? > 0),
         )
         .map_err(|e| StockError::DbError(e))?;
-
     if exists {
-        return Err(StockError::BusinessError("该股票已有持仓记录".to_string()));
+        return Err(StockError::BusinessError(
+            "该账户中此股票已有持仓记录，请录入买入委托加仓".to_string(),
+        ));
+    }
+    if !req.cost.is_finite() || req.cost <= 0.0 {
+        return Err(StockError::BusinessError(format!(
+            "持仓成本必须大于0: {}",
+            req.cost
+        )));
     }
 
-    // 插入新记录（含hold_time和status）
-    let mut stmt = conn
-        .prepare(
-            "INSERT INTO holdings 
-         (code, name, cost, quantity, hold_time, status)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-         RETURNING id",
-        )
-        .map_err(|e| StockError::DbError(e))?;
-
-    let new_id: i32 = stmt
-        .query_row(
-            params![
-                req.code.to_uppercase(),
-                req.name,
-                req.cost,
-                req.quantity,
-                req.hold_time,
-                1 // status = 1 表示当前持仓
-            ],
-            |row| row.get(0),
-        )
-        .map_err(|e| StockError::DbError(e))?;
-
-    Ok(new_id)
-}
-
-/// 3. 更新持仓（可以更新所有字段）
-pub fn update_holding(app: &AppHandle, req: &UpdateHoldingReq) -> Result<bool, StockError> {
-    let conn = get_holdings_db_conn(app)?;
-
-    let affected_rows = conn
-        .execute(
-            "UPDATE holdings SET 
-             cost = ?1, 
-             quantity = ?2, 
-             status = ?3,
-             sell_time = ?4,
-             sell_price = ?5,
-             profit = ?6
-             WHERE id = ?7",
-            params![
-                req.cost,
-                req.quantity,
-                req.status,
-                req.sell_time,
-                req.sell_price,
-                req.profit,
-                req.id
-            ],
-        )
-        .map_err(|e| StockError::DbError(e))?;
-
-    Ok(affected_rows > 0)
+    record_order(
+        conn,
+        &AddOrderParams {
+            code: code.clone(),
+            name: req.name.clone(),
+            time: req.hold_time.clone(),
+            quantity: req.quantity,
+            cost: req.cost,
+            action: BUY_ACTION.to_string(),
+            account_id: req.account_id,
+        },
+    )?;
+    conn.query_row(
+        "SELECT id FROM holdings WHERE code = ?1 AND status = 1 AND account_id = ?2",
+        params![code, req.account_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| StockError::DbError(e))
 }
 
-/// 4. 查询所有当前持仓（status = 1）
-pub fn query_holdings(app: &AppHandle) -> Result<Vec<Holding>, StockError> {
-    let conn = get_holdings_db_conn(app)?;
-
+/// 4. 查询所有当前持仓（status = 1），指定账户时只查该账户
+pub fn query_holdings(
+    conn: &Connection,
+    account_id: Option<i32>,
+) -> Result<Vec<Holding>, StockError> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit 
+            "SELECT id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit,
+                    account_id
              FROM holdings 
-             WHERE status = 1 
+             WHERE status = 1 AND (?1 IS NULL OR account_id = ?1)
              ORDER BY id ASC",
         )
         .map_err(|e| StockError::DbError(e))?;
 
     let holdings = stmt
-        .query_map([], |row| {
+        .query_map(params![account_id], |row| {
             Ok(Holding {
                 id: row.get(0)?,
                 code: row.get(1)?,
@@ -111,6 +81,7 @@ pub fn query_holdings(app: &AppHandle) -> Result<Vec<Holding>, StockError> {
                 sell_time: row.get(7)?,
                 sell_price: row.get(8)?,
                 profit: row.get(9)?,
+                account_id: row.get(10)?,
             })
         })
         .map_err(|e| StockError::DbError(e))?
@@ -120,21 +91,21 @@ pub fn query_holdings(app: &AppHandle) -> Result<Vec<Holding>, StockError> {
     Ok(holdings)
 }
 
+/// 5. 分页查询卖出历史（每笔卖出一条，包括部分卖出），指定账户时只查该账户
 pub fn query_history_holdings(
-    app: &AppHandle,
+    conn: &Connection,
     page: i32,
     page_size: i32,
-) -> Result<PagedResult<Holding>, StockError> {
-    let conn = get_holdings_db_conn(app)?;
-
+    account_id: Option<i32>,
+) -> Result<PagedResult<HoldingExit>, StockError> {
     // 计算偏移量
     let offset = (page - 1) * page_size;
 
     // 首先查询总记录数
     let total: i32 = conn
         .query_row(
-            "SELECT COUNT(*) FROM holdings WHERE status = 0",
-            [],
+            "SELECT COUNT(*) FROM holding_exit_history WHERE ?1 IS NULL OR account_id = ?1",
+            params![account_id],
             |row| row.get(0),
         )
         .map_err(|e| StockError::DbError(e))?;
@@ -142,38 +113,43 @@ pub fn query_history_holdings(
     // 查询分页数据
     let mut stmt = conn
         .prepare(
-            "SELECT id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit 
-             FROM holdings 
-             WHERE status = 0 
-             ORDER BY sell_time DESC
+            "SELECT id, holding_id, order_id, code, name, hold_time, sell_time, sell_price,
+                    quantity, cost, profit, closed, fee, account_id
+             FROM holding_exit_history
+             WHERE ?3 IS NULL OR account_id = ?3
+             ORDER BY sell_time DESC, id DESC
              LIMIT ?1 OFFSET ?2",
         )
         .map_err(|e| StockError::DbError(e))?;
 
-    let holdings = stmt
-        .query_map(params![page_size, offset], |row| {
-            Ok(Holding {
+    let exits = stmt
+        .query_map(params![page_size, offset, account_id], |row| {
+            Ok(HoldingExit {
                 id: row.get(0)?,
-                code: row.get(1)?,
-                name: row.get(2)?,
-                cost: row.get(3)?,
-                quantity: row.get(4)?,
+                holding_id: row.get(1)?,
+                order_id: row.get(2)?,
+                code: row.get(3)?,
+                name: row.get(4)?,
                 hold_time: row.get(5)?,
-                status: row.get(6)?,
-                sell_time: row.get(7)?,
-                sell_price: row.get(8)?,
-                profit: row.get(9)?,
+                sell_time: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                sell_price: row.get::<_, Option<f64>>(7)?.unwrap_or_default(),
+                quantity: row.get(8)?,
+                cost: row.get(9)?,
+                profit: row.get::<_, Option<f64>>(10)?.unwrap_or_default(),
+                closed: row.get(11)?,
+                fee: row.get(12)?,
+                account_id: row.get(13)?,
             })
         })
         .map_err(|e| StockError::DbError(e))?
-        .collect::<Result<Vec<Holding>, _>>()
+        .collect::<Result<Vec<HoldingExit>, _>>()
         .map_err(|e| StockError::DbError(e))?;
 
     // 计算总页数
     let total_pages = (total as f64 / page_size as f64).ceil() as i32;
 
     Ok(PagedResult {
-        data: holdings,
+        data: exits,
         total,
         page,
         page_size,
@@ -181,27 +157,66 @@ pub fn query_history_holdings(
     })
 }
 
+/// 查询全部卖出记录（按卖出时间正序，用于组合分析），指定账户时只查该账户
+pub fn query_all_exits(
+    conn: &Connection,
+    account_id: Option<i32>,
+) -> Result<Vec<HoldingExit>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, holding_id, order_id, code, name, hold_time, sell_time, sell_price,
+                    quantity, cost, profit, closed, fee, account_id
+             FROM holding_exit_history
+             WHERE sell_time IS NOT NULL AND (?1 IS NULL OR account_id = ?1)
+             ORDER BY sell_time ASC, id ASC",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let exits = stmt
+        .query_map(params![account_id], |row| {
+            Ok(HoldingExit {
+                id: row.get(0)?,
+                holding_id: row.get(1)?,
+                order_id: row.get(2)?,
+                code: row.get(3)?,
+                name: row.get(4)?,
+                hold_time: row.get(5)?,
+                sell_time: row.get(6)?,
+                sell_price: row.get::<_, Option<f64>>(7)?.unwrap_or_default(),
+                quantity: row.get(8)?,
+                cost: row.get(9)?,
+                profit: row.get::<_, Option<f64>>(10)?.unwrap_or_default(),
+                closed: row.get(11)?,
+                fee: row.get(12)?,
+                account_id: row.get(13)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<HoldingExit>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(exits)
+}
+
+/// 月度交易统计，指定账户时只统计该账户
 pub fn query_monthly_stats(
-    app: &AppHandle,
+    conn: &Connection,
     year: i32,
     month: i32,
+    account_id: Option<i32>,
 ) -> Result<MonthlyStats, StockError> {
-    let conn = get_holdings_db_conn(app)?;
-
-    // 构造该月的起止时间（ISO8601 格式：YYYY-MM-DD）
-    let start_date = format!("{:04}-{:02}-01", year, month);
-    let end_year;
-    let end_month;
-    if month == 12 {
-        end_year = year + 1;
-        end_month = 1;
-    } else {
-        end_year = year;
-        end_month = month + 1;
-    }
-    let end_date = format!("{:04}-{:02}-01", end_year, end_month);
-
-    // 查询当月已卖出的持仓记录统计信息
+    // 构造该月的起止日期（ISO8601 格式：YYYY-MM-DD）
+    let first_day = u32::try_from(month)
+        .ok()
+        .and_then(|month| NaiveDate::from_ymd_opt(year, month, 1))
+        .ok_or_else(|| StockError::BusinessError(format!("无效的月份: {}-{}", year, month)))?;
+    let next_month = first_day
+        .checked_add_months(Months::new(1))
+        .ok_or_else(|| StockError::BusinessError(format!("无效的月份: {}-{}", year, month)))?;
+    let start_date = first_day.to_string();
+    let end_date = next_month.to_string();
+
+    // 查询当月卖出记录的统计信息（部分卖出单独计一次操作）
     let (total_count, win_count, total_profit): (i32, i32, f64) = conn
         .query_row(
             r#"
@@ -209,12 +224,12 @@ pub fn query_monthly_stats(
                 COUNT(*),
                 COUNT(CASE WHEN profit > 0 THEN 1 END),
                 COALESCE(SUM(profit), 0)
-            FROM holdings 
-            WHERE status = 0 
-              AND sell_time >= ?1 
+            FROM holding_exit_history 
+            WHERE sell_time >= ?1 
               AND sell_time < ?2
+              AND (?3 IS NULL OR account_id = ?3)
             "#,
-            params![start_date, end_date],
+            params![start_date, end_date, account_id],
             |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )
         .map_err(|e| StockError::DbError(e))?;
@@ -225,26 +240,56 @@ pub fn query_monthly_stats(
         0.0
     };
 
+    // 按交易日历统计当月交易日数和平均持有交易日数（节假日和周末不计）
+    let calendar = TradingCalendar::load(conn)?;
+    let trading_days = calendar
+        .trading_days_in_month(year, first_day.month())
+        .map_or(0, |days| days.len() as i32);
+    let mut stmt = conn
+        .prepare(
+            "SELECT hold_time, sell_time FROM holding_exit_history
+             WHERE sell_time >= ?1 AND sell_time < ?2 AND (?3 IS NULL OR account_id = ?3)",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    let held: Vec<i64> = stmt
+        .query_map(params![start_date, end_date, account_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StockError::DbError(e))?
+        .iter()
+        .filter_map(|(hold_time, sell_time)| {
+            Some(calendar.trading_days_between(parse_date(hold_time)?, parse_date(sell_time)?))
+        })
+        .collect();
+    let average_holding_trading_days = if held.is_empty() {
+        None
+    } else {
+        Some(held.iter().sum::<i64>() as f64 / held.len() as f64)
+    };
+
     Ok(MonthlyStats {
         year,
         month,
         operation_count: total_count,
         win_rate,
         total_profit,
+        trading_days,
+        average_holding_trading_days,
     })
 }
 
 /// 6. 根据股票代码获取最近的持仓数据
 pub fn query_latest_holding_by_code(
-    app: &AppHandle,
+    conn: &Connection,
     code: &str,
 ) -> Result<Option<Holding>, StockError> {
-    let conn = get_holdings_db_conn(app)?;
-
     // 先查询当前持仓，如果没有则查询最近的历史记录
     let mut stmt = conn
         .prepare(
-            "SELECT id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit 
+            "SELECT id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit,
+                    account_id
              FROM holdings 
              WHERE code = ?1 
              ORDER BY 
@@ -266,6 +311,7 @@ pub fn query_latest_holding_by_code(
             sell_time: row.get(7)?,
             sell_price: row.get(8)?,
             profit: row.get(9)?,
+            account_id: row.get(10)?,
         })
     });
 
@@ -276,13 +322,243 @@ pub fn query_latest_holding_by_code(
     }
 }
 
-/// 2. 删除持仓
-pub fn delete_holding(app: &AppHandle, req: &DeleteHoldingReq) -> Result<bool, StockError> {
-    let conn = get_holdings_db_conn(app)?;
+/// 查询持仓的买入批次（按买入时间先后）
+pub fn query_holding_lots(
+    conn: &Connection,
+    holding_id: i32,
+) -> Result<Vec<HoldingLot>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, holding_id, order_id, code, buy_time, price, quantity, remaining, fee
+             FROM holding_lots
+             WHERE holding_id = ?1
+             ORDER BY buy_time ASC, id ASC",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let lots = stmt
+        .query_map(params![holding_id], |row| {
+            Ok(HoldingLot {
+                id: row.get(0)?,
+                holding_id: row.get(1)?,
+                order_id: row.get(2)?,
+                code: row.get(3)?,
+                buy_time: row.get(4)?,
+                price: row.get(5)?,
+                quantity: row.get(6)?,
+                remaining: row.get(7)?,
+                fee: row.get(8)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<HoldingLot>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(lots)
+}
 
-    let affected_rows = conn
-        .execute("DELETE FROM holdings WHERE id = ?1", params![req.id])
+/// 查询持仓的现金分红记录（按除息日先后）
+pub fn query_holding_dividends(
+    conn: &Connection,
+    holding_id: i32,
+) -> Result<Vec<HoldingDividend>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, holding_id, action_id, code, ex_date, quantity, per_share, amount, account_id
+             FROM holding_dividends
+             WHERE holding_id = ?1
+             ORDER BY ex_date ASC, id ASC",
+        )
         .map_err(|e| StockError::DbError(e))?;
 
-    Ok(affected_rows > 0)
+    let dividends = stmt
+        .query_map(params![holding_id], |row| {
+            Ok(HoldingDividend {
+                id: row.get(0)?,
+                holding_id: row.get(1)?,
+                action_id: row.get(2)?,
+                code: row.get(3)?,
+                ex_date: row.get(4)?,
+                quantity: row.get(5)?,
+                per_share: row.get(6)?,
+                amount: row.get(7)?,
+                account_id: row.get(8)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<HoldingDividend>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(dividends)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::account_db::create_account;
+    use crate::db::app_config_db::set_fee_model;
+    use crate::db::common::open_test_connection;
+    use crate::db::position_db::{rebuild_holdings, SELL_ACTION};
+    use crate::structs::account_structs::DEFAULT_ACCOUNT_ID;
+    use crate::structs::orders_structs::FeeModel;
+
+    // 不收费用，便于核对成本与盈亏
+    fn fee_free_connection() -> Connection {
+        let conn = open_test_connection();
+        let zero = FeeModel {
+            commission_rate: 0.0,
+            min_commission: 0.0,
+            stamp_duty_rate: 0.0,
+            transfer_fee_rate: 0.0,
+        };
+        set_fee_model(&conn, &zero).unwrap();
+        conn
+    }
+
+    fn holding(code: &str, cost: f64, hold_time: &str, account_id: i32) -> AddHoldingReq {
+        AddHoldingReq {
+            code: code.to_string(),
+            name: "测试股票".to_string(),
+            cost,
+            quantity: 100,
+            hold_time: hold_time.to_string(),
+            status: None,
+            account_id,
+        }
+    }
+
+    fn buy(conn: &mut Connection, code: &str, cost: f64, hold_time: &str) -> i32 {
+        add_holding(conn, &holding(code, cost, hold_time, DEFAULT_ACCOUNT_ID)).unwrap()
+    }
+
+    // 全部卖出 100 股
+    fn close(conn: &mut Connection, code: &str, sell_time: &str, sell_price: f64) {
+        record_order(
+            conn,
+            &AddOrderParams {
+                code: code.to_string(),
+                name: "测试股票".to_string(),
+                time: sell_time.to_string(),
+                quantity: 100,
+                cost: sell_price,
+                action: SELL_ACTION.to_string(),
+                account_id: DEFAULT_ACCOUNT_ID,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn manual_holding_is_recorded_as_order_and_survives_rebuild() {
+        let mut conn = fee_free_connection();
+        let id = buy(&mut conn, "sh600000", 10.0, "2024-01-02 10:00:00");
+
+        let holdings = query_holdings(&conn, None).unwrap();
+        assert_eq!(holdings.len(), 1);
+        assert_eq!(holdings[0].id, id);
+        assert_eq!(holdings[0].code, "SH600000");
+        assert_eq!(holdings[0].status, 1);
+        assert_eq!(query_holding_lots(&conn, id).unwrap().len(), 1);
+        assert!(add_holding(
+            &mut conn,
+            &holding("SH600001", 0.0, "2024-01-02 10:00:00", DEFAULT_ACCOUNT_ID)
+        )
+        .is_err());
+
+        // 重新生成持仓后仍然存在
+        rebuild_holdings(&mut conn).unwrap();
+        let holdings = query_holdings(&conn, None).unwrap();
+        assert_eq!(holdings.len(), 1);
+        assert_eq!((holdings[0].quantity, holdings[0].cost), (100, 10.0));
+    }
+
+    #[test]
+    fn add_rejects_second_open_position_in_same_account() {
+        let mut conn = fee_free_connection();
+        buy(&mut conn, "SH600000", 10.0, "2024-01-02 10:00:00");
+
+        let result = add_holding(
+            &mut conn,
+            &holding("SH600000", 11.0, "2024-01-03 10:00:00", DEFAULT_ACCOUNT_ID),
+        );
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+
+        // 其他账户可以同时持有同一只股票
+        let margin = create_account(&conn, "融资账户").unwrap();
+        add_holding(
+            &mut conn,
+            &holding("SH600000", 11.0, "2024-01-03 10:00:00", margin),
+        )
+        .unwrap();
+        assert_eq!(query_holdings(&conn, None).unwrap().len(), 2);
+        assert_eq!(query_holdings(&conn, Some(margin)).unwrap()[0].cost, 11.0);
+    }
+
+    #[test]
+    fn closed_holdings_move_to_history_and_stats() {
+        let mut conn = fee_free_connection();
+        buy(&mut conn, "SH600000", 10.0, "2024-01-02 10:00:00");
+        buy(&mut conn, "SZ000001", 10.0, "2024-01-03 10:00:00");
+        close(&mut conn, "SH600000", "2024-02-05 14:00:00", 12.0);
+        close(&mut conn, "SZ000001", "2024-02-06 14:00:00", 9.0);
+
+        assert!(query_holdings(&conn, None).unwrap().is_empty());
+
+        let page = query_history_holdings(&conn, 1, 1, None).unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.total_pages, 2);
+        assert_eq!(page.data[0].code, "SZ000001");
+        assert!(page.data[0].id.is_some());
+        assert!(page.data[0].closed);
+
+        let stats = query_monthly_stats(&conn, 2024, 2, None).unwrap();
+        assert_eq!(stats.operation_count, 2);
+        assert!((stats.win_rate - 0.5).abs() < 1e-9);
+        assert!((stats.total_profit - 100.0).abs() < 1e-9);
+
+        // 2024 年 2 月有 15 个交易日（春节休市 6 个工作日），两笔均持有 24 个交易日
+        assert_eq!(stats.trading_days, 15);
+        assert_eq!(stats.average_holding_trading_days, Some(24.0));
+
+        let empty = query_monthly_stats(&conn, 2024, 3, None).unwrap();
+        assert_eq!(empty.operation_count, 0);
+        assert_eq!(empty.average_holding_trading_days, None);
+        assert!(query_monthly_stats(&conn, 2024, 13, None).is_err());
+    }
+
+    #[test]
+    fn legacy_closed_holding_without_exits_is_listed_once() {
+        let conn = open_test_connection();
+        conn.execute(
+            "INSERT INTO holdings (code, name, cost, quantity, hold_time, status, sell_time,
+                sell_price, profit)
+             VALUES ('SH600000', '测试股票', 10.0, 100, '2024-01-02 10:00:00', 0,
+                '2024-02-05 14:00:00', 12.0, 200.0)",
+            [],
+        )
+        .unwrap();
+
+        // 没有卖出明细的旧清仓记录按一次全部卖出展示
+        let page = query_history_holdings(&conn, 1, 10, None).unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.data[0].id, None);
+        assert!(page.data[0].closed);
+        assert_eq!(page.data[0].profit, 200.0);
+    }
+
+    #[test]
+    fn latest_holding_prefers_open_position() {
+        let mut conn = fee_free_connection();
+        buy(&mut conn, "SH600000", 10.0, "2024-01-02 10:00:00");
+        close(&mut conn, "SH600000", "2024-01-10 14:00:00", 11.0);
+        let current = buy(&mut conn, "SH600000", 9.0, "2024-01-20 10:00:00");
+
+        let latest = query_latest_holding_by_code(&conn, "sh600000")
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.id, current);
+        assert!(query_latest_holding_by_code(&conn, "SZ000002")
+            .unwrap()
+            .is_none());
+    }
 }
@@ -1,383 +1,1095 @@
-use crate::db::init_db::{
-    init_all_stocks_database, init_app_config_database, init_holdings_database,
-    init_market_analysis_database, init_my_selection_database, init_orders_database,
-    init_self_reflect_database, init_stock_review_database, init_trend_lines_database,
-};
-use rusqlite::{params, Connection, Result};
-use tauri::AppHandle;
-
-// 导入单个数据库
-pub fn import_single_database(
-    app: &AppHandle,
-    db_name: &str,
-    db_data: &[u8],
-) -> Result<(), String> {
-    // 创建临时数据库文件
-    let temp_dir = tempfile::tempdir().map_err(|e| format!("创建临时目录失败: {}", e))?;
-    let temp_db_path = temp_dir.path().join(format!("temp_{}.db", db_name));
-
-    // 写入数据库数据到临时文件
-    std::fs::write(&temp_db_path, db_data).map_err(|e| format!("写入临时数据库文件失败: {}", e))?;
-
-    // 连接到临时数据库（备份数据库）
-    let backup_conn =
-        Connection::open(&temp_db_path).map_err(|e| format!("打开备份数据库失败: {}", e))?;
-
-    // 连接到当前数据库 - 使用可变绑定
-    let mut current_conn = match db_name {
-        "app_config" => init_app_config_database(app),
-        "all_stocks" => init_all_stocks_database(app),
-        "my_selection" => init_my_selection_database(app),
-        "stock_review" => init_stock_review_database(app),
-        "self_reflect" => init_self_reflect_database(app),
-        "market_analysis" => init_market_analysis_database(app),
-        "trend_lines" => init_trend_lines_database(app),
-        "holdings" => init_holdings_database(app),
-        "orders" => init_orders_database(app),
-        _ => return Err(format!("未知的数据库类型: {}", db_name)),
-    }?;
-
-    // 根据数据库类型执行不同的导入逻辑
-    match db_name {
-        "app_config" => import_app_config_data(&mut current_conn, &backup_conn),
-        "all_stocks" => import_all_stocks_data(&mut current_conn, &backup_conn),
-        "my_selection" => import_my_selection_data(&mut current_conn, &backup_conn),
-        "stock_review" => import_stock_review_data(&mut current_conn, &backup_conn),
-        "self_reflect" => import_self_reflect_data(&mut current_conn, &backup_conn),
-        "market_analysis" => import_market_analysis_data(&mut current_conn, &backup_conn),
-        "trend_lines" => import_trend_lines_data(&mut current_conn, &backup_conn),
-        "holdings" => import_holdings_data(&mut current_conn, &backup_conn),
-        "orders" => import_orders_data(&mut current_conn, &backup_conn),
-        _ => Ok(()),
-    }
+use crate::db::app_config_db::LOCAL_ONLY_CONFIG_KEYS;
+use crate::db::init_db::{ALL_DATABASES, DERIVED_TABLES, HOLDINGS_SCHEMA, TREND_LINES_SCHEMA};
+use crate::db::migrations::{run_migrations, ColumnDef, ColumnType, DbSchema, TableDef};
+use crate::db::position_db::{add_opening_orders, read_legacy_holdings, rebuild_all_holdings};
+use crate::structs::holdings_structs::Holding;
+use crate::structs::import_structs::{ImportStrategy, TableImportStats};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, OptionalExtension, Transaction};
+use std::collections::{HashMap, HashSet};
+
+// 每张表最多记录的错误原因条数
+const MAX_ERRORS: usize = 20;
+
+// 各表备份中的 id 与写入本地后的 id 的对应关系
+type IdMap = HashMap<&'static str, HashMap<i64, i64>>;
+
+// 导入后需要重新生成持仓的表
+const HOLDING_SOURCES: &[&str] = &[
+    "orders",
+    "position_transfers",
+    "corporate_actions",
+    "app_config",
+];
+
+/// 可从备份导入的数据库（与初始化共用 ALL_DATABASES 注册表）
+pub fn importable_databases() -> impl Iterator<Item = &'static DbSchema> {
+    ALL_DATABASES
+        .iter()
+        .copied()
+        .filter(|schema| !schema.tables.is_empty())
 }
 
-// 为每个数据库类型实现具体的导入函数 - 现在接受 &mut Connection
-fn import_app_config_data(
-    current_conn: &mut Connection,
-    backup_conn: &Connection,
-) -> Result<(), String> {
-    let mut stmt = backup_conn
-        .prepare("SELECT key, value FROM app_config")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
+/// 需要从备份压缩包中读取的数据库：可导入的数据库，以及旧版备份中的 holdings
+/// 持仓表本身不导入，只用于为没有委托的旧版手动持仓补建期初委托
+pub fn backup_database_names() -> Vec<&'static str> {
+    importable_databases()
+        .map(|schema| schema.name)
+        .chain(std::iter::once(HOLDINGS_SCHEMA.name))
+        .collect()
+}
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+/// 压缩包中的一个数据库文件，以及要从中导入的数据库
+#[derive(Clone)]
+pub struct BackupSource {
+    pub path: String,
+    pub db_names: Vec<String>,
+    pub data: Vec<u8>,
+}
 
-    let tx = current_conn
+/// 按策略导入全部备份数据：所有表在同一个事务中写入，表结构无法识别等错误会整体回滚，本地数据保持不变
+/// 单行数据无法转换或写入时跳过该行，并计入导入统计
+/// dry_run 为 true 时只统计将要发生的变更，最后回滚
+pub fn import_databases(
+    conn: &mut Connection,
+    sources: &[BackupSource],
+    strategy: ImportStrategy,
+    dry_run: bool,
+) -> Result<Vec<TableImportStats>, String> {
+    let temp_dir = tempfile::tempdir().map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let tx = conn
         .transaction()
         .map_err(|e| format!("开始事务失败: {}", e))?;
 
-    for row in rows {
-        let (key, value) = row.map_err(|e| format!("读取行数据失败: {}", e))?;
-        tx.execute(
-            "INSERT OR REPLACE INTO app_config (key, value) VALUES (?, ?)",
-            params![key, value],
-        )
-        .map_err(|e| format!("插入数据失败: {}", e))?;
+    let mut stats = Vec::new();
+    let mut id_map = IdMap::new();
+    // 备份中的持仓记录和有委托或划转的 (账户, 股票)，旧版备份可能分布在不同文件中
+    let mut backup_holdings = Vec::new();
+    let mut backup_backed = HashSet::new();
+    for (index, source) in sources.iter().enumerate() {
+        // 写入临时文件后打开备份数据库
+        let temp_db_path = temp_dir.path().join(format!("backup_{}.db", index));
+        std::fs::write(&temp_db_path, &source.data)
+            .map_err(|e| format!("写入临时数据库文件失败: {}", e))?;
+        let mut backup_conn = Connection::open(&temp_db_path)
+            .map_err(|e| format!("打开备份数据库 {} 失败: {}", source.path, e))?;
+
+        for db_name in &source.db_names {
+            let schema = ALL_DATABASES
+                .iter()
+                .find(|schema| schema.name == db_name)
+                .ok_or_else(|| format!("未知的数据库类型: {}", db_name))?;
+            if schema.name == TREND_LINES_SCHEMA.name {
+                upgrade_legacy_trend_lines(&mut backup_conn)?;
+            }
+            for table in schema.tables {
+                // 旧版备份可能缺少部分表
+                let backup_columns = backup_columns(&backup_conn, table.name)?;
+                if backup_columns.is_empty() {
+                    continue;
+                }
+                let mut table_stats = import_table(
+                    &tx,
+                    &backup_conn,
+                    table,
+                    &backup_columns,
+                    strategy,
+                    &mut id_map,
+                )
+                .map_err(|e| format!("导入数据库 {} 失败: {}", db_name, e))?;
+                table_stats.database = schema.name.to_string();
+                stats.push(table_stats);
+            }
+        }
+        ensure_nothing_left_behind(&backup_conn, source)?;
+        if !backup_columns(&backup_conn, "holdings")?.is_empty() {
+            backup_holdings.extend(
+                read_legacy_holdings(&backup_conn)
+                    .map_err(|e| format!("读取备份持仓失败: {}", e))?,
+            );
+        }
+        backup_backed.extend(backup_position_keys(&backup_conn)?);
     }
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
-}
+    // 旧版备份中手动录入、没有委托的持仓：补建期初委托，否则重新生成持仓时会丢失
+    let orphans: Vec<Holding> = backup_holdings
+        .into_iter()
+        .filter(|holding| {
+            !backup_backed.contains(&(holding.account_id, holding.code.trim().to_uppercase()))
+        })
+        .map(|mut holding| {
+            let account_id = holding.account_id as i64;
+            if let Some(local_id) = id_map.get("accounts").and_then(|ids| ids.get(&account_id)) {
+                holding.account_id = *local_id as i32;
+            }
+            holding
+        })
+        .collect();
+    let opened =
+        add_opening_orders(&tx, &orphans).map_err(|e| format!("补建期初委托失败: {}", e))?;
+    if opened > 0 {
+        match stats.iter_mut().find(|stats| stats.table == "orders") {
+            Some(order_stats) => order_stats.inserted += opened,
+            None => stats.push(TableImportStats {
+                database: "orders".to_string(),
+                table: "orders".to_string(),
+                inserted: opened,
+                ..Default::default()
+            }),
+        }
+    }
 
-fn import_all_stocks_data(
-    current_conn: &mut Connection,
-    backup_conn: &Connection,
-) -> Result<(), String> {
-    let mut stmt = backup_conn
-        .prepare("SELECT symbol, name FROM all_stocks")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
+    // 持仓由委托、划转和公司行动推导（成本计算方式保存在配置中），导入这些数据后重新生成
+    if stats
+        .iter()
+        .any(|stats| HOLDING_SOURCES.contains(&stats.table.as_str()))
+    {
+        rebuild_all_holdings(&tx).map_err(|e| format!("重新生成持仓失败: {}", e))?;
+    }
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+    if dry_run {
+        tx.rollback().map_err(|e| format!("回滚事务失败: {}", e))?;
+    } else {
+        tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+    }
+    Ok(stats)
+}
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+// 备份中有委托或划转的 (账户, 股票)，旧版备份的委托没有 account_id 列时归入默认账户
+fn backup_position_keys(backup_conn: &Connection) -> Result<HashSet<(i32, String)>, String> {
+    let mut queries = Vec::new();
+    let order_columns = backup_columns(backup_conn, "orders")?;
+    if !order_columns.is_empty() {
+        let account = if order_columns.contains("account_id") {
+            "account_id"
+        } else {
+            "1"
+        };
+        queries.push(format!("SELECT {}, UPPER(code) FROM orders", account));
+    }
+    if !backup_columns(backup_conn, "position_transfers")?.is_empty() {
+        queries.push("SELECT from_account_id, UPPER(code) FROM position_transfers".to_string());
+        queries.push("SELECT to_account_id, UPPER(code) FROM position_transfers".to_string());
+    }
 
-    for row in rows {
-        let (symbol, name) = row.map_err(|e| format!("读取行数据失败: {}", e))?;
-        tx.execute(
-            "INSERT OR REPLACE INTO all_stocks (symbol, name) VALUES (?, ?)",
-            params![symbol, name],
-        )
-        .map_err(|e| format!("插入数据失败: {}", e))?;
+    let mut keys = HashSet::new();
+    for sql in queries {
+        let mut stmt = backup_conn
+            .prepare(&sql)
+            .map_err(|e| format!("读取备份委托失败: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| format!("读取备份委托失败: {}", e))?;
+        for row in rows {
+            keys.insert(row.map_err(|e| format!("读取备份委托失败: {}", e))?);
+        }
     }
+    Ok(keys)
+}
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
+// 备份中该表的列名（表不存在时为空）
+fn backup_columns(conn: &Connection, table: &str) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info(\"{}\")", table))
+        .map_err(|e| format!("读取备份表 {} 结构失败: {}", table, e))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| format!("读取备份表 {} 结构失败: {}", table, e))?;
+    columns
+        .collect::<Result<HashSet<String>, _>>()
+        .map_err(|e| format!("读取备份表 {} 结构失败: {}", table, e))
 }
 
-fn import_my_selection_data(
-    current_conn: &mut Connection,
+// 备份中有数据、但当前版本没有登记的表（如更新版本新增的表）无法导入，恢复后会丢失，此时拒绝导入
+fn ensure_nothing_left_behind(
     backup_conn: &Connection,
+    source: &BackupSource,
 ) -> Result<(), String> {
     let mut stmt = backup_conn
-        .prepare("SELECT code, name, color, remark, sort FROM my_selection")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
-
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, Option<String>>(2)?,
-                row.get::<_, Option<String>>(3)?,
-                row.get::<_, i64>(4)?,
-            ))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+        .prepare(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'schema_migrations'",
+        )
+        .map_err(|e| format!("读取备份表列表失败: {}", e))?;
+    let tables = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("读取备份表列表失败: {}", e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| format!("读取备份表列表失败: {}", e))?;
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+    let mut left_behind = Vec::new();
+    for table in tables {
+        let registered = importable_databases()
+            .flat_map(|schema| schema.tables)
+            .any(|def| def.name == table);
+        if registered || DERIVED_TABLES.contains(&table.as_str()) {
+            continue;
+        }
+        let has_rows: bool = backup_conn
+            .query_row(
+                &format!("SELECT EXISTS (SELECT 1 FROM \"{}\")", table),
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("读取备份表 {} 失败: {}", table, e))?;
+        if has_rows {
+            left_behind.push(table);
+        }
+    }
+    if left_behind.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "备份 {} 中的表 {} 无法导入，为避免丢失数据已取消导入，请升级应用后再试",
+            source.path,
+            left_behind.join("、")
+        ))
+    }
+}
 
-    for row in rows {
-        let (code, name, color, remark, sort) =
-            row.map_err(|e| format!("读取行数据失败: {}", e))?;
-        tx.execute(
-            "INSERT OR REPLACE INTO my_selection (code, name, color, remark, sort) VALUES (?, ?, ?, ?, ?)",
-            params![code, name, color, remark, sort],
-        ).map_err(|e| format!("插入数据失败: {}", e))?;
+// 旧版备份只有 trend_lines 表：先在备份副本中执行迁移，转换为通用画线表 drawings
+fn upgrade_legacy_trend_lines(backup_conn: &mut Connection) -> Result<(), String> {
+    if backup_columns(backup_conn, "trend_lines")?.is_empty()
+        || !backup_columns(backup_conn, "drawings")?.is_empty()
+    {
+        return Ok(());
     }
+    run_migrations(backup_conn, &TREND_LINES_SCHEMA)
+}
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
+// 将文本按列类型解析（兼容数字被存成文本的旧备份）
+fn parse_text(text: &str, kind: ColumnType) -> Option<Value> {
+    let text = text.trim();
+    match kind {
+        ColumnType::Text => Some(Value::Text(text.to_string())),
+        ColumnType::Integer => text
+            .parse::<i64>()
+            .ok()
+            .or_else(|| {
+                text.parse::<f64>()
+                    .ok()
+                    .filter(|v| v.is_finite() && v.fract() == 0.0)
+                    .map(|v| v as i64)
+            })
+            .map(Value::Integer),
+        ColumnType::Real => text.parse::<f64>().ok().map(Value::Real),
+    }
 }
 
-fn import_stock_review_data(
-    current_conn: &mut Connection,
-    backup_conn: &Connection,
-) -> Result<(), String> {
-    let mut stmt = backup_conn
-        .prepare("SELECT id, title, code, date, type, description FROM stock_review")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
+/// 按列定义转换备份中的值：数字与文本互相转换，空值使用默认值
+fn convert_value(value: Value, column: &ColumnDef) -> Result<Value, String> {
+    let converted = match (value, column.kind) {
+        (Value::Null, _) if column.nullable => Some(Value::Null),
+        (Value::Null, kind) => column.default.and_then(|value| parse_text(value, kind)),
+        (Value::Integer(v), ColumnType::Integer) => Some(Value::Integer(v)),
+        (Value::Integer(v), ColumnType::Real) => Some(Value::Real(v as f64)),
+        (Value::Integer(v), ColumnType::Text) => Some(Value::Text(v.to_string())),
+        (Value::Real(v), ColumnType::Real) => Some(Value::Real(v)),
+        (Value::Real(v), ColumnType::Integer) => Some(v)
+            .filter(|v| v.fract() == 0.0)
+            .map(|v| Value::Integer(v as i64)),
+        (Value::Real(v), ColumnType::Text) => Some(Value::Text(v.to_string())),
+        (Value::Text(text), kind) => parse_text(&text, kind),
+        (Value::Blob(blob), kind) => String::from_utf8(blob)
+            .ok()
+            .and_then(|text| parse_text(&text, kind)),
+    };
+    converted.ok_or_else(|| format!("字段 {} 的值无法转换为 {:?}", column.name, column.kind))
+}
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, Option<String>>(5)?,
-            ))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+fn record_error(stats: &mut TableImportStats, message: String) {
+    if stats.errors.len() < MAX_ERRORS {
+        stats.errors.push(message);
+    }
+}
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+fn is_protected(key: &Value, protected_keys: &[&str]) -> bool {
+    matches!(key, Value::Text(text) if protected_keys.contains(&text.as_str()))
+}
 
-    for row in rows {
-        let (id, title, code, date, review_type, description) =
-            row.map_err(|e| format!("读取行数据失败: {}", e))?;
-        tx.execute(
-            "INSERT OR REPLACE INTO stock_review (id, title, code, date, type, description) VALUES (?, ?, ?, ?, ?, ?)",
-            params![id, title, code, date, review_type, description],
-        ).map_err(|e| format!("插入数据失败: {}", e))?;
+// 按引用的表在本次导入中的 id 对应关系更新引用列（引用的记录没有导入时保持不变）
+fn remap_references(values: &mut [Value], table: &TableDef, id_map: &IdMap) {
+    for (value, column) in values.iter_mut().zip(table.columns) {
+        let Some(target) = column.references else {
+            continue;
+        };
+        if let Value::Integer(id) = value {
+            if let Some(local_id) = id_map.get(target).and_then(|ids| ids.get(id)) {
+                *id = *local_id;
+            }
+        }
     }
-
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
 }
 
-fn import_self_reflect_data(
-    current_conn: &mut Connection,
+// 读取备份中的一张表，按策略写入当前事务
+fn import_table(
+    tx: &Transaction,
     backup_conn: &Connection,
-) -> Result<(), String> {
+    table: &TableDef,
+    backup_columns: &HashSet<String>,
+    strategy: ImportStrategy,
+    id_map: &mut IdMap,
+) -> Result<TableImportStats, String> {
+    let key = table.columns[0].name;
+    if !backup_columns.contains(key) {
+        return Err(format!("备份中的表 {} 缺少主键列 {}", table.name, key));
+    }
+
+    let names: Vec<&str> = table.columns.iter().map(|column| column.name).collect();
+    let key_columns = table.key_columns();
+    // 旧版备份缺少的有默认值的列按默认值比较
+    if let Some(column) = key_columns
+        .iter()
+        .find(|column| !backup_columns.contains(column.name) && column.default.is_none())
+    {
+        return Err(format!("备份中的表 {} 缺少列 {}", table.name, column.name));
+    }
+    let key_indexes: Vec<usize> = key_columns
+        .iter()
+        .map(|key| names.iter().position(|name| *name == key.name).unwrap())
+        .collect();
+    let columns = names.join(", ");
+    let placeholders = vec!["?"; names.len()].join(", ");
+    let insert_sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table.name, columns, placeholders
+    );
+    // 不带自增 id 插入，由本地重新分配
+    let append_sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table.name,
+        names[1..].join(", "),
+        vec!["?"; names.len() - 1].join(", ")
+    );
+    let exists_sql = format!(
+        "SELECT {} FROM {} WHERE {}",
+        key,
+        table.name,
+        key_columns
+            .iter()
+            .map(|column| format!("{} IS ?", column.name))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    );
+    let id_taken_sql = format!("SELECT 1 FROM {} WHERE {} = ?", table.name, key);
+    // 备份覆盖本地记录：按本地主键更新其余列
+    let update_sql = format!(
+        "UPDATE {} SET {} WHERE {} = ?",
+        table.name,
+        names[1..]
+            .iter()
+            .map(|name| format!("{} = ?", name))
+            .collect::<Vec<_>>()
+            .join(", "),
+        key
+    );
+    // 旧版备份缺少的列按空值读取，转换时使用默认值
+    let select_list = names
+        .iter()
+        .map(|name| {
+            if backup_columns.contains(*name) {
+                format!("\"{}\"", name)
+            } else {
+                "NULL".to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
     let mut stmt = backup_conn
-        .prepare("SELECT id, title, code, date, description FROM self_reflect")
+        .prepare(&format!("SELECT {} FROM \"{}\"", select_list, table.name))
         .map_err(|e| format!("准备查询失败: {}", e))?;
-
     let rows = stmt
         .query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, Option<String>>(4)?,
-            ))
+            (0..names.len())
+                .map(|i| row.get::<_, Value>(i))
+                .collect::<Result<Vec<Value>, _>>()
         })
         .map_err(|e| format!("查询备份数据失败: {}", e))?;
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+    let mut stats = TableImportStats {
+        table: table.name.to_string(),
+        ..Default::default()
+    };
+    // 只保存在本机的配置项（加密的 Cookie 等）既不被清空，也不被备份覆盖
+    let protected_keys: &[&str] = if table.name == "app_config" {
+        LOCAL_ONLY_CONFIG_KEYS
+    } else {
+        &[]
+    };
+    if strategy == ImportStrategy::Replace {
+        let delete_sql = if protected_keys.is_empty() {
+            format!("DELETE FROM {}", table.name)
+        } else {
+            format!(
+                "DELETE FROM {} WHERE {} NOT IN ({})",
+                table.name,
+                key,
+                vec!["?"; protected_keys.len()].join(", ")
+            )
+        };
+        stats.deleted = tx
+            .execute(&delete_sql, params_from_iter(protected_keys))
+            .map_err(|e| format!("清空本地数据失败: {}", e))?;
+    }
+
+    for (index, row) in rows.enumerate() {
+        let line = index + 1;
+        let raw = row.map_err(|e| format!("读取行数据失败: {}", e))?;
+        let mut values = match raw
+            .into_iter()
+            .zip(table.columns)
+            .map(|(value, column)| convert_value(value, column))
+            .collect::<Result<Vec<Value>, String>>()
+        {
+            Ok(values) if is_protected(&values[0], protected_keys) => continue,
+            Ok(values) => values,
+            Err(e) => {
+                stats.skipped += 1;
+                record_error(&mut stats, format!("{} 第 {} 行: {}", table.name, line, e));
+                continue;
+            }
+        };
+        remap_references(&mut values, table, id_map);
 
-    for row in rows {
-        let (id, title, code, date, description) =
-            row.map_err(|e| format!("读取行数据失败: {}", e))?;
-        tx.execute(
-            "INSERT OR REPLACE INTO self_reflect (id, title, code, date, description) VALUES (?, ?, ?, ?, ?)",
-            params![id, title, code, date, description],
-        ).map_err(|e| format!("插入数据失败: {}", e))?;
+        // 写入后该行在本地的主键
+        let result = {
+            let keys: Vec<&Value> = key_indexes.iter().map(|&i| &values[i]).collect();
+            let local_key: Option<Value> = tx
+                .query_row(&exists_sql, params_from_iter(keys), |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("查询本地数据失败: {}", e))?;
+            match local_key {
+                None => {
+                    // 自增表按自然主键判定冲突：备份中的 id 已被本地另一条记录占用时重新分配，
+                    // 不会覆盖或丢弃不相关的记录；追加模式总是重新分配
+                    let id_taken = table.auto_id
+                        && tx
+                            .query_row(&id_taken_sql, [&values[0]], |_| Ok(()))
+                            .optional()
+                            .map_err(|e| format!("查询本地数据失败: {}", e))?
+                            .is_some();
+                    if id_taken {
+                        stats.rekeyed += 1;
+                    }
+                    if id_taken || (table.auto_id && strategy == ImportStrategy::Append) {
+                        tx.execute(&append_sql, params_from_iter(&values[1..]))
+                            .map(|_| Value::Integer(tx.last_insert_rowid()))
+                    } else {
+                        tx.execute(&insert_sql, params_from_iter(&values))
+                            .map(|_| values[0].clone())
+                    }
+                    .map(|local_key| {
+                        stats.inserted += 1;
+                        local_key
+                    })
+                }
+                Some(local_key) => {
+                    stats.conflicts += 1;
+                    // 保留本地（以及追加模式）时跳过冲突记录
+                    if strategy == ImportStrategy::KeepBackup {
+                        let params = values[1..].iter().chain(std::iter::once(&local_key));
+                        tx.execute(&update_sql, params_from_iter(params)).map(|_| {
+                            stats.updated += 1;
+                            local_key
+                        })
+                    } else {
+                        Ok(local_key)
+                    }
+                }
+            }
+        };
+        // 单行写入失败（如违反约束）不影响事务中的其他行
+        match result {
+            Ok(Value::Integer(local_id)) if table.auto_id => {
+                if let Value::Integer(backup_id) = values[0] {
+                    id_map
+                        .entry(table.name)
+                        .or_default()
+                        .insert(backup_id, local_id);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                stats.failed += 1;
+                record_error(
+                    &mut stats,
+                    format!("{} 第 {} 行写入失败: {}", table.name, line, e),
+                );
+            }
+        }
     }
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
+    Ok(stats)
 }
 
-fn import_market_analysis_data(
-    current_conn: &mut Connection,
-    backup_conn: &Connection,
-) -> Result<(), String> {
-    let mut stmt = backup_conn
-        .prepare("SELECT date, analysis, status FROM market_analysis")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::account_db::create_account;
+    use crate::db::backup_db::export_database_zip;
+    use crate::db::common::{open_test_connection, APP_DB_NAME};
+    use crate::db::position_db::{
+        rebuild_holdings, record_corporate_action, record_order, transfer_position, BUY_ACTION,
+        SELL_ACTION,
+    };
+    use crate::db::trend_lines_db::query_trend_lines;
+    use crate::structs::account_structs::TransferPositionParams;
+    use crate::structs::corporate_action_structs::CorporateAction;
+    use crate::structs::orders_structs::AddOrderParams;
+    use std::collections::BTreeMap;
+    use std::io::{Cursor, Read};
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+    // 备份：两条复盘（id 1、2）和一条自选
+    fn backup_source() -> BackupSource {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("backup.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE stock_review (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL,
+                    code TEXT NOT NULL, date TEXT NOT NULL, type TEXT NOT NULL, description TEXT);
+                 INSERT INTO stock_review VALUES (1, '备份复盘1', 'SH600000', '2024-01-02', '1', '备份说明');
+                 INSERT INTO stock_review VALUES (2, '备份复盘2', 'SH600000', '2024-01-03', '1', NULL);
+                 CREATE TABLE my_selection (code TEXT PRIMARY KEY, name TEXT NOT NULL, color TEXT,
+                    remark TEXT, sort INTEGER DEFAULT 0);
+                 INSERT INTO my_selection VALUES ('SH600000', '浦发银行', NULL, '备份备注', 1);",
+            )
+            .unwrap();
+        }
+        BackupSource {
+            path: "app.db".to_string(),
+            db_names: vec![
+                "stock_review".to_string(),
+                "my_selection".to_string(),
+                "orders".to_string(), // 备份中没有该表，应跳过
+            ],
+            data: std::fs::read(&path).unwrap(),
+        }
+    }
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+    // 本地：与备份 id 1 是同一条复盘（内容不同）、与备份 id 2 编号相同的另一条复盘，以及一条自选（与备份冲突）
+    fn local_connection() -> Connection {
+        let conn = open_test_connection();
+        conn.execute_batch(
+            "INSERT INTO stock_review (id, title, code, date, type, description)
+                 VALUES (1, '备份复盘1', 'SH600000', '2024-01-02', '1', '本地说明');
+             INSERT INTO stock_review (id, title, code, date, type) VALUES (2, '本地复盘', 'SZ000001', '2024-02-01', '1');
+             INSERT INTO my_selection (code, name, remark, sort) VALUES ('SH600000', '浦发银行', '本地备注', 1);",
+        )
+        .unwrap();
+        conn
+    }
 
-    for row in rows {
-        let (date, analysis, status) = row.map_err(|e| format!("读取行数据失败: {}", e))?;
-        tx.execute(
-            "INSERT OR REPLACE INTO market_analysis (date, analysis, status) VALUES (?, ?, ?)",
-            params![date, analysis, status],
+    fn review_titles(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT title FROM stock_review ORDER BY id")
+            .unwrap();
+        let titles = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        titles
+    }
+
+    fn review_description(conn: &Connection) -> String {
+        conn.query_row(
+            "SELECT description FROM stock_review WHERE id = 1",
+            [],
+            |row| row.get(0),
         )
-        .map_err(|e| format!("插入数据失败: {}", e))?;
+        .unwrap()
     }
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
-}
+    fn selection_remark(conn: &Connection) -> String {
+        conn.query_row("SELECT remark FROM my_selection", [], |row| row.get(0))
+            .unwrap()
+    }
 
-fn import_trend_lines_data(
-    current_conn: &mut Connection,
-    backup_conn: &Connection,
-) -> Result<(), String> {
-    let mut stmt = backup_conn
-        .prepare("SELECT id, code, period, start_time, end_time, start_price, end_price FROM trend_lines")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
+    fn run(strategy: ImportStrategy, dry_run: bool) -> (Connection, Vec<TableImportStats>) {
+        let mut conn = local_connection();
+        let stats = import_databases(&mut conn, &[backup_source()], strategy, dry_run).unwrap();
+        (conn, stats)
+    }
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?, // 时间戳字符串（如 "1717020800000"）
-                row.get::<_, String>(4)?,
-                row.get::<_, f64>(5)?,
-                row.get::<_, f64>(6)?,
-            ))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+    #[test]
+    fn strategies_resolve_conflicts_differently() {
+        // 备份 id 2 与本地另一条复盘编号相同：不是同一条记录，重新分配 id 后写入
+        let merged = vec!["备份复盘1", "本地复盘", "备份复盘2"];
+        let (conn, stats) = run(ImportStrategy::KeepLocal, false);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            (
+                stats[0].inserted,
+                stats[0].conflicts,
+                stats[0].updated,
+                stats[0].rekeyed
+            ),
+            (1, 1, 0, 1)
+        );
+        assert_eq!(review_titles(&conn), merged);
+        assert_eq!(review_description(&conn), "本地说明");
+        assert_eq!(selection_remark(&conn), "本地备注");
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+        let (conn, stats) = run(ImportStrategy::KeepBackup, false);
+        assert_eq!(
+            (
+                stats[0].inserted,
+                stats[0].conflicts,
+                stats[0].updated,
+                stats[0].rekeyed
+            ),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(review_titles(&conn), merged);
+        assert_eq!(review_description(&conn), "备份说明");
+        assert_eq!(selection_remark(&conn), "备份备注");
 
-    for row in rows {
-        let (id, code, period, start_time, end_time, start_price, end_price) =
-            row.map_err(|e| format!("读取行数据失败: {}", e))?;
+        // 追加：新记录重新分配 id，同一条复盘和自选仍保留本地
+        let (conn, stats) = run(ImportStrategy::Append, false);
+        assert_eq!((stats[0].inserted, stats[0].conflicts), (1, 1));
+        assert_eq!(stats[1].conflicts, 1);
+        assert_eq!(review_titles(&conn), merged);
+        assert_eq!(review_description(&conn), "本地说明");
+        assert_eq!(selection_remark(&conn), "本地备注");
 
-        tx.execute(
-            "INSERT OR REPLACE INTO trend_lines (id, code, period, start_time, end_time, start_price, end_price) VALUES (?, ?, ?, ?, ?, ?, ?)",
-            params![id, code, period, start_time, end_time, start_price, end_price],
-        ).map_err(|e| format!("插入 trend_lines 数据失败: {}", e))?;
+        let (conn, stats) = run(ImportStrategy::Replace, false);
+        assert_eq!((stats[0].deleted, stats[0].inserted), (2, 2));
+        assert_eq!(review_titles(&conn), vec!["备份复盘1", "备份复盘2"]);
     }
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
-}
-fn import_holdings_data(
-    current_conn: &mut Connection,
-    backup_conn: &Connection,
-) -> Result<(), String> {
-    let mut stmt = backup_conn.prepare("SELECT id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit FROM holdings")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
+    #[test]
+    fn dry_run_reports_without_writing() {
+        let (conn, stats) = run(ImportStrategy::KeepBackup, true);
+        assert_eq!(
+            (stats[0].inserted, stats[0].updated, stats[0].rekeyed),
+            (1, 1, 1)
+        );
+        assert_eq!(review_titles(&conn), vec!["备份复盘1", "本地复盘"]);
+        assert_eq!(review_description(&conn), "本地说明");
+        assert_eq!(selection_remark(&conn), "本地备注");
+    }
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, f64>(3)?,
-                row.get::<_, i64>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, i64>(6)?,
-                row.get::<_, Option<String>>(7)?,
-                row.get::<_, Option<f64>>(8)?,
-                row.get::<_, Option<f64>>(9)?,
-            ))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+    // 修改备份数据库后重新读取
+    fn modify_backup(source: &mut BackupSource, sql: &str) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("modified.db");
+        std::fs::write(&path, &source.data).unwrap();
+        Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+        source.data = std::fs::read(&path).unwrap();
+    }
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+    #[test]
+    fn unreadable_table_leaves_local_data_untouched() {
+        let mut conn = local_connection();
+        let mut source = backup_source();
+        // 备份中的自选缺少主键列，整张表无法导入
+        modify_backup(
+            &mut source,
+            "DROP TABLE my_selection;
+             CREATE TABLE my_selection (name TEXT, remark TEXT);
+             INSERT INTO my_selection VALUES ('万科A', NULL);",
+        );
 
-    for row in rows {
-        let (id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit) =
-            row.map_err(|e| format!("读取行数据失败: {}", e))?;
+        let result = import_databases(&mut conn, &[source], ImportStrategy::Replace, false);
+        assert!(result.is_err());
+        assert_eq!(review_titles(&conn), vec!["备份复盘1", "本地复盘"]);
+        assert_eq!(selection_remark(&conn), "本地备注");
+    }
+
+    #[test]
+    fn rows_are_converted_by_declared_column_types() {
+        let mut conn = open_test_connection();
+        let mut source = backup_source();
+        source.db_names = vec!["trend_lines".to_string(), "orders".to_string()];
+        // 旧版备份：趋势线时间戳存为文本（无效行转换时保留为旧版趋势线）、委托没有费用和账户列、一行缺少必填的名称
+        modify_backup(
+            &mut source,
+            "CREATE TABLE trend_lines (id INTEGER PRIMARY KEY, code TEXT, period TEXT,
+                start_time TEXT, end_time TEXT, start_price TEXT, end_price REAL);
+             INSERT INTO trend_lines VALUES (1, 'SH600000', 'day', '1717020800000', '1717107200000', '10.5', 11);
+             INSERT INTO trend_lines VALUES (2, 'SH600000', 'day', 'abc', '1717107200000', 10, 11);
+             CREATE TABLE orders (id INTEGER PRIMARY KEY, code TEXT, name TEXT, time TEXT,
+                quantity TEXT, cost REAL, action TEXT);
+             INSERT INTO orders VALUES (1, 'SH600000', '浦发银行', '2024-01-02 09:30:00', '100', 10, '1');
+             INSERT INTO orders VALUES (2, 'SH600000', NULL, '2024-01-03 09:30:00', 100, 10, '0');",
+        );
+
+        let stats =
+            import_databases(&mut conn, &[source], ImportStrategy::KeepLocal, false).unwrap();
+        // 迁移旧版趋势线时一并创建了（为空的）突破记录表
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats[0].table, "drawings");
+        assert_eq!((stats[0].inserted, stats[0].skipped), (1, 0));
+        assert_eq!(stats[1].table, "trend_line_breaks");
+        assert_eq!(stats[2].table, "trend_lines_legacy");
+        assert_eq!(stats[2].inserted, 1);
+        assert_eq!((stats[3].inserted, stats[3].skipped), (1, 1));
+        assert_eq!(stats[3].errors.len(), 1);
 
-        tx.execute(
-            "INSERT OR REPLACE INTO holdings (id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            params![id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit],
-        ).map_err(|e| format!("插入数据失败: {}", e))?;
+        let lines = query_trend_lines(&conn, "SH600000", "day").unwrap();
+        assert_eq!(
+            (lines[0].id, lines[0].start_time, lines[0].start_price),
+            (1, 1717020800000, 10.5)
+        );
+        let (quantity, commission, account_id): (i64, f64, i64) = conn
+            .query_row(
+                "SELECT quantity, commission, account_id FROM orders WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((quantity, commission, account_id), (100, 0.0, 1));
     }
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
-}
+    #[test]
+    fn backup_with_tables_that_cannot_be_imported_is_refused() {
+        let mut conn = local_connection();
+        let mut source = backup_source();
+        // 更新版本的备份中新增的表：为空时不影响导入
+        modify_backup(
+            &mut source,
+            "CREATE TABLE watch_notes (id INTEGER PRIMARY KEY, note TEXT);",
+        );
+        import_databases(
+            &mut conn,
+            &[source.clone()],
+            ImportStrategy::KeepLocal,
+            true,
+        )
+        .unwrap();
 
-fn import_orders_data(
-    current_conn: &mut Connection,
-    backup_conn: &Connection,
-) -> Result<(), String> {
-    let mut stmt = backup_conn
-        .prepare("SELECT id, code, name, time, quantity, cost, action FROM orders")
-        .map_err(|e| format!("准备查询失败: {}", e))?;
+        modify_backup(
+            &mut source,
+            "INSERT INTO watch_notes VALUES (1, '新版本数据');",
+        );
+        let result = import_databases(&mut conn, &[source], ImportStrategy::Replace, false);
+        assert!(result.unwrap_err().contains("watch_notes"));
+        assert_eq!(review_titles(&conn), vec!["备份复盘1", "本地复盘"]);
+        assert_eq!(selection_remark(&conn), "本地备注");
+    }
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, i64>(4)?,
-                row.get::<_, f64>(5)?,
-                row.get::<_, String>(6)?,
-            ))
-        })
-        .map_err(|e| format!("查询备份数据失败: {}", e))?;
+    #[test]
+    fn local_only_config_is_neither_cleared_nor_overwritten() {
+        let mut conn = open_test_connection();
+        conn.execute_batch(
+            "INSERT INTO app_config VALUES ('xueqiu_cookie', 'enc:v1:local');
+             INSERT INTO app_config VALUES ('offline_mode', '0');",
+        )
+        .unwrap();
+        let mut source = backup_source();
+        source.db_names = vec!["app_config".to_string()];
+        // 旧版备份中以明文保存的 Cookie
+        modify_backup(
+            &mut source,
+            "CREATE TABLE app_config (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             INSERT INTO app_config VALUES ('xueqiu_cookie', 'xq_a_token=plain');
+             INSERT INTO app_config VALUES ('offline_mode', '1');",
+        );
 
-    let tx = current_conn
-        .transaction()
-        .map_err(|e| format!("开始事务失败: {}", e))?;
+        let stats = import_databases(&mut conn, &[source], ImportStrategy::Replace, false).unwrap();
+        assert_eq!((stats[0].deleted, stats[0].inserted), (1, 1));
+        let cookie: String = conn
+            .query_row(
+                "SELECT value FROM app_config WHERE key = 'xueqiu_cookie'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(cookie, "enc:v1:local");
+    }
+
+    // 缓存数据不从备份导入
+    const CACHE_TABLES: &[&str] = &["kline_cache", "kline_cache_head", "market_snapshot"];
+
+    fn order(code: &str, time: &str, quantity: i32, cost: f64, action: &str) -> AddOrderParams {
+        AddOrderParams {
+            code: code.to_string(),
+            name: "测试股票".to_string(),
+            time: time.to_string(),
+            quantity,
+            cost,
+            action: action.to_string(),
+            account_id: 1,
+        }
+    }
+
+    // 每张表都有数据的数据库：委托、公司行动和划转（转入 account 账户）生成持仓、批次、卖出和分红记录
+    fn populated_with(account: &str) -> Connection {
+        let mut conn = open_test_connection();
+        conn.execute_batch(
+            "INSERT INTO app_config VALUES ('offline_mode', '1');
+             INSERT INTO all_stocks VALUES ('SH600000', '浦发银行');
+             INSERT INTO my_selection (code, name, sort) VALUES ('SH600000', '浦发银行', 1);
+             INSERT INTO stock_review (title, code, date, type) VALUES ('复盘', 'SH600000', '2024-01-02', '1');
+             INSERT INTO self_reflect (title, code, date) VALUES ('反思', 'SH600000', '2024-01-02');
+             INSERT INTO market_analysis VALUES ('2024-01-02', '震荡', '1');
+             INSERT INTO drawings (code, period, kind, points)
+                 VALUES ('SH600000', 'day', 'horizontal_line', '[{\"time\":1704153600000,\"price\":10}]');
+             INSERT INTO trend_line_breaks (line_id, code, period, mode, direction, line_price, price,
+                 message, triggered_at)
+                 VALUES (1, 'SH600000', 'day', 'cross', 'up', 10, 10.5, '突破', '2024-01-03 10:00:00');
+             INSERT INTO trend_lines_legacy (id, code, period, start_time, start_price, end_time, end_price)
+                 VALUES (2, 'SH600000', 'day', '0', '10', '1704758400000', '11');
+             INSERT INTO alert_rules (code, kind, threshold, created_at)
+                 VALUES ('SH600000', 'price_above', 11, '2024-01-02 09:00:00');
+             INSERT INTO alert_events (rule_id, code, kind, threshold, value, message, triggered_at)
+                 VALUES (1, 'SH600000', 'price_above', 11, 11.2, '价格突破', '2024-01-04 10:00:00');
+             INSERT INTO cash_ledger (time, kind, amount, note)
+                 VALUES ('2024-01-01 09:00:00', 'deposit', 100000, '入金');
+             INSERT INTO kline_cache VALUES ('SH600000', 'day', 1704153600000, 10, 10, 10, 10, 1, 0, 0,
+                 '2024-01-02 15:00:00');",
+        )
+        .unwrap();
+        let margin = create_account(&conn, account).unwrap();
+        record_order(
+            &mut conn,
+            &order("SH600000", "2024-01-02 10:00:00", 1000, 10.0, BUY_ACTION),
+        )
+        .unwrap();
+        record_corporate_action(
+            &mut conn,
+            &CorporateAction {
+                id: 0,
+                code: "SH600000".to_string(),
+                ex_date: "2024-03-01".to_string(),
+                cash_dividend: 0.5,
+                bonus_shares: 0.3,
+                transfer_shares: 0.0,
+                split_ratio: 1.0,
+                source: "manual".to_string(),
+                note: String::new(),
+            },
+        )
+        .unwrap();
+        record_order(
+            &mut conn,
+            &order("SH600000", "2024-04-01 10:00:00", 300, 9.0, SELL_ACTION),
+        )
+        .unwrap();
+        transfer_position(
+            &mut conn,
+            &TransferPositionParams {
+                from_account_id: 1,
+                to_account_id: margin,
+                code: "SH600000".to_string(),
+                quantity: 500,
+                time: Some("2024-05-01 10:00:00".to_string()),
+            },
+        )
+        .unwrap();
+        conn
+    }
+
+    fn populated_connection() -> Connection {
+        populated_with("融资账户")
+    }
+
+    // 导出压缩包中的数据库快照
+    fn exported_source(conn: &Connection) -> BackupSource {
+        let (zip_data, _) = export_database_zip(conn).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip_data)).unwrap();
+        let path = format!("databases/{}.db", APP_DB_NAME);
+        let mut data = Vec::new();
+        archive
+            .by_name(&path)
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        BackupSource {
+            path,
+            db_names: importable_databases()
+                .map(|schema| schema.name.to_string())
+                .collect(),
+            data,
+        }
+    }
 
-    for row in rows {
-        let (id, code, name, time, quantity, cost, action) =
-            row.map_err(|e| format!("读取行数据失败: {}", e))?;
-        tx.execute(
-            "INSERT OR REPLACE INTO orders (id, code, name, time, quantity, cost, action) VALUES (?, ?, ?, ?, ?, ?, ?)",
-            params![id, code, name, time, quantity, cost, action],
-        ).map_err(|e| format!("插入数据失败: {}", e))?;
+    fn row_counts(conn: &Connection) -> BTreeMap<String, i64> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT name FROM sqlite_master
+                 WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            )
+            .unwrap();
+        let tables = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        tables
+            .into_iter()
+            .filter(|table| !CACHE_TABLES.contains(&table.as_str()))
+            .map(|table| {
+                let count = conn
+                    .query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table), [], |row| {
+                        row.get(0)
+                    })
+                    .unwrap();
+                (table, count)
+            })
+            .collect()
     }
 
-    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
-    Ok(())
+    #[test]
+    fn export_then_import_restores_every_table() {
+        let conn = populated_connection();
+        let expected = row_counts(&conn);
+        let empty: Vec<&String> = expected
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(table, _)| table)
+            .collect();
+        assert!(empty.is_empty(), "未填充数据的表: {:?}", empty);
+
+        let mut restored = open_test_connection();
+        import_databases(
+            &mut restored,
+            &[exported_source(&conn)],
+            ImportStrategy::Replace,
+            false,
+        )
+        .unwrap();
+        assert_eq!(row_counts(&restored), expected);
+    }
+
+    // 引用的持仓、公司行动、账户、提醒规则或画线不存在的记录数
+    fn orphan_count(conn: &Connection) -> i64 {
+        conn.query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM holding_lots
+                    WHERE holding_id NOT IN (SELECT id FROM holdings))
+              + (SELECT COUNT(*) FROM holding_exits
+                    WHERE holding_id NOT IN (SELECT id FROM holdings))
+              + (SELECT COUNT(*) FROM holding_dividends
+                    WHERE holding_id NOT IN (SELECT id FROM holdings)
+                       OR action_id NOT IN (SELECT id FROM corporate_actions))
+              + (SELECT COUNT(*) FROM orders WHERE account_id NOT IN (SELECT id FROM accounts))
+              + (SELECT COUNT(*) FROM cash_ledger WHERE account_id NOT IN (SELECT id FROM accounts))
+              + (SELECT COUNT(*) FROM position_transfers
+                    WHERE from_account_id NOT IN (SELECT id FROM accounts)
+                       OR to_account_id NOT IN (SELECT id FROM accounts))
+              + (SELECT COUNT(*) FROM alert_events WHERE rule_id NOT IN (SELECT id FROM alert_rules))
+              + (SELECT COUNT(*) FROM trend_line_breaks WHERE line_id NOT IN (SELECT id FROM drawings))",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    fn holding_rows(conn: &Connection) -> Vec<(i32, String, i32, f64, i32)> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT account_id, code, quantity, cost, status FROM holdings
+                 ORDER BY account_id, code, hold_time",
+            )
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        rows
+    }
+
+    #[test]
+    fn every_strategy_keeps_references_intact() {
+        // 备份中第二个账户名称不同，本地的同一 id 已被占用
+        let backup = exported_source(&populated_with("长线账户"));
+        let strategies = [
+            (ImportStrategy::Replace, Some(500)),
+            // 备份的划转转入另一个账户，与本地 id 相同的划转不是同一条记录，重新分配 id 后写入
+            (ImportStrategy::KeepLocal, Some(500)),
+            (ImportStrategy::KeepBackup, Some(500)),
+            (ImportStrategy::Append, Some(500)),
+        ];
+        for (strategy, transferred) in strategies {
+            let mut conn = populated_connection();
+            import_databases(&mut conn, &[backup.clone()], strategy, false).unwrap();
+            assert_eq!(orphan_count(&conn), 0, "{:?}", strategy);
+
+            // 持仓与按委托重新生成的结果一致
+            let holdings = holding_rows(&conn);
+            rebuild_holdings(&mut conn).unwrap();
+            assert_eq!(holding_rows(&conn), holdings, "{:?}", strategy);
+
+            // 划转记录指向导入后“长线账户”的 id
+            let quantity: Option<i32> = conn
+                .query_row(
+                    "SELECT h.quantity FROM holdings h JOIN accounts a ON a.id = h.account_id
+                     WHERE a.name = '长线账户' AND h.status = 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()
+                .unwrap();
+            assert_eq!(quantity, transferred, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn legacy_manual_holdings_survive_import() {
+        // 旧版备份：holdings.db 中手动录入的持仓没有对应委托，orders.db 中只有另一只股票的委托
+        let temp_dir = tempfile::tempdir().unwrap();
+        let holdings_path = temp_dir.path().join("holdings.db");
+        let orders_path = temp_dir.path().join("orders.db");
+        Connection::open(&holdings_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE holdings (id INTEGER PRIMARY KEY AUTOINCREMENT, code TEXT NOT NULL,
+                    name TEXT NOT NULL, cost REAL NOT NULL, quantity INTEGER NOT NULL,
+                    hold_time TEXT NOT NULL, status INTEGER NOT NULL DEFAULT 1, sell_time TEXT,
+                    sell_price REAL, profit REAL);
+                 INSERT INTO holdings (code, name, cost, quantity, hold_time)
+                 VALUES ('SH600000', '浦发银行', 10.0, 100, '2024-01-02 10:00:00');
+                 INSERT INTO holdings (code, name, cost, quantity, hold_time)
+                 VALUES ('SZ000001', '平安银行', 12.0, 200, '2024-01-02 10:00:00');",
+            )
+            .unwrap();
+        Connection::open(&orders_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY AUTOINCREMENT, code TEXT NOT NULL,
+                    name TEXT NOT NULL, time TEXT NOT NULL, quantity INTEGER NOT NULL,
+                    cost REAL NOT NULL, action TEXT NOT NULL);
+                 INSERT INTO orders (code, name, time, quantity, cost, action)
+                 VALUES ('SZ000001', '平安银行', '2024-01-02 10:00:00', 200, 12.0, '1');",
+            )
+            .unwrap();
+        let sources = [
+            BackupSource {
+                path: "holdings.db".to_string(),
+                db_names: vec!["holdings".to_string()],
+                data: std::fs::read(&holdings_path).unwrap(),
+            },
+            BackupSource {
+                path: "orders.db".to_string(),
+                db_names: vec!["orders".to_string()],
+                data: std::fs::read(&orders_path).unwrap(),
+            },
+        ];
+
+        let mut conn = open_test_connection();
+        let stats = import_databases(&mut conn, &sources, ImportStrategy::Replace, false).unwrap();
+        let orders = stats.iter().find(|stats| stats.table == "orders").unwrap();
+        assert_eq!(orders.inserted, 2);
+        assert_eq!(
+            holding_rows(&conn)
+                .into_iter()
+                .map(|(_, code, quantity, _, _)| (code, quantity))
+                .collect::<Vec<_>>(),
+            vec![("SH600000".to_string(), 100), ("SZ000001".to_string(), 200)]
+        );
+    }
 }
@@ -1,115 +1,159 @@
-use crate::db::common::init_database;
+use crate::db::common::{get_db_directory, get_db_path, AppDb, APP_DB_NAME};
+use crate::db::legacy_db::migrate_legacy_databases;
+use crate::db::migrations::ColumnType::{Integer, Real, Text};
+use crate::db::migrations::{run_migrations, ColumnDef, DbSchema, Migration, TableDef};
+use crate::db::trend_lines_db::count_legacy_trend_lines;
 use rusqlite::{Connection, Result};
 use tauri::AppHandle;
-pub fn init_app_config_database(app: &AppHandle) -> Result<Connection, String> {
-    let conn = init_database(app, "app_config")?;
+use tauri_plugin_log::log;
 
-    // 创建用户表
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS app_config (
+// --------------------------
+// 各数据库的迁移清单
+// 新增字段/表时只能在末尾追加新的 Migration，不要修改已发布的版本
+// --------------------------
+pub const APP_CONFIG_SCHEMA: DbSchema = DbSchema {
+    name: "app_config",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 app_config 表",
+        sql: "CREATE TABLE IF NOT EXISTS app_config (
             key TEXT PRIMARY KEY,
             value TEXT NOT NULL
-        )",
-        [],
-    )
-    .map_err(|e| format!("无法创建app_config表: {}", e))?;
+        );",
+    }],
+    tables: &[TableDef {
+        name: "app_config",
+        columns: &[ColumnDef::new("key", Text), ColumnDef::new("value", Text)],
+        auto_id: false,
+    }],
+};
 
-    Ok(conn)
-}
-
-pub fn init_all_stocks_database(app: &AppHandle) -> Result<Connection, String> {
-    let conn = init_database(app, "all_stocks")?;
-
-    // 创建用户表
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS all_stocks (
+pub const ALL_STOCKS_SCHEMA: DbSchema = DbSchema {
+    name: "all_stocks",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 all_stocks 表",
+        sql: "CREATE TABLE IF NOT EXISTS all_stocks (
             symbol TEXT PRIMARY KEY,  -- 股票代码作为主键（避免重复）
             name TEXT NOT NULL       -- 股票名称
-        )",
-        [],
-    )
-    .map_err(|e| format!("无法创建app_config表: {}", e))?;
+        );",
+    }],
+    tables: &[TableDef {
+        name: "all_stocks",
+        columns: &[ColumnDef::new("symbol", Text), ColumnDef::new("name", Text)],
+        auto_id: false,
+    }],
+};
 
-    Ok(conn)
-}
+pub const MY_SELECTION_SCHEMA: DbSchema = DbSchema {
+    name: "my_selection",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 my_selection 表",
+        sql: "CREATE TABLE IF NOT EXISTS my_selection (
+            code TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            color TEXT,
+            remark TEXT,
+            sort INTEGER DEFAULT 0
+        );",
+    }],
+    tables: &[TableDef {
+        name: "my_selection",
+        columns: &[
+            ColumnDef::new("code", Text),
+            ColumnDef::new("name", Text),
+            ColumnDef::new("color", Text).nullable(),
+            ColumnDef::new("remark", Text).nullable(),
+            ColumnDef::new("sort", Integer).default("0"),
+        ],
+        auto_id: false,
+    }],
+};
 
-pub fn init_my_selection_database(app: &AppHandle) -> Result<Connection, String> {
-    // 1. 调用通用初始化函数，获取数据库连接（确保数据库文件路径正确、目录存在）
-    let conn = init_database(app, "my_selection")?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS my_selection (
-            code TEXT PRIMARY KEY,       
-            name TEXT NOT NULL,           
-            color TEXT,                   
-            remark TEXT,                  
-            sort INTEGER DEFAULT 0        
-        )",
-        [], // 无参数，仅创建表结构
-    )
-    .map_err(|e| format!("无法创建 my_selection 表: {}", e))?;
-
-    Ok(conn)
-}
-
-pub fn init_stock_review_database(app: &AppHandle) -> Result<Connection, String> {
-    // 1. 调用通用初始化函数，获取数据库连接（确保 my_selection.db 文件路径正确、目录存在）
-    let conn = init_database(app, "stock_review")?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS stock_review (
+pub const STOCK_REVIEW_SCHEMA: DbSchema = DbSchema {
+    name: "stock_review",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 stock_review 表",
+        sql: "CREATE TABLE IF NOT EXISTS stock_review (
             id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID（删除用）
-            title TEXT NOT NULL,          
-            code TEXT NOT NULL,           
-            date TEXT NOT NULL,           
-            type TEXT NOT NULL,           
-            description TEXT              
-        )",
-        [], // 无参数，仅创建表结构
-    )
-    .map_err(|e| format!("无法创建 stock_review 表: {}", e))?;
-    Ok(conn)
-}
-
-pub fn init_self_reflect_database(app: &AppHandle) -> Result<Connection, String> {
-    // 1. 调用通用初始化函数，获取数据库连接（确保 my_selection.db 文件路径正确、目录存在）
-    let conn = init_database(app, "self_reflect")?;
+            title TEXT NOT NULL,
+            code TEXT NOT NULL,
+            date TEXT NOT NULL,
+            type TEXT NOT NULL,
+            description TEXT
+        );",
+    }],
+    tables: &[TableDef {
+        name: "stock_review",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("title", Text).key(), // 同一股票同一日期同标题的复盘视为同一条
+            ColumnDef::new("code", Text).key(),
+            ColumnDef::new("date", Text).key(),
+            ColumnDef::new("type", Text).key(),
+            ColumnDef::new("description", Text).nullable(),
+        ],
+        auto_id: true,
+    }],
+};
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS self_reflect (
+pub const SELF_REFLECT_SCHEMA: DbSchema = DbSchema {
+    name: "self_reflect",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 self_reflect 表",
+        sql: "CREATE TABLE IF NOT EXISTS self_reflect (
             id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID（删除用）
-            title TEXT NOT NULL,          
-            code TEXT NOT NULL,           
-            date TEXT NOT NULL,           
-            description TEXT              
-        )",
-        [], // 无参数，仅创建表结构
-    )
-    .map_err(|e| format!("无法创建 self_reflect 表: {}", e))?;
-    Ok(conn)
-}
-
-pub fn init_market_analysis_database(app: &AppHandle) -> Result<Connection, String> {
-    let conn = init_database(app, "market_analysis")?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS market_analysis (
-            date TEXT PRIMARY KEY,   
-            analysis TEXT NOT NULL,   
-            status TEXT NOT NULL   
-        
-        )",
-        [], // 无参数，仅创建表结构
-    )
-    .map_err(|e| format!("无法创建 market_analysis 表: {}", e))?;
-    Ok(conn)
-}
+            title TEXT NOT NULL,
+            code TEXT NOT NULL,
+            date TEXT NOT NULL,
+            description TEXT
+        );",
+    }],
+    tables: &[TableDef {
+        name: "self_reflect",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("title", Text).key(), // 同一股票同一日期同标题的反思视为同一条
+            ColumnDef::new("code", Text).key(),
+            ColumnDef::new("date", Text).key(),
+            ColumnDef::new("description", Text).nullable(),
+        ],
+        auto_id: true,
+    }],
+};
 
-pub fn init_trend_lines_database(app: &AppHandle) -> Result<Connection, String> {
-    // 初始化数据库连接，表文件名为 "trend_lines"
-    let conn = init_database(app, "trend_lines")?;
+pub const MARKET_ANALYSIS_SCHEMA: DbSchema = DbSchema {
+    name: "market_analysis",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 market_analysis 表",
+        sql: "CREATE TABLE IF NOT EXISTS market_analysis (
+            date TEXT PRIMARY KEY,
+            analysis TEXT NOT NULL,
+            status TEXT NOT NULL
+        );",
+    }],
+    tables: &[TableDef {
+        name: "market_analysis",
+        columns: &[
+            ColumnDef::new("date", Text),
+            ColumnDef::new("analysis", Text),
+            ColumnDef::new("status", Text),
+        ],
+        auto_id: false,
+    }],
+};
 
-    // 创建用于存储斜线（趋势线）的新表
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS trend_lines (
+pub const TREND_LINES_SCHEMA: DbSchema = DbSchema {
+    name: "trend_lines",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "创建 trend_lines 表",
+            sql: "CREATE TABLE IF NOT EXISTS trend_lines (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             code TEXT NOT NULL,               -- 股票代码
             period TEXT NOT NULL,             -- 周期（如 'day', 'week' 等）
@@ -117,57 +161,712 @@ pub fn init_trend_lines_database(app: &AppHandle) -> Result<Connection, String>
             start_price REAL NOT NULL,        -- 起始价格
             end_time INTEGER NOT NULL,        -- 结束K线时间戳（13位毫秒）
             end_price REAL NOT NULL           -- 结束价格
-        )",
-        [], // 无参数
-    )
-    .map_err(|e| format!("无法创建 trend_lines 表: {}", e))?;
+        );",
+        },
+        Migration {
+            version: 2,
+            description: "趋势线突破提醒设置与突破记录",
+            sql: "ALTER TABLE trend_lines ADD COLUMN alert_enabled INTEGER NOT NULL DEFAULT 0;  -- 是否开启突破提醒
+            ALTER TABLE trend_lines ADD COLUMN alert_mode TEXT NOT NULL DEFAULT 'cross';      -- cross/touch/close
+            ALTER TABLE trend_lines ADD COLUMN tolerance REAL NOT NULL DEFAULT 0.5;           -- 触及容差（%）
 
-    Ok(conn)
-}
+            CREATE TABLE IF NOT EXISTS trend_line_breaks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
+                line_id INTEGER NOT NULL,              -- 趋势线ID（趋势线删除后保留记录）
+                code TEXT NOT NULL,                    -- 股票代码
+                period TEXT NOT NULL,                  -- 趋势线所在周期
+                mode TEXT NOT NULL,                    -- 触发方式
+                direction TEXT NOT NULL,               -- up/down/touch
+                line_price REAL NOT NULL,              -- 触发时趋势线的价格
+                price REAL NOT NULL,                   -- 触发时的价格
+                bar_time INTEGER,                      -- 收盘模式下触发的K线时间
+                message TEXT NOT NULL,                 -- 提醒内容
+                triggered_at TEXT NOT NULL             -- 触发时间
+            );
+            CREATE INDEX IF NOT EXISTS idx_trend_line_breaks_line ON trend_line_breaks (line_id, bar_time);",
+        },
+        Migration {
+            version: 3,
+            description: "趋势线迁移到通用画线表 drawings",
+            // 保留原趋势线 ID，突破记录中的 line_id 继续有效；数字被存成文本的旧数据按数字转换
+            sql: "CREATE TABLE IF NOT EXISTS drawings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,       -- 唯一自增ID
+                code TEXT NOT NULL,                         -- 股票代码
+                period TEXT NOT NULL,                       -- 周期（如 'day', 'week' 等）
+                kind TEXT NOT NULL,                         -- 画线类型（trend_line/horizontal_line/ray/...）
+                points TEXT NOT NULL,                       -- 锚点 JSON 数组：[{time: 13位时间戳, price: 价格}]
+                style TEXT NOT NULL DEFAULT '{}',           -- 样式 JSON（颜色、线宽等）
+                label TEXT NOT NULL DEFAULT '',             -- 文字标注/备注
+                alert_enabled INTEGER NOT NULL DEFAULT 0,   -- 是否开启突破提醒
+                alert_mode TEXT NOT NULL DEFAULT 'cross',   -- cross/touch/close
+                tolerance REAL NOT NULL DEFAULT 0.5,        -- 触及容差（%）
+                created_at TEXT NOT NULL DEFAULT '',        -- 创建时间
+                updated_at TEXT NOT NULL DEFAULT ''         -- 修改时间
+            );
+            INSERT INTO drawings
+                (id, code, period, kind, points, alert_enabled, alert_mode, tolerance, created_at, updated_at)
+            SELECT id, UPPER(code), period, 'trend_line',
+                   json_array(
+                       json_object('time', CAST(start_time AS INTEGER), 'price', CAST(start_price AS REAL)),
+                       json_object('time', CAST(end_time AS INTEGER), 'price', CAST(end_price AS REAL))
+                   ),
+                   alert_enabled, alert_mode, tolerance,
+                   strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime'),
+                   strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')
+            FROM trend_lines
+            WHERE CAST(start_time AS INTEGER) > 0 AND CAST(end_time AS INTEGER) > 0
+              AND CAST(start_price AS REAL) > 0 AND CAST(end_price AS REAL) > 0;
+            -- 时间或价格无效、无法转换为画线的趋势线原样保留，启动时提示条数
+            CREATE TABLE IF NOT EXISTS trend_lines_legacy (
+                id INTEGER PRIMARY KEY,                     -- 原趋势线ID
+                code TEXT NOT NULL,                         -- 股票代码
+                period TEXT NOT NULL,                       -- 周期
+                start_time TEXT,                            -- 原起始时间（保留原值）
+                start_price TEXT,                           -- 原起始价格
+                end_time TEXT,                              -- 原结束时间
+                end_price TEXT,                             -- 原结束价格
+                alert_enabled INTEGER NOT NULL DEFAULT 0,   -- 是否开启突破提醒
+                alert_mode TEXT NOT NULL DEFAULT 'cross',   -- cross/touch/close
+                tolerance REAL NOT NULL DEFAULT 0.5         -- 触及容差（%）
+            );
+            INSERT INTO trend_lines_legacy
+            SELECT id, code, period, start_time, start_price, end_time, end_price,
+                   alert_enabled, alert_mode, tolerance
+            FROM trend_lines
+            WHERE id NOT IN (SELECT id FROM drawings);
+            DROP TABLE trend_lines;
+            CREATE INDEX IF NOT EXISTS idx_drawings_code_period ON drawings (code, period);",
+        },
+    ],
+    tables: &[TableDef {
+        name: "drawings",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("code", Text).key(), // 同一图表上位置相同的画线视为同一条
+            ColumnDef::new("period", Text).key(),
+            ColumnDef::new("kind", Text).key(),
+            ColumnDef::new("points", Text).key(),
+            ColumnDef::new("style", Text).default("{}"),
+            ColumnDef::new("label", Text).default(""),
+            ColumnDef::new("alert_enabled", Integer).default("0"),
+            ColumnDef::new("alert_mode", Text).default("cross"),
+            ColumnDef::new("tolerance", Real).default("0.5"),
+            ColumnDef::new("created_at", Text).default(""),
+            ColumnDef::new("updated_at", Text).default(""),
+        ],
+        auto_id: true,
+    },
+    TableDef {
+        name: "trend_line_breaks",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("line_id", Integer).references("drawings").key(), // 同一画线同一时刻的突破只记一次
+            ColumnDef::new("code", Text),
+            ColumnDef::new("period", Text),
+            ColumnDef::new("mode", Text),
+            ColumnDef::new("direction", Text).key(),
+            ColumnDef::new("line_price", Real),
+            ColumnDef::new("price", Real),
+            ColumnDef::new("bar_time", Integer).nullable(),
+            ColumnDef::new("message", Text),
+            ColumnDef::new("triggered_at", Text).key(),
+        ],
+        auto_id: true,
+    },
+    TableDef {
+        name: "trend_lines_legacy",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("code", Text),
+            ColumnDef::new("period", Text),
+            ColumnDef::new("start_time", Text).nullable(),
+            ColumnDef::new("start_price", Text).nullable(),
+            ColumnDef::new("end_time", Text).nullable(),
+            ColumnDef::new("end_price", Text).nullable(),
+            ColumnDef::new("alert_enabled", Integer).default("0"),
+            ColumnDef::new("alert_mode", Text).default("cross"),
+            ColumnDef::new("tolerance", Real).default("0.5"),
+        ],
+        auto_id: false,
+    }],
+};
+
+pub const HOLDINGS_SCHEMA: DbSchema = DbSchema {
+    name: "holdings",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "创建 holdings 表",
+            sql: "CREATE TABLE IF NOT EXISTS holdings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
+                code TEXT NOT NULL,                    -- 股票代码
+                name TEXT NOT NULL,                    -- 股票名称
+                cost REAL NOT NULL,                    -- 成本价
+                quantity INTEGER NOT NULL,             -- 持有数量
+                hold_time TEXT NOT NULL,               -- 持仓时间（如：YYYY-MM-DD HH:MM:SS）
+                status INTEGER NOT NULL DEFAULT 1,     -- 状态：1-当前持仓，0-历史记录
+                sell_time TEXT,                        -- 卖出时间（清仓时记录）
+                sell_price REAL,                       -- 卖出价格（清仓时记录）
+                profit REAL                            -- 盈利（清仓时记录）
+            );",
+        },
+        Migration {
+            version: 2,
+            description: "新增持仓批次与卖出记录",
+            sql: "CREATE TABLE IF NOT EXISTS holding_lots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                holding_id INTEGER NOT NULL,           -- 所属持仓
+                order_id INTEGER,                      -- 对应的买入委托
+                code TEXT NOT NULL,                    -- 股票代码
+                buy_time TEXT NOT NULL,                -- 买入时间
+                price REAL NOT NULL,                   -- 买入价格
+                quantity INTEGER NOT NULL,             -- 买入数量
+                remaining INTEGER NOT NULL             -- 剩余未卖出数量
+            );
+            CREATE INDEX IF NOT EXISTS idx_holding_lots_holding ON holding_lots (holding_id);
+
+            CREATE TABLE IF NOT EXISTS holding_exits (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                holding_id INTEGER NOT NULL,           -- 所属持仓
+                order_id INTEGER,                      -- 对应的卖出委托
+                code TEXT NOT NULL,                    -- 股票代码
+                name TEXT NOT NULL,                    -- 股票名称
+                sell_time TEXT NOT NULL,               -- 卖出时间
+                sell_price REAL NOT NULL,              -- 卖出价格
+                quantity INTEGER NOT NULL,             -- 卖出数量
+                cost REAL NOT NULL,                    -- 卖出部分的每股成本
+                profit REAL NOT NULL,                  -- 本次卖出的已实现盈亏
+                closed INTEGER NOT NULL DEFAULT 0      -- 本次卖出后是否清仓
+            );
+            CREATE INDEX IF NOT EXISTS idx_holding_exits_holding ON holding_exits (holding_id);
+
+            -- 卖出历史：每笔卖出一条；没有卖出明细的旧清仓记录按一次全部卖出展示
+            CREATE VIEW IF NOT EXISTS holding_exit_history AS
+                SELECT e.id, e.holding_id, e.order_id, e.code, e.name, h.hold_time,
+                       e.sell_time, e.sell_price, e.quantity, e.cost, e.profit, e.closed
+                FROM holding_exits e
+                JOIN holdings h ON h.id = e.holding_id
+                UNION ALL
+                SELECT NULL, h.id, NULL, h.code, h.name, h.hold_time,
+                       h.sell_time, h.sell_price, h.quantity, h.cost, h.profit, 1
+                FROM holdings h
+                WHERE h.status = 0
+                  AND NOT EXISTS (SELECT 1 FROM holding_exits e WHERE e.holding_id = h.id);",
+        },
+        Migration {
+            version: 3,
+            description: "批次与卖出记录新增交易费用",
+            sql: "ALTER TABLE holding_lots ADD COLUMN fee REAL NOT NULL DEFAULT 0;   -- 买入费用
+                ALTER TABLE holding_exits ADD COLUMN fee REAL NOT NULL DEFAULT 0;    -- 卖出费用
+
+            DROP VIEW IF EXISTS holding_exit_history;
+            CREATE VIEW holding_exit_history AS
+                SELECT e.id, e.holding_id, e.order_id, e.code, e.name, h.hold_time,
+                       e.sell_time, e.sell_price, e.quantity, e.cost, e.profit, e.closed, e.fee
+                FROM holding_exits e
+                JOIN holdings h ON h.id = e.holding_id
+                UNION ALL
+                SELECT NULL, h.id, NULL, h.code, h.name, h.hold_time,
+                       h.sell_time, h.sell_price, h.quantity, h.cost, h.profit, 1, 0
+                FROM holdings h
+                WHERE h.status = 0
+                  AND NOT EXISTS (SELECT 1 FROM holding_exits e WHERE e.holding_id = h.id);",
+        },
+        Migration {
+            version: 4,
+            description: "持仓按账户区分",
+            sql:
+                "ALTER TABLE holdings ADD COLUMN account_id INTEGER NOT NULL DEFAULT 1;  -- 所属账户
+            CREATE INDEX IF NOT EXISTS idx_holdings_account_code ON holdings (account_id, code);
+
+            DROP VIEW IF EXISTS holding_exit_history;
+            CREATE VIEW holding_exit_history AS
+                SELECT e.id, e.holding_id, e.order_id, e.code, e.name, h.hold_time,
+                       e.sell_time, e.sell_price, e.quantity, e.cost, e.profit, e.closed, e.fee,
+                       h.account_id
+                FROM holding_exits e
+                JOIN holdings h ON h.id = e.holding_id
+                UNION ALL
+                SELECT NULL, h.id, NULL, h.code, h.name, h.hold_time,
+                       h.sell_time, h.sell_price, h.quantity, h.cost, h.profit, 1, 0,
+                       h.account_id
+                FROM holdings h
+                WHERE h.status = 0
+                  AND NOT EXISTS (SELECT 1 FROM holding_exits e WHERE e.holding_id = h.id);",
+        },
+        Migration {
+            version: 5,
+            description: "新增持仓分红记录",
+            sql: "CREATE TABLE IF NOT EXISTS holding_dividends (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                holding_id INTEGER NOT NULL,           -- 所属持仓
+                action_id INTEGER NOT NULL,            -- 对应的公司行动
+                code TEXT NOT NULL,                    -- 股票代码
+                ex_date TEXT NOT NULL,                 -- 除息日
+                quantity INTEGER NOT NULL,             -- 享有分红的持仓数量
+                per_share REAL NOT NULL,               -- 每股派息
+                amount REAL NOT NULL,                  -- 分红金额
+                account_id INTEGER NOT NULL DEFAULT 1  -- 所属账户
+            );
+            CREATE INDEX IF NOT EXISTS idx_holding_dividends_holding ON holding_dividends (holding_id);",
+        },
+    ],
+    // 持仓、批次、卖出和分红记录都由委托、划转和公司行动推导，导入后重新生成
+    tables: &[],
+};
 
-pub fn init_holdings_database(app: &AppHandle) -> Result<Connection, String> {
-    // 初始化数据库连接，表文件名为"holdings"
-    let conn = init_database(app, "holdings")?;
-
-    // 创建持仓表（包含状态字段）
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS holdings (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
-            code TEXT NOT NULL,                    -- 股票代码
-            name TEXT NOT NULL,                    -- 股票名称
-            cost REAL NOT NULL,                    -- 成本价
-            quantity INTEGER NOT NULL,             -- 持有数量
-            hold_time TEXT NOT NULL,               -- 持仓时间（如：YYYY-MM-DD HH:MM:SS）
-            status INTEGER NOT NULL DEFAULT 1,     -- 状态：1-当前持仓，0-历史记录
-            sell_time TEXT,                        -- 卖出时间（清仓时记录）
-            sell_price REAL,                       -- 卖出价格（清仓时记录）
-            profit REAL                            -- 盈利（清仓时记录）
-        )",
-        [], // 无参数
-    )
-    .map_err(|e| format!("无法创建 holdings 表: {}", e))?;
-
-    Ok(conn)
+pub const ORDERS_SCHEMA: DbSchema = DbSchema {
+    name: "orders",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "创建 orders 表",
+            sql: "CREATE TABLE IF NOT EXISTS orders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
+                code TEXT NOT NULL,                    -- 股票代码
+                name TEXT NOT NULL,                    -- 股票名称
+                time TEXT NOT NULL,                    -- 委托时间
+                quantity INTEGER NOT NULL,             -- 委托数量
+                cost REAL NOT NULL,                    -- 委托价格/成本
+                action TEXT NOT NULL                   -- 操作类型：买入/卖出
+            );",
+        },
+        Migration {
+            version: 2,
+            description: "新增委托费用字段",
+            sql: "ALTER TABLE orders ADD COLUMN commission REAL NOT NULL DEFAULT 0;     -- 佣金
+                ALTER TABLE orders ADD COLUMN stamp_duty REAL NOT NULL DEFAULT 0;     -- 印花税
+                ALTER TABLE orders ADD COLUMN transfer_fee REAL NOT NULL DEFAULT 0;   -- 过户费",
+        },
+        Migration {
+            version: 3,
+            description: "委托按账户区分",
+            sql: "ALTER TABLE orders ADD COLUMN account_id INTEGER NOT NULL DEFAULT 1;  -- 所属账户
+            CREATE INDEX IF NOT EXISTS idx_orders_account_code ON orders (account_id, code);",
+        },
+    ],
+    tables: &[TableDef {
+        name: "orders",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("code", Text).key(), // 同一账户同一时间、价格和数量的成交视为同一笔
+            ColumnDef::new("name", Text),
+            ColumnDef::new("time", Text).key(),
+            ColumnDef::new("quantity", Integer).key(),
+            ColumnDef::new("cost", Real).key(),
+            ColumnDef::new("action", Text).key(),
+            ColumnDef::new("commission", Real).default("0"),
+            ColumnDef::new("stamp_duty", Real).default("0"),
+            ColumnDef::new("transfer_fee", Real).default("0"),
+            ColumnDef::new("account_id", Integer)
+                .default("1")
+                .references("accounts")
+                .key(),
+        ],
+        auto_id: true,
+    }],
+};
+
+pub const KLINE_CACHE_SCHEMA: DbSchema = DbSchema {
+    name: "kline_cache",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "创建 kline_cache 表",
+            sql: "CREATE TABLE IF NOT EXISTS kline_cache (
+                symbol TEXT NOT NULL,             -- 股票代码（大写）
+                period TEXT NOT NULL,             -- 周期（day/week/month 等）
+                timestamp INTEGER NOT NULL,       -- K线时间戳（13位毫秒）
+                open REAL NOT NULL,
+                high REAL NOT NULL,
+                low REAL NOT NULL,
+                close REAL NOT NULL,
+                volume REAL NOT NULL,
+                percent REAL NOT NULL,
+                turnoverrate REAL NOT NULL,
+                updated_at TEXT NOT NULL,         -- 写入时间
+                PRIMARY KEY (symbol, period, timestamp)
+            ) WITHOUT ROWID;",
+        },
+        Migration {
+            version: 2,
+            description: "记录已获取到上市首根K线的股票，避免反复向前补数据",
+            sql: "CREATE TABLE IF NOT EXISTS kline_cache_head (
+                symbol TEXT NOT NULL,             -- 股票代码（大写）
+                period TEXT NOT NULL,
+                first_timestamp INTEGER NOT NULL, -- 上市后第一根K线的时间戳
+                PRIMARY KEY (symbol, period)
+            );",
+        },
+    ],
+    tables: &[], // 缓存数据不从备份导入
+};
+
+pub const MARKET_SNAPSHOT_SCHEMA: DbSchema = DbSchema {
+    name: "market_snapshot",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 market_snapshot 表",
+        sql: "CREATE TABLE IF NOT EXISTS market_snapshot (
+            kind TEXT NOT NULL,               -- 数据类型：quote/detail/minute
+            symbol TEXT NOT NULL,             -- 股票代码（大写）
+            data TEXT NOT NULL,               -- 最后一次成功获取的数据（JSON）
+            as_of TEXT NOT NULL,              -- 获取时间（YYYY-MM-DD HH:MM:SS）
+            PRIMARY KEY (kind, symbol)
+        );",
+    }],
+    tables: &[], // 缓存数据不从备份导入
+};
+
+pub const CASH_LEDGER_SCHEMA: DbSchema = DbSchema {
+    name: "cash_ledger",
+    migrations: &[
+        Migration {
+            version: 1,
+        description: "创建 cash_ledger 表与资金流水视图",
+        sql: "CREATE TABLE IF NOT EXISTS cash_ledger (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
+                time TEXT NOT NULL,                    -- 发生时间（YYYY-MM-DD HH:MM:SS）
+                kind TEXT NOT NULL,                    -- 类型：deposit/withdraw/dividend/fee
+                amount REAL NOT NULL,                  -- 金额（入账为正，出账为负）
+                code TEXT,                             -- 相关股票代码（如分红）
+                note TEXT NOT NULL DEFAULT ''          -- 备注
+            );
+
+            -- 资金流水：手动记录 + 委托自动产生的买入支出与卖出收入（已含费用）
+            CREATE VIEW IF NOT EXISTS cash_flows AS
+                SELECT id, NULL AS order_id, time, kind, amount, code, note
+                FROM cash_ledger
+                UNION ALL
+                SELECT NULL, id, time,
+                       CASE WHEN action IN ('1', '买入') THEN 'buy' ELSE 'sell' END,
+                       CASE WHEN action IN ('1', '买入')
+                            THEN -(cost * quantity + commission + stamp_duty + transfer_fee)
+                            ELSE cost * quantity - commission - stamp_duty - transfer_fee
+                       END,
+                       code, name
+                FROM orders;",
+        },
+        Migration {
+            version: 2,
+            description: "资金流水按账户区分",
+            sql: "ALTER TABLE cash_ledger ADD COLUMN account_id INTEGER NOT NULL DEFAULT 1;  -- 所属账户
+
+            DROP VIEW IF EXISTS cash_flows;
+            CREATE VIEW cash_flows AS
+                SELECT id, NULL AS order_id, time, kind, amount, code, note, account_id
+                FROM cash_ledger
+                UNION ALL
+                SELECT NULL, id, time,
+                       CASE WHEN action IN ('1', '买入') THEN 'buy' ELSE 'sell' END,
+                       CASE WHEN action IN ('1', '买入')
+                            THEN -(cost * quantity + commission + stamp_duty + transfer_fee)
+                            ELSE cost * quantity - commission - stamp_duty - transfer_fee
+                       END,
+                       code, name, account_id
+                FROM orders;",
+        },
+        Migration {
+            version: 3,
+            description: "资金流水包含公司行动产生的现金分红",
+            sql: "DROP VIEW IF EXISTS cash_flows;
+            CREATE VIEW cash_flows AS
+                SELECT id, NULL AS order_id, time, kind, amount, code, note, account_id
+                FROM cash_ledger
+                UNION ALL
+                SELECT NULL, id, time,
+                       CASE WHEN action IN ('1', '买入') THEN 'buy' ELSE 'sell' END,
+                       CASE WHEN action IN ('1', '买入')
+                            THEN -(cost * quantity + commission + stamp_duty + transfer_fee)
+                            ELSE cost * quantity - commission - stamp_duty - transfer_fee
+                       END,
+                       code, name, account_id
+                FROM orders
+                UNION ALL
+                SELECT NULL, NULL, ex_date || ' 00:00:00', 'dividend', amount, code,
+                       '现金分红', account_id
+                FROM holding_dividends;",
+        },
+        Migration {
+            version: 4,
+            description: "已由公司行动计入的手动分红不再重复入账",
+            // 旧版本手动录入的分红与公司行动产生的分红对应（同账户、同股票、除息日前后）时
+            // 标记为 duplicate_dividend 并按 0 计入，记录保留供用户核对或删除
+            sql: "DROP VIEW IF EXISTS cash_flows;
+            CREATE VIEW cash_flows AS
+                SELECT id, NULL AS order_id, time,
+                       CASE WHEN duplicate THEN 'duplicate_dividend' ELSE kind END AS kind,
+                       CASE WHEN duplicate THEN 0 ELSE amount END AS amount,
+                       code, note, account_id
+                FROM (
+                    SELECT l.*,
+                           l.kind = 'dividend' AND EXISTS (
+                               SELECT 1 FROM holding_dividends d
+                               WHERE d.account_id = l.account_id
+                                 AND UPPER(d.code) = UPPER(l.code)
+                                 AND substr(l.time, 1, 10)
+                                     BETWEEN date(d.ex_date, '-3 days') AND date(d.ex_date, '+30 days')
+                           ) AS duplicate
+                    FROM cash_ledger l
+                )
+                UNION ALL
+                SELECT NULL, id, time,
+                       CASE WHEN action IN ('1', '买入') THEN 'buy' ELSE 'sell' END,
+                       CASE WHEN action IN ('1', '买入')
+                            THEN -(cost * quantity + commission + stamp_duty + transfer_fee)
+                            ELSE cost * quantity - commission - stamp_duty - transfer_fee
+                       END,
+                       code, name, account_id
+                FROM orders
+                UNION ALL
+                SELECT NULL, NULL, ex_date || ' 00:00:00', 'dividend', amount, code,
+                       '现金分红', account_id
+                FROM holding_dividends;",
+        },
+    ],
+    tables: &[TableDef {
+        name: "cash_ledger",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("time", Text).key(), // 同一账户同一时间、类型和金额的流水视为同一条
+            ColumnDef::new("kind", Text).key(),
+            ColumnDef::new("amount", Real).key(),
+            ColumnDef::new("code", Text).nullable(),
+            ColumnDef::new("note", Text).default(""),
+            ColumnDef::new("account_id", Integer)
+                .default("1")
+                .references("accounts").key(),
+        ],
+        auto_id: true,
+    }],
+};
+
+pub const ACCOUNTS_SCHEMA: DbSchema = DbSchema {
+    name: "accounts",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 accounts 表与持仓划转记录",
+        sql: "CREATE TABLE IF NOT EXISTS accounts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
+                name TEXT NOT NULL UNIQUE,             -- 账户名称
+                archived INTEGER NOT NULL DEFAULT 0,   -- 是否已归档
+                created_at TEXT NOT NULL               -- 创建时间
+            );
+            -- 默认账户：升级前的委托、持仓和资金流水都归入此账户
+            INSERT OR IGNORE INTO accounts (id, name, archived, created_at)
+                VALUES (1, '默认账户', 0, datetime('now', 'localtime'));
+
+            CREATE TABLE IF NOT EXISTS position_transfers (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
+                from_account_id INTEGER NOT NULL,      -- 转出账户
+                to_account_id INTEGER NOT NULL,        -- 转入账户
+                code TEXT NOT NULL,                    -- 股票代码
+                name TEXT NOT NULL,                    -- 股票名称
+                quantity INTEGER NOT NULL,             -- 划转数量
+                cost REAL NOT NULL,                    -- 划转时的每股成本（含买入费用）
+                time TEXT NOT NULL                     -- 划转时间
+            );
+            CREATE INDEX IF NOT EXISTS idx_position_transfers_code ON position_transfers (code);",
+    }],
+    tables: &[
+        TableDef {
+            name: "accounts",
+            columns: &[
+                ColumnDef::new("id", Integer),
+                ColumnDef::new("name", Text).key(), // 账户名称唯一
+                ColumnDef::new("archived", Integer).default("0"),
+                ColumnDef::new("created_at", Text),
+            ],
+            auto_id: true,
+        },
+        TableDef {
+            name: "position_transfers",
+            columns: &[
+                ColumnDef::new("id", Integer),
+                ColumnDef::new("from_account_id", Integer)
+                    .references("accounts")
+                    .key(), // 同一时间在同两个账户间划转同样数量视为同一笔
+                ColumnDef::new("to_account_id", Integer)
+                    .references("accounts")
+                    .key(),
+                ColumnDef::new("code", Text).key(),
+                ColumnDef::new("name", Text),
+                ColumnDef::new("quantity", Integer).key(),
+                ColumnDef::new("cost", Real),
+                ColumnDef::new("time", Text).key(),
+            ],
+            auto_id: true,
+        },
+    ],
+};
+
+pub const CORPORATE_ACTIONS_SCHEMA: DbSchema = DbSchema {
+    name: "corporate_actions",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建 corporate_actions 表",
+        sql: "CREATE TABLE IF NOT EXISTS corporate_actions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,   -- 唯一自增ID
+                code TEXT NOT NULL,                     -- 股票代码（大写）
+                ex_date TEXT NOT NULL,                  -- 除权除息日（YYYY-MM-DD）
+                cash_dividend REAL NOT NULL DEFAULT 0,  -- 每股派息
+                bonus_shares REAL NOT NULL DEFAULT 0,   -- 每股送股
+                transfer_shares REAL NOT NULL DEFAULT 0, -- 每股转增
+                split_ratio REAL NOT NULL DEFAULT 1,    -- 拆合股比例
+                source TEXT NOT NULL DEFAULT 'manual',  -- 来源：manual 或数据源 id
+                note TEXT NOT NULL DEFAULT ''           -- 备注（如分配方案原文）
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_corporate_actions_code_date
+                ON corporate_actions (code, ex_date);",
+    }],
+    tables: &[TableDef {
+        name: "corporate_actions",
+        columns: &[
+            ColumnDef::new("id", Integer),
+            ColumnDef::new("code", Text).key(), // 同一股票同一除权日只有一条
+            ColumnDef::new("ex_date", Text).key(),
+            ColumnDef::new("cash_dividend", Real).default("0"),
+            ColumnDef::new("bonus_shares", Real).default("0"),
+            ColumnDef::new("transfer_shares", Real).default("0"),
+            ColumnDef::new("split_ratio", Real).default("1"),
+            ColumnDef::new("source", Text).default("manual"),
+            ColumnDef::new("note", Text).default(""),
+        ],
+        auto_id: true,
+    }],
+};
+
+pub const PRICE_ALERTS_SCHEMA: DbSchema = DbSchema {
+    name: "price_alerts",
+    migrations: &[Migration {
+        version: 1,
+        description: "创建价格提醒规则与提醒记录表",
+        sql: "CREATE TABLE IF NOT EXISTS alert_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,       -- 唯一自增ID
+                code TEXT NOT NULL,                         -- 股票代码（大写）
+                kind TEXT NOT NULL,                         -- 条件类型（price_above 等）
+                threshold REAL NOT NULL,                    -- 阈值
+                enabled INTEGER NOT NULL DEFAULT 1,         -- 是否启用
+                cooldown_minutes INTEGER NOT NULL DEFAULT 30, -- 再次触发的最短间隔（分钟）
+                note TEXT NOT NULL DEFAULT '',              -- 备注
+                created_at TEXT NOT NULL,                   -- 创建时间
+                last_triggered_at TEXT                      -- 最近一次触发时间
+            );
+            CREATE INDEX IF NOT EXISTS idx_alert_rules_code ON alert_rules (code);
+
+            CREATE TABLE IF NOT EXISTS alert_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
+                rule_id INTEGER NOT NULL,              -- 触发的规则（规则删除后保留记录）
+                code TEXT NOT NULL,                    -- 股票代码
+                name TEXT NOT NULL DEFAULT '',         -- 股票名称
+                kind TEXT NOT NULL,                    -- 条件类型
+                threshold REAL NOT NULL,               -- 触发时的阈值
+                value REAL NOT NULL,                   -- 触发时的观测值
+                price REAL,                            -- 触发时的现价
+                message TEXT NOT NULL,                 -- 提醒内容
+                triggered_at TEXT NOT NULL             -- 触发时间
+            );
+            CREATE INDEX IF NOT EXISTS idx_alert_events_time ON alert_events (triggered_at);",
+    }],
+    tables: &[
+        TableDef {
+            name: "alert_rules",
+            columns: &[
+                ColumnDef::new("id", Integer),
+                ColumnDef::new("code", Text).key(), // 同一时间创建的同一条件视为同一条规则
+                ColumnDef::new("kind", Text).key(),
+                ColumnDef::new("threshold", Real).key(),
+                ColumnDef::new("enabled", Integer).default("1"),
+                ColumnDef::new("cooldown_minutes", Integer).default("30"),
+                ColumnDef::new("note", Text).default(""),
+                ColumnDef::new("created_at", Text).key(),
+                ColumnDef::new("last_triggered_at", Text).nullable(),
+            ],
+            auto_id: true,
+        },
+        TableDef {
+            name: "alert_events",
+            columns: &[
+                ColumnDef::new("id", Integer),
+                ColumnDef::new("rule_id", Integer)
+                    .references("alert_rules")
+                    .key(), // 同一规则同一时刻只触发一次
+                ColumnDef::new("code", Text),
+                ColumnDef::new("name", Text).default(""),
+                ColumnDef::new("kind", Text),
+                ColumnDef::new("threshold", Real),
+                ColumnDef::new("value", Real),
+                ColumnDef::new("price", Real).nullable(),
+                ColumnDef::new("message", Text),
+                ColumnDef::new("triggered_at", Text).key(),
+            ],
+            auto_id: true,
+        },
+    ],
+};
+
+/// 不从备份导入的表：缓存可以重新获取，持仓相关的表导入后按委托重新生成
+pub const DERIVED_TABLES: &[&str] = &[
+    "holdings",
+    "holding_lots",
+    "holding_exits",
+    "holding_dividends",
+    "kline_cache",
+    "kline_cache_head",
+    "market_snapshot",
+];
+
+/// 所有数据库的注册表（初始化、版本检查和备份导入都以此为准）
+/// 导入时按此顺序写入，被其他表引用的账户排在前面
+pub const ALL_DATABASES: &[&DbSchema] = &[
+    &APP_CONFIG_SCHEMA,
+    &ACCOUNTS_SCHEMA,
+    &ALL_STOCKS_SCHEMA,
+    &MY_SELECTION_SCHEMA,
+    &STOCK_REVIEW_SCHEMA,
+    &SELF_REFLECT_SCHEMA,
+    &MARKET_ANALYSIS_SCHEMA,
+    &TREND_LINES_SCHEMA,
+    &HOLDINGS_SCHEMA,
+    &ORDERS_SCHEMA,
+    &KLINE_CACHE_SCHEMA,
+    &MARKET_SNAPSHOT_SCHEMA,
+    &CASH_LEDGER_SCHEMA,
+    &CORPORATE_ACTIONS_SCHEMA,
+    &PRICE_ALERTS_SCHEMA,
+];
+
+/// 在同一个连接上执行所有数据库的迁移
+pub fn migrate_all(conn: &mut Connection) -> Result<(), String> {
+    for schema in ALL_DATABASES {
+        run_migrations(conn, schema)?;
+    }
+    Ok(())
 }
 
-pub fn init_orders_database(app: &AppHandle) -> Result<Connection, String> {
-    // 初始化数据库连接，表文件名为"orders"
-    let conn = init_database(app, "orders")?;
-
-    // 创建委托表
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS orders (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,  -- 唯一自增ID
-            code TEXT NOT NULL,                    -- 股票代码
-            name TEXT NOT NULL,                    -- 股票名称
-            time TEXT NOT NULL,                    -- 委托时间
-            quantity INTEGER NOT NULL,             -- 委托数量
-            cost REAL NOT NULL,                    -- 委托价格/成本
-            action TEXT NOT NULL                   -- 操作类型：买入/卖出
-        )",
-        [], // 无参数
-    )
-    .map_err(|e| format!("无法创建 orders 表: {}", e))?;
-
-    Ok(conn)
+/// 打开应用数据库：执行迁移，并一次性合并旧版按功能拆分的数据库文件
+pub fn init_app_database(app: &AppHandle) -> Result<AppDb, String> {
+    let db_path = get_db_path(app, APP_DB_NAME)?;
+    let db = AppDb::open(&db_path)?;
+
+    {
+        let mut conn = db.conn().map_err(|e| e.to_string())?;
+        migrate_all(&mut conn)?;
+
+        let db_dir = get_db_directory(app)?;
+        let merged = migrate_legacy_databases(&mut conn, &db_dir)?;
+        if merged > 0 {
+            log::info!("已合并 {} 个旧版数据库文件到 {}.db", merged, APP_DB_NAME);
+        }
+
+        let legacy_lines = count_legacy_trend_lines(&conn).map_err(|e| e.to_string())?;
+        if legacy_lines > 0 {
+            log::warn!(
+                "{} 条旧版趋势线的时间或价格无效，未转换为画线，原样保留在 trend_lines_legacy 表中",
+                legacy_lines
+            );
+        }
+    }
+
+    Ok(db)
 }
@@ -0,0 +1,323 @@
+use crate::structs::xueqiu_structs::StockKlineItem;
+use crate::structs::StockError;
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// 查询某只股票某个周期已缓存K线的时间范围（最早, 最新），无缓存时返回 None
+pub fn query_cached_range(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+) -> Result<Option<(i64, i64)>, StockError> {
+    let range: Option<(Option<i64>, Option<i64>)> = conn
+        .query_row(
+            "SELECT MIN(timestamp), MAX(timestamp)
+             FROM kline_cache
+             WHERE symbol = ?1 AND period = ?2",
+            params![symbol.to_uppercase(), period],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(match range {
+        Some((Some(oldest), Some(newest))) => Some((oldest, newest)),
+        _ => None,
+    })
+}
+
+/// 查询 time 所在位置的缓存K线时间：forward 为 false 时取不晚于 time 的最后一根，否则取不早于 time 的第一根
+pub fn query_cached_bar_time(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+    time: i64,
+    forward: bool,
+) -> Result<Option<i64>, StockError> {
+    let sql = if forward {
+        "SELECT MIN(timestamp) FROM kline_cache
+         WHERE symbol = ?1 AND period = ?2 AND timestamp >= ?3"
+    } else {
+        "SELECT MAX(timestamp) FROM kline_cache
+         WHERE symbol = ?1 AND period = ?2 AND timestamp <= ?3"
+    };
+    conn.query_row(sql, params![symbol.to_uppercase(), period, time], |row| {
+        row.get(0)
+    })
+    .map_err(|e| StockError::DbError(e))
+}
+
+/// 统计截至 end（含）的缓存K线数量
+pub fn count_cached_klines(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+    end: i64,
+) -> Result<i64, StockError> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM kline_cache
+         WHERE symbol = ?1 AND period = ?2 AND timestamp <= ?3",
+        params![symbol.to_uppercase(), period, end],
+        |row| row.get(0),
+    )
+    .map_err(|e| StockError::DbError(e))
+}
+
+/// 查询截至 end（含）的最近 limit 条K线，按时间升序返回
+pub fn query_cached_klines(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+    end: i64,
+    limit: i32,
+) -> Result<Vec<StockKlineItem>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, open, high, low, close, volume, percent, turnoverrate
+             FROM kline_cache
+             WHERE symbol = ?1 AND period = ?2 AND timestamp <= ?3
+             ORDER BY timestamp DESC
+             LIMIT ?4",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let mut items = stmt
+        .query_map(params![symbol.to_uppercase(), period, end, limit], |row| {
+            Ok(StockKlineItem {
+                date: row.get(0)?,
+                open: row.get(1)?,
+                high: row.get(2)?,
+                low: row.get(3)?,
+                close: row.get(4)?,
+                volume: row.get(5)?,
+                percent: row.get(6)?,
+                turnoverrate: row.get(7)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<StockKlineItem>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    items.reverse();
+    Ok(items)
+}
+
+/// 查询 [start, end] 时间范围内的全部缓存K线，按时间升序返回
+pub fn query_cached_klines_between(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+    start: i64,
+    end: i64,
+) -> Result<Vec<StockKlineItem>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, open, high, low, close, volume, percent, turnoverrate
+             FROM kline_cache
+             WHERE symbol = ?1 AND period = ?2 AND timestamp >= ?3 AND timestamp <= ?4
+             ORDER BY timestamp ASC",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let items = stmt
+        .query_map(params![symbol.to_uppercase(), period, start, end], |row| {
+            Ok(StockKlineItem {
+                date: row.get(0)?,
+                open: row.get(1)?,
+                high: row.get(2)?,
+                low: row.get(3)?,
+                close: row.get(4)?,
+                volume: row.get(5)?,
+                percent: row.get(6)?,
+                turnoverrate: row.get(7)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<StockKlineItem>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(items)
+}
+
+/// 查询某只股票某个周期缓存最后一次写入的时间（离线时用于标注数据时间）
+pub fn query_cache_updated_at(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+) -> Result<Option<String>, StockError> {
+    conn.query_row(
+        "SELECT MAX(updated_at) FROM kline_cache WHERE symbol = ?1 AND period = ?2",
+        params![symbol.to_uppercase(), period],
+        |row| row.get(0),
+    )
+    .map_err(|e| StockError::DbError(e))
+}
+
+/// 查询已知的上市首根K线时间（数据源返回的K线少于请求数量时记录）
+pub fn query_listing_start(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+) -> Result<Option<i64>, StockError> {
+    conn.query_row(
+        "SELECT first_timestamp FROM kline_cache_head WHERE symbol = ?1 AND period = ?2",
+        params![symbol.to_uppercase(), period],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| StockError::DbError(e))
+}
+
+/// 记录上市首根K线时间，之后不再向前补数据
+pub fn save_listing_start(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+    first_timestamp: i64,
+) -> Result<(), StockError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO kline_cache_head (symbol, period, first_timestamp)
+         VALUES (?1, ?2, ?3)",
+        params![symbol.to_uppercase(), period, first_timestamp],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    Ok(())
+}
+
+/// 合并K线到缓存（同一时间戳覆盖旧数据，用于刷新未收盘的K线）
+pub fn save_klines(
+    conn: &mut Connection,
+    symbol: &str,
+    period: &str,
+    items: &[StockKlineItem],
+) -> Result<(), StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    insert_klines(&tx, symbol, period, items)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(())
+}
+
+/// 用新数据整体替换某只股票某个周期的缓存（缓存与最新数据之间出现缺口时使用）
+pub fn replace_klines(
+    conn: &mut Connection,
+    symbol: &str,
+    period: &str,
+    items: &[StockKlineItem],
+) -> Result<(), StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    tx.execute(
+        "DELETE FROM kline_cache WHERE symbol = ?1 AND period = ?2",
+        params![symbol.to_uppercase(), period],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    insert_klines(&tx, symbol, period, items)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(())
+}
+
+/// 清除K线缓存（symbol 为空时清除全部），返回删除的条数
+/// 除权除息或切换复权方式后历史价格会变化，需要重新获取
+pub fn delete_cached_klines(conn: &Connection, symbol: Option<&str>) -> Result<usize, StockError> {
+    let symbol = symbol.map(|symbol| symbol.to_uppercase());
+    conn.execute(
+        "DELETE FROM kline_cache_head WHERE ?1 IS NULL OR symbol = ?1",
+        params![symbol],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    conn.execute(
+        "DELETE FROM kline_cache WHERE ?1 IS NULL OR symbol = ?1",
+        params![symbol],
+    )
+    .map_err(|e| StockError::DbError(e))
+}
+
+fn insert_klines(
+    conn: &Connection,
+    symbol: &str,
+    period: &str,
+    items: &[StockKlineItem],
+) -> Result<(), StockError> {
+    let symbol = symbol.to_uppercase();
+    let updated_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let mut stmt = conn
+        .prepare(
+            "INSERT OR REPLACE INTO kline_cache
+             (symbol, period, timestamp, open, high, low, close, volume, percent, turnoverrate, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    for item in items {
+        stmt.execute(params![
+            symbol,
+            period,
+            item.date,
+            item.open,
+            item.high,
+            item.low,
+            item.close,
+            item.volume,
+            item.percent,
+            item.turnoverrate,
+            updated_at
+        ])
+        .map_err(|e| StockError::DbError(e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    fn bar(date: i64, close: f64) -> StockKlineItem {
+        StockKlineItem {
+            date,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100.0,
+            percent: 0.0,
+            turnoverrate: 0.0,
+        }
+    }
+
+    #[test]
+    fn save_merges_and_overwrites_same_timestamp() {
+        let mut conn = open_test_connection();
+        save_klines(&mut conn, "sh600000", "day", &[bar(1, 10.0), bar(2, 11.0)]).unwrap();
+        save_klines(&mut conn, "SH600000", "day", &[bar(2, 12.0), bar(3, 13.0)]).unwrap();
+
+        assert_eq!(
+            query_cached_range(&conn, "SH600000", "day").unwrap(),
+            Some((1, 3))
+        );
+        let items = query_cached_klines(&conn, "SH600000", "day", i64::MAX, 10).unwrap();
+        let closes: Vec<f64> = items.iter().map(|k| k.close).collect();
+        assert_eq!(closes, vec![10.0, 12.0, 13.0]);
+        assert!(query_cached_range(&conn, "SH600000", "week")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn query_returns_latest_bars_before_end_ascending() {
+        let mut conn = open_test_connection();
+        let bars: Vec<StockKlineItem> = (1..=10).map(|i| bar(i, i as f64)).collect();
+        save_klines(&mut conn, "SH600000", "day", &bars).unwrap();
+
+        let items = query_cached_klines(&conn, "SH600000", "day", 7, 3).unwrap();
+        let dates: Vec<i64> = items.iter().map(|k| k.date).collect();
+        assert_eq!(dates, vec![5, 6, 7]);
+        assert_eq!(count_cached_klines(&conn, "SH600000", "day", 7).unwrap(), 7);
+
+        replace_klines(&mut conn, "SH600000", "day", &[bar(20, 1.0)]).unwrap();
+        assert_eq!(
+            query_cached_range(&conn, "SH600000", "day").unwrap(),
+            Some((20, 20))
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::db::init_db::ALL_DATABASES;
+use crate::db::migrations::run_migrations;
+use crate::db::position_db::{add_opening_orders, read_legacy_holdings, rebuild_all_holdings};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use tauri_plugin_log::log;
+
+/// 旧版数据库文件合并后移动到的子目录（保留作为备份）
+const LEGACY_BACKUP_DIR: &str = "legacy";
+
+/// 将旧版按功能拆分的数据库文件（holdings.db、orders.db 等）合并进当前连接
+/// 合并完成的文件会被移动到 legacy 子目录，因此每个文件只会合并一次
+/// 返回：本次合并的文件数量
+pub fn migrate_legacy_databases(conn: &mut Connection, db_dir: &Path) -> Result<usize, String> {
+    let mut merged = 0;
+
+    for schema in ALL_DATABASES {
+        let legacy_path = db_dir.join(format!("{}.db", schema.name));
+        if !legacy_path.exists() {
+            continue;
+        }
+
+        // 1. 先把旧文件本身迁移到最新结构，保证列与当前表一致
+        {
+            let mut legacy_conn = Connection::open(&legacy_path)
+                .map_err(|e| format!("打开旧版数据库 {} 失败: {}", schema.name, e))?;
+            run_migrations(&mut legacy_conn, schema)?;
+        }
+
+        // 2. 附加旧文件并在事务中复制所有业务表
+        copy_legacy_tables(conn, &legacy_path)
+            .map_err(|e| format!("合并旧版数据库 {} 失败: {}", schema.name, e))?;
+
+        // 3. 移动旧文件，避免重复合并
+        let backup_dir = db_dir.join(LEGACY_BACKUP_DIR);
+        std::fs::create_dir_all(&backup_dir)
+            .map_err(|e| format!("无法创建旧版数据库备份目录: {}", e))?;
+        std::fs::rename(&legacy_path, backup_dir.join(format!("{}.db", schema.name)))
+            .map_err(|e| format!("移动旧版数据库 {} 失败: {}", schema.name, e))?;
+
+        log::info!("已合并旧版数据库: {}", schema.name);
+        merged += 1;
+    }
+
+    if merged > 0 {
+        rebuild_merged_holdings(conn)?;
+    }
+    Ok(merged)
+}
+
+// 持仓只由委托推导：旧版手动录入、没有委托的持仓先补建期初委托，再重新生成持仓
+fn rebuild_merged_holdings(conn: &mut Connection) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+    let holdings = read_legacy_holdings(&tx).map_err(|e| e.to_string())?;
+    let opened = add_opening_orders(&tx, &holdings).map_err(|e| e.to_string())?;
+    rebuild_all_holdings(&tx).map_err(|e| format!("重新生成持仓失败: {}", e))?;
+    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+    if opened > 0 {
+        log::info!("已为 {} 条没有委托记录的旧版持仓补建期初委托", opened);
+    }
+    Ok(())
+}
+
+fn copy_legacy_tables(conn: &mut Connection, legacy_path: &Path) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "ATTACH DATABASE ?1 AS legacy",
+        params![legacy_path.to_string_lossy()],
+    )?;
+
+    let result = (|| {
+        let tables: Vec<String> = {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM legacy.sqlite_master
+                 WHERE type = 'table'
+                   AND name NOT LIKE 'sqlite_%'
+                   AND name != 'schema_migrations'",
+            )?;
+            let names = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            names
+        };
+
+        let tx = conn.transaction()?;
+        for table in &tables {
+            let columns = table_columns(&tx, table)?;
+            if columns.is_empty() {
+                continue;
+            }
+            let column_list = columns.join(", ");
+            // 已存在的主键保留当前数据，避免覆盖
+            tx.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO main.\"{table}\" ({cols}) SELECT {cols} FROM legacy.\"{table}\"",
+                    table = table,
+                    cols = column_list
+                ),
+                [],
+            )?;
+        }
+        tx.commit()
+    })();
+
+    // 无论复制是否成功都要分离旧库
+    conn.execute("DETACH DATABASE legacy", [])?;
+    result
+}
+
+// 读取主库中某个表的列名（主库不存在该表时返回空）
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA main.table_info(\"{}\")", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .map(|r| r.map(|name| format!("\"{}\"", name)))
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::AppDb;
+    use crate::db::holdings_db::query_holdings;
+    use crate::db::init_db::migrate_all;
+
+    #[test]
+    fn legacy_files_are_merged_once() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            // 旧版 holdings.db：没有 schema_migrations 表
+            let legacy = Connection::open(dir.path().join("holdings.db")).unwrap();
+            legacy
+                .execute_batch(
+                    "CREATE TABLE holdings (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        code TEXT NOT NULL,
+                        name TEXT NOT NULL,
+                        cost REAL NOT NULL,
+                        quantity INTEGER NOT NULL,
+                        hold_time TEXT NOT NULL,
+                        status INTEGER NOT NULL DEFAULT 1,
+                        sell_time TEXT,
+                        sell_price REAL,
+                        profit REAL
+                    );
+                    INSERT INTO holdings (code, name, cost, quantity, hold_time)
+                    VALUES ('SH600000', '浦发银行', 10.0, 100, '2024-01-02 10:00:00');",
+                )
+                .unwrap();
+        }
+
+        let db = AppDb::open(&dir.path().join("app.db")).unwrap();
+        let mut conn = db.conn().unwrap();
+        migrate_all(&mut conn).unwrap();
+
+        assert_eq!(migrate_legacy_databases(&mut conn, dir.path()).unwrap(), 1);
+        assert!(dir.path().join("legacy").join("holdings.db").exists());
+        assert!(!dir.path().join("holdings.db").exists());
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM holdings", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // 自增 ID 从旧数据之后继续（旧持仓 1，重新生成的持仓 2）
+        let next_id: i64 = conn
+            .query_row(
+                "INSERT INTO holdings (code, name, cost, quantity, hold_time)
+                 VALUES ('SZ000001', '平安银行', 12.0, 100, '2024-01-03 10:00:00')
+                 RETURNING id",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(next_id, 3);
+
+        assert_eq!(migrate_legacy_databases(&mut conn, dir.path()).unwrap(), 0);
+    }
+
+    #[test]
+    fn legacy_manual_holdings_get_opening_orders() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let legacy = Connection::open(dir.path().join("holdings.db")).unwrap();
+            legacy
+                .execute_batch(
+                    "CREATE TABLE holdings (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        code TEXT NOT NULL,
+                        name TEXT NOT NULL,
+                        cost REAL NOT NULL,
+                        quantity INTEGER NOT NULL,
+                        hold_time TEXT NOT NULL,
+                        status INTEGER NOT NULL DEFAULT 1,
+                        sell_time TEXT,
+                        sell_price REAL,
+                        profit REAL
+                    );
+                    INSERT INTO holdings (code, name, cost, quantity, hold_time, status, sell_time, sell_price)
+                    VALUES ('SZ000001', '平安银行', 10.0, 200, '2023-06-01 10:00:00', 0,
+                            '2023-09-01 10:00:00', 12.0);
+                    INSERT INTO holdings (code, name, cost, quantity, hold_time)
+                    VALUES ('SZ000001', '平安银行', 11.0, 100, '2024-01-02 10:00:00');
+                    INSERT INTO holdings (code, name, cost, quantity, hold_time)
+                    VALUES ('SH600000', '浦发银行', 8.0, 300, '2024-01-03 10:00:00');",
+                )
+                .unwrap();
+        }
+
+        let db = AppDb::open(&dir.path().join("app.db")).unwrap();
+        let mut conn = db.conn().unwrap();
+        migrate_all(&mut conn).unwrap();
+        // 浦发银行已有委托，只按委托生成，不再补建
+        conn.execute(
+            "INSERT INTO orders (code, name, time, quantity, cost, action)
+             VALUES ('SH600000', '浦发银行', '2024-01-03 10:00:00', 300, 8.0, '1')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(migrate_legacy_databases(&mut conn, dir.path()).unwrap(), 1);
+
+        let orders: i64 = conn
+            .query_row("SELECT COUNT(*) FROM orders", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(orders, 4);
+        let holdings = query_holdings(&conn, None).unwrap();
+        let held: Vec<(&str, i32, f64)> = holdings
+            .iter()
+            .map(|h| (h.code.as_str(), h.quantity, h.cost))
+            .collect();
+        assert_eq!(held, vec![("SH600000", 300, 8.0), ("SZ000001", 100, 11.0)]);
+        let profit: f64 = conn
+            .query_row(
+                "SELECT profit FROM holdings WHERE status = 0 AND code = 'SZ000001'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(profit, 400.0);
+    }
+}
@@ -1,22 +1,13 @@
-use crate::db::common::init_database;
 use crate::structs::market_analysis_structs::{AddMarketAnalysisReq, MarketAnalysis};
 use crate::structs::StockError;
 use rusqlite::{params, Connection, OptionalExtension};
-use tauri::AppHandle;
-
-/// 获取 market_analysis 数据库连接（保持不变）
-pub fn get_market_analysis_db_conn(app: &AppHandle) -> Result<Connection, StockError> {
-    init_database(app, "market_analysis")
-        .map_err(|e| StockError::BusinessError(format!("获取市场分析数据库连接失败: {}", e)))
-}
 
 /// 新增/更新市场分析（修复事务借用冲突）
 /// 返回值：(是否为更新操作, 新增/更新后的完整数据)
 pub fn add_or_update_market_analysis(
-    app: &AppHandle,
+    conn: &mut Connection,
     req: &AddMarketAnalysisReq,
 ) -> Result<(bool, MarketAnalysis), StockError> {
-    let mut conn = get_market_analysis_db_conn(app)?;
     let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
 
     // 1. 显式查询日期是否存在（使用临时 stmt，查询结束后生命周期自动结束）
@@ -88,9 +79,8 @@ pub fn add_or_update_market_analysis(
 
 /// 查询最后10条市场分析（保持不变，无借用问题）
 pub fn query_last_10_market_analysis(
-    app: &AppHandle,
+    conn: &Connection,
 ) -> Result<Option<Vec<MarketAnalysis>>, StockError> {
-    let conn = get_market_analysis_db_conn(app)?;
     let mut stmt = conn
         .prepare(
             "SELECT date, analysis, status 
@@ -118,3 +108,48 @@ pub fn query_last_10_market_analysis(
         Some(analysis_list)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    fn analysis(date: &str, text: &str) -> AddMarketAnalysisReq {
+        AddMarketAnalysisReq {
+            date: date.to_string(),
+            analysis: text.to_string(),
+            status: "1".to_string(),
+        }
+    }
+
+    #[test]
+    fn same_date_updates_existing_row() {
+        let mut conn = open_test_connection();
+        let (is_update, _) =
+            add_or_update_market_analysis(&mut conn, &analysis("2024-01-02", "震荡")).unwrap();
+        assert!(!is_update);
+
+        let (is_update, saved) =
+            add_or_update_market_analysis(&mut conn, &analysis("2024-01-02", "放量上攻")).unwrap();
+        assert!(is_update);
+        assert_eq!(saved.analysis, "放量上攻");
+
+        let list = query_last_10_market_analysis(&conn).unwrap().unwrap();
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn latest_analysis_newest_first() {
+        let mut conn = open_test_connection();
+        assert!(query_last_10_market_analysis(&conn).unwrap().is_none());
+
+        for day in 1..=8 {
+            let date = format!("2024-01-{:02}", day);
+            add_or_update_market_analysis(&mut conn, &analysis(&date, "分析")).unwrap();
+        }
+
+        let list = query_last_10_market_analysis(&conn).unwrap().unwrap();
+        assert_eq!(list.len(), 6);
+        assert_eq!(list[0].date, "2024-01-08");
+    }
+}
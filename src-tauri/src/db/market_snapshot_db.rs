@@ -0,0 +1,87 @@
+use crate::structs::StockError;
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// 快照类型：批量报价中的单只股票
+pub const SNAPSHOT_QUOTE: &str = "quote";
+/// 快照类型：单只股票详情
+pub const SNAPSHOT_DETAIL: &str = "detail";
+/// 快照类型：当日分时数据
+pub const SNAPSHOT_MINUTE: &str = "minute";
+
+/// 保存最后一次成功获取的行情数据（同类型同代码只保留一份）
+pub fn save_snapshot<T: Serialize>(
+    conn: &Connection,
+    kind: &str,
+    symbol: &str,
+    data: &T,
+) -> Result<(), StockError> {
+    let json = serde_json::to_string(data)?;
+    let as_of = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT OR REPLACE INTO market_snapshot (kind, symbol, data, as_of)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![kind, symbol.to_uppercase(), json, as_of],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    Ok(())
+}
+
+/// 读取行情快照，返回 (数据, 获取时间)
+pub fn load_snapshot<T: DeserializeOwned>(
+    conn: &Connection,
+    kind: &str,
+    symbol: &str,
+) -> Result<Option<(T, String)>, StockError> {
+    let row: Option<(String, String)> = conn
+        .query_row(
+            "SELECT data, as_of FROM market_snapshot WHERE kind = ?1 AND symbol = ?2",
+            params![kind, symbol.to_uppercase()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| StockError::DbError(e))?;
+
+    match row {
+        Some((json, as_of)) => Ok(Some((serde_json::from_str(&json)?, as_of))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+    use crate::structs::xueqiu_structs::MinuteChartItem;
+
+    #[test]
+    fn snapshot_round_trip_keeps_latest() {
+        let conn = open_test_connection();
+        let first = vec![MinuteChartItem {
+            percent: 0.1,
+            timestamp: 1,
+            volume: 100,
+        }];
+        let second = vec![MinuteChartItem {
+            percent: 0.2,
+            timestamp: 2,
+            volume: 200,
+        }];
+        save_snapshot(&conn, SNAPSHOT_MINUTE, "sh600000", &first).unwrap();
+        save_snapshot(&conn, SNAPSHOT_MINUTE, "SH600000", &second).unwrap();
+
+        let (items, as_of): (Vec<MinuteChartItem>, String) =
+            load_snapshot(&conn, SNAPSHOT_MINUTE, "SH600000")
+                .unwrap()
+                .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].timestamp, 2);
+        assert!(!as_of.is_empty());
+
+        let missing: Option<(Vec<MinuteChartItem>, String)> =
+            load_snapshot(&conn, SNAPSHOT_DETAIL, "SH600000").unwrap();
+        assert!(missing.is_none());
+    }
+}
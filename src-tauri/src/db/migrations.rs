@@ -0,0 +1,291 @@
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri_plugin_log::log;
+
+/// 单个迁移步骤（version 从 1 开始，按顺序严格递增）
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// 列类型（导入备份时按它转换数据）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+/// 列定义：非空且没有默认值的列为必填
+pub struct ColumnDef {
+    pub name: &'static str,
+    pub kind: ColumnType,
+    pub nullable: bool,
+    pub default: Option<&'static str>, // 备份中缺少该列或为空时使用的值
+    pub key: bool,                     // 导入时判定冲突的列（自然主键）
+    pub references: Option<&'static str>, // 引用的表的 id，导入时按重新分配的 id 更新
+}
+
+impl ColumnDef {
+    pub const fn new(name: &'static str, kind: ColumnType) -> Self {
+        ColumnDef {
+            name,
+            kind,
+            nullable: false,
+            default: None,
+            key: false,
+            references: None,
+        }
+    }
+
+    pub const fn nullable(self) -> Self {
+        ColumnDef {
+            nullable: true,
+            ..self
+        }
+    }
+
+    pub const fn default(self, value: &'static str) -> Self {
+        ColumnDef {
+            default: Some(value),
+            ..self
+        }
+    }
+
+    pub const fn key(self) -> Self {
+        ColumnDef { key: true, ..self }
+    }
+
+    pub const fn references(self, table: &'static str) -> Self {
+        ColumnDef {
+            references: Some(table),
+            ..self
+        }
+    }
+}
+
+/// 表定义：第一列为主键，导入时按标记为 key 的列判断与本地记录是否冲突
+/// 没有标记时：自增 id 的表按 id 以外的全部列判断（不同安装的 id 相同不代表是同一条记录），其余按主键
+pub struct TableDef {
+    pub name: &'static str,
+    pub columns: &'static [ColumnDef],
+    pub auto_id: bool, // 主键为自增 id，追加导入时由本地重新分配
+}
+
+impl TableDef {
+    /// 判定冲突的列
+    pub fn key_columns(&self) -> Vec<&ColumnDef> {
+        let keys: Vec<&ColumnDef> = self.columns.iter().filter(|column| column.key).collect();
+        if keys.is_empty() && self.auto_id {
+            self.columns.iter().skip(1).collect()
+        } else if keys.is_empty() {
+            self.columns.iter().take(1).collect()
+        } else {
+            keys
+        }
+    }
+}
+
+/// 一个数据库的结构定义：名称 + 有序迁移列表 + 可从备份导入的表
+pub struct DbSchema {
+    pub name: &'static str,
+    pub migrations: &'static [Migration],
+    pub tables: &'static [TableDef], // 为空的数据库（如缓存）不参与备份导入
+}
+
+impl DbSchema {
+    /// 当前应用所知的最新结构版本
+    pub fn latest_version(&self) -> i64 {
+        self.migrations.last().map(|m| m.version).unwrap_or(0)
+    }
+}
+
+// 记录已执行迁移的表（每个数据库文件内各有一份）
+fn ensure_migrations_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            name TEXT NOT NULL,              -- 数据库名称
+            version INTEGER NOT NULL,        -- 已执行的迁移版本
+            description TEXT,                -- 迁移说明
+            applied_at TEXT NOT NULL,        -- 执行时间
+            PRIMARY KEY (name, version)
+        )",
+        [],
+    )
+    .map_err(|e| format!("无法创建 schema_migrations 表: {}", e))?;
+    Ok(())
+}
+
+/// 读取数据库当前的结构版本（从未迁移过的数据库返回 0）
+pub fn current_version(conn: &Connection, name: &str) -> Result<i64, String> {
+    let has_table: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+            [],
+            |row| Ok(row.get::<_, i32>(0)? > 0),
+        )
+        .map_err(|e| format!("读取数据库 {} 结构版本失败: {}", name, e))?;
+
+    if !has_table {
+        return Ok(0);
+    }
+
+    let version: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(version) FROM schema_migrations WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("读取数据库 {} 结构版本失败: {}", name, e))?
+        .flatten();
+
+    Ok(version.unwrap_or(0))
+}
+
+/// 检查数据库版本是否被当前应用支持（比应用新的数据库拒绝打开）
+pub fn ensure_supported(conn: &Connection, schema: &DbSchema) -> Result<(), String> {
+    let current = current_version(conn, schema.name)?;
+    let latest = schema.latest_version();
+    if current > latest {
+        return Err(format!(
+            "数据库 {} 的结构版本（{}）高于当前应用支持的版本（{}），请升级应用后再打开",
+            schema.name, current, latest
+        ));
+    }
+    Ok(())
+}
+
+/// 按顺序执行尚未应用的迁移，每个迁移步骤在独立事务中完成
+pub fn run_migrations(conn: &mut Connection, schema: &DbSchema) -> Result<(), String> {
+    ensure_migrations_table(conn)?;
+    ensure_supported(conn, schema)?;
+
+    let current = current_version(conn, schema.name)?;
+    let mut last_version = 0;
+
+    for migration in schema.migrations {
+        if migration.version <= last_version {
+            return Err(format!(
+                "数据库 {} 的迁移版本必须递增（{} 位于 {} 之后）",
+                schema.name, migration.version, last_version
+            ));
+        }
+        last_version = migration.version;
+
+        if migration.version <= current {
+            continue;
+        }
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        tx.execute_batch(migration.sql).map_err(|e| {
+            format!(
+                "数据库 {} 迁移到版本 {}（{}）失败: {}",
+                schema.name, migration.version, migration.description, e
+            )
+        })?;
+
+        tx.execute(
+            "INSERT INTO schema_migrations (name, version, description, applied_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                schema.name,
+                migration.version,
+                migration.description,
+                Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+            ],
+        )
+        .map_err(|e| format!("记录迁移版本失败: {}", e))?;
+
+        tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+        log::info!(
+            "数据库 {} 已迁移到版本 {}: {}",
+            schema.name,
+            migration.version,
+            migration.description
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_SCHEMA: DbSchema = DbSchema {
+        name: "test",
+        tables: &[],
+        migrations: &[
+            Migration {
+                version: 1,
+                description: "创建 notes 表",
+                sql: "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT NOT NULL);",
+            },
+            Migration {
+                version: 2,
+                description: "notes 增加 pinned 列",
+                sql: "ALTER TABLE notes ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;",
+            },
+        ],
+    };
+
+    #[test]
+    fn migrations_apply_once_in_order() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(current_version(&conn, "test").unwrap(), 0);
+
+        run_migrations(&mut conn, &TEST_SCHEMA).unwrap();
+        assert_eq!(current_version(&conn, "test").unwrap(), 2);
+
+        // 再次执行不会重复 ALTER TABLE
+        run_migrations(&mut conn, &TEST_SCHEMA).unwrap();
+        conn.execute("INSERT INTO notes (body, pinned) VALUES ('x', 1)", [])
+            .unwrap();
+    }
+
+    #[test]
+    fn newer_database_is_rejected() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, &TEST_SCHEMA).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (name, version, description, applied_at)
+             VALUES ('test', 3, '未来版本', '2099-01-01 00:00:00')",
+            [],
+        )
+        .unwrap();
+
+        assert!(ensure_supported(&conn, &TEST_SCHEMA).is_err());
+        assert!(run_migrations(&mut conn, &TEST_SCHEMA).is_err());
+    }
+
+    #[test]
+    fn failed_migration_is_rolled_back() {
+        const BROKEN: DbSchema = DbSchema {
+            name: "broken",
+            tables: &[],
+            migrations: &[Migration {
+                version: 1,
+                description: "第二条语句出错",
+                sql: "CREATE TABLE a (id INTEGER); CREATE TABLE a (id INTEGER);",
+            }],
+        };
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert!(run_migrations(&mut conn, &BROKEN).is_err());
+        assert_eq!(current_version(&conn, "broken").unwrap(), 0);
+        let tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'a'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 0);
+    }
+}
@@ -1,10 +1,21 @@
+pub mod account_db;
+pub mod alert_db;
 pub mod app_config_db;
+pub mod backup_db;
+pub mod cash_db;
 pub mod common;
+pub mod corporate_action_db;
+pub mod drawings_db;
 pub mod holdings_db;
 pub mod import_db;
 pub mod init_db;
+pub mod kline_cache_db;
+pub mod legacy_db;
 pub mod market_analysis_db;
+pub mod market_snapshot_db;
+pub mod migrations;
 pub mod orders_db;
+pub mod position_db;
 pub mod selection_db;
 pub mod self_reflect_db;
 pub mod stock_db;
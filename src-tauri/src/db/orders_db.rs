@@ -1,25 +1,25 @@
-use crate::db::common::init_database;
+use crate::db::app_config_db::get_fee_model;
+use crate::db::position_db::is_buy_action;
 use crate::structs::orders_structs::{AddOrderParams, Order, PaginatedOrders, QueryOrdersParams};
 use crate::structs::StockError;
 use rusqlite::{params, Connection};
-use tauri::AppHandle;
 
-/// 获取委托数据库连接
-pub fn get_orders_db_conn(app: &AppHandle) -> Result<Connection, StockError> {
-    init_database(app, "orders")
-        .map_err(|e| StockError::BusinessError(format!("获取委托数据库连接失败: {}", e)))
-}
-
-/// 添加委托
-pub fn add_order(app: &AppHandle, req: &AddOrderParams) -> Result<i32, StockError> {
-    let conn = get_orders_db_conn(app)?;
+/// 添加委托（按当前费用模型自动计算并保存佣金、印花税和过户费）
+pub fn add_order(conn: &Connection, req: &AddOrderParams) -> Result<i32, StockError> {
+    let fees = get_fee_model(conn)?.calculate(
+        &req.code,
+        is_buy_action(&req.action),
+        req.cost,
+        req.quantity,
+    );
 
     // 插入新记录并返回自增ID
     let mut stmt = conn
         .prepare(
             "INSERT INTO orders 
-         (code, name, time, quantity, cost, action)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         (code, name, time, quantity, cost, action, commission, stamp_duty, transfer_fee,
+          account_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
          RETURNING id",
         )
         .map_err(|e| StockError::DbError(e))?;
@@ -32,7 +32,11 @@ pub fn add_order(app: &AppHandle, req: &AddOrderParams) -> Result<i32, StockErro
                 req.time,
                 req.quantity,
                 req.cost,
-                req.action
+                req.action,
+                fees.commission,
+                fees.stamp_duty,
+                fees.transfer_fee,
+                req.account_id
             ],
             |row| row.get(0),
         )
@@ -41,43 +45,54 @@ pub fn add_order(app: &AppHandle, req: &AddOrderParams) -> Result<i32, StockErro
     Ok(new_id)
 }
 
-/// 查询所有委托（按时间倒序，最新的在前）
+/// 查询所有委托（按时间倒序，最新的在前），指定账户时只查该账户
 pub fn query_orders(
-    app: &AppHandle,
+    conn: &Connection,
     params: &QueryOrdersParams,
 ) -> Result<PaginatedOrders, StockError> {
-    let conn = get_orders_db_conn(app)?;
-
     // 计算偏移量
     let offset = (params.page - 1) * params.page_size;
 
     // 查询总记录数
     let total: i32 = conn
-        .query_row("SELECT COUNT(*) FROM orders", [], |row| row.get(0))
+        .query_row(
+            "SELECT COUNT(*) FROM orders WHERE ?1 IS NULL OR account_id = ?1",
+            params![params.account_id],
+            |row| row.get(0),
+        )
         .map_err(|e| StockError::DbError(e))?;
 
     // 查询分页数据
     let mut stmt = conn
         .prepare(
-            "SELECT id, code, name, time, quantity, cost, action 
+            "SELECT id, code, name, time, quantity, cost, action, commission, stamp_duty, transfer_fee,
+                    account_id
              FROM orders 
+             WHERE ?3 IS NULL OR account_id = ?3
              ORDER BY time DESC, id DESC 
              LIMIT ?1 OFFSET ?2",
         )
         .map_err(|e| StockError::DbError(e))?;
 
     let orders = stmt
-        .query_map([params.page_size, offset], |row| {
-            Ok(Order {
-                id: row.get(0)?,
-                code: row.get(1)?,
-                name: row.get(2)?,
-                time: row.get(3)?,
-                quantity: row.get(4)?,
-                cost: row.get(5)?,
-                action: row.get(6)?,
-            })
-        })
+        .query_map(
+            params![params.page_size, offset, params.account_id],
+            |row| {
+                Ok(Order {
+                    id: row.get(0)?,
+                    code: row.get(1)?,
+                    name: row.get(2)?,
+                    time: row.get(3)?,
+                    quantity: row.get(4)?,
+                    cost: row.get(5)?,
+                    action: row.get(6)?,
+                    commission: row.get(7)?,
+                    stamp_duty: row.get(8)?,
+                    transfer_fee: row.get(9)?,
+                    account_id: row.get(10)?,
+                })
+            },
+        )
         .map_err(|e| StockError::DbError(e))?
         .collect::<Result<Vec<Order>, _>>()
         .map_err(|e| StockError::DbError(e))?;
@@ -94,22 +109,25 @@ pub fn query_orders(
     })
 }
 
-/// 根据股票代码查询所有委托记录（按时间倒序，最新的在前）
-pub fn query_orders_by_code(app: &AppHandle, code: &str) -> Result<Vec<Order>, StockError> {
-    let conn = get_orders_db_conn(app)?;
-
+/// 根据股票代码查询所有委托记录（按时间倒序，最新的在前），指定账户时只查该账户
+pub fn query_orders_by_code(
+    conn: &Connection,
+    code: &str,
+    account_id: Option<i32>,
+) -> Result<Vec<Order>, StockError> {
     // 查询指定代码的所有委托记录
     let mut stmt = conn
         .prepare(
-            "SELECT id, code, name, time, quantity, cost, action 
+            "SELECT id, code, name, time, quantity, cost, action, commission, stamp_duty, transfer_fee,
+                    account_id
              FROM orders 
-             WHERE code = ?1
+             WHERE code = ?1 AND (?2 IS NULL OR account_id = ?2)
              ORDER BY time DESC, id DESC",
         )
         .map_err(|e| StockError::DbError(e))?;
 
     let orders = stmt
-        .query_map(params![code.to_uppercase()], |row| {
+        .query_map(params![code.to_uppercase(), account_id], |row| {
             Ok(Order {
                 id: row.get(0)?,
                 code: row.get(1)?,
@@ -118,6 +136,10 @@ pub fn query_orders_by_code(app: &AppHandle, code: &str) -> Result<Vec<Order>, S
                 quantity: row.get(4)?,
                 cost: row.get(5)?,
                 action: row.get(6)?,
+                commission: row.get(7)?,
+                stamp_duty: row.get(8)?,
+                transfer_fee: row.get(9)?,
+                account_id: row.get(10)?,
             })
         })
         .map_err(|e| StockError::DbError(e))?
@@ -126,12 +148,157 @@ pub fn query_orders_by_code(app: &AppHandle, code: &str) -> Result<Vec<Order>, S
 
     Ok(orders)
 }
-/// 删除委托
-pub fn delete_order(app: &AppHandle, id: i32) -> Result<(), StockError> {
-    let conn = get_orders_db_conn(app)?;
 
+/// 查询全部委托记录（按时间正序，用于组合分析），指定账户时只查该账户
+pub fn query_all_orders(
+    conn: &Connection,
+    account_id: Option<i32>,
+) -> Result<Vec<Order>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, code, name, time, quantity, cost, action, commission, stamp_duty, transfer_fee,
+                    account_id
+             FROM orders
+             WHERE ?1 IS NULL OR account_id = ?1
+             ORDER BY time ASC, id ASC",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+
+    let orders = stmt
+        .query_map(params![account_id], |row| {
+            Ok(Order {
+                id: row.get(0)?,
+                code: row.get(1)?,
+                name: row.get(2)?,
+                time: row.get(3)?,
+                quantity: row.get(4)?,
+                cost: row.get(5)?,
+                action: row.get(6)?,
+                commission: row.get(7)?,
+                stamp_duty: row.get(8)?,
+                transfer_fee: row.get(9)?,
+                account_id: row.get(10)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<Order>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+
+    Ok(orders)
+}
+
+/// 删除委托
+pub fn delete_order(conn: &Connection, id: i32) -> Result<(), StockError> {
     conn.execute("DELETE FROM orders WHERE id = ?1", params![id])
         .map_err(|e| StockError::DbError(e))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+    use crate::structs::account_structs::DEFAULT_ACCOUNT_ID;
+
+    fn order(code: &str, time: &str, action: &str) -> AddOrderParams {
+        AddOrderParams {
+            code: code.to_string(),
+            name: "测试股票".to_string(),
+            time: time.to_string(),
+            quantity: 100,
+            cost: 10.0,
+            action: action.to_string(),
+            account_id: DEFAULT_ACCOUNT_ID,
+        }
+    }
+
+    #[test]
+    fn add_and_page_orders_newest_first() {
+        let conn = open_test_connection();
+        add_order(&conn, &order("sh600000", "2024-01-02 10:00:00", "1")).unwrap();
+        let newest = add_order(&conn, &order("SZ000001", "2024-01-03 10:00:00", "1")).unwrap();
+        add_order(&conn, &order("SH600000", "2024-01-01 10:00:00", "1")).unwrap();
+
+        let page = query_orders(
+            &conn,
+            &QueryOrdersParams {
+                page: 1,
+                page_size: 2,
+                account_id: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.total_pages, 2);
+        assert_eq!(page.orders.len(), 2);
+        assert_eq!(page.orders[0].id, newest);
+    }
+
+    #[test]
+    fn query_by_code_is_case_insensitive() {
+        let conn = open_test_connection();
+        add_order(&conn, &order("sh600000", "2024-01-02 10:00:00", "1")).unwrap();
+        add_order(&conn, &order("SH600000", "2024-01-05 10:00:00", "2")).unwrap();
+        add_order(&conn, &order("SZ000001", "2024-01-03 10:00:00", "1")).unwrap();
+
+        let orders = query_orders_by_code(&conn, "sh600000", None).unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].action, "2");
+        assert!(orders.iter().all(|o| o.code == "SH600000"));
+    }
+
+    #[test]
+    fn add_order_records_fees_from_model() {
+        let conn = open_test_connection();
+        let mut sell = order("SH600000", "2024-01-02 10:00:00", "2");
+        sell.quantity = 10_000;
+        add_order(&conn, &sell).unwrap();
+
+        let saved = &query_orders_by_code(&conn, "SH600000", None).unwrap()[0];
+        assert_eq!(saved.commission, 25.0);
+        assert_eq!(saved.stamp_duty, 50.0);
+        assert_eq!(saved.transfer_fee, 1.0);
+        assert_eq!(saved.total_fee(), 76.0);
+    }
+
+    #[test]
+    fn delete_order_removes_row() {
+        let conn = open_test_connection();
+        let id = add_order(&conn, &order("SH600000", "2024-01-02 10:00:00", "1")).unwrap();
+
+        delete_order(&conn, id).unwrap();
+        assert!(query_orders_by_code(&conn, "SH600000", None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn queries_filter_by_account() {
+        let conn = open_test_connection();
+        add_order(&conn, &order("SH600000", "2024-01-02 10:00:00", "1")).unwrap();
+        let mut other = order("SH600000", "2024-01-03 10:00:00", "1");
+        other.account_id = 2;
+        let other_id = add_order(&conn, &other).unwrap();
+
+        let page = query_orders(
+            &conn,
+            &QueryOrdersParams {
+                page: 1,
+                page_size: 10,
+                account_id: Some(2),
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.orders[0].id, other_id);
+        assert_eq!(page.orders[0].account_id, 2);
+
+        assert_eq!(
+            query_orders_by_code(&conn, "SH600000", None).unwrap().len(),
+            2
+        );
+        let main = query_orders_by_code(&conn, "SH600000", Some(DEFAULT_ACCOUNT_ID)).unwrap();
+        assert_eq!(main.len(), 1);
+    }
+}
@@ -0,0 +1,1418 @@
+use crate::db::account_db::{add_transfer, ensure_active_account, query_transfers};
+use crate::db::app_config_db::{get_cost_method, set_cost_method};
+use crate::db::cash_db::ensure_cash_available;
+use crate::db::corporate_action_db::{
+    add_corporate_action, delete_corporate_action, query_corporate_actions, save_fetched_actions,
+};
+use crate::db::kline_cache_db::delete_cached_klines;
+use crate::db::orders_db::{add_order, delete_order, query_all_orders, query_orders_by_code};
+use crate::structs::account_structs::{PositionTransfer, TransferPositionParams};
+use crate::structs::corporate_action_structs::CorporateAction;
+use crate::structs::holdings_structs::{
+    CostMethod, Holding, HoldingDividend, HoldingExit, HoldingLot,
+};
+use crate::structs::orders_structs::{AddOrderParams, Order};
+use crate::structs::statement_structs::{SkippedRow, StatementOrder, StatementPreview};
+use crate::structs::StockError;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeSet, HashMap};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 前端录入委托使用的操作类型
+pub const BUY_ACTION: &str = "1";
+pub const SELL_ACTION: &str = "0";
+
+/// 持仓划转在回放时使用的操作类型（不会写入委托表）
+pub const TRANSFER_IN_ACTION: &str = "转入";
+pub const TRANSFER_OUT_ACTION: &str = "转出";
+
+/// 委托是否为买入（前端使用 "1" 表示买入，划转转入按成本价买入处理）
+pub fn is_buy_action(action: &str) -> bool {
+    matches!(action.trim(), BUY_ACTION | "买入" | TRANSFER_IN_ACTION)
+}
+
+/// 是否为持仓划转（转入或转出）
+pub fn is_transfer_action(action: &str) -> bool {
+    matches!(action.trim(), TRANSFER_IN_ACTION | TRANSFER_OUT_ACTION)
+}
+
+/// 把涉及某账户的持仓划转转换为该账户的回放委托：转入视为按成本价买入，转出视为不结转盈亏的减仓
+pub fn transfer_orders(transfers: &[PositionTransfer], account_id: i32) -> Vec<Order> {
+    transfers
+        .iter()
+        .filter(|t| t.from_account_id == account_id || t.to_account_id == account_id)
+        .map(|t| Order {
+            id: 0,
+            code: t.code.to_uppercase(),
+            name: t.name.clone(),
+            time: t.time.clone(),
+            quantity: t.quantity,
+            cost: t.cost,
+            action: if t.to_account_id == account_id {
+                TRANSFER_IN_ACTION.to_string()
+            } else {
+                TRANSFER_OUT_ACTION.to_string()
+            },
+            commission: 0.0,
+            stamp_duty: 0.0,
+            transfer_fee: 0.0,
+            account_id,
+        })
+        .collect()
+}
+
+/// 委托时间统一为本地时间 YYYY-MM-DD HH:MM:SS（前端传入的是 ISO8601 UTC 时间）
+pub fn normalize_order_time(time: &str) -> String {
+    let time = time.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(time) {
+        return dt.with_timezone(&Local).format(TIME_FORMAT).to_string();
+    }
+    for format in [TIME_FORMAT, "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(time, format) {
+            return dt.format(TIME_FORMAT).to_string();
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        return format!("{} 00:00:00", date);
+    }
+    time.to_string()
+}
+
+/// 一轮完整的持仓（从建仓到清仓，或仍在持有）及其批次、卖出和分红记录
+#[derive(Debug)]
+pub struct Position {
+    pub holding: Holding,
+    pub lots: Vec<HoldingLot>,
+    pub exits: Vec<HoldingExit>,
+    pub dividends: Vec<HoldingDividend>,
+}
+
+// 回放过程中正在持有的仓位
+struct OpenPosition {
+    position: Position,
+    held: i32,          // 当前持有数量
+    average_cost: f64,  // 平均成本法下的持仓均价
+    bought: i32,        // 本轮累计买入数量
+    bought_amount: f64, // 本轮累计买入金额
+    realized: f64,      // 本轮累计已实现盈亏
+}
+
+impl OpenPosition {
+    fn open(order: &Order, time: &str) -> Self {
+        OpenPosition {
+            position: Position {
+                holding: Holding {
+                    id: 0,
+                    code: order.code.to_uppercase(),
+                    name: order.name.clone(),
+                    cost: 0.0,
+                    quantity: 0,
+                    hold_time: time.to_string(),
+                    status: 1,
+                    sell_time: None,
+                    sell_price: None,
+                    profit: None,
+                    account_id: order.account_id,
+                },
+                lots: Vec::new(),
+                exits: Vec::new(),
+                dividends: Vec::new(),
+            },
+            held: 0,
+            average_cost: 0.0,
+            bought: 0,
+            bought_amount: 0.0,
+            realized: 0.0,
+        }
+    }
+
+    fn buy(&mut self, order: &Order, time: &str) {
+        // 买入费用计入成本
+        let amount = order.cost * order.quantity as f64 + order.total_fee();
+        self.average_cost =
+            (self.average_cost * self.held as f64 + amount) / (self.held + order.quantity) as f64;
+        self.held += order.quantity;
+        self.bought += order.quantity;
+        self.bought_amount += amount;
+        // 划转转入的批次没有对应委托
+        let order_id = if is_transfer_action(&order.action) {
+            None
+        } else {
+            Some(order.id)
+        };
+        self.position.lots.push(HoldingLot {
+            id: 0,
+            holding_id: 0,
+            order_id,
+            code: order.code.to_uppercase(),
+            buy_time: time.to_string(),
+            price: order.cost,
+            quantity: order.quantity,
+            remaining: order.quantity,
+            fee: order.total_fee(),
+        });
+    }
+
+    // 按先进先出消耗批次，返回被卖出部分的买入金额（含按比例分摊的买入费用）
+    fn consume_lots(&mut self, quantity: i32) -> f64 {
+        let mut left = quantity;
+        let mut amount = 0.0;
+        for lot in self.position.lots.iter_mut() {
+            if left == 0 {
+                break;
+            }
+            let take = lot.remaining.min(left);
+            lot.remaining -= take;
+            left -= take;
+            amount += lot_amount(lot, take);
+        }
+        amount
+    }
+
+    fn sell(&mut self, order: &Order, time: &str, method: CostMethod) {
+        let fifo_amount = self.consume_lots(order.quantity);
+        let basis = match method {
+            CostMethod::Fifo => fifo_amount,
+            CostMethod::Average => self.average_cost * order.quantity as f64,
+        };
+        let profit = order.cost * order.quantity as f64 - order.total_fee() - basis;
+
+        self.held -= order.quantity;
+        self.realized += profit;
+        self.position.exits.push(HoldingExit {
+            id: None,
+            holding_id: 0,
+            order_id: Some(order.id),
+            code: order.code.to_uppercase(),
+            name: order.name.clone(),
+            hold_time: self.position.holding.hold_time.clone(),
+            sell_time: time.to_string(),
+            sell_price: order.cost,
+            quantity: order.quantity,
+            cost: basis / order.quantity as f64,
+            profit,
+            closed: self.held == 0,
+            fee: order.total_fee(),
+            account_id: order.account_id,
+        });
+    }
+
+    // 划转转出：按先进先出消耗批次并减少持仓，不产生卖出记录和盈亏
+    fn transfer_out(&mut self, quantity: i32) {
+        self.consume_lots(quantity);
+        self.held -= quantity;
+    }
+
+    // 除权除息：现金分红按除权前的持仓计入已实现盈亏；送转和拆合股按比例调整剩余批次的
+    // 数量和价格，总成本不变（不足一股的部分舍去）
+    fn apply_action(&mut self, action: &CorporateAction) {
+        if action.cash_dividend > 0.0 {
+            let amount = action.cash_dividend * self.held as f64;
+            self.realized += amount;
+            self.position.dividends.push(HoldingDividend {
+                id: 0,
+                holding_id: 0,
+                action_id: action.id,
+                code: self.position.holding.code.clone(),
+                ex_date: action.ex_date.clone(),
+                quantity: self.held,
+                per_share: action.cash_dividend,
+                amount,
+                account_id: self.position.holding.account_id,
+            });
+        }
+
+        let factor = action.share_factor();
+        if factor <= 0.0 || (factor - 1.0).abs() < 1e-9 {
+            return;
+        }
+        let scale = |quantity: i32| (quantity as f64 * factor + 1e-6).floor() as i32;
+        let before = self.held;
+        for lot in self
+            .position
+            .lots
+            .iter_mut()
+            .filter(|lot| lot.remaining > 0)
+        {
+            lot.quantity = scale(lot.quantity).max(1);
+            lot.remaining = scale(lot.remaining);
+            lot.price /= factor;
+        }
+        self.held = self.position.lots.iter().map(|lot| lot.remaining).sum();
+        self.bought = scale(self.bought);
+        if self.held > 0 {
+            self.average_cost = self.average_cost * before as f64 / self.held as f64;
+        }
+    }
+
+    // 本轮是否有已实现的收益（卖出或分红）
+    fn has_realized(&self) -> bool {
+        !self.position.exits.is_empty() || !self.position.dividends.is_empty()
+    }
+
+    // 当前持仓的每股成本（含买入费用）：先进先出取剩余批次均价，平均成本法取持仓均价
+    fn current_cost(&self, method: CostMethod) -> f64 {
+        match method {
+            CostMethod::Fifo => {
+                let amount: f64 = self
+                    .position
+                    .lots
+                    .iter()
+                    .map(|lot| lot_amount(lot, lot.remaining))
+                    .sum();
+                amount / self.held as f64
+            }
+            CostMethod::Average => self.average_cost,
+        }
+    }
+
+    // 仍在持有：数量为剩余数量，盈利为已实现部分
+    fn into_open(mut self, method: CostMethod) -> Position {
+        let cost = self.current_cost(method);
+        let realized = if self.has_realized() {
+            Some(self.realized)
+        } else {
+            None
+        };
+        let holding = &mut self.position.holding;
+        holding.cost = cost;
+        holding.quantity = self.held;
+        holding.profit = realized;
+        self.position
+    }
+
+    // 已清仓：数量和成本为本轮买入汇总，盈利为本轮总盈亏
+    fn into_closed(mut self) -> Position {
+        let last = self.position.exits.last().cloned();
+        let holding = &mut self.position.holding;
+        holding.status = 0;
+        holding.quantity = self.bought;
+        holding.cost = self.bought_amount / self.bought as f64;
+        holding.profit = Some(self.realized);
+        if let Some(last) = last {
+            holding.sell_time = Some(last.sell_time);
+            holding.sell_price = Some(last.sell_price);
+        }
+        self.position
+    }
+}
+
+// 批次中 quantity 股的买入金额，买入费用按数量比例分摊
+fn lot_amount(lot: &HoldingLot, quantity: i32) -> f64 {
+    lot.price * quantity as f64 + lot.fee * quantity as f64 / lot.quantity as f64
+}
+
+// 从当前持仓中划出 quantity 股的每股成本：先进先出取最早的剩余批次，平均成本法取持仓均价
+fn transfer_cost(position: &Position, quantity: i32, method: CostMethod) -> f64 {
+    match method {
+        CostMethod::Fifo => {
+            let mut left = quantity;
+            let mut amount = 0.0;
+            for lot in &position.lots {
+                let take = lot.remaining.min(left);
+                amount += lot_amount(lot, take);
+                left -= take;
+            }
+            amount / quantity as f64
+        }
+        CostMethod::Average => position.holding.cost,
+    }
+}
+
+/// 回放事件：公司行动排在除权日所有委托之前
+#[derive(Debug, Clone, Copy)]
+pub enum ReplayEvent<'a> {
+    Action(&'a CorporateAction),
+    Order(&'a Order),
+}
+
+/// 回放中的一步（一笔委托或持有期间的一次公司行动）及之后的持仓数量
+#[derive(Debug, Clone)]
+pub struct ReplayStep<'a> {
+    pub time: String,
+    pub event: ReplayEvent<'a>,
+    pub dividend: f64, // 公司行动派发的现金分红
+    pub held: i32,
+}
+
+/// 按时间顺序回放同一只股票的委托和公司行动，得到每一轮持仓（已清仓的在前，当前持仓在最后）
+/// - 每笔买入形成一个批次，持仓成本按 method 计算并包含买入费用
+/// - 每笔卖出生成一条卖出记录，已实现盈亏按 method 结转成本并扣除卖出费用
+/// - 除权除息日调整当前持仓：分红计入已实现盈亏，送转和拆合股调整数量与每股成本
+/// - 卖出后数量归零即清仓，转为历史记录；全部划出且没有收益的一轮持仓不保留
+pub fn replay_with_actions(
+    orders: &[Order],
+    actions: &[CorporateAction],
+    method: CostMethod,
+) -> Result<Vec<Position>, StockError> {
+    replay(orders, actions, method, &mut Vec::new())
+}
+
+/// 与生成持仓相同的回放，返回每一步之后的持仓数量（组合分析按它逐日估值）
+/// 批次总是按先进先出消耗，持仓数量与成本计算方式无关
+pub fn replay_steps<'a>(
+    orders: &'a [Order],
+    actions: &'a [CorporateAction],
+) -> Result<Vec<ReplayStep<'a>>, StockError> {
+    let mut steps = Vec::new();
+    replay(orders, actions, CostMethod::Fifo, &mut steps)?;
+    Ok(steps)
+}
+
+fn replay<'a>(
+    orders: &'a [Order],
+    actions: &'a [CorporateAction],
+    method: CostMethod,
+    steps: &mut Vec<ReplayStep<'a>>,
+) -> Result<Vec<Position>, StockError> {
+    let mut sorted: Vec<(String, u8, i32, ReplayEvent)> = orders
+        .iter()
+        .map(|order| {
+            let time = normalize_order_time(&order.time);
+            (time, 1, order.id, ReplayEvent::Order(order))
+        })
+        .collect();
+    sorted.extend(actions.iter().map(|action| {
+        let time = normalize_order_time(&action.ex_date);
+        (time, 0, action.id, ReplayEvent::Action(action))
+    }));
+    sorted.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut positions = Vec::new();
+    let mut open: Option<OpenPosition> = None;
+
+    for (time, _, _, event) in sorted {
+        let order = match event {
+            ReplayEvent::Action(action) => {
+                if let Some(current) = open.as_mut() {
+                    let dividend = action.cash_dividend.max(0.0) * current.held as f64;
+                    current.apply_action(action);
+                    steps.push(ReplayStep {
+                        time: time.clone(),
+                        event,
+                        dividend,
+                        held: current.held,
+                    });
+                    // 合股后不足一股的持仓视为清仓
+                    if current.held == 0 {
+                        if let Some(closed) = open.take().filter(|p| p.has_realized()) {
+                            positions.push(closed.into_closed());
+                        }
+                    }
+                }
+                continue;
+            }
+            ReplayEvent::Order(order) => order,
+        };
+        if order.quantity <= 0 {
+            return Err(StockError::BusinessError(format!(
+                "委托数量必须大于0（委托ID: {}）",
+                order.id
+            )));
+        }
+
+        if is_buy_action(&order.action) {
+            let current = open.get_or_insert_with(|| OpenPosition::open(order, &time));
+            current.buy(order, &time);
+            steps.push(ReplayStep {
+                time,
+                event,
+                dividend: 0.0,
+                held: current.held,
+            });
+            continue;
+        }
+
+        let current = open.as_mut().ok_or_else(|| {
+            StockError::BusinessError(format!(
+                "无法卖出未持有的股票: {}({})，委托时间 {}",
+                order.name, order.code, time
+            ))
+        })?;
+        if order.quantity > current.held {
+            return Err(StockError::BusinessError(format!(
+                "卖出数量超过持仓数量: 持仓 {}，卖出 {}（委托时间 {}）",
+                current.held, order.quantity, time
+            )));
+        }
+
+        if is_transfer_action(&order.action) {
+            current.transfer_out(order.quantity);
+        } else {
+            current.sell(order, &time, method);
+        }
+        steps.push(ReplayStep {
+            time,
+            event,
+            dividend: 0.0,
+            held: current.held,
+        });
+        if current.held == 0 {
+            if let Some(closed) = open.take().filter(|p| p.has_realized()) {
+                positions.push(closed.into_closed());
+            }
+        }
+    }
+
+    if let Some(current) = open {
+        positions.push(current.into_open(method));
+    }
+    Ok(positions)
+}
+
+fn insert_position(conn: &Connection, position: &Position) -> Result<(), StockError> {
+    let holding = &position.holding;
+    conn.execute(
+        "INSERT INTO holdings
+         (code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit, account_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            holding.code,
+            holding.name,
+            holding.cost,
+            holding.quantity,
+            holding.hold_time,
+            holding.status,
+            holding.sell_time,
+            holding.sell_price,
+            holding.profit,
+            holding.account_id
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    let holding_id = conn.last_insert_rowid();
+
+    for lot in &position.lots {
+        conn.execute(
+            "INSERT INTO holding_lots
+             (holding_id, order_id, code, buy_time, price, quantity, remaining, fee)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                holding_id,
+                lot.order_id,
+                lot.code,
+                lot.buy_time,
+                lot.price,
+                lot.quantity,
+                lot.remaining,
+                lot.fee
+            ],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    }
+
+    for exit in &position.exits {
+        conn.execute(
+            "INSERT INTO holding_exits
+             (holding_id, order_id, code, name, sell_time, sell_price, quantity, cost, profit, closed, fee)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                holding_id,
+                exit.order_id,
+                exit.code,
+                exit.name,
+                exit.sell_time,
+                exit.sell_price,
+                exit.quantity,
+                exit.cost,
+                exit.profit,
+                exit.closed,
+                exit.fee
+            ],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    }
+
+    for dividend in &position.dividends {
+        conn.execute(
+            "INSERT INTO holding_dividends
+             (holding_id, action_id, code, ex_date, quantity, per_share, amount, account_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                holding_id,
+                dividend.action_id,
+                dividend.code,
+                dividend.ex_date,
+                dividend.quantity,
+                dividend.per_share,
+                dividend.amount,
+                dividend.account_id
+            ],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    }
+    Ok(())
+}
+
+// 某账户中某只股票的全部委托和划转
+fn account_code_orders(
+    conn: &Connection,
+    account_id: i32,
+    code: &str,
+) -> Result<Vec<Order>, StockError> {
+    let mut orders = query_orders_by_code(conn, code, Some(account_id))?;
+    let transfers = query_transfers(conn, Some(account_id), Some(code))?;
+    orders.extend(transfer_orders(&transfers, account_id));
+    Ok(orders)
+}
+
+// 用某账户中某只股票的全部委托、划转和公司行动重新生成它的持仓、批次、卖出和分红记录
+fn rebuild_code_holdings(
+    conn: &Connection,
+    account_id: i32,
+    code: &str,
+) -> Result<usize, StockError> {
+    let method = get_cost_method(conn)?;
+    let orders = account_code_orders(conn, account_id, code)?;
+    let actions = query_corporate_actions(conn, Some(code))?;
+    let positions = replay_with_actions(&orders, &actions, method)?;
+
+    let code = code.to_uppercase();
+    for table in ["holding_lots", "holding_exits", "holding_dividends"] {
+        conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE holding_id IN
+                 (SELECT id FROM holdings WHERE code = ?1 AND account_id = ?2)",
+                table
+            ),
+            params![code, account_id],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    }
+    conn.execute(
+        "DELETE FROM holdings WHERE code = ?1 AND account_id = ?2",
+        params![code, account_id],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    for position in &positions {
+        insert_position(conn, position)?;
+    }
+    Ok(positions.len())
+}
+
+// 有委托或划转记录的 (账户, 股票代码)，code 为空时返回全部
+fn holding_keys(conn: &Connection, code: Option<&str>) -> Result<Vec<(i32, String)>, StockError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT account_id, code FROM orders WHERE ?1 IS NULL OR code = ?1
+             UNION SELECT from_account_id, code FROM position_transfers WHERE ?1 IS NULL OR code = ?1
+             UNION SELECT to_account_id, code FROM position_transfers WHERE ?1 IS NULL OR code = ?1
+             ORDER BY 1, 2",
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    let keys = stmt
+        .query_map(params![code.map(|code| code.to_uppercase())], |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<(i32, String)>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(keys)
+}
+
+/// 清空持仓相关表并按全部委托和划转重新生成（在调用方的事务中执行）
+pub fn rebuild_all_holdings(conn: &Connection) -> Result<usize, StockError> {
+    let keys = holding_keys(conn, None)?;
+
+    conn.execute_batch(
+        "DELETE FROM holding_lots;
+         DELETE FROM holding_exits;
+         DELETE FROM holding_dividends;
+         DELETE FROM holdings;",
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    let mut total = 0;
+    for (account_id, code) in &keys {
+        total += rebuild_code_holdings(conn, *account_id, code)?;
+    }
+    Ok(total)
+}
+
+// 公司行动变化后：重新生成所有账户中该股票的持仓，并清除它的K线缓存（复权价格随之改变）
+// 现金分红随之增减，严格模式下检查相关账户的资金
+fn apply_corporate_action_change(conn: &Connection, code: &str) -> Result<(), StockError> {
+    for (account_id, code) in holding_keys(conn, Some(code))? {
+        rebuild_code_holdings(conn, account_id, &code)?;
+        ensure_cash_available(conn, account_id)?;
+    }
+    delete_cached_klines(conn, Some(code))?;
+    Ok(())
+}
+
+/// 记录委托并同步更新持仓（同一事务内完成，任一步失败都不会留下半条记录）
+/// 资金严格模式下会检查所属账户在各时点的可用资金（补录的早期委托可能影响之后的余额）
+pub fn record_order(conn: &mut Connection, req: &AddOrderParams) -> Result<i32, StockError> {
+    if is_transfer_action(&req.action) {
+        return Err(StockError::BusinessError(
+            "持仓划转请使用划转功能，不能作为委托录入".to_string(),
+        ));
+    }
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    ensure_active_account(&tx, req.account_id)?;
+    let id = add_order(&tx, req)?;
+    rebuild_code_holdings(&tx, req.account_id, &req.code)?;
+    ensure_cash_available(&tx, req.account_id)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(id)
+}
+
+/// 删除委托并回退它对持仓的影响，委托不存在时返回 false
+/// 严格模式下删除卖出委托后资金不足时拒绝
+pub fn remove_order(conn: &mut Connection, id: i32) -> Result<bool, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    let order: Option<(String, i32)> = tx
+        .query_row(
+            "SELECT code, account_id FROM orders WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| StockError::DbError(e))?;
+
+    let Some((code, account_id)) = order else {
+        return Ok(false);
+    };
+    delete_order(&tx, id)?;
+    rebuild_code_holdings(&tx, account_id, &code)?;
+    ensure_cash_available(&tx, account_id)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(true)
+}
+
+/// 按成本价把持仓从一个账户划转到另一个账户，返回划转记录ID
+/// 划转不产生卖出记录和盈亏，转入账户按划转成本新增一个批次
+pub fn transfer_position(
+    conn: &mut Connection,
+    req: &TransferPositionParams,
+) -> Result<i32, StockError> {
+    if req.quantity <= 0 {
+        return Err(StockError::BusinessError(format!(
+            "划转数量必须大于0: {}",
+            req.quantity
+        )));
+    }
+    if req.from_account_id == req.to_account_id {
+        return Err(StockError::BusinessError(
+            "转出账户和转入账户不能相同".to_string(),
+        ));
+    }
+    let code = req.code.trim().to_uppercase();
+    let time = match req.time.as_deref().map(str::trim) {
+        Some(time) if !time.is_empty() => normalize_order_time(time),
+        _ => Local::now().format(TIME_FORMAT).to_string(),
+    };
+
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    ensure_active_account(&tx, req.from_account_id)?;
+    ensure_active_account(&tx, req.to_account_id)?;
+
+    // 按划转时间之前的委托确定转出账户的持仓和划转成本
+    let method = get_cost_method(&tx)?;
+    let before: Vec<Order> = account_code_orders(&tx, req.from_account_id, &code)?
+        .into_iter()
+        .filter(|order| normalize_order_time(&order.time) <= time)
+        .collect();
+    let actions: Vec<CorporateAction> = query_corporate_actions(&tx, Some(&code))?
+        .into_iter()
+        .filter(|action| normalize_order_time(&action.ex_date) <= time)
+        .collect();
+    let position = replay_with_actions(&before, &actions, method)?
+        .pop()
+        .filter(|position| position.holding.status == 1)
+        .ok_or_else(|| StockError::BusinessError(format!("转出账户没有持有 {}", code)))?;
+    if req.quantity > position.holding.quantity {
+        return Err(StockError::BusinessError(format!(
+            "划转数量超过持仓数量: 持仓 {}，划转 {}",
+            position.holding.quantity, req.quantity
+        )));
+    }
+
+    let id = add_transfer(
+        &tx,
+        &PositionTransfer {
+            id: 0,
+            from_account_id: req.from_account_id,
+            to_account_id: req.to_account_id,
+            code: code.clone(),
+            name: position.holding.name.clone(),
+            quantity: req.quantity,
+            cost: transfer_cost(&position, req.quantity, method),
+            time,
+        },
+    )?;
+    rebuild_code_holdings(&tx, req.from_account_id, &code)?;
+    rebuild_code_holdings(&tx, req.to_account_id, &code)?;
+    ensure_cash_available(&tx, req.from_account_id)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(id)
+}
+
+/// 撤销一条持仓划转并重新生成两个账户的持仓，记录不存在时返回 false
+/// 划转影响两个账户的现金分红，严格模式下分别检查资金
+pub fn remove_transfer(conn: &mut Connection, id: i32) -> Result<bool, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    let transfer: Option<(i32, i32, String)> = tx
+        .query_row(
+            "SELECT from_account_id, to_account_id, code FROM position_transfers WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| StockError::DbError(e))?;
+
+    let Some((from_account_id, to_account_id, code)) = transfer else {
+        return Ok(false);
+    };
+    tx.execute("DELETE FROM position_transfers WHERE id = ?1", params![id])
+        .map_err(|e| StockError::DbError(e))?;
+    rebuild_code_holdings(&tx, from_account_id, &code)?;
+    rebuild_code_holdings(&tx, to_account_id, &code)?;
+    ensure_cash_available(&tx, from_account_id)?;
+    ensure_cash_available(&tx, to_account_id)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(true)
+}
+
+/// 录入公司行动并重新生成相关持仓，返回记录ID
+pub fn record_corporate_action(
+    conn: &mut Connection,
+    action: &CorporateAction,
+) -> Result<i32, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    let id = add_corporate_action(&tx, action)?;
+    apply_corporate_action_change(&tx, &action.code)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(id)
+}
+
+/// 保存从数据源获取的公司行动（已有同一除权日记录的跳过），返回新增数量
+pub fn import_corporate_actions(
+    conn: &mut Connection,
+    code: &str,
+    actions: &[CorporateAction],
+) -> Result<usize, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    let added = save_fetched_actions(&tx, actions)?;
+    if added > 0 {
+        apply_corporate_action_change(&tx, code)?;
+    }
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(added)
+}
+
+/// 删除公司行动并重新生成相关持仓，记录不存在时返回 false
+pub fn remove_corporate_action(conn: &mut Connection, id: i32) -> Result<bool, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    let Some(code) = delete_corporate_action(&tx, id)? else {
+        return Ok(false);
+    };
+    apply_corporate_action_change(&tx, &code)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(true)
+}
+
+// 判断重复成交的键：时间、代码、方向、数量和价格（精确到 0.0001 元）
+type TradeKey = (String, String, bool, i32, i64);
+
+fn trade_key(time: &str, code: &str, action: &str, quantity: i32, cost: f64) -> TradeKey {
+    (
+        normalize_order_time(time),
+        code.to_uppercase(),
+        is_buy_action(action),
+        quantity,
+        (cost * 10_000.0).round() as i64,
+    )
+}
+
+/// 将交割单成交与账户中已有的委托比对，区分新增和已存在的成交
+/// 同一笔成交可能在文件中出现多次（分笔成交），按次数逐一抵消已有委托
+pub fn preview_statement(
+    conn: &Connection,
+    account_id: i32,
+    orders: Vec<StatementOrder>,
+    skipped: Vec<SkippedRow>,
+) -> Result<StatementPreview, StockError> {
+    let mut existing: HashMap<TradeKey, usize> = HashMap::new();
+    for order in query_all_orders(conn, Some(account_id))? {
+        let key = trade_key(
+            &order.time,
+            &order.code,
+            &order.action,
+            order.quantity,
+            order.cost,
+        );
+        *existing.entry(key).or_default() += 1;
+    }
+
+    let (mut new_orders, mut duplicates) = (Vec::new(), Vec::new());
+    for order in orders {
+        let key = trade_key(
+            &order.time,
+            &order.code,
+            &order.action,
+            order.quantity,
+            order.cost,
+        );
+        match existing.get_mut(&key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                duplicates.push(order);
+            }
+            _ => new_orders.push(order),
+        }
+    }
+
+    Ok(StatementPreview {
+        orders: new_orders,
+        duplicates,
+        skipped,
+    })
+}
+
+/// 导入交割单中的新成交并重新生成相关持仓（同一事务内完成，任一笔失败整体回滚）
+/// 返回实际导入的预览结果
+pub fn import_statement(
+    conn: &mut Connection,
+    account_id: i32,
+    orders: Vec<StatementOrder>,
+    skipped: Vec<SkippedRow>,
+) -> Result<StatementPreview, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    ensure_active_account(&tx, account_id)?;
+    let preview = preview_statement(&tx, account_id, orders, skipped)?;
+
+    let mut codes = BTreeSet::new();
+    for order in &preview.orders {
+        let params = AddOrderParams {
+            code: order.code.clone(),
+            name: order.name.clone(),
+            time: order.time.clone(),
+            quantity: order.quantity,
+            cost: order.cost,
+            action: order.action.clone(),
+            account_id,
+        };
+        add_order(&tx, &params)?;
+        codes.insert(order.code.clone());
+    }
+    for code in &codes {
+        rebuild_code_holdings(&tx, account_id, code).map_err(|e| {
+            StockError::BusinessError(format!("{} 的成交无法生成持仓: {}", code, e))
+        })?;
+    }
+    ensure_cash_available(&tx, account_id)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(preview)
+}
+
+/// 读取持仓表中的全部记录（包括已清仓的），旧版没有 account_id 列时归入默认账户
+/// 用于在生成持仓前找出旧版手动录入、没有委托支撑的持仓
+pub fn read_legacy_holdings(conn: &Connection) -> Result<Vec<Holding>, StockError> {
+    let has_account: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM pragma_table_info('holdings') WHERE name = 'account_id')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, code, name, cost, quantity, hold_time, status, sell_time, sell_price, profit,
+                    {}
+             FROM holdings
+             ORDER BY id ASC",
+            if has_account { "account_id" } else { "1" }
+        ))
+        .map_err(|e| StockError::DbError(e))?;
+    let holdings = stmt
+        .query_map([], |row| {
+            Ok(Holding {
+                id: row.get(0)?,
+                code: row.get(1)?,
+                name: row.get(2)?,
+                cost: row.get(3)?,
+                quantity: row.get(4)?,
+                hold_time: row.get(5)?,
+                status: row.get(6)?,
+                sell_time: row.get(7)?,
+                sell_price: row.get(8)?,
+                profit: row.get(9)?,
+                account_id: row.get(10)?,
+            })
+        })
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<Holding>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(holdings)
+}
+
+/// 为没有委托和划转支撑的持仓补建期初委托（按持仓成本买入，已清仓的再按卖出价卖出，不计费用）
+/// 持仓只由委托推导，不补建时这些旧版手动录入的持仓会在重新生成时丢失
+/// 返回补建了委托的持仓数量
+pub fn add_opening_orders(conn: &Connection, holdings: &[Holding]) -> Result<usize, StockError> {
+    // 先确定已有委托的股票，同一股票的多轮持仓（已清仓和当前持仓）一起补建
+    let backed: BTreeSet<(i32, String)> = holding_keys(conn, None)?
+        .into_iter()
+        .map(|(account_id, code)| (account_id, code.to_uppercase()))
+        .collect();
+    let mut added = 0;
+    for holding in holdings {
+        let code = holding.code.trim().to_uppercase();
+        if holding.quantity <= 0 || backed.contains(&(holding.account_id, code.clone())) {
+            continue;
+        }
+        let hold_time = normalize_order_time(&holding.hold_time);
+        let mut orders = vec![(hold_time.clone(), holding.cost, BUY_ACTION)];
+        if holding.status == 0 {
+            if let Some(price) = holding.sell_price {
+                let sell_time = holding
+                    .sell_time
+                    .as_deref()
+                    .map(normalize_order_time)
+                    .unwrap_or(hold_time);
+                orders.push((sell_time, price, SELL_ACTION));
+            }
+        }
+        for (time, price, action) in orders {
+            conn.execute(
+                "INSERT INTO orders (code, name, time, quantity, cost, action, account_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    code,
+                    holding.name,
+                    time,
+                    holding.quantity,
+                    price,
+                    action,
+                    holding.account_id
+                ],
+            )
+            .map_err(|e| StockError::DbError(e))?;
+        }
+        added += 1;
+    }
+    Ok(added)
+}
+
+/// 清空持仓表并按全部委托重新生成，返回生成的持仓记录数
+/// 旧版手动录入的持仓在合并旧数据库和导入备份时已补建期初委托
+pub fn rebuild_holdings(conn: &mut Connection) -> Result<usize, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    let total = rebuild_all_holdings(&tx)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(total)
+}
+
+/// 切换成本计算方式，并按新方式重新生成全部持仓
+pub fn change_cost_method(conn: &mut Connection, method: CostMethod) -> Result<usize, StockError> {
+    let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
+    set_cost_method(&tx, method)?;
+    let total = rebuild_all_holdings(&tx)?;
+    tx.commit().map_err(|e| StockError::DbError(e))?;
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::account_db::create_account;
+    use crate::db::app_config_db::set_fee_model;
+    use crate::db::common::open_test_connection;
+    use crate::db::holdings_db::{
+        query_history_holdings, query_holding_dividends, query_holding_lots, query_holdings,
+        query_monthly_stats,
+    };
+    use crate::structs::account_structs::DEFAULT_ACCOUNT_ID;
+    use crate::structs::orders_structs::FeeModel;
+
+    fn order(time: &str, action: &str, quantity: i32, cost: f64) -> AddOrderParams {
+        AddOrderParams {
+            code: "sh600000".to_string(),
+            name: "浦发银行".to_string(),
+            time: time.to_string(),
+            quantity,
+            cost,
+            action: action.to_string(),
+            account_id: DEFAULT_ACCOUNT_ID,
+        }
+    }
+
+    // 不收费用，便于核对成本与盈亏
+    fn fee_free_connection() -> Connection {
+        let conn = open_test_connection();
+        let zero = FeeModel {
+            commission_rate: 0.0,
+            min_commission: 0.0,
+            stamp_duty_rate: 0.0,
+            transfer_fee_rate: 0.0,
+        };
+        set_fee_model(&conn, &zero).unwrap();
+        conn
+    }
+
+    // 两笔买入后卖出一半：均价 11，先进先出时卖出的是 10 元那批
+    fn scale_in_and_out(conn: &mut Connection) {
+        record_order(conn, &order("2024-01-02 10:00:00", "1", 100, 10.0)).unwrap();
+        record_order(conn, &order("2024-01-03 10:00:00", "1", 100, 12.0)).unwrap();
+        record_order(conn, &order("2024-01-04 10:00:00", "2", 100, 13.0)).unwrap();
+    }
+
+    #[test]
+    fn average_cost_partial_sell() {
+        let mut conn = fee_free_connection();
+        scale_in_and_out(&mut conn);
+
+        let holdings = query_holdings(&conn, None).unwrap();
+        assert_eq!(holdings.len(), 1);
+        assert_eq!(holdings[0].code, "SH600000");
+        assert_eq!(holdings[0].quantity, 100);
+        assert_eq!(holdings[0].hold_time, "2024-01-02 10:00:00");
+        assert!((holdings[0].cost - 11.0).abs() < 1e-9);
+        assert_eq!(holdings[0].profit, Some(200.0));
+
+        // 部分卖出也出现在历史记录中
+        let history = query_history_holdings(&conn, 1, 10, None).unwrap();
+        assert_eq!(history.total, 1);
+        let exit = &history.data[0];
+        assert_eq!(exit.quantity, 100);
+        assert!((exit.cost - 11.0).abs() < 1e-9);
+        assert!((exit.profit - 200.0).abs() < 1e-9);
+        assert!(!exit.closed);
+    }
+
+    #[test]
+    fn fifo_consumes_oldest_lots() {
+        let mut conn = fee_free_connection();
+        change_cost_method(&mut conn, CostMethod::Fifo).unwrap();
+        scale_in_and_out(&mut conn);
+
+        let holding = &query_holdings(&conn, None).unwrap()[0];
+        assert!((holding.cost - 12.0).abs() < 1e-9);
+        assert_eq!(holding.profit, Some(300.0));
+
+        let lots = query_holding_lots(&conn, holding.id).unwrap();
+        let remaining: Vec<i32> = lots.iter().map(|lot| lot.remaining).collect();
+        assert_eq!(remaining, vec![0, 100]);
+
+        // 切换回平均成本后重新结转
+        change_cost_method(&mut conn, CostMethod::Average).unwrap();
+        assert_eq!(query_holdings(&conn, None).unwrap()[0].profit, Some(200.0));
+    }
+
+    #[test]
+    fn full_sell_closes_position_and_lists_each_exit() {
+        let mut conn = fee_free_connection();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 100, 10.0)).unwrap();
+        record_order(&mut conn, &order("2024-01-03 10:00:00", "2", 50, 12.0)).unwrap();
+        record_order(&mut conn, &order("2024-02-04 10:00:00", "2", 50, 11.0)).unwrap();
+
+        assert!(query_holdings(&conn, None).unwrap().is_empty());
+        let history = query_history_holdings(&conn, 1, 10, None).unwrap();
+        assert_eq!(history.total, 2);
+        assert_eq!(history.data[0].sell_time, "2024-02-04 10:00:00");
+        assert!(history.data[0].closed);
+        assert!((history.data[0].profit - 50.0).abs() < 1e-9);
+        assert!((history.data[1].profit - 100.0).abs() < 1e-9);
+
+        // 月度统计按卖出记录计算
+        let january = query_monthly_stats(&conn, 2024, 1, None).unwrap();
+        assert_eq!(january.operation_count, 1);
+        assert!((january.total_profit - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fees_reduce_realized_profit() {
+        // 默认费率：沪市 10 元买入、11 元卖出各 1 万股
+        let mut conn = open_test_connection();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 10_000, 10.0)).unwrap();
+
+        // 买入费用 25 + 1 计入成本
+        let holding = &query_holdings(&conn, None).unwrap()[0];
+        assert!((holding.cost - 10.0026).abs() < 1e-9);
+
+        record_order(&mut conn, &order("2024-01-03 10:00:00", "2", 10_000, 11.0)).unwrap();
+        let exit = &query_history_holdings(&conn, 1, 10, None).unwrap().data[0];
+        // 卖出费用：佣金 27.5 + 印花税 55 + 过户费 1.1
+        assert!((exit.fee - 83.6).abs() < 1e-9);
+        assert!((exit.profit - (10_000.0 - 26.0 - 83.6)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn invalid_sell_rolls_back_order() {
+        let mut conn = fee_free_connection();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 100, 10.0)).unwrap();
+
+        let result = record_order(&mut conn, &order("2024-01-03 10:00:00", "2", 200, 12.0));
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+        assert_eq!(
+            query_orders_by_code(&conn, "SH600000", None).unwrap().len(),
+            1
+        );
+        assert_eq!(query_holdings(&conn, None).unwrap()[0].quantity, 100);
+
+        // 早于首次买入的卖出同样无效
+        let result = record_order(&mut conn, &order("2024-01-01 10:00:00", "2", 100, 12.0));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn deleting_order_unwinds_position() {
+        let mut conn = fee_free_connection();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 100, 10.0)).unwrap();
+        let sell = record_order(&mut conn, &order("2024-01-03 10:00:00", "2", 100, 12.0)).unwrap();
+        assert!(query_holdings(&conn, None).unwrap().is_empty());
+
+        assert!(remove_order(&mut conn, sell).unwrap());
+        let holdings = query_holdings(&conn, None).unwrap();
+        assert_eq!(holdings.len(), 1);
+        assert_eq!(holdings[0].cost, 10.0);
+        assert_eq!(query_history_holdings(&conn, 1, 10, None).unwrap().total, 0);
+        assert!(!remove_order(&mut conn, sell).unwrap());
+    }
+
+    #[test]
+    fn rebuild_replays_orders_deterministically() {
+        let mut conn = fee_free_connection();
+        // 委托乱序录入，回放时按时间排序
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 100, 10.0)).unwrap();
+        record_order(&mut conn, &order("2024-02-02 10:00:00", "1", 100, 8.0)).unwrap();
+        record_order(&mut conn, &order("2024-01-10 10:00:00", "2", 100, 11.0)).unwrap();
+        conn.execute("UPDATE holdings SET quantity = 999 WHERE status = 1", [])
+            .unwrap();
+
+        assert_eq!(rebuild_holdings(&mut conn).unwrap(), 2);
+        let current = query_holdings(&conn, None).unwrap();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].quantity, 100);
+        assert_eq!(current[0].cost, 8.0);
+        let history = query_history_holdings(&conn, 1, 10, None).unwrap();
+        assert_eq!(history.data[0].profit, 100.0);
+    }
+
+    #[test]
+    fn accounts_hold_same_code_separately() {
+        let mut conn = fee_free_connection();
+        let margin = create_account(&conn, "融资账户").unwrap();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 100, 10.0)).unwrap();
+        let mut margin_buy = order("2024-01-03 10:00:00", "1", 200, 11.0);
+        margin_buy.account_id = margin;
+        record_order(&mut conn, &margin_buy).unwrap();
+        let mut margin_sell = order("2024-01-04 10:00:00", "2", 200, 12.0);
+        margin_sell.account_id = margin;
+        record_order(&mut conn, &margin_sell).unwrap();
+
+        // 汇总视图包含两个账户，分账户视图互不影响
+        assert_eq!(query_holdings(&conn, None).unwrap().len(), 1);
+        assert_eq!(
+            query_holdings(&conn, Some(DEFAULT_ACCOUNT_ID)).unwrap()[0].quantity,
+            100
+        );
+        assert!(query_holdings(&conn, Some(margin)).unwrap().is_empty());
+        assert_eq!(
+            query_history_holdings(&conn, 1, 10, Some(margin))
+                .unwrap()
+                .total,
+            1
+        );
+        assert_eq!(
+            query_history_holdings(&conn, 1, 10, Some(DEFAULT_ACCOUNT_ID))
+                .unwrap()
+                .total,
+            0
+        );
+        let stats = query_monthly_stats(&conn, 2024, 1, Some(margin)).unwrap();
+        assert!((stats.total_profit - 200.0).abs() < 1e-9);
+
+        // 其他账户的卖出不能超过本账户持仓
+        let mut oversell = order("2024-01-05 10:00:00", "2", 200, 12.0);
+        oversell.account_id = DEFAULT_ACCOUNT_ID;
+        assert!(record_order(&mut conn, &oversell).is_err());
+    }
+
+    #[test]
+    fn transfer_moves_lots_at_cost() {
+        let mut conn = fee_free_connection();
+        change_cost_method(&mut conn, CostMethod::Fifo).unwrap();
+        let margin = create_account(&conn, "融资账户").unwrap();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 100, 10.0)).unwrap();
+        record_order(&mut conn, &order("2024-01-03 10:00:00", "1", 100, 12.0)).unwrap();
+
+        let transfer = TransferPositionParams {
+            from_account_id: DEFAULT_ACCOUNT_ID,
+            to_account_id: margin,
+            code: "sh600000".to_string(),
+            quantity: 150,
+            time: Some("2024-01-05 10:00:00".to_string()),
+        };
+        let transfer_id = transfer_position(&mut conn, &transfer).unwrap();
+
+        // 先进先出划出 10 元的 100 股和 12 元的 50 股，转出账户不产生卖出记录
+        let remaining = &query_holdings(&conn, Some(DEFAULT_ACCOUNT_ID)).unwrap()[0];
+        assert_eq!(remaining.quantity, 50);
+        assert!((remaining.cost - 12.0).abs() < 1e-9);
+        assert_eq!(remaining.profit, None);
+        let moved = &query_holdings(&conn, Some(margin)).unwrap()[0];
+        assert_eq!(moved.quantity, 150);
+        assert!((moved.cost - 1_600.0 / 150.0).abs() < 1e-9);
+        assert_eq!(moved.hold_time, "2024-01-05 10:00:00");
+        assert_eq!(query_history_holdings(&conn, 1, 10, None).unwrap().total, 0);
+
+        let mut over = transfer.clone();
+        over.quantity = 100;
+        assert!(transfer_position(&mut conn, &over).is_err());
+        over.to_account_id = DEFAULT_ACCOUNT_ID;
+        assert!(transfer_position(&mut conn, &over).is_err());
+
+        let mut sell = order("2024-01-08 10:00:00", "2", 150, 13.0);
+        sell.account_id = margin;
+        record_order(&mut conn, &sell).unwrap();
+        let exit = &query_history_holdings(&conn, 1, 10, Some(margin))
+            .unwrap()
+            .data[0];
+        assert!((exit.profit - 350.0).abs() < 1e-9);
+
+        // 划转后转入账户已卖出，撤销划转会导致卖出无效，整体回滚
+        assert!(remove_transfer(&mut conn, transfer_id).is_err());
+        assert_eq!(rebuild_holdings(&mut conn).unwrap(), 2);
+        assert_eq!(query_holdings(&conn, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn transfer_actions_are_not_orders() {
+        let mut conn = fee_free_connection();
+        let result = record_order(&mut conn, &order("2024-01-02 10:00:00", "转入", 100, 10.0));
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+
+        let mut archived = order("2024-01-02 10:00:00", "1", 100, 10.0);
+        archived.account_id = create_account(&conn, "家庭账户").unwrap();
+        crate::db::account_db::set_account_archived(&conn, archived.account_id, true).unwrap();
+        assert!(record_order(&mut conn, &archived).is_err());
+    }
+
+    fn bonus_action(ex_date: &str, cash_dividend: f64, bonus_shares: f64) -> CorporateAction {
+        CorporateAction {
+            id: 0,
+            code: "SH600000".to_string(),
+            ex_date: ex_date.to_string(),
+            cash_dividend,
+            bonus_shares,
+            transfer_shares: 0.0,
+            split_ratio: 1.0,
+            source: "manual".to_string(),
+            note: String::new(),
+        }
+    }
+
+    #[test]
+    fn corporate_actions_adjust_lots_and_realize_dividends() {
+        let mut conn = fee_free_connection();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 1000, 10.0)).unwrap();
+        // 10送5派1元：每股派 0.1 元、送 0.5 股
+        let action_id =
+            record_corporate_action(&mut conn, &bonus_action("2024-06-20", 0.1, 0.5)).unwrap();
+
+        let holding = &query_holdings(&conn, None).unwrap()[0];
+        assert_eq!(holding.quantity, 1500);
+        assert!((holding.cost - 10_000.0 / 1500.0).abs() < 1e-9);
+        assert_eq!(holding.profit, Some(100.0));
+        let lots = query_holding_lots(&conn, holding.id).unwrap();
+        assert_eq!(lots[0].remaining, 1500);
+        let dividends = query_holding_dividends(&conn, holding.id).unwrap();
+        assert_eq!(dividends.len(), 1);
+        assert_eq!(dividends[0].quantity, 1000);
+        assert!((dividends[0].amount - 100.0).abs() < 1e-9);
+
+        // 除权日之后买入的不享有权益
+        record_order(&mut conn, &order("2024-06-20 10:00:00", "1", 500, 7.0)).unwrap();
+        record_order(&mut conn, &order("2024-06-21 10:00:00", "2", 2000, 8.0)).unwrap();
+        let history = query_history_holdings(&conn, 1, 10, None).unwrap();
+        assert!(history.data[0].closed);
+        assert!((history.data[0].profit - 2_500.0).abs() < 1e-9);
+
+        // 删除后卖出数量超过持仓，整体回滚
+        assert!(remove_corporate_action(&mut conn, action_id).is_err());
+        assert_eq!(query_corporate_actions(&conn, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn removing_corporate_action_restores_position() {
+        let mut conn = fee_free_connection();
+        record_order(&mut conn, &order("2024-01-02 10:00:00", "1", 1000, 10.0)).unwrap();
+        let fetched = [bonus_action("2024-06-20", 0.0, 1.0)];
+        assert_eq!(
+            import_corporate_actions(&mut conn, "SH600000", &fetched).unwrap(),
+            1
+        );
+        assert_eq!(
+            import_corporate_actions(&mut conn, "SH600000", &fetched).unwrap(),
+            0
+        );
+        assert_eq!(query_holdings(&conn, None).unwrap()[0].quantity, 2000);
+
+        let id = query_corporate_actions(&conn, Some("SH600000")).unwrap()[0].id;
+        assert!(remove_corporate_action(&mut conn, id).unwrap());
+        assert!(!remove_corporate_action(&mut conn, id).unwrap());
+        let holding = &query_holdings(&conn, None).unwrap()[0];
+        assert_eq!(holding.quantity, 1000);
+        assert!((holding.cost - 10.0).abs() < 1e-9);
+        assert_eq!(holding.profit, None);
+    }
+
+    fn fill(line: usize, time: &str, action: &str, quantity: i32, cost: f64) -> StatementOrder {
+        StatementOrder {
+            line,
+            code: "SH600000".to_string(),
+            name: "浦发银行".to_string(),
+            time: time.to_string(),
+            quantity,
+            cost,
+            action: action.to_string(),
+        }
+    }
+
+    #[test]
+    fn statement_import_skips_existing_fills() {
+        let mut conn = fee_free_connection();
+        // 手动录入过的成交
+        record_order(&mut conn, &order("2024-01-02 09:31:05", "1", 100, 10.0)).unwrap();
+
+        let fills = vec![
+            fill(2, "2024-01-02 09:31:05", BUY_ACTION, 100, 10.0),
+            fill(3, "2024-01-02 09:31:05", BUY_ACTION, 100, 10.0),
+            fill(4, "2024-01-03 10:00:00", SELL_ACTION, 150, 11.0),
+        ];
+        let preview = preview_statement(&conn, DEFAULT_ACCOUNT_ID, fills.clone(), vec![]).unwrap();
+        assert_eq!(preview.orders.len(), 2);
+        assert_eq!(preview.duplicates[0].line, 2);
+
+        let imported =
+            import_statement(&mut conn, DEFAULT_ACCOUNT_ID, fills.clone(), vec![]).unwrap();
+        assert_eq!(imported.orders.len(), 2);
+        let holding = &query_holdings(&conn, None).unwrap()[0];
+        assert_eq!(holding.quantity, 50);
+        assert_eq!(holding.profit, Some(150.0));
+
+        // 再次导入同一文件不会重复
+        let again = import_statement(&mut conn, DEFAULT_ACCOUNT_ID, fills, vec![]).unwrap();
+        assert!(again.orders.is_empty());
+        assert_eq!(again.duplicates.len(), 3);
+
+        // 卖出超过持仓时整体回滚
+        let oversell = vec![fill(2, "2024-01-04 10:00:00", SELL_ACTION, 500, 11.0)];
+        assert!(import_statement(&mut conn, DEFAULT_ACCOUNT_ID, oversell, vec![]).is_err());
+        assert_eq!(query_all_orders(&conn, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn normalizes_order_times() {
+        assert_eq!(
+            normalize_order_time("2024-01-02 10:00"),
+            "2024-01-02 10:00:00"
+        );
+        assert_eq!(normalize_order_time("2024-01-02"), "2024-01-02 00:00:00");
+        assert!(is_buy_action("1"));
+        assert!(!is_buy_action("2"));
+    }
+}
@@ -1,20 +1,9 @@
-use crate::db::common::init_database;
 use crate::structs::selection_structs::{Selection, UpdateSelectionSortParams};
 use crate::structs::StockError;
 use rusqlite::{params, Connection, Error as RusqliteError, OptionalExtension};
-use tauri::AppHandle;
-
-/// 获取自选股数据库连接
-pub fn get_selection_db_conn(app: &AppHandle) -> Result<Connection, StockError> {
-    init_database(app, "my_selection")
-        .map_err(|e| StockError::BusinessError(format!("获取数据库连接失败: {}", e)))
-}
 
 /// 获取所有自选股（按 sort 升序排列）
-pub fn get_all_selections(app: &AppHandle) -> Result<Vec<Selection>, StockError> {
-    // 获取数据库连接
-    let conn = get_selection_db_conn(app)?;
-
+pub fn get_all_selections(conn: &Connection) -> Result<Vec<Selection>, StockError> {
     // 准备查询语句
     let mut stmt = conn
         .prepare(
@@ -43,9 +32,10 @@ pub fn get_all_selections(app: &AppHandle) -> Result<Vec<Selection>, StockError>
 }
 
 /// 根据股票代码获取单个自选股
-pub fn get_selection_by_code(app: &AppHandle, code: &str) -> Result<Option<Selection>, StockError> {
-    let conn = get_selection_db_conn(app)?;
-
+pub fn get_selection_by_code(
+    conn: &Connection,
+    code: &str,
+) -> Result<Option<Selection>, StockError> {
     let mut stmt = conn
         .prepare(
             "SELECT code, name, color, remark, sort 
@@ -72,9 +62,7 @@ pub fn get_selection_by_code(app: &AppHandle, code: &str) -> Result<Option<Selec
 }
 
 /// 检查自选股是否存在
-pub fn is_selection_exists(app: &AppHandle, code: &str) -> Result<bool, StockError> {
-    let conn = get_selection_db_conn(app)?;
-
+pub fn is_selection_exists(conn: &Connection, code: &str) -> Result<bool, StockError> {
     // 查询匹配代码的记录数
     let count: i64 = conn
         .query_row(
@@ -90,8 +78,10 @@ pub fn is_selection_exists(app: &AppHandle, code: &str) -> Result<bool, StockErr
 }
 
 /// 批量插入/更新自选股（存在则更新，不存在则插入）
-pub fn add_or_update_selection(app: &AppHandle, selection: &Selection) -> Result<(), StockError> {
-    let mut conn = get_selection_db_conn(app)?;
+pub fn add_or_update_selection(
+    conn: &mut Connection,
+    selection: &Selection,
+) -> Result<(), StockError> {
     let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
 
     // 关键修复：用代码块包裹 stmt 的创建和执行
@@ -123,10 +113,9 @@ pub fn add_or_update_selection(app: &AppHandle, selection: &Selection) -> Result
 
 /// 更新自选股排序
 pub fn update_selection_sort(
-    app: &AppHandle,
+    conn: &mut Connection,
     params: &UpdateSelectionSortParams,
 ) -> Result<(), StockError> {
-    let mut conn = get_selection_db_conn(app)?;
     let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
 
     // 按新顺序更新 sort（index+1 确保 sort 从 1 开始）
@@ -146,8 +135,7 @@ pub fn update_selection_sort(
 }
 
 /// 删除自选股（返回是否删除成功）
-pub fn delete_selection(app: &AppHandle, code: &str) -> Result<bool, StockError> {
-    let mut conn = get_selection_db_conn(app)?;
+pub fn delete_selection(conn: &mut Connection, code: &str) -> Result<bool, StockError> {
     // 使用事务确保删除和排序调整的原子性
     let tx = conn.transaction().map_err(|e| StockError::DbError(e))?;
 
@@ -186,3 +174,80 @@ pub fn delete_selection(app: &AppHandle, code: &str) -> Result<bool, StockError>
 
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    fn selection(code: &str, sort: i32) -> Selection {
+        Selection {
+            code: code.to_string(),
+            name: format!("股票{}", code),
+            color: "#FF0000".to_string(),
+            remark: String::new(),
+            sort,
+        }
+    }
+
+    #[test]
+    fn add_update_and_query_selection() {
+        let mut conn = open_test_connection();
+        add_or_update_selection(&mut conn, &selection("SH600000", 1)).unwrap();
+        assert!(is_selection_exists(&conn, "SH600000").unwrap());
+
+        let mut updated = selection("SH600000", 1);
+        updated.remark = "关注".to_string();
+        add_or_update_selection(&mut conn, &updated).unwrap();
+
+        let found = get_selection_by_code(&conn, "SH600000").unwrap().unwrap();
+        assert_eq!(found.remark, "关注");
+        assert_eq!(get_all_selections(&conn).unwrap().len(), 1);
+        assert!(get_selection_by_code(&conn, "SZ000001").unwrap().is_none());
+    }
+
+    #[test]
+    fn update_sort_reorders_selections() {
+        let mut conn = open_test_connection();
+        for (i, code) in ["SH600000", "SZ000001", "SZ300750"].iter().enumerate() {
+            add_or_update_selection(&mut conn, &selection(code, i as i32 + 1)).unwrap();
+        }
+
+        update_selection_sort(
+            &mut conn,
+            &UpdateSelectionSortParams {
+                new_order: vec![
+                    "SZ300750".to_string(),
+                    "SH600000".to_string(),
+                    "SZ000001".to_string(),
+                ],
+            },
+        )
+        .unwrap();
+
+        let codes: Vec<String> = get_all_selections(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.code)
+            .collect();
+        assert_eq!(codes, vec!["SZ300750", "SH600000", "SZ000001"]);
+    }
+
+    #[test]
+    fn delete_selection_closes_sort_gap() {
+        let mut conn = open_test_connection();
+        for (i, code) in ["SH600000", "SZ000001", "SZ300750"].iter().enumerate() {
+            add_or_update_selection(&mut conn, &selection(code, i as i32 + 1)).unwrap();
+        }
+
+        assert!(delete_selection(&mut conn, "SZ000001").unwrap());
+        assert!(!delete_selection(&mut conn, "SZ000001").unwrap());
+
+        let sorts: Vec<i32> = get_all_selections(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.sort)
+            .collect();
+        assert_eq!(sorts, vec![1, 2]);
+    }
+}
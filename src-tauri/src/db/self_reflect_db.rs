@@ -1,21 +1,11 @@
-use crate::db::common::init_database;
 use crate::structs::self_reflect_structs::{
     AddReflectReq, GetOrDeleteReflectReq, ReflectItem, ReflectListItem,
 };
 use crate::structs::StockError;
 use rusqlite::{params, Connection, OptionalExtension};
-use tauri::AppHandle;
-
-/// 获取 self_reflect 数据库连接（沿用项目风格）
-pub fn get_self_reflect_db_conn(app: &AppHandle) -> Result<Connection, StockError> {
-    init_database(app, "self_reflect")
-        .map_err(|e| StockError::BusinessError(format!("获取股票评论数据库连接失败: {}", e)))
-}
 
 /// 1. 获取反省列表（对应Python的get_self_reflect）
-pub fn get_self_reflect_list(app: &AppHandle) -> Result<Vec<ReflectListItem>, StockError> {
-    let conn = get_self_reflect_db_conn(app)?;
-
+pub fn get_self_reflect_list(conn: &Connection) -> Result<Vec<ReflectListItem>, StockError> {
     // 构造SQL：按类型筛选 + 标题模糊搜索（不区分大小写）
     let mut stmt = conn
         .prepare(
@@ -42,11 +32,9 @@ pub fn get_self_reflect_list(app: &AppHandle) -> Result<Vec<ReflectListItem>, St
 
 /// 2. 新增评论（对应Python的add_self_reflect）
 pub fn add_or_edit_self_reflect(
-    app: &AppHandle,
+    conn: &Connection,
     req: &AddReflectReq,
 ) -> Result<ReflectItem, StockError> {
-    // 1. 获取数据库连接（复用你原有的 get_self_reflect_db_conn 方法，不新增）
-    let conn = get_self_reflect_db_conn(app)?;
     if req.title.is_empty() {
         return Err(StockError::BusinessError(
             "评论标题（title）不能为空".to_string(),
@@ -150,11 +138,9 @@ pub fn add_or_edit_self_reflect(
 /// 3. 获取单条评论（对应Python的get_single_self_reflect）
 /// 按类型+ID精准查询
 pub fn get_single_self_reflect(
-    app: &AppHandle,
+    conn: &Connection,
     req: &GetOrDeleteReflectReq,
 ) -> Result<Option<ReflectItem>, StockError> {
-    let conn = get_self_reflect_db_conn(app)?;
-
     // 执行精准查询（type + id 唯一确定一条记录）
     let mut stmt = conn
         .prepare(
@@ -184,11 +170,9 @@ pub fn get_single_self_reflect(
 /// 4. 删除评论（对应Python的delete_self_reflect）
 /// 按类型+ID精准删除
 pub fn delete_self_reflect(
-    app: &AppHandle,
+    conn: &Connection,
     req: &GetOrDeleteReflectReq,
 ) -> Result<bool, StockError> {
-    let conn = get_self_reflect_db_conn(app)?;
-
     // 执行删除
     let affected_rows = conn
         .execute(
@@ -201,3 +185,49 @@ pub fn delete_self_reflect(
     // 影响行数>0表示删除成功
     Ok(affected_rows > 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    fn reflect(id: Option<i32>, title: &str, date: &str) -> AddReflectReq {
+        AddReflectReq {
+            id,
+            code: "sz000001".to_string(),
+            title: title.to_string(),
+            date: date.to_string(),
+            description: "反省内容".to_string(),
+        }
+    }
+
+    #[test]
+    fn add_edit_and_list_reflects() {
+        let conn = open_test_connection();
+        let first = add_or_edit_self_reflect(&conn, &reflect(None, "追高", "2024-01-02")).unwrap();
+        add_or_edit_self_reflect(&conn, &reflect(None, "不止损", "2024-01-05")).unwrap();
+        add_or_edit_self_reflect(&conn, &reflect(Some(first.id), "追高被套", "2024-01-02"))
+            .unwrap();
+
+        let list = get_self_reflect_list(&conn).unwrap();
+        let titles: Vec<&str> = list.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["不止损", "追高被套"]);
+
+        let stored = get_single_self_reflect(&conn, &GetOrDeleteReflectReq { id: first.id })
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.code, "SZ000001");
+    }
+
+    #[test]
+    fn validation_and_delete() {
+        let conn = open_test_connection();
+        let result = add_or_edit_self_reflect(&conn, &reflect(None, "", "2024-01-02"));
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+
+        let added = add_or_edit_self_reflect(&conn, &reflect(None, "追高", "2024-01-02")).unwrap();
+        let req = GetOrDeleteReflectReq { id: added.id };
+        assert!(delete_self_reflect(&conn, &req).unwrap());
+        assert!(get_single_self_reflect(&conn, &req).unwrap().is_none());
+    }
+}
@@ -1,25 +1,15 @@
-use crate::db::common::init_database;
 use crate::structs::stock_review_structs::{
     AddReviewReq, GetOrDeleteReviewReq, GetReviewListReq, StockReview, StockReviewListItem,
 };
 use crate::structs::StockError;
 use rusqlite::{params, Connection, OptionalExtension};
-use tauri::AppHandle;
-
-/// 获取 stock_review 数据库连接（沿用项目风格）
-pub fn get_stock_review_db_conn(app: &AppHandle) -> Result<Connection, StockError> {
-    init_database(app, "stock_review")
-        .map_err(|e| StockError::BusinessError(format!("获取股票评论数据库连接失败: {}", e)))
-}
 
 /// 1. 获取评论列表（对应Python的get_stock_review）
 /// 支持按类型筛选、标题关键字模糊搜索（不区分大小写）
 pub fn get_stock_review_list(
-    app: &AppHandle,
+    conn: &Connection,
     req: &GetReviewListReq,
 ) -> Result<Vec<StockReviewListItem>, StockError> {
-    let conn = get_stock_review_db_conn(app)?;
-
     // 处理关键字：转为小写，构造模糊搜索条件（%keyword%）
     let keyword = req
         .keyword
@@ -55,12 +45,9 @@ pub fn get_stock_review_list(
 
 /// 2. 新增评论（对应Python的add_stock_review）
 pub fn add_or_edit_stock_review(
-    app: &AppHandle,
+    conn: &Connection,
     req: &AddReviewReq,
 ) -> Result<StockReview, StockError> {
-    // 1. 获取数据库连接（复用你原有的 get_stock_review_db_conn 方法，不新增）
-    let conn = get_stock_review_db_conn(app)?;
-
     // 2. 保留原有的参数校验逻辑（只加不减）
     if req.r#type.is_empty() {
         return Err(StockError::BusinessError(
@@ -174,11 +161,9 @@ pub fn add_or_edit_stock_review(
 /// 3. 获取单条评论（对应Python的get_single_stock_review）
 /// 按类型+ID精准查询
 pub fn get_single_stock_review(
-    app: &AppHandle,
+    conn: &Connection,
     req: &GetOrDeleteReviewReq,
 ) -> Result<Option<StockReview>, StockError> {
-    let conn = get_stock_review_db_conn(app)?;
-
     // 执行精准查询（type + id 唯一确定一条记录）
     let mut stmt = conn
         .prepare(
@@ -209,11 +194,9 @@ pub fn get_single_stock_review(
 /// 4. 删除评论（对应Python的delete_stock_review）
 /// 按类型+ID精准删除
 pub fn delete_stock_review(
-    app: &AppHandle,
+    conn: &Connection,
     req: &GetOrDeleteReviewReq,
 ) -> Result<bool, StockError> {
-    let conn = get_stock_review_db_conn(app)?;
-
     // 执行删除
     let affected_rows = conn
         .execute(
@@ -226,3 +209,90 @@ pub fn delete_stock_review(
     // 影响行数>0表示删除成功
     Ok(affected_rows > 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    fn review(id: Option<i32>, r#type: &str, title: &str, date: &str) -> AddReviewReq {
+        AddReviewReq {
+            id,
+            r#type: r#type.to_string(),
+            code: "sh600000".to_string(),
+            title: title.to_string(),
+            date: date.to_string(),
+            description: "复盘内容".to_string(),
+        }
+    }
+
+    #[test]
+    fn add_then_edit_review() {
+        let conn = open_test_connection();
+        let added =
+            add_or_edit_stock_review(&conn, &review(None, "1", "突破", "2024-01-02")).unwrap();
+        assert_eq!(added.code, "SH600000");
+
+        let edited =
+            add_or_edit_stock_review(&conn, &review(Some(added.id), "1", "假突破", "2024-01-02"))
+                .unwrap();
+        assert_eq!(edited.id, added.id);
+
+        let stored = get_single_stock_review(&conn, &GetOrDeleteReviewReq { id: added.id })
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.title, "假突破");
+    }
+
+    #[test]
+    fn edit_missing_review_fails() {
+        let conn = open_test_connection();
+        let result = add_or_edit_stock_review(&conn, &review(Some(42), "1", "标题", "2024-01-02"));
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+
+        let result = add_or_edit_stock_review(&conn, &review(None, "", "标题", "2024-01-02"));
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+    }
+
+    #[test]
+    fn list_filters_by_type_and_keyword() {
+        let conn = open_test_connection();
+        add_or_edit_stock_review(&conn, &review(None, "1", "MACD 金叉", "2024-01-02")).unwrap();
+        add_or_edit_stock_review(&conn, &review(None, "1", "放量突破", "2024-01-03")).unwrap();
+        add_or_edit_stock_review(&conn, &review(None, "2", "macd 背离", "2024-01-04")).unwrap();
+
+        let all = get_stock_review_list(
+            &conn,
+            &GetReviewListReq {
+                r#type: "1".to_string(),
+                keyword: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].title, "放量突破");
+
+        let filtered = get_stock_review_list(
+            &conn,
+            &GetReviewListReq {
+                r#type: "1".to_string(),
+                keyword: Some("macd".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].title, "MACD 金叉");
+    }
+
+    #[test]
+    fn delete_review() {
+        let conn = open_test_connection();
+        let added =
+            add_or_edit_stock_review(&conn, &review(None, "1", "突破", "2024-01-02")).unwrap();
+        let req = GetOrDeleteReviewReq { id: added.id };
+
+        assert!(delete_stock_review(&conn, &req).unwrap());
+        assert!(!delete_stock_review(&conn, &req).unwrap());
+        assert!(get_single_stock_review(&conn, &req).unwrap().is_none());
+    }
+}
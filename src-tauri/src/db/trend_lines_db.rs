@@ -1,89 +1,478 @@
-use crate::db::common::init_database;
-use crate::structs::trend_lines_structs::{AddTrendLineReq, DeleteTrendLineReq, TrendLine};
+use crate::db::drawings_db::{
+    add_drawing, alertable_kinds_sql, delete_drawing, query_drawings, row_to_drawing,
+    update_drawing_points, DRAWING_COLUMNS,
+};
+use crate::db::kline_cache_db::query_cached_bar_time;
+use crate::requests::kline_cache::period_duration_ms;
+use crate::structs::drawings_structs::{DrawingKind, DrawingParams, DrawingPoint};
+use crate::structs::trend_lines_structs::{
+    AddTrendLineReq, DeleteTrendLineReq, TrendLine, TrendLineAlertMode, TrendLineAlertReq,
+    TrendLineBreak, UpdateTrendLineReq,
+};
 use crate::structs::StockError;
 use rusqlite::{params, Connection};
-use tauri::AppHandle;
 
-/// 获取 trend_lines 数据库连接
-pub fn get_trend_lines_db_conn(app: &AppHandle) -> Result<Connection, StockError> {
-    init_database(app, "trend_lines")
-        .map_err(|e| StockError::BusinessError(format!("获取趋势线数据库连接失败: {}", e)))
-}
+// 趋势线保存在通用画线表 drawings 中（类型为 trend_line）
 
 /// 1. 新增趋势线（斜线）
-pub fn add_trend_line(app: &AppHandle, req: &AddTrendLineReq) -> Result<i32, StockError> {
-    let conn = get_trend_lines_db_conn(app)?;
-
-    let mut stmt = conn
-        .prepare(
-            "INSERT INTO trend_lines 
-             (code, period, start_time, start_price, end_time, end_price)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-             RETURNING id",
-        )
-        .map_err(|e| StockError::DbError(e))?;
-
-    let new_id: i32 = stmt
-        .query_row(
-            params![
-                req.code.to_uppercase(),
-                req.period,
-                req.start_time,
-                req.start_price,
-                req.end_time,
-                req.end_price,
+pub fn add_trend_line(conn: &Connection, req: &AddTrendLineReq) -> Result<i32, StockError> {
+    add_drawing(
+        conn,
+        &DrawingParams {
+            code: req.code.clone(),
+            period: req.period.clone(),
+            kind: DrawingKind::TrendLine.as_str().to_string(),
+            points: vec![
+                DrawingPoint {
+                    time: req.start_time,
+                    price: req.start_price,
+                },
+                DrawingPoint {
+                    time: req.end_time,
+                    price: req.end_price,
+                },
             ],
-            |row| row.get(0),
-        )
-        .map_err(|e| StockError::DbError(e))?;
-
-    Ok(new_id)
+            style: serde_json::Value::Null,
+            label: String::new(),
+        },
+    )
 }
 
 /// 2. 查询趋势线（按 code + period）
 pub fn query_trend_lines(
-    app: &AppHandle,
+    conn: &Connection,
+    code: &str,
+    period: &str,
+) -> Result<Vec<TrendLine>, StockError> {
+    let drawings = query_drawings(conn, code, Some(period), Some(DrawingKind::TrendLine))?;
+    Ok(drawings
+        .iter()
+        .filter_map(|drawing| drawing.to_trend_line())
+        .collect())
+}
+
+/// 2.1 查询某只股票全部周期的趋势线并投影到 period
+/// 在其他周期画的趋势线，起止点移到 period 中对应的缓存K线（没有缓存时保留原时间），价格按原趋势线计算；
+/// 返回的 period 仍为画线时的周期，前端据此区分投影的趋势线
+pub fn query_projected_trend_lines(
+    conn: &Connection,
     code: &str,
     period: &str,
 ) -> Result<Vec<TrendLine>, StockError> {
-    let conn = get_trend_lines_db_conn(app)?;
+    let drawings = query_drawings(conn, code, None, Some(DrawingKind::TrendLine))?;
+    let mut lines = Vec::new();
+    for line in drawings
+        .iter()
+        .filter_map(|drawing| drawing.to_trend_line())
+    {
+        if line.period == period {
+            lines.push(line);
+            continue;
+        }
+        let start_time = snap_to_period(conn, &line, period, line.start_time)?;
+        let end_time = snap_to_period(conn, &line, period, line.end_time)?;
+        // 起止点落在同一根K线时保留原时间
+        lines.push(
+            line.reanchored(start_time, end_time)
+                .unwrap_or_else(|| line.clone()),
+        );
+    }
+    Ok(lines)
+}
+
+// time 在目标周期中对应的K线时间：目标周期更长时取包含 time 的K线，
+// 更短时取 time 之后、原周期这根K线之内的第一根；找不到缓存K线时返回原时间
+fn snap_to_period(
+    conn: &Connection,
+    line: &TrendLine,
+    period: &str,
+    time: i64,
+) -> Result<i64, StockError> {
+    let (Some(source), Some(target)) =
+        (period_duration_ms(&line.period), period_duration_ms(period))
+    else {
+        return Ok(time);
+    };
+    // 月、季等周期的时长是估算值，放宽一倍
+    let snapped = if target >= source {
+        query_cached_bar_time(conn, &line.code, period, time, false)?
+            .filter(|bar| *bar > time - 2 * target)
+    } else {
+        query_cached_bar_time(conn, &line.code, period, time, true)?
+            .filter(|bar| *bar < time + source)
+    };
+    Ok(snapped.unwrap_or(time))
+}
+
+/// 3. 删除趋势线（按 ID）
+pub fn delete_trend_line(conn: &Connection, req: &DeleteTrendLineReq) -> Result<bool, StockError> {
+    delete_drawing(conn, req.id)
+}
+
+/// 3.1 修改趋势线的起止点（保留 ID 和提醒设置），趋势线不存在时返回 false
+pub fn update_trend_line(conn: &Connection, req: &UpdateTrendLineReq) -> Result<bool, StockError> {
+    update_drawing_points(
+        conn,
+        req.id,
+        DrawingKind::TrendLine,
+        &[
+            DrawingPoint {
+                time: req.start_time,
+                price: req.start_price,
+            },
+            DrawingPoint {
+                time: req.end_time,
+                price: req.end_price,
+            },
+        ],
+    )
+}
+
+/// 4. 开启/关闭直线类画线（趋势线、水平线、射线、直线）的突破提醒，画线不存在时返回 false
+pub fn set_trend_line_alert(
+    conn: &Connection,
+    req: &TrendLineAlertReq,
+) -> Result<bool, StockError> {
+    let mode = match req.mode.as_deref() {
+        Some(mode) => TrendLineAlertMode::parse(mode)
+            .ok_or_else(|| StockError::BusinessError(format!("不支持的突破提醒方式: {}", mode)))?,
+        None => TrendLineAlertMode::Cross,
+    };
+    let tolerance = req.tolerance.unwrap_or(0.5);
+    if !tolerance.is_finite() || !(0.0..=10.0).contains(&tolerance) {
+        return Err(StockError::BusinessError(format!(
+            "触及容差应在 0 到 10（%）之间: {}",
+            tolerance
+        )));
+    }
+    let affected_rows = conn
+        .execute(
+            &format!(
+                "UPDATE drawings SET alert_enabled = ?1, alert_mode = ?2, tolerance = ?3
+                 WHERE id = ?4 AND kind IN ({})",
+                alertable_kinds_sql()
+            ),
+            params![req.enabled, mode.as_str(), tolerance, req.id],
+        )
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(affected_rows > 0)
+}
 
+/// 5. 查询开启了突破提醒的全部直线类画线
+pub fn query_alert_trend_lines(conn: &Connection) -> Result<Vec<TrendLine>, StockError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM drawings
+             WHERE alert_enabled = 1 AND kind IN ({})
+             ORDER BY code, id",
+            DRAWING_COLUMNS,
+            alertable_kinds_sql()
+        ))
+        .map_err(|e| StockError::DbError(e))?;
+    let drawings = stmt
+        .query_map([], row_to_drawing)
+        .map_err(|e| StockError::DbError(e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StockError::DbError(e))?;
+    Ok(drawings
+        .iter()
+        .filter_map(|drawing| drawing.to_trend_line())
+        .collect())
+}
+
+/// 6. 记录趋势线突破，返回记录ID
+/// 收盘模式下同一趋势线同一根K线只记录一次，重复时返回 None
+pub fn record_trend_line_break(
+    conn: &Connection,
+    record: &TrendLineBreak,
+) -> Result<Option<i32>, StockError> {
+    if let Some(bar_time) = record.bar_time {
+        let existed: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM trend_line_breaks
+                 WHERE line_id = ?1 AND mode = ?2 AND bar_time = ?3",
+                params![record.line_id, record.mode, bar_time],
+                |row| Ok(row.get::<_, i32>(0)? > 0),
+            )
+            .map_err(|e| StockError::DbError(e))?;
+        if existed {
+            return Ok(None);
+        }
+    }
+    conn.execute(
+        "INSERT INTO trend_line_breaks
+         (line_id, code, period, mode, direction, line_price, price, bar_time, message, triggered_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            record.line_id,
+            record.code,
+            record.period,
+            record.mode,
+            record.direction,
+            record.line_price,
+            record.price,
+            record.bar_time,
+            record.message,
+            record.triggered_at
+        ],
+    )
+    .map_err(|e| StockError::DbError(e))?;
+    Ok(Some(conn.last_insert_rowid() as i32))
+}
+
+/// 7. 查询趋势线突破记录（最新的在前），可按股票代码或趋势线过滤
+pub fn query_trend_line_breaks(
+    conn: &Connection,
+    code: Option<&str>,
+    line_id: Option<i32>,
+    limit: i64,
+) -> Result<Vec<TrendLineBreak>, StockError> {
+    let code = code.map(|code| code.trim().to_uppercase());
     let mut stmt = conn
         .prepare(
-            "SELECT 
-                id, code, period, start_time, start_price, end_time, end_price
-             FROM trend_lines 
-             WHERE code = ?1 AND period = ?2
-             ORDER BY id ASC",
+            "SELECT id, line_id, code, period, mode, direction, line_price, price, bar_time,
+                    message, triggered_at
+             FROM trend_line_breaks
+             WHERE (?1 IS NULL OR code = ?1) AND (?2 IS NULL OR line_id = ?2)
+             ORDER BY triggered_at DESC, id DESC
+             LIMIT ?3",
         )
         .map_err(|e| StockError::DbError(e))?;
-
-    let lines = stmt
-        .query_map(params![code.to_uppercase(), period], |row| {
-            Ok(TrendLine {
+    let records = stmt
+        .query_map(params![code, line_id, limit], |row| {
+            Ok(TrendLineBreak {
                 id: row.get(0)?,
-                code: row.get(1)?,
-                period: row.get(2)?,
-                start_time: row.get(3)?,
-                start_price: row.get(4)?,
-                end_time: row.get(5)?,
-                end_price: row.get(6)?,
+                line_id: row.get(1)?,
+                code: row.get(2)?,
+                period: row.get(3)?,
+                mode: row.get(4)?,
+                direction: row.get(5)?,
+                line_price: row.get(6)?,
+                price: row.get(7)?,
+                bar_time: row.get(8)?,
+                message: row.get(9)?,
+                triggered_at: row.get(10)?,
             })
         })
         .map_err(|e| StockError::DbError(e))?
-        .collect::<Result<Vec<TrendLine>, _>>()
+        .collect::<Result<Vec<_>, _>>()
         .map_err(|e| StockError::DbError(e))?;
+    Ok(records)
+}
 
-    Ok(lines)
+/// 迁移到画线表时因时间或价格无效而未转换、保留在 trend_lines_legacy 中的趋势线条数
+pub fn count_legacy_trend_lines(conn: &Connection) -> Result<i64, StockError> {
+    conn.query_row("SELECT COUNT(*) FROM trend_lines_legacy", [], |row| {
+        row.get(0)
+    })
+    .map_err(|e| StockError::DbError(e))
 }
 
-/// 3. 删除趋势线（按 ID）
-pub fn delete_trend_line(app: &AppHandle, req: &DeleteTrendLineReq) -> Result<bool, StockError> {
-    let conn = get_trend_lines_db_conn(app)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+    use crate::db::kline_cache_db::save_klines;
+    use crate::structs::xueqiu_structs::StockKlineItem;
 
-    let affected_rows = conn
-        .execute("DELETE FROM trend_lines WHERE id = ?1", params![req.id])
-        .map_err(|e| StockError::DbError(e))?;
+    fn line(code: &str, period: &str) -> AddTrendLineReq {
+        AddTrendLineReq {
+            code: code.to_string(),
+            period: period.to_string(),
+            start_time: 1_704_153_600_000,
+            start_price: 10.0,
+            end_time: 1_704_758_400_000,
+            end_price: 11.0,
+        }
+    }
 
-    Ok(affected_rows > 0)
+    #[test]
+    fn add_query_and_delete_trend_lines() {
+        let conn = open_test_connection();
+        let id = add_trend_line(&conn, &line("sh600000", "day")).unwrap();
+        add_trend_line(&conn, &line("SH600000", "week")).unwrap();
+
+        let lines = query_trend_lines(&conn, "sh600000", "day").unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].id, id);
+        assert_eq!(lines[0].code, "SH600000");
+
+        assert!(delete_trend_line(&conn, &DeleteTrendLineReq { id }).unwrap());
+        assert!(query_trend_lines(&conn, "SH600000", "day")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn update_keeps_id_and_alert_settings() {
+        let conn = open_test_connection();
+        let id = add_trend_line(&conn, &line("SH600000", "day")).unwrap();
+        let alert = TrendLineAlertReq {
+            id,
+            enabled: true,
+            mode: None,
+            tolerance: None,
+        };
+        set_trend_line_alert(&conn, &alert).unwrap();
+
+        let moved = UpdateTrendLineReq {
+            id,
+            start_time: 1_704_240_000_000,
+            start_price: 10.2,
+            end_time: 1_704_844_800_000,
+            end_price: 11.4,
+        };
+        assert!(update_trend_line(&conn, &moved).unwrap());
+        assert!(!update_trend_line(
+            &conn,
+            &UpdateTrendLineReq {
+                id: 999,
+                ..moved.clone()
+            }
+        )
+        .unwrap());
+        assert!(update_trend_line(
+            &conn,
+            &UpdateTrendLineReq {
+                end_price: 0.0,
+                ..moved
+            }
+        )
+        .is_err());
+
+        let lines = query_trend_lines(&conn, "SH600000", "day").unwrap();
+        assert_eq!((lines[0].id, lines[0].end_price), (id, 11.4));
+        assert!(lines[0].alert_enabled);
+    }
+
+    #[test]
+    fn lines_are_projected_onto_cached_bars_of_other_periods() {
+        const DAY: i64 = 86_400_000;
+        const HOUR: i64 = 3_600_000;
+        let mut conn = open_test_connection();
+        // 日线趋势线：第 10 天 10 元到第 20 天 11 元
+        let daily = AddTrendLineReq {
+            code: "SH600000".to_string(),
+            period: "day".to_string(),
+            start_time: 10 * DAY,
+            start_price: 10.0,
+            end_time: 20 * DAY,
+            end_price: 11.0,
+        };
+        add_trend_line(&conn, &daily).unwrap();
+        let bar = |date: i64| StockKlineItem {
+            date,
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            volume: 0.0,
+            percent: 0.0,
+            turnoverrate: 0.0,
+        };
+        // 周线：第 7、14 天；60 分钟：第 10、20 天的 10:30 起
+        save_klines(
+            &mut conn,
+            "SH600000",
+            "week",
+            &[bar(7 * DAY), bar(14 * DAY)],
+        )
+        .unwrap();
+        let hourly = [
+            10 * DAY + HOUR * 2,
+            10 * DAY + HOUR * 3,
+            20 * DAY + HOUR * 2,
+        ];
+        save_klines(&mut conn, "SH600000", "60m", &hourly.map(bar)).unwrap();
+
+        let weekly = query_projected_trend_lines(&conn, "SH600000", "week").unwrap();
+        assert_eq!(
+            (weekly[0].start_time, weekly[0].end_time),
+            (7 * DAY, 14 * DAY)
+        );
+        assert!((weekly[0].start_price - 9.7).abs() < 1e-9);
+        assert_eq!(weekly[0].period, "day");
+
+        let hourly = query_projected_trend_lines(&conn, "SH600000", "60m").unwrap();
+        assert_eq!(hourly[0].start_time, 10 * DAY + HOUR * 2);
+        assert_eq!(hourly[0].end_time, 20 * DAY + HOUR * 2);
+        assert!(hourly[0].start_price > 10.0);
+
+        // 没有缓存的周期保留原起止点
+        let monthly = query_projected_trend_lines(&conn, "SH600000", "month").unwrap();
+        assert_eq!(
+            (monthly[0].start_time, monthly[0].start_price),
+            (10 * DAY, 10.0)
+        );
+        // 不投影时只返回本周期画的趋势线
+        assert!(query_trend_lines(&conn, "SH600000", "week")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn alert_settings_and_break_records() {
+        let conn = open_test_connection();
+        let id = add_trend_line(&conn, &line("SH600000", "day")).unwrap();
+        assert!(query_alert_trend_lines(&conn).unwrap().is_empty());
+
+        let req = TrendLineAlertReq {
+            id,
+            enabled: true,
+            mode: Some("close".to_string()),
+            tolerance: None,
+        };
+        assert!(set_trend_line_alert(&conn, &req).unwrap());
+        let invalid = TrendLineAlertReq {
+            mode: Some("bounce".to_string()),
+            ..req.clone()
+        };
+        assert!(set_trend_line_alert(&conn, &invalid).is_err());
+        // 文字标注不支持突破提醒
+        let text = add_drawing(
+            &conn,
+            &DrawingParams {
+                code: "SH600000".to_string(),
+                period: "day".to_string(),
+                kind: "text".to_string(),
+                points: vec![DrawingPoint {
+                    time: 1_704_153_600_000,
+                    price: 10.0,
+                }],
+                style: serde_json::Value::Null,
+                label: "注释".to_string(),
+            },
+        )
+        .unwrap();
+        let text_alert = TrendLineAlertReq {
+            id: text,
+            ..req.clone()
+        };
+        assert!(!set_trend_line_alert(&conn, &text_alert).unwrap());
+        let lines = query_alert_trend_lines(&conn).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(
+            (lines[0].alert_mode.as_str(), lines[0].tolerance),
+            ("close", 0.5)
+        );
+
+        let record = TrendLineBreak {
+            id: 0,
+            line_id: id,
+            code: "SH600000".to_string(),
+            period: "day".to_string(),
+            mode: "close".to_string(),
+            direction: "down".to_string(),
+            line_price: 11.2,
+            price: 11.0,
+            bar_time: Some(1_705_000_000_000),
+            message: "跌破".to_string(),
+            triggered_at: "2024-01-12 15:00:00".to_string(),
+        };
+        assert!(record_trend_line_break(&conn, &record).unwrap().is_some());
+        // 同一根K线不重复记录
+        assert!(record_trend_line_break(&conn, &record).unwrap().is_none());
+        let breaks = query_trend_line_breaks(&conn, Some("sh600000"), Some(id), 10).unwrap();
+        assert_eq!(breaks.len(), 1);
+    }
 }
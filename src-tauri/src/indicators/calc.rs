@@ -0,0 +1,318 @@
+//! 技术指标的基础算法，输入按时间升序排列，输出与输入等长
+//! 数据不足以计算的位置为 None
+
+/// 简单移动平均 MA(n)
+pub fn sma(values: &[f64], n: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if n == 0 {
+        return out;
+    }
+    let mut sum = 0.0;
+    for i in 0..values.len() {
+        sum += values[i];
+        if i >= n {
+            sum -= values[i - n];
+        }
+        if i + 1 >= n {
+            out[i] = Some(sum / n as f64);
+        }
+    }
+    out
+}
+
+/// 指数移动平均 EMA(n)，以第一根的值作为初始值（与通达信一致）
+pub fn ema(values: &[f64], n: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if n == 0 || values.is_empty() {
+        return out;
+    }
+    let alpha = 2.0 / (n as f64 + 1.0);
+    let mut prev = values[0];
+    out[0] = Some(prev);
+    for i in 1..values.len() {
+        prev = alpha * values[i] + (1.0 - alpha) * prev;
+        out[i] = Some(prev);
+    }
+    out
+}
+
+/// MACD(fast, slow, signal)：返回 (DIF, DEA, MACD柱)，柱值按 A 股习惯为 2*(DIF-DEA)
+pub fn macd(
+    closes: &[f64],
+    fast: usize,
+    slow: usize,
+    signal: usize,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let fast_ema = ema(closes, fast);
+    let slow_ema = ema(closes, slow);
+    let dif: Vec<f64> = fast_ema
+        .iter()
+        .zip(slow_ema.iter())
+        .map(|(f, s)| f.unwrap_or(0.0) - s.unwrap_or(0.0))
+        .collect();
+    let dea = ema(&dif, signal);
+    let hist = dif
+        .iter()
+        .zip(dea.iter())
+        .map(|(d, e)| e.map(|e| 2.0 * (d - e)))
+        .collect();
+    (dif.into_iter().map(Some).collect(), dea, hist)
+}
+
+/// KDJ(n, m1, m2)：RSV 取 n 日高低点，K、D 为 1/m 权重的平滑，初始值 50
+pub fn kdj(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    n: usize,
+    m1: usize,
+    m2: usize,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let len = closes.len();
+    let mut k_out = vec![None; len];
+    let mut d_out = vec![None; len];
+    let mut j_out = vec![None; len];
+    if n == 0 || m1 == 0 || m2 == 0 {
+        return (k_out, d_out, j_out);
+    }
+
+    let (mut k, mut d) = (50.0, 50.0);
+    for i in (n - 1)..len {
+        let window = (i + 1 - n)..=i;
+        let hhv = highs[window.clone()]
+            .iter()
+            .cloned()
+            .fold(f64::MIN, f64::max);
+        let llv = lows[window].iter().cloned().fold(f64::MAX, f64::min);
+        let rsv = if hhv > llv {
+            (closes[i] - llv) / (hhv - llv) * 100.0
+        } else {
+            50.0
+        };
+        k = (rsv + (m1 as f64 - 1.0) * k) / m1 as f64;
+        d = (k + (m2 as f64 - 1.0) * d) / m2 as f64;
+        k_out[i] = Some(k);
+        d_out[i] = Some(d);
+        j_out[i] = Some(3.0 * k - 2.0 * d);
+    }
+    (k_out, d_out, j_out)
+}
+
+/// RSI(n)：Wilder 平滑，前 n 个涨跌幅取简单平均作为初始值
+pub fn rsi(closes: &[f64], n: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; closes.len()];
+    if n == 0 || closes.len() <= n {
+        return out;
+    }
+
+    let (mut avg_gain, mut avg_loss) = (0.0, 0.0);
+    for i in 1..=n {
+        let change = closes[i] - closes[i - 1];
+        avg_gain += change.max(0.0);
+        avg_loss += (-change).max(0.0);
+    }
+    avg_gain /= n as f64;
+    avg_loss /= n as f64;
+    out[n] = Some(rsi_value(avg_gain, avg_loss));
+
+    for i in (n + 1)..closes.len() {
+        let change = closes[i] - closes[i - 1];
+        avg_gain = (avg_gain * (n as f64 - 1.0) + change.max(0.0)) / n as f64;
+        avg_loss = (avg_loss * (n as f64 - 1.0) + (-change).max(0.0)) / n as f64;
+        out[i] = Some(rsi_value(avg_gain, avg_loss));
+    }
+    out
+}
+
+fn rsi_value(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_gain + avg_loss == 0.0 {
+        50.0
+    } else {
+        avg_gain / (avg_gain + avg_loss) * 100.0
+    }
+}
+
+/// 布林带 BOLL(n, k)：返回 (中轨, 上轨, 下轨)，标准差为总体标准差
+pub fn boll(
+    closes: &[f64],
+    n: usize,
+    k: f64,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let mid = sma(closes, n);
+    let mut upper = vec![None; closes.len()];
+    let mut lower = vec![None; closes.len()];
+    for i in 0..closes.len() {
+        if let Some(m) = mid[i] {
+            let window = &closes[(i + 1 - n)..=i];
+            let variance = window.iter().map(|c| (c - m).powi(2)).sum::<f64>() / n as f64;
+            let std = variance.sqrt();
+            upper[i] = Some(m + k * std);
+            lower[i] = Some(m - k * std);
+        }
+    }
+    (mid, upper, lower)
+}
+
+/// ATR(n)：真实波幅的 Wilder 平滑，第 n 根起有值
+pub fn atr(highs: &[f64], lows: &[f64], closes: &[f64], n: usize) -> Vec<Option<f64>> {
+    let len = closes.len();
+    let mut out = vec![None; len];
+    if n == 0 || len < n {
+        return out;
+    }
+
+    let tr: Vec<f64> = (0..len)
+        .map(|i| {
+            let range = highs[i] - lows[i];
+            if i == 0 {
+                range
+            } else {
+                range
+                    .max((highs[i] - closes[i - 1]).abs())
+                    .max((lows[i] - closes[i - 1]).abs())
+            }
+        })
+        .collect();
+
+    let mut prev = tr[..n].iter().sum::<f64>() / n as f64;
+    out[n - 1] = Some(prev);
+    for i in n..len {
+        prev = (prev * (n as f64 - 1.0) + tr[i]) / n as f64;
+        out[i] = Some(prev);
+    }
+    out
+}
+
+/// OBV：收盘价上涨累加成交量、下跌累减，首根为 0
+pub fn obv(closes: &[f64], volumes: &[f64]) -> Vec<Option<f64>> {
+    let mut out = Vec::with_capacity(closes.len());
+    let mut total = 0.0;
+    for i in 0..closes.len() {
+        if i > 0 {
+            if closes[i] > closes[i - 1] {
+                total += volumes[i];
+            } else if closes[i] < closes[i - 1] {
+                total -= volumes[i];
+            }
+        }
+        out.push(Some(total));
+    }
+    out
+}
+
+/// VWAP：以 (高+低+收)/3 为价格、成交量为权重累计，session 变化时重新累计
+pub fn vwap(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    volumes: &[f64],
+    sessions: &[i64],
+) -> Vec<Option<f64>> {
+    let mut out = Vec::with_capacity(closes.len());
+    let (mut pv, mut vol) = (0.0, 0.0);
+    for i in 0..closes.len() {
+        if i > 0 && sessions[i] != sessions[i - 1] {
+            pv = 0.0;
+            vol = 0.0;
+        }
+        let typical = (highs[i] + lows[i] + closes[i]) / 3.0;
+        pv += typical * volumes[i];
+        vol += volumes[i];
+        out.push(if vol > 0.0 { Some(pv / vol) } else { None });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: Option<f64>, b: f64) -> bool {
+        a.map_or(false, |a| (a - b).abs() < 1e-6)
+    }
+
+    #[test]
+    fn sma_and_ema() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let ma = sma(&values, 3);
+        assert_eq!(ma[1], None);
+        assert!(approx(ma[2], 2.0));
+        assert!(approx(ma[4], 4.0));
+
+        let e = ema(&values, 3);
+        assert!(approx(e[0], 1.0));
+        assert!(approx(e[1], 1.5));
+        assert!(approx(e[2], 2.25));
+    }
+
+    #[test]
+    fn macd_of_flat_series_is_zero() {
+        let closes = [10.0; 40];
+        let (dif, dea, hist) = macd(&closes, 12, 26, 9);
+        assert!(approx(dif[39], 0.0));
+        assert!(approx(dea[39], 0.0));
+        assert!(approx(hist[39], 0.0));
+    }
+
+    #[test]
+    fn rsi_bounds() {
+        let rising: Vec<f64> = (1..=20).map(|v| v as f64).collect();
+        let r = rsi(&rising, 14);
+        assert_eq!(r[13], None);
+        assert!(approx(r[14], 100.0));
+
+        let mixed = [10.0, 11.0, 10.0, 11.0, 10.0];
+        assert!(approx(rsi(&mixed, 2)[2], 50.0));
+    }
+
+    #[test]
+    fn kdj_at_window_high() {
+        let highs = [10.0, 11.0, 12.0];
+        let lows = [9.0, 10.0, 11.0];
+        let closes = [10.0, 11.0, 12.0];
+        let (k, d, j) = kdj(&highs, &lows, &closes, 3, 3, 3);
+        assert_eq!(k[1], None);
+        // RSV = 100，K = (100 + 2*50)/3，D = (K + 2*50)/3
+        let k2 = 200.0 / 3.0;
+        let d2 = (k2 + 100.0) / 3.0;
+        assert!(approx(k[2], k2));
+        assert!(approx(d[2], d2));
+        assert!(approx(j[2], 3.0 * k2 - 2.0 * d2));
+    }
+
+    #[test]
+    fn boll_bands_are_symmetric() {
+        let closes = [1.0, 2.0, 3.0];
+        let (mid, upper, lower) = boll(&closes, 3, 2.0);
+        let std = (2.0f64 / 3.0).sqrt();
+        assert!(approx(mid[2], 2.0));
+        assert!(approx(upper[2], 2.0 + 2.0 * std));
+        assert!(approx(lower[2], 2.0 - 2.0 * std));
+    }
+
+    #[test]
+    fn atr_obv_vwap() {
+        let highs = [11.0, 12.0, 13.0];
+        let lows = [9.0, 10.0, 11.0];
+        let closes = [10.0, 11.0, 10.0];
+        let volumes = [100.0, 200.0, 300.0];
+
+        let a = atr(&highs, &lows, &closes, 2);
+        assert_eq!(a[0], None);
+        assert!(approx(a[1], 2.0));
+        // 第三根 TR = max(2, |13-11|, |11-11|) = 2
+        assert!(approx(a[2], 2.0));
+
+        let o = obv(&closes, &volumes);
+        assert!(approx(o[1], 200.0));
+        assert!(approx(o[2], -100.0));
+
+        let v = vwap(&highs, &lows, &closes, &volumes, &[1, 1, 2]);
+        let tp0 = 10.0;
+        let tp1 = 11.0;
+        assert!(approx(v[0], tp0));
+        assert!(approx(v[1], (tp0 * 100.0 + tp1 * 200.0) / 300.0));
+        // 新的交易日重新累计
+        assert!(approx(v[2], 34.0 / 3.0));
+    }
+}
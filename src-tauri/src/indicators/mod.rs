@@ -0,0 +1,286 @@
+pub mod calc;
+
+use crate::structs::indicator_structs::{IndicatorResult, IndicatorSpec};
+use crate::structs::xueqiu_structs::StockKlineItem;
+use std::collections::BTreeMap;
+
+// 预热K线数量上限，避免参数过大时一次请求过多数据
+const MAX_WARMUP: usize = 500;
+// 周期类参数上限，避免计算预热数量时溢出
+const MAX_PERIOD: f64 = 1000.0;
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// 解析后的技术指标
+#[derive(Debug, Clone, PartialEq)]
+pub enum Indicator {
+    Ma(usize),
+    Ema(usize),
+    Macd(usize, usize, usize),
+    Kdj(usize, usize, usize),
+    Rsi(usize),
+    Boll(usize, f64),
+    Atr(usize),
+    Obv,
+    Vwap,
+}
+
+// 读取第 i 个周期类参数（1 ~ 1000 的整数），缺省时使用默认值
+fn period_param(spec: &IndicatorSpec, i: usize, default: usize) -> Result<usize, String> {
+    match spec.params.get(i) {
+        None => Ok(default),
+        Some(&v) if (1.0..=MAX_PERIOD).contains(&v) && v.fract() == 0.0 => Ok(v as usize),
+        Some(v) => Err(format!("指标 {} 的参数无效: {}", spec.name, v)),
+    }
+}
+
+impl Indicator {
+    /// 从请求参数解析指标，未知指标或参数无效时返回错误
+    pub fn parse(spec: &IndicatorSpec) -> Result<Self, String> {
+        let indicator = match spec.name.trim().to_lowercase().as_str() {
+            "ma" => Indicator::Ma(period_param(spec, 0, 5)?),
+            "ema" => Indicator::Ema(period_param(spec, 0, 12)?),
+            "macd" => Indicator::Macd(
+                period_param(spec, 0, 12)?,
+                period_param(spec, 1, 26)?,
+                period_param(spec, 2, 9)?,
+            ),
+            "kdj" => Indicator::Kdj(
+                period_param(spec, 0, 9)?,
+                period_param(spec, 1, 3)?,
+                period_param(spec, 2, 3)?,
+            ),
+            "rsi" => Indicator::Rsi(period_param(spec, 0, 14)?),
+            "boll" => {
+                let k = spec.params.get(1).copied().unwrap_or(2.0);
+                if k.is_nan() || k <= 0.0 {
+                    return Err(format!("指标 {} 的参数无效: {}", spec.name, k));
+                }
+                Indicator::Boll(period_param(spec, 0, 20)?, k)
+            }
+            "atr" => Indicator::Atr(period_param(spec, 0, 14)?),
+            "obv" => Indicator::Obv,
+            "vwap" => Indicator::Vwap,
+            _ => return Err(format!("不支持的技术指标: {}", spec.name)),
+        };
+        Ok(indicator)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Indicator::Ma(_) => "ma",
+            Indicator::Ema(_) => "ema",
+            Indicator::Macd(..) => "macd",
+            Indicator::Kdj(..) => "kdj",
+            Indicator::Rsi(_) => "rsi",
+            Indicator::Boll(..) => "boll",
+            Indicator::Atr(_) => "atr",
+            Indicator::Obv => "obv",
+            Indicator::Vwap => "vwap",
+        }
+    }
+
+    // 实际使用的参数（含默认值），随结果一起返回
+    fn params(&self) -> Vec<f64> {
+        match *self {
+            Indicator::Ma(n) | Indicator::Ema(n) | Indicator::Rsi(n) | Indicator::Atr(n) => {
+                vec![n as f64]
+            }
+            Indicator::Macd(a, b, c) | Indicator::Kdj(a, b, c) => {
+                vec![a as f64, b as f64, c as f64]
+            }
+            Indicator::Boll(n, k) => vec![n as f64, k],
+            Indicator::Obv | Indicator::Vwap => Vec::new(),
+        }
+    }
+
+    // 需要在窗口之前额外获取的K线数量：
+    // 窗口类指标补足一个窗口，递推类指标多取几倍周期让初始值的影响衰减
+    fn warmup(&self) -> usize {
+        match *self {
+            Indicator::Ma(n) | Indicator::Boll(n, _) => n - 1,
+            Indicator::Ema(n) | Indicator::Rsi(n) | Indicator::Atr(n) => n * 3,
+            Indicator::Macd(fast, slow, signal) => fast.max(slow) * 3 + signal,
+            Indicator::Kdj(n, m1, m2) => n + (m1 + m2) * 3,
+            Indicator::Obv | Indicator::Vwap => 0,
+        }
+    }
+}
+
+/// 解析请求中的全部指标
+pub fn parse_indicators(specs: &[IndicatorSpec]) -> Result<Vec<Indicator>, String> {
+    specs.iter().map(Indicator::parse).collect()
+}
+
+/// 计算这些指标需要在窗口前额外获取的K线数量
+pub fn warmup_bars(indicators: &[Indicator]) -> usize {
+    indicators
+        .iter()
+        .map(Indicator::warmup)
+        .max()
+        .unwrap_or(0)
+        .min(MAX_WARMUP)
+}
+
+// 分钟级K线的 VWAP 按交易日（北京时间）分段累计，日线及以上从窗口起点累计
+fn vwap_sessions(items: &[StockKlineItem], period: &str) -> Vec<i64> {
+    let intraday = period.ends_with('m') && period != "month";
+    items
+        .iter()
+        .map(|k| {
+            if intraday {
+                (k.date + 8 * HOUR_MS).div_euclid(DAY_MS)
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+fn compute_one(
+    indicator: &Indicator,
+    items: &[StockKlineItem],
+    period: &str,
+) -> BTreeMap<String, Vec<Option<f64>>> {
+    let closes: Vec<f64> = items.iter().map(|k| k.close).collect();
+    let highs: Vec<f64> = items.iter().map(|k| k.high).collect();
+    let lows: Vec<f64> = items.iter().map(|k| k.low).collect();
+    let volumes: Vec<f64> = items.iter().map(|k| k.volume).collect();
+
+    let lines = match *indicator {
+        Indicator::Ma(n) => vec![("ma", calc::sma(&closes, n))],
+        Indicator::Ema(n) => vec![("ema", calc::ema(&closes, n))],
+        Indicator::Macd(fast, slow, signal) => {
+            let (dif, dea, hist) = calc::macd(&closes, fast, slow, signal);
+            vec![("dif", dif), ("dea", dea), ("macd", hist)]
+        }
+        Indicator::Kdj(n, m1, m2) => {
+            let (k, d, j) = calc::kdj(&highs, &lows, &closes, n, m1, m2);
+            vec![("k", k), ("d", d), ("j", j)]
+        }
+        Indicator::Rsi(n) => vec![("rsi", calc::rsi(&closes, n))],
+        Indicator::Boll(n, k) => {
+            let (mid, upper, lower) = calc::boll(&closes, n, k);
+            vec![("mid", mid), ("upper", upper), ("lower", lower)]
+        }
+        Indicator::Atr(n) => vec![("atr", calc::atr(&highs, &lows, &closes, n))],
+        Indicator::Obv => vec![("obv", calc::obv(&closes, &volumes))],
+        Indicator::Vwap => {
+            let sessions = vwap_sessions(items, period);
+            vec![(
+                "vwap",
+                calc::vwap(&highs, &lows, &closes, &volumes, &sessions),
+            )]
+        }
+    };
+
+    lines
+        .into_iter()
+        .map(|(key, values)| (key.to_string(), values))
+        .collect()
+}
+
+/// 在包含预热数据的K线上计算指标，然后只保留最后 limit 根
+/// 返回的K线与每条指标序列长度一致、一一对齐
+pub fn compute_for_window(
+    mut items: Vec<StockKlineItem>,
+    indicators: &[Indicator],
+    period: &str,
+    limit: usize,
+) -> (Vec<StockKlineItem>, Vec<IndicatorResult>) {
+    let skip = items.len().saturating_sub(limit);
+
+    let results = indicators
+        .iter()
+        .map(|indicator| {
+            let series = compute_one(indicator, &items, period)
+                .into_iter()
+                .map(|(key, values)| (key, values[skip..].to_vec()))
+                .collect();
+            IndicatorResult {
+                name: indicator.name().to_string(),
+                params: indicator.params(),
+                series,
+            }
+        })
+        .collect();
+
+    items.drain(..skip);
+    (items, results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, params: &[f64]) -> IndicatorSpec {
+        IndicatorSpec {
+            name: name.to_string(),
+            params: params.to_vec(),
+        }
+    }
+
+    fn bar(date: i64, close: f64) -> StockKlineItem {
+        StockKlineItem {
+            date,
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 100.0,
+            percent: 0.0,
+            turnoverrate: 0.0,
+        }
+    }
+
+    #[test]
+    fn parse_uses_defaults_and_rejects_invalid() {
+        assert_eq!(
+            Indicator::parse(&spec("MACD", &[])).unwrap(),
+            Indicator::Macd(12, 26, 9)
+        );
+        assert_eq!(
+            Indicator::parse(&spec("boll", &[10.0])).unwrap(),
+            Indicator::Boll(10, 2.0)
+        );
+        assert!(Indicator::parse(&spec("ma", &[0.0])).is_err());
+        assert!(Indicator::parse(&spec("ma", &[2.5])).is_err());
+        assert!(Indicator::parse(&spec("cci", &[])).is_err());
+        assert!(Indicator::parse(&spec("ema", &[1001.0])).is_err());
+        assert!(Indicator::parse(&spec("macd", &[12.0, 1e19])).is_err());
+
+        let indicators = parse_indicators(&[spec("ma", &[60.0]), spec("obv", &[])]).unwrap();
+        assert_eq!(warmup_bars(&indicators), 59);
+        assert_eq!(warmup_bars(&[]), 0);
+    }
+
+    #[test]
+    fn window_is_trimmed_after_warmup() {
+        let items: Vec<StockKlineItem> = (1..=10).map(|i| bar(i, i as f64)).collect();
+        let indicators = vec![Indicator::Ma(3), Indicator::Macd(12, 26, 9)];
+        let (items, results) = compute_for_window(items, &indicators, "day", 5);
+
+        assert_eq!(items.len(), 5);
+        assert_eq!(items[0].date, 6);
+        let ma = &results[0].series["ma"];
+        assert_eq!(ma.len(), 5);
+        // 预热数据让窗口第一根就有 MA3 = (4+5+6)/3
+        assert_eq!(ma[0], Some(5.0));
+        assert_eq!(results[1].params, vec![12.0, 26.0, 9.0]);
+        assert!(results[1].series.values().all(|values| values.len() == 5));
+    }
+
+    #[test]
+    fn intraday_vwap_resets_each_trading_day() {
+        // 北京时间 2024-01-02 14:55 与 2024-01-03 09:35
+        let first_day = 1_704_178_500_000;
+        let next_day = 1_704_245_700_000;
+        let items = vec![bar(first_day, 10.0), bar(next_day, 20.0)];
+        let (_, results) = compute_for_window(items.clone(), &[Indicator::Vwap], "5m", 10);
+        assert_eq!(results[0].series["vwap"], vec![Some(10.0), Some(20.0)]);
+
+        let (_, results) = compute_for_window(items, &[Indicator::Vwap], "day", 10);
+        assert_eq!(results[0].series["vwap"], vec![Some(10.0), Some(15.0)]);
+    }
+}
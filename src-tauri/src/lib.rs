@@ -1,16 +1,48 @@
+mod alerts;
+mod analytics;
+mod backup;
+mod calendar;
 mod command;
 mod db;
+mod indicators;
 mod requests;
+mod secrets;
+mod statement;
 mod structs;
 
+use tauri::Manager;
+use tauri_plugin_log::log;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // 创建存储实例（用于保存 Cookie）
     tauri::Builder::default()
+        // 日志插件需先注册，后端各模块通过 log 宏输出的日志才会写入日志文件和终端
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .level(if cfg!(debug_assertions) {
+                    log::LevelFilter::Debug
+                } else {
+                    log::LevelFilter::Info
+                })
+                .build(),
+        )
         .setup(|app| {
             // 在应用启动时自动初始化所有数据库
             match command::common::init_all_databases(app.handle()) {
-                Ok(_) => println!("所有数据库初始化成功"),
+                Ok(db) => {
+                    // 加载敏感配置的密钥（设置了密码时等待用户解锁）
+                    // 失败时敏感配置保持锁定，原因通过 get_secret_status_cmd 告知界面
+                    if let Err(e) = secrets::init_secrets(app.handle(), &db) {
+                        log::error!("敏感配置密钥加载失败，敏感配置保持锁定: {}", e);
+                    }
+                    app.manage(db);
+                    println!("所有数据库初始化成功");
+                    // 启动自动备份（启动时先备份一次）
+                    backup::scheduler::start_backup_scheduler(app.handle().clone());
+                    // 启动后台行情轮询与价格提醒
+                    alerts::engine::start_alert_engine(app.handle().clone());
+                }
                 Err(e) => eprintln!("数据库初始化失败: {}", e),
             }
             Ok(())
@@ -26,6 +58,18 @@ pub fn run() {
             command::stock_command::crawl_and_save_stocks,
             command::stock_command::search_stocks_by_keyword,
             command::app_config_command::save_xueqiu_cookie,
+            command::app_config_command::get_market_data_provider_cmd,
+            command::app_config_command::set_market_data_provider_cmd,
+            command::app_config_command::get_offline_mode_cmd,
+            command::app_config_command::set_offline_mode_cmd,
+            command::app_config_command::get_cost_method_cmd,
+            command::app_config_command::set_cost_method_cmd,
+            command::app_config_command::get_fee_model_cmd,
+            command::app_config_command::set_fee_model_cmd,
+            command::app_config_command::get_cash_strict_mode_cmd,
+            command::app_config_command::set_cash_strict_mode_cmd,
+            command::app_config_command::get_kline_adjust_cmd,
+            command::app_config_command::set_kline_adjust_cmd,
             command::xueqiu_command::get_kline_data,
             command::xueqiu_command::get_batch_stock_quote,
             command::xueqiu_command::get_single_stock_detail,
@@ -45,20 +89,75 @@ pub fn run() {
             command::holdings_command::add_holding_cmd,
             command::holdings_command::get_all_holdings_cmd,
             command::holdings_command::get_history_holdings_cmd,
-            command::holdings_command::update_holding_cmd,
-            command::holdings_command::delete_holding_cmd,
             command::holdings_command::get_latest_holding_by_code_cmd,
             command::holdings_command::get_monthly_stats_cmd,
+            command::holdings_command::rebuild_holdings_cmd,
+            command::holdings_command::get_holding_lots_cmd,
             command::orders_command::get_all_orders_cmd,
             command::orders_command::get_orders_by_code_cmd,
             command::orders_command::add_order_cmd,
             command::orders_command::delete_order_cmd,
+            command::orders_command::add_order_with_holding_cmd,
+            command::orders_command::delete_order_with_holding_cmd,
+            command::analytics_command::get_portfolio_analytics_cmd,
+            command::analytics_command::get_equity_curve_cmd,
+            command::cash_command::add_cash_entry_cmd,
+            command::cash_command::delete_cash_entry_cmd,
+            command::cash_command::get_cash_flows_cmd,
+            command::cash_command::get_account_summary_cmd,
+            command::account_command::get_accounts_cmd,
+            command::account_command::create_account_cmd,
+            command::account_command::rename_account_cmd,
+            command::account_command::archive_account_cmd,
+            command::account_command::transfer_position_cmd,
+            command::account_command::get_position_transfers_cmd,
+            command::account_command::delete_position_transfer_cmd,
+            command::corporate_action_command::get_corporate_actions_cmd,
+            command::corporate_action_command::add_corporate_action_cmd,
+            command::corporate_action_command::delete_corporate_action_cmd,
+            command::corporate_action_command::fetch_corporate_actions_cmd,
+            command::corporate_action_command::get_holding_dividends_cmd,
+            command::statement_command::get_statement_brokers_cmd,
+            command::statement_command::save_statement_columns_cmd,
+            command::statement_command::preview_statement_import_cmd,
+            command::statement_command::import_statement_cmd,
             command::import_command::import_database,
+            command::import_command::export_database,
+            command::backup_command::get_backup_settings_cmd,
+            command::backup_command::set_backup_settings_cmd,
+            command::backup_command::list_backup_snapshots_cmd,
+            command::backup_command::create_backup_snapshot_cmd,
+            command::backup_command::restore_backup_snapshot_cmd,
+            command::secret_command::get_secret_status_cmd,
+            command::secret_command::unlock_secrets_cmd,
+            command::secret_command::set_secret_passphrase_cmd,
+            command::alert_command::get_alert_rules_cmd,
+            command::alert_command::add_alert_rule_cmd,
+            command::alert_command::update_alert_rule_cmd,
+            command::alert_command::set_alert_rule_enabled_cmd,
+            command::alert_command::delete_alert_rule_cmd,
+            command::alert_command::get_alert_events_cmd,
+            command::alert_command::clear_alert_events_cmd,
+            command::alert_command::get_alert_settings_cmd,
+            command::alert_command::set_alert_settings_cmd,
             command::trend_lines_cmd::add_trend_lines_cmd,
             command::trend_lines_cmd::query_trend_lines_cmd,
             command::trend_lines_cmd::delete_trend_line_cmd,
+            command::trend_lines_cmd::update_trend_line_cmd,
+            command::trend_lines_cmd::set_trend_line_alert_cmd,
+            command::trend_lines_cmd::get_trend_line_breaks_cmd,
+            command::trend_lines_cmd::get_legacy_trend_line_count_cmd,
+            command::drawings_cmd::get_drawings_cmd,
+            command::drawings_cmd::add_drawing_cmd,
+            command::drawings_cmd::update_drawing_cmd,
+            command::drawings_cmd::delete_drawing_cmd,
+            command::calendar_command::get_market_status_cmd,
+            command::calendar_command::get_trading_calendar_cmd,
+            command::calendar_command::count_trading_days_cmd,
+            command::calendar_command::set_trading_holidays_cmd,
         ])
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,11 +1,39 @@
 use crate::db::app_config_db::get_xueqiu_cookie_from_db;
-use crate::db::common::init_database;
+use crate::db::common::AppDb;
 use reqwest::{header, Client};
+use rusqlite::Connection;
 use std::time::Duration;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 
 /// 优化命名：明确函数作用是「创建带请求头的HTTP客户端」
 pub fn create_xueqiu_http_client(app: &AppHandle) -> Result<Client, String> {
+    // 从数据库获取雪球Cookie
+    let cookie = {
+        let db = app
+            .try_state::<AppDb>()
+            .ok_or_else(|| "数据库尚未初始化".to_string())?;
+        let conn = db
+            .conn()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?; // 转换数据库错误为String
+        read_xueqiu_cookie(&conn)?
+    };
+
+    build_xueqiu_http_client(&cookie)
+}
+
+/// 从数据库读取雪球Cookie（未设置时返回错误提示）
+pub fn read_xueqiu_cookie(conn: &Connection) -> Result<String, String> {
+    let cookie =
+        get_xueqiu_cookie_from_db(conn).map_err(|e| format!("从数据库读取Cookie失败: {}", e))?;
+
+    match cookie {
+        Some(c) => Ok(c),
+        None => Err("数据库中未找到雪球Cookie，请先通过前端设置Cookie".to_string()),
+    }
+}
+
+/// 用给定的Cookie创建雪球HTTP客户端
+pub fn build_xueqiu_http_client(cookie_str: &str) -> Result<Client, String> {
     // 1. 构建模拟浏览器的请求头
     let mut headers = header::HeaderMap::new();
 
@@ -32,23 +60,12 @@ pub fn create_xueqiu_http_client(app: &AppHandle) -> Result<Client, String> {
         header::HeaderValue::from_static("1"),
     );
 
-    // 2. 从数据库获取雪球Cookie
-    let conn = init_database(app, "app_config").map_err(|e| format!("初始化数据库失败: {}", e))?; // 转换数据库错误为String
-
-    let cookie =
-        get_xueqiu_cookie_from_db(&conn).map_err(|e| format!("从数据库读取Cookie失败: {}", e))?; // 转换Cookie读取错误
-
-    let cookie_str = match cookie {
-        Some(c) => c,
-        None => return Err("数据库中未找到雪球Cookie，请先通过前端设置Cookie".to_string()),
-    };
-
-    // 3. 将Cookie添加到请求头（验证Cookie格式有效性）
-    let cookie_header = header::HeaderValue::from_str(&cookie_str)
+    // 2. 将Cookie添加到请求头（验证Cookie格式有效性）
+    let cookie_header = header::HeaderValue::from_str(cookie_str)
         .map_err(|e| format!("Cookie格式无效（可能包含特殊字符）: {}", e))?;
     headers.insert("Cookie", cookie_header);
 
-    // 4. 构建HTTP客户端（关键：用map_err转换reqwest::Error为String）
+    // 3. 构建HTTP客户端（关键：用map_err转换reqwest::Error为String）
     let client = Client::builder()
         .timeout(Duration::from_secs(10)) // 10秒超时，避免请求挂起
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36")
@@ -58,3 +75,16 @@ pub fn create_xueqiu_http_client(app: &AppHandle) -> Result<Client, String> {
 
     Ok(client)
 }
+
+/// 创建不带 Cookie 的公开行情HTTP客户端（referer 为接口要求的来源页）
+pub fn build_public_http_client(referer: &'static str) -> Result<Client, String> {
+    let mut headers = header::HeaderMap::new();
+    headers.insert("Referer", header::HeaderValue::from_static(referer));
+
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36")
+        .default_headers(headers)
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))
+}
@@ -3,6 +3,7 @@ use crate::structs::stock_structs::{StockApiResponse, StockItem};
 use crate::structs::StockError;
 use reqwest::Client;
 use std::time::Duration;
+use tauri_plugin_log::log;
 
 /// 爬取所有 A 股股票数据（分页请求雪球 API）
 pub async fn crawl_all_stocks(app: tauri::AppHandle) -> Result<Vec<StockItem>, StockError> {
@@ -12,7 +13,7 @@ pub async fn crawl_all_stocks(app: tauri::AppHandle) -> Result<Vec<StockItem>, S
     // 2. 先请求第 1 页，获取总数据条数（count）
     let first_page_url = "https://xueqiu.com/service/screener/screen?category=CN&exchange=sh_sz&areacode=&indcode=&order_by=symbol&order=desc&page=1&size=90&only_count=0";
     let first_response = client.get(first_page_url).send().await?;
-    println!("响应状态: {}", first_response.status());
+    log::debug!("响应状态: {}", first_response.status());
 
     // 检查响应状态（如 403/404 等错误）
     if !first_response.status().is_success() {
@@ -47,7 +48,7 @@ pub async fn crawl_all_stocks(app: tauri::AppHandle) -> Result<Vec<StockItem>, S
         // 请求当前页
         let response = client.get(&url).send().await?;
         if !response.status().is_success() {
-            eprintln!("爬取第 {} 页失败，状态码: {}", page, response.status());
+            log::warn!("爬取第 {} 页失败，状态码: {}", page, response.status());
             continue; // 跳过失败页面，继续爬取下一页
         }
 
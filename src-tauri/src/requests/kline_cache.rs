@@ -0,0 +1,441 @@
+use crate::calendar::TradingCalendar;
+use crate::db::common::AppDb;
+use crate::db::kline_cache_db::{
+    count_cached_klines, query_cached_klines, query_cached_range, query_listing_start,
+    replace_klines, save_klines, save_listing_start,
+};
+use crate::requests::provider::{ProviderChain, ProviderError};
+use crate::structs::xueqiu_structs::StockKlineItem;
+use crate::structs::StockError;
+
+/// 数据全部来自本地缓存时的来源标识
+pub const CACHE_SOURCE: &str = "cache";
+
+const MINUTE_MS: i64 = 60 * 1000;
+const DAY_MS: i64 = 24 * 60 * MINUTE_MS;
+// 比对复权价格时容忍的浮点误差
+const PRICE_EPSILON: f64 = 1e-6;
+
+/// 读取缓存后的K线结果
+pub struct KlineLoad {
+    pub items: Vec<StockKlineItem>,
+    /// 最后一次补数据的数据源 id，全部命中缓存时为 CACHE_SOURCE
+    pub source: &'static str,
+}
+
+// 每根K线的大致时长，用于估算距上次缓存缺了多少根（宁多勿少）
+pub(crate) fn period_duration_ms(period: &str) -> Option<i64> {
+    let ms = match period {
+        "1m" => MINUTE_MS,
+        "5m" => 5 * MINUTE_MS,
+        "15m" => 15 * MINUTE_MS,
+        "30m" => 30 * MINUTE_MS,
+        "60m" => 60 * MINUTE_MS,
+        "120m" => 120 * MINUTE_MS,
+        "day" => DAY_MS,
+        "week" => 7 * DAY_MS,
+        "month" => 28 * DAY_MS,
+        "quarter" => 89 * DAY_MS,
+        "year" => 365 * DAY_MS,
+        _ => return None,
+    };
+    Some(ms)
+}
+
+// 估算从 newest 到 now 需要重新获取的K线数量（包含 newest 本身，用于刷新未收盘K线）
+fn tail_fetch_count(period: &str, newest: i64, now: i64, limit: i32) -> i32 {
+    match period_duration_ms(period) {
+        Some(duration) => {
+            let elapsed = ((now - newest).max(0) / duration) as i32;
+            elapsed.saturating_add(2).min(limit)
+        }
+        None => limit,
+    }
+}
+
+fn db_err(e: StockError) -> ProviderError {
+    ProviderError::Other(e.to_string())
+}
+
+// 数据源返回的K线少于请求数量说明已到上市首日，记录首根K线时间
+fn mark_listing_start(
+    db: &AppDb,
+    code: &str,
+    period: &str,
+    items: &[StockKlineItem],
+    requested: i32,
+) -> Result<(), ProviderError> {
+    match items.first() {
+        Some(first) if items.len() < requested as usize => db
+            .with_conn(|conn| save_listing_start(conn, code, period, first.date))
+            .map_err(db_err),
+        _ => Ok(()),
+    }
+}
+
+// 前复权价格在每次除权除息后整体变化：取尾部中一根已收盘的K线与缓存比对，不一致说明缓存需整体替换
+fn price_base_changed(
+    db: &AppDb,
+    code: &str,
+    period: &str,
+    tail: &[StockKlineItem],
+    newest: i64,
+) -> Result<bool, ProviderError> {
+    let Some(fresh) = tail.iter().rev().find(|item| item.date < newest) else {
+        return Ok(false);
+    };
+    let cached = db
+        .with_conn(|conn| query_cached_klines(conn, code, period, fresh.date, 1))
+        .map_err(db_err)?;
+    Ok(match cached.first() {
+        Some(cached) if cached.date == fresh.date => {
+            (cached.close - fresh.close).abs() > PRICE_EPSILON
+                || (cached.open - fresh.open).abs() > PRICE_EPSILON
+        }
+        _ => false,
+    })
+}
+
+/// 优先从本地缓存读取K线，只向数据源请求缺失的部分
+/// - timestamp 为 None 表示最新的 limit 根；否则为截至 timestamp（含）的 limit 根
+/// - 缓存末尾的K线可能尚未收盘，每次都会与缺失的尾部一起重新获取并覆盖
+/// - 向前翻页时只补齐缓存最早一根之前缺少的部分，缓存已到上市首根K线时不再补
+/// - 缓存在 (symbol, period) 内始终是连续的一段，出现缺口时整体替换或不写入
+/// - 已收盘K线与新获取的价格不一致（除权除息导致复权基准变化）时整体替换缓存
+pub async fn load_klines(
+    db: &AppDb,
+    chain: &ProviderChain,
+    code: &str,
+    period: &str,
+    timestamp: Option<i64>,
+    limit: i32,
+    now: i64,
+) -> Result<KlineLoad, ProviderError> {
+    // 雪球以 begin 为终点向前取，最新数据以最近交易日的收盘时间（北京时间）为终点，
+    // 交易时段内包含尚未收盘的K线
+    let end = match timestamp {
+        Some(timestamp) => timestamp,
+        None => db
+            .with_conn(|conn| TradingCalendar::load(conn))
+            .map_err(db_err)?
+            .kline_end_ms(now),
+    };
+    let mut source = CACHE_SOURCE;
+
+    let range = db
+        .with_conn(|conn| query_cached_range(conn, code, period))
+        .map_err(db_err)?;
+
+    // 1. 没有缓存：整段获取后写入
+    let (oldest, newest) = match range {
+        Some(range) => range,
+        None => {
+            let (items, provider) = chain.fetch_kline(code, period, end, limit).await?;
+            db.with_conn(|conn| save_klines(conn, code, period, &items))
+                .map_err(db_err)?;
+            mark_listing_start(db, code, period, &items, limit)?;
+            return Ok(KlineLoad {
+                items,
+                source: provider,
+            });
+        }
+    };
+
+    // 2. 窗口覆盖到缓存最新一根：刷新最新一根（可能未收盘）并补齐之后的K线
+    if end >= newest {
+        let count = tail_fetch_count(period, newest, now.min(end), limit);
+        let (tail, provider) = chain.fetch_kline(code, period, end, count).await?;
+        source = provider;
+
+        let overlaps = tail.first().map_or(true, |first| first.date <= newest);
+        if overlaps && !price_base_changed(db, code, period, &tail, newest)? {
+            db.with_conn(|conn| save_klines(conn, code, period, &tail))
+                .map_err(db_err)?;
+        } else {
+            // 缺口太大或复权基准已变化：重新获取整个窗口并替换缓存，保证缓存连续且价格一致
+            let items = if count < limit {
+                chain.fetch_kline(code, period, end, limit).await?.0
+            } else {
+                tail
+            };
+            db.with_conn(|conn| replace_klines(conn, code, period, &items))
+                .map_err(db_err)?;
+            if count < limit {
+                mark_listing_start(db, code, period, &items, limit)?;
+            }
+            return Ok(KlineLoad { items, source });
+        }
+    }
+
+    // 3. 缓存中窗口内的K线不足：向前补齐（缓存最早一根已是上市首根时无需补）
+    let (have, listing_start) = db
+        .with_conn(|conn| {
+            Ok((
+                count_cached_klines(conn, code, period, end)?,
+                query_listing_start(conn, code, period)?,
+            ))
+        })
+        .map_err(db_err)?;
+    let reached_listing = listing_start.is_some_and(|first| first >= oldest);
+    if have < limit as i64 && !reached_listing {
+        if end < oldest {
+            // 窗口完全早于缓存，直接返回，不写入以免缓存出现缺口
+            let (items, provider) = chain.fetch_kline(code, period, end, limit).await?;
+            return Ok(KlineLoad {
+                items,
+                source: provider,
+            });
+        }
+
+        // 从缓存最早一根（含）向前获取，保证与缓存衔接
+        let count = limit - have as i32 + 1;
+        let (head, provider) = chain.fetch_kline(code, period, oldest, count).await?;
+        source = provider;
+        db.with_conn(|conn| save_klines(conn, code, period, &head))
+            .map_err(db_err)?;
+        mark_listing_start(db, code, period, &head, count)?;
+    }
+
+    let items = db
+        .with_conn(|conn| query_cached_klines(conn, code, period, end, limit))
+        .map_err(db_err)?;
+    Ok(KlineLoad { items, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+    use crate::requests::provider::MarketDataProvider;
+    use crate::structs::xueqiu_structs::{MinuteChartItem, StockDetail, StockQuote};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    // 模拟数据源：按天生成K线，并记录每次请求的 (begin, count)
+    struct FakeProvider {
+        bars: Arc<Mutex<Vec<StockKlineItem>>>,
+        calls: Arc<Mutex<Vec<(i64, i32)>>>,
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for FakeProvider {
+        fn id(&self) -> &'static str {
+            "fake"
+        }
+
+        async fn fetch_kline(
+            &self,
+            _code: &str,
+            _period: &str,
+            timestamp: i64,
+            limit: i32,
+        ) -> Result<Vec<StockKlineItem>, ProviderError> {
+            self.calls.lock().unwrap().push((timestamp, limit));
+            let bars = self.bars.lock().unwrap();
+            let before: Vec<StockKlineItem> = bars
+                .iter()
+                .filter(|b| b.date <= timestamp)
+                .cloned()
+                .collect();
+            let skip = before.len().saturating_sub(limit as usize);
+            Ok(before[skip..].to_vec())
+        }
+
+        async fn fetch_batch_quote(
+            &self,
+            _symbols: &[String],
+        ) -> Result<Vec<StockQuote>, ProviderError> {
+            Ok(Vec::new())
+        }
+
+        async fn fetch_stock_detail(
+            &self,
+            _code: &str,
+        ) -> Result<Option<StockDetail>, ProviderError> {
+            Ok(None)
+        }
+
+        async fn fetch_minute_chart(
+            &self,
+            _code: &str,
+        ) -> Result<Vec<MinuteChartItem>, ProviderError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn bar(day: i64, close: f64) -> StockKlineItem {
+        StockKlineItem {
+            date: day * DAY_MS,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100.0,
+            percent: 0.0,
+            turnoverrate: 0.0,
+        }
+    }
+
+    struct Fixture {
+        db: AppDb,
+        chain: ProviderChain,
+        bars: Arc<Mutex<Vec<StockKlineItem>>>,
+        calls: Arc<Mutex<Vec<(i64, i32)>>>,
+    }
+
+    // 100 根日K，最后一根为第 100 天
+    fn fixture() -> Fixture {
+        let bars = Arc::new(Mutex::new((1..=100).map(|d| bar(d, d as f64)).collect()));
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let chain = ProviderChain::new(vec![Box::new(FakeProvider {
+            bars: bars.clone(),
+            calls: calls.clone(),
+        })]);
+        Fixture {
+            db: AppDb::from_connection(open_test_connection()).unwrap(),
+            chain,
+            bars,
+            calls,
+        }
+    }
+
+    const NOW: i64 = 100 * DAY_MS + 10 * 60 * MINUTE_MS;
+
+    #[tokio::test]
+    async fn second_load_only_fetches_tail() {
+        let f = fixture();
+        let first = load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, NOW)
+            .await
+            .unwrap();
+        assert_eq!(first.items.len(), 30);
+        assert_eq!(first.source, "fake");
+
+        // 当日K线尚未收盘，价格发生变化
+        f.bars.lock().unwrap()[99].close = 123.0;
+
+        let second = load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, NOW)
+            .await
+            .unwrap();
+        assert_eq!(second.items.len(), 30);
+        assert_eq!(second.items.last().unwrap().close, 123.0);
+
+        let calls = f.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].1, 2);
+    }
+
+    #[tokio::test]
+    async fn new_bars_are_appended() {
+        let f = fixture();
+        load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, NOW)
+            .await
+            .unwrap();
+
+        f.bars
+            .lock()
+            .unwrap()
+            .extend([bar(101, 101.0), bar(102, 102.0)]);
+        let later = NOW + 2 * DAY_MS;
+        let loaded = load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, later)
+            .await
+            .unwrap();
+
+        let dates: Vec<i64> = loaded.items.iter().map(|k| k.date / DAY_MS).collect();
+        assert_eq!(dates.first(), Some(&73));
+        assert_eq!(dates.last(), Some(&102));
+        assert_eq!(f.calls.lock().unwrap()[1].1, 4);
+    }
+
+    #[tokio::test]
+    async fn scrolling_back_fetches_only_missing_head() {
+        let f = fixture();
+        load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, NOW)
+            .await
+            .unwrap();
+
+        // 从缓存最早一根（第 71 天）向前翻 30 根
+        let loaded = load_klines(
+            &f.db,
+            &f.chain,
+            "SH600000",
+            "day",
+            Some(80 * DAY_MS),
+            30,
+            NOW,
+        )
+        .await
+        .unwrap();
+        let dates: Vec<i64> = loaded.items.iter().map(|k| k.date / DAY_MS).collect();
+        assert_eq!(dates.first(), Some(&51));
+        assert_eq!(dates.last(), Some(&80));
+
+        // 第 71~80 天已在缓存中，只需补 20 根（含衔接的第 71 天）
+        assert_eq!(f.calls.lock().unwrap()[1], (71 * DAY_MS, 21));
+
+        // 再次打开同一窗口完全命中缓存
+        let again = load_klines(
+            &f.db,
+            &f.chain,
+            "SH600000",
+            "day",
+            Some(80 * DAY_MS),
+            30,
+            NOW,
+        )
+        .await
+        .unwrap();
+        assert_eq!(again.source, CACHE_SOURCE);
+        assert_eq!(f.calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn ex_dividend_rebases_the_whole_cache() {
+        let f = fixture();
+        load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, NOW)
+            .await
+            .unwrap();
+
+        // 第 101 天除息，前复权价格整体下调
+        {
+            let mut bars = f.bars.lock().unwrap();
+            for b in bars.iter_mut() {
+                b.close -= 0.5;
+                b.open -= 0.5;
+            }
+            bars.push(bar(101, 101.0));
+        }
+        let later = NOW + DAY_MS;
+        let loaded = load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, later)
+            .await
+            .unwrap();
+
+        assert_eq!(loaded.items.len(), 30);
+        assert_eq!(loaded.items[0].close, 71.5);
+        assert_eq!(loaded.items[28].close, 99.5);
+        let cached =
+            f.db.with_conn(|conn| query_cached_klines(conn, "SH600000", "day", later, 100))
+                .unwrap();
+        assert_eq!(cached.len(), 30);
+        assert_eq!(cached[0].close, 71.5);
+    }
+
+    #[tokio::test]
+    async fn newly_listed_stock_does_not_refetch_head() {
+        let f = fixture();
+        // 上市只有 10 天（第 91~100 天）
+        f.bars.lock().unwrap().drain(..90);
+        let first = load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, NOW)
+            .await
+            .unwrap();
+        assert_eq!(first.items.len(), 10);
+
+        // 只刷新尾部，不再向前补数据
+        let second = load_klines(&f.db, &f.chain, "SH600000", "day", None, 30, NOW)
+            .await
+            .unwrap();
+        assert_eq!(second.items.len(), 10);
+        let calls = f.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].1, 2);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod common;
 pub mod get_all_stock;
+pub mod kline_cache;
+pub mod provider;
+pub mod tencent_request;
 pub mod xueqiu_request;
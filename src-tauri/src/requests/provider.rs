@@ -0,0 +1,358 @@
+use crate::db::app_config_db::{get_config_value, set_config_value};
+use crate::requests::tencent_request::TencentProvider;
+use crate::requests::xueqiu_request::XueqiuProvider;
+use crate::structs::corporate_action_structs::CorporateAction;
+use crate::structs::xueqiu_structs::{MinuteChartItem, StockDetail, StockKlineItem, StockQuote};
+use crate::structs::StockError;
+use async_trait::async_trait;
+use rusqlite::Connection;
+use std::future::Future;
+use std::pin::Pin;
+use tauri_plugin_log::log;
+use thiserror::Error;
+
+/// app_config 中保存当前行情数据源的键
+pub const ACTIVE_PROVIDER_KEY: &str = "market_data_provider";
+
+/// 默认行情数据源
+pub const DEFAULT_PROVIDER: &str = "xueqiu";
+
+/// 已注册的行情数据源（id, 显示名称），排在前面的优先作为备用
+pub const PROVIDERS: &[(&str, &str)] = &[("xueqiu", "雪球"), ("tencent", "腾讯行情")];
+
+/// 行情数据源错误（区分是否需要切换到备用数据源）
+#[derive(Error, Debug)]
+pub enum ProviderError {
+    // 401/403：Cookie 失效或被封禁
+    #[error("认证失败（状态码 {0}），Cookie已过期或无效")]
+    Unauthorized(u16),
+    // 接口返回非成功状态码
+    #[error("接口请求失败，状态码: {0}")]
+    Status(u16),
+    // 返回内容无法解析（通常是接口格式变化）
+    #[error("数据解析失败: {0}")]
+    Parse(String),
+    // 网络不可用、超时等
+    #[error("网络请求失败: {0}")]
+    Network(String),
+    // 配置缺失等其他错误
+    #[error("{0}")]
+    Other(String),
+}
+
+impl ProviderError {
+    /// 是否应该尝试下一个数据源（认证失败或数据格式变化）
+    pub fn should_fallback(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::Unauthorized(_) | ProviderError::Parse(_)
+        )
+    }
+
+    /// 根据 HTTP 状态码构造错误
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 | 403 => ProviderError::Unauthorized(status),
+            _ => ProviderError::Status(status),
+        }
+    }
+}
+
+impl From<ProviderError> for String {
+    fn from(e: ProviderError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 行情数据源：返回统一的业务结构体，屏蔽各家接口差异
+#[async_trait]
+pub trait MarketDataProvider: Send + Sync {
+    /// 数据源 id（与 PROVIDERS 中一致）
+    fn id(&self) -> &'static str;
+
+    /// K线数据：timestamp 为 13 位毫秒时间戳，向前获取 limit 条
+    async fn fetch_kline(
+        &self,
+        code: &str,
+        period: &str,
+        timestamp: i64,
+        limit: i32,
+    ) -> Result<Vec<StockKlineItem>, ProviderError>;
+
+    /// 批量报价
+    async fn fetch_batch_quote(&self, symbols: &[String])
+        -> Result<Vec<StockQuote>, ProviderError>;
+
+    /// 单只股票详情（无数据时返回 None）
+    async fn fetch_stock_detail(&self, code: &str) -> Result<Option<StockDetail>, ProviderError>;
+
+    /// 当日分时数据
+    async fn fetch_minute_chart(&self, code: &str) -> Result<Vec<MinuteChartItem>, ProviderError>;
+
+    /// 分红送转等公司行动（不支持的数据源返回空列表）
+    async fn fetch_corporate_actions(
+        &self,
+        _code: &str,
+    ) -> Result<Vec<CorporateAction>, ProviderError> {
+        Ok(Vec::new())
+    }
+}
+
+/// 按优先级排列的数据源链：当前数据源失败时依次尝试后面的数据源
+pub struct ProviderChain {
+    providers: Vec<Box<dyn MarketDataProvider>>,
+}
+
+/// 链式请求的返回值：数据 + 实际提供数据的数据源 id
+pub type ChainResult<T> = Result<(T, &'static str), ProviderError>;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+impl ProviderChain {
+    pub fn new(providers: Vec<Box<dyn MarketDataProvider>>) -> Self {
+        Self { providers }
+    }
+
+    // 依次尝试每个数据源，只有认证失败/解析失败才切换到下一个
+    async fn first_success<'a, T, F>(&'a self, label: &str, fetch: F) -> ChainResult<T>
+    where
+        F: Fn(&'a dyn MarketDataProvider) -> BoxFuture<'a, Result<T, ProviderError>>,
+    {
+        let mut last_err = ProviderError::Other("没有可用的行情数据源".to_string());
+        for provider in &self.providers {
+            match fetch(provider.as_ref()).await {
+                Ok(data) => return Ok((data, provider.id())),
+                Err(e) if e.should_fallback() => {
+                    log::warn!(
+                        "数据源 {} 获取{}失败，尝试备用数据源: {}",
+                        provider.id(),
+                        label,
+                        e
+                    );
+                    last_err = e;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_err)
+    }
+
+    pub async fn fetch_kline(
+        &self,
+        code: &str,
+        period: &str,
+        timestamp: i64,
+        limit: i32,
+    ) -> ChainResult<Vec<StockKlineItem>> {
+        self.first_success("K线", |p| p.fetch_kline(code, period, timestamp, limit))
+            .await
+    }
+
+    pub async fn fetch_batch_quote(&self, symbols: &[String]) -> ChainResult<Vec<StockQuote>> {
+        self.first_success("批量报价", |p| p.fetch_batch_quote(symbols))
+            .await
+    }
+
+    pub async fn fetch_stock_detail(&self, code: &str) -> ChainResult<Option<StockDetail>> {
+        self.first_success("股票详情", |p| p.fetch_stock_detail(code))
+            .await
+    }
+
+    pub async fn fetch_minute_chart(&self, code: &str) -> ChainResult<Vec<MinuteChartItem>> {
+        self.first_success("分时数据", |p| p.fetch_minute_chart(code))
+            .await
+    }
+
+    pub async fn fetch_corporate_actions(&self, code: &str) -> ChainResult<Vec<CorporateAction>> {
+        self.first_success("分红送转", |p| p.fetch_corporate_actions(code))
+            .await
+    }
+}
+
+/// 读取当前选择的数据源（未设置或已下线时使用默认数据源）
+pub fn get_active_provider(conn: &Connection) -> Result<String, StockError> {
+    let active = get_config_value(conn, ACTIVE_PROVIDER_KEY)?;
+    Ok(match active {
+        Some(id) if PROVIDERS.iter().any(|(p, _)| *p == id) => id,
+        _ => DEFAULT_PROVIDER.to_string(),
+    })
+}
+
+/// 保存当前数据源（只接受已注册的 id）
+pub fn set_active_provider(conn: &Connection, provider: &str) -> Result<(), StockError> {
+    if !PROVIDERS.iter().any(|(p, _)| *p == provider) {
+        return Err(StockError::BusinessError(format!(
+            "未知的行情数据源: {}",
+            provider
+        )));
+    }
+    set_config_value(conn, ACTIVE_PROVIDER_KEY, provider)
+}
+
+// 根据 id 创建数据源实例
+fn create_provider(
+    conn: &Connection,
+    id: &str,
+) -> Result<Box<dyn MarketDataProvider>, ProviderError> {
+    match id {
+        "xueqiu" => Ok(Box::new(XueqiuProvider::from_db(conn)?)),
+        "tencent" => Ok(Box::new(TencentProvider::from_db(conn)?)),
+        _ => Err(ProviderError::Other(format!("未知的行情数据源: {}", id))),
+    }
+}
+
+/// 构建数据源链：当前数据源在前，其余已注册数据源按顺序作为备用
+/// 无法创建的备用数据源（如缺少 Cookie）会被跳过
+pub fn build_provider_chain(conn: &Connection) -> Result<ProviderChain, ProviderError> {
+    let active = get_active_provider(conn).map_err(|e| ProviderError::Other(e.to_string()))?;
+
+    let mut order = vec![active.as_str()];
+    order.extend(
+        PROVIDERS
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| *id != active),
+    );
+
+    let mut providers = Vec::new();
+    let mut first_err = None;
+    for id in order {
+        match create_provider(conn, id) {
+            Ok(p) => providers.push(p),
+            Err(e) => {
+                log::warn!("行情数据源 {} 不可用: {}", id, e);
+                first_err.get_or_insert(e);
+            }
+        }
+    }
+
+    if providers.is_empty() {
+        return Err(
+            first_err.unwrap_or_else(|| ProviderError::Other("没有可用的行情数据源".to_string()))
+        );
+    }
+    Ok(ProviderChain::new(providers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::common::open_test_connection;
+
+    // 测试用数据源：按预设结果返回
+    struct FakeProvider {
+        id: &'static str,
+        fail_with: Option<fn() -> ProviderError>,
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for FakeProvider {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        async fn fetch_kline(
+            &self,
+            _code: &str,
+            _period: &str,
+            timestamp: i64,
+            _limit: i32,
+        ) -> Result<Vec<StockKlineItem>, ProviderError> {
+            if let Some(err) = self.fail_with {
+                return Err(err());
+            }
+            Ok(vec![StockKlineItem {
+                date: timestamp,
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: 1.0,
+                volume: 0.0,
+                percent: 0.0,
+                turnoverrate: 0.0,
+            }])
+        }
+
+        async fn fetch_batch_quote(
+            &self,
+            _symbols: &[String],
+        ) -> Result<Vec<StockQuote>, ProviderError> {
+            Ok(Vec::new())
+        }
+
+        async fn fetch_stock_detail(
+            &self,
+            _code: &str,
+        ) -> Result<Option<StockDetail>, ProviderError> {
+            Ok(None)
+        }
+
+        async fn fetch_minute_chart(
+            &self,
+            _code: &str,
+        ) -> Result<Vec<MinuteChartItem>, ProviderError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn chain(first_err: Option<fn() -> ProviderError>) -> ProviderChain {
+        ProviderChain::new(vec![
+            Box::new(FakeProvider {
+                id: "first",
+                fail_with: first_err,
+            }),
+            Box::new(FakeProvider {
+                id: "second",
+                fail_with: None,
+            }),
+        ])
+    }
+
+    #[tokio::test]
+    async fn falls_back_on_auth_and_parse_errors() {
+        let (_, source) = chain(Some(|| ProviderError::Unauthorized(403)))
+            .fetch_kline("SH600000", "day", 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(source, "second");
+
+        let (_, source) = chain(Some(|| ProviderError::Parse("bad json".to_string())))
+            .fetch_kline("SH600000", "day", 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(source, "second");
+
+        let (_, source) = chain(None)
+            .fetch_kline("SH600000", "day", 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(source, "first");
+    }
+
+    #[tokio::test]
+    async fn other_errors_do_not_fall_back() {
+        let result = chain(Some(|| ProviderError::Status(500)))
+            .fetch_kline("SH600000", "day", 1, 10)
+            .await;
+        assert!(matches!(result, Err(ProviderError::Status(500))));
+    }
+
+    #[test]
+    fn active_provider_defaults_and_validates() {
+        let conn = open_test_connection();
+        assert_eq!(get_active_provider(&conn).unwrap(), DEFAULT_PROVIDER);
+
+        assert!(set_active_provider(&conn, "unknown").is_err());
+        set_active_provider(&conn, "tencent").unwrap();
+        assert_eq!(get_active_provider(&conn).unwrap(), "tencent");
+    }
+
+    #[test]
+    fn chain_works_without_cookie() {
+        // 没有雪球 Cookie 时仍可使用不需要登录的腾讯行情
+        let conn = open_test_connection();
+        let chain = build_provider_chain(&conn).unwrap();
+        let ids: Vec<&str> = chain.providers.iter().map(|p| p.id()).collect();
+        assert_eq!(ids, vec!["tencent"]);
+    }
+}
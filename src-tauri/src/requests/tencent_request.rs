@@ -0,0 +1,415 @@
+use crate::db::app_config_db::get_kline_adjust;
+use crate::requests::common::build_public_http_client;
+use crate::requests::provider::{MarketDataProvider, ProviderError};
+use crate::structs::corporate_action_structs::KlineAdjust;
+use crate::structs::xueqiu_structs::{MinuteChartItem, StockDetail, StockKlineItem, StockQuote};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use reqwest::Client;
+use rusqlite::Connection;
+use serde_json::Value;
+use tauri_plugin_log::log;
+
+/// 腾讯行情数据源：公开行情接口，不需要 Cookie，作为雪球的备用数据源（不支持分红送转）
+pub struct TencentProvider {
+    client: Client,
+    adjust: KlineAdjust, // K线复权方式
+}
+
+impl TencentProvider {
+    pub fn from_db(conn: &Connection) -> Result<Self, ProviderError> {
+        let client =
+            build_public_http_client("https://gu.qq.com/").map_err(ProviderError::Other)?;
+        let adjust = get_kline_adjust(conn).map_err(|e| ProviderError::Other(e.to_string()))?;
+        Ok(Self { client, adjust })
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for TencentProvider {
+    fn id(&self) -> &'static str {
+        "tencent"
+    }
+
+    async fn fetch_kline(
+        &self,
+        code: &str,
+        period: &str,
+        timestamp: i64,
+        limit: i32,
+    ) -> Result<Vec<StockKlineItem>, ProviderError> {
+        let symbol = tencent_symbol(code);
+        // 多取一条用于计算第一条K线的涨跌幅
+        let count = limit.max(1) + 1;
+        let bars = match minute_period(period) {
+            Some(minutes) => {
+                let url = format!(
+                    "https://ifzq.gtimg.cn/appstock/app/kline/mkline?param={},m{},,{}",
+                    symbol, minutes, count
+                );
+                log::debug!("腾讯分钟K线请求URL: {}", url);
+                let body = get_json(&self.client, &url, "K线").await?;
+                let rows = body["data"][&symbol][format!("m{}", minutes)].as_array();
+                let mut bars = parse_kline_rows(rows.ok_or_else(|| missing("K线"))?);
+                // 分钟K线接口只能取最新的数据，按结束时间截取
+                bars.retain(|bar| bar.date <= timestamp);
+                bars
+            }
+            None => {
+                let kind = match period {
+                    "day" | "week" | "month" => period,
+                    _ => {
+                        return Err(ProviderError::Other(format!(
+                            "腾讯行情不支持 {} 周期的K线",
+                            period
+                        )))
+                    }
+                };
+                let fq = match self.adjust {
+                    KlineAdjust::None => "",
+                    KlineAdjust::Before => "qfq",
+                    KlineAdjust::After => "hfq",
+                };
+                let url = format!(
+                    "https://web.ifzq.gtimg.cn/appstock/app/fqkline/get?param={},{},,{},{},{}",
+                    symbol,
+                    kind,
+                    millis_to_beijing(timestamp, "%Y-%m-%d").unwrap_or_default(),
+                    count,
+                    fq
+                );
+                log::debug!("腾讯K线请求URL: {}", url);
+                let body = get_json(&self.client, &url, "K线").await?;
+                let data = &body["data"][&symbol];
+                // 没有除权的股票只返回不带复权前缀的数据
+                let rows = data[format!("{}{}", fq, kind)]
+                    .as_array()
+                    .or_else(|| data[kind].as_array());
+                parse_kline_rows(rows.ok_or_else(|| missing("K线"))?)
+            }
+        };
+        let skip = bars.len().saturating_sub(limit.max(0) as usize);
+        Ok(bars.into_iter().skip(skip).collect())
+    }
+
+    async fn fetch_batch_quote(
+        &self,
+        symbols: &[String],
+    ) -> Result<Vec<StockQuote>, ProviderError> {
+        let fields = fetch_quote_fields(&self.client, symbols).await?;
+        Ok(fields
+            .iter()
+            .filter_map(|(symbol, fields)| {
+                Some(StockQuote {
+                    symbol: symbol.clone(),
+                    code: fields.get(2)?.to_string(),
+                    name: fields.get(1)?.to_string(),
+                    current: number_at(fields, 3)?,
+                    percent: number_at(fields, 32).unwrap_or(0.0),
+                })
+            })
+            .collect())
+    }
+
+    async fn fetch_stock_detail(&self, code: &str) -> Result<Option<StockDetail>, ProviderError> {
+        let fields = fetch_quote_fields(&self.client, &[code.to_string()]).await?;
+        Ok(fields
+            .into_iter()
+            .next()
+            .map(|(symbol, fields)| quote_to_detail(symbol, &fields)))
+    }
+
+    async fn fetch_minute_chart(&self, code: &str) -> Result<Vec<MinuteChartItem>, ProviderError> {
+        let symbol = tencent_symbol(code);
+        let url = format!(
+            "https://web.ifzq.gtimg.cn/appstock/app/minute/query?code={}",
+            symbol
+        );
+        log::debug!("腾讯分时图请求URL: {}", url);
+        let body = get_json(&self.client, &url, "分时图").await?;
+        let data = &body["data"][&symbol];
+        let last_close = data["qt"][&symbol]
+            .as_array()
+            .and_then(|quote| quote.get(4))
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.parse::<f64>().ok())
+            .ok_or_else(|| missing("分时图"))?;
+        let date = data["data"]["date"]
+            .as_str()
+            .ok_or_else(|| missing("分时图"))?;
+        let rows = data["data"]["data"]
+            .as_array()
+            .ok_or_else(|| missing("分时图"))?;
+        Ok(parse_minute_rows(date, rows, last_close))
+    }
+}
+
+// 接口返回的数据缺少预期字段（通常是接口格式变化）
+fn missing(label: &str) -> ProviderError {
+    ProviderError::Parse(format!("{}数据格式无法识别", label))
+}
+
+// 发送 GET 请求并读取文本（行情接口返回 GBK 编码）
+async fn get_text(client: &Client, url: &str, label: &str) -> Result<String, ProviderError> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| ProviderError::Network(format!("{}请求发送失败: {}", label, e)))?;
+
+    if !response.status().is_success() {
+        return Err(ProviderError::from_status(response.status().as_u16()));
+    }
+
+    let data = response
+        .bytes()
+        .await
+        .map_err(|e| ProviderError::Network(format!("{}读取响应失败: {}", label, e)))?;
+    Ok(match std::str::from_utf8(&data) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::GBK.decode(&data).0.into_owned(),
+    })
+}
+
+async fn get_json(client: &Client, url: &str, label: &str) -> Result<Value, ProviderError> {
+    let text = get_text(client, url, label).await?;
+    serde_json::from_str(&text)
+        .map_err(|e| ProviderError::Parse(format!("{}JSON解析失败: {}", label, e)))
+}
+
+// 批量请求报价，返回（SH600000 形式的代码, 以 ~ 分隔的字段）
+async fn fetch_quote_fields(
+    client: &Client,
+    symbols: &[String],
+) -> Result<Vec<(String, Vec<String>)>, ProviderError> {
+    let query: Vec<String> = symbols.iter().map(|s| tencent_symbol(s)).collect();
+    let url = format!("https://qt.gtimg.cn/q={}", query.join(","));
+    log::debug!("腾讯报价请求URL: {}", url);
+    let text = get_text(client, &url, "报价").await?;
+    Ok(parse_quote_lines(&text))
+}
+
+// SH600000 -> sh600000
+fn tencent_symbol(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+// 分钟周期（腾讯只提供 1/5/15/30/60 分钟K线）
+fn minute_period(period: &str) -> Option<u32> {
+    match period {
+        "1m" => Some(1),
+        "5m" => Some(5),
+        "15m" => Some(15),
+        "30m" => Some(30),
+        "60m" => Some(60),
+        _ => None,
+    }
+}
+
+fn beijing() -> FixedOffset {
+    FixedOffset::east_opt(8 * 3600).unwrap()
+}
+
+fn millis_to_beijing(millis: i64, format: &str) -> Option<String> {
+    let time = DateTime::from_timestamp_millis(millis)?.with_timezone(&beijing());
+    Some(time.format(format).to_string())
+}
+
+// 北京时间的日期（YYYY-MM-DD）或分钟（YYYYMMDDHHMM）转为 13 位毫秒时间戳
+fn beijing_millis(text: &str) -> Option<i64> {
+    let naive = match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Ok(date) => date.and_hms_opt(0, 0, 0)?,
+        Err(_) => NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M").ok()?,
+    };
+    Some(
+        naive
+            .and_local_timezone(beijing())
+            .single()?
+            .timestamp_millis(),
+    )
+}
+
+fn value_number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.as_f64(),
+        _ => None,
+    }
+}
+
+/// 解析K线数组：[时间, 开, 收, 高, 低, 成交量(手), _, 换手率, 成交额(万元)]
+/// 腾讯不返回涨跌幅，按前一条收盘价计算，第一条作为计算基准不返回
+pub fn parse_kline_rows(rows: &[Value]) -> Vec<StockKlineItem> {
+    let mut bars = Vec::new();
+    let mut last_close: Option<f64> = None;
+    for row in rows {
+        let Some(row) = row.as_array() else { continue };
+        let Some(date) = row
+            .first()
+            .and_then(|v| v.as_str())
+            .and_then(beijing_millis)
+        else {
+            continue;
+        };
+        let num = |i: usize| value_number(row.get(i)).unwrap_or(0.0);
+        let close = num(2);
+        if let Some(prev) = last_close.filter(|prev| *prev > 0.0) {
+            bars.push(StockKlineItem {
+                date,
+                open: num(1),
+                high: num(3),
+                low: num(4),
+                close,
+                // 与雪球一致，volume 为成交额（元）
+                volume: num(8) * 10_000.0,
+                percent: (close - prev) / prev * 100.0,
+                turnoverrate: num(7),
+            });
+        }
+        last_close = Some(close);
+    }
+    bars
+}
+
+/// 解析报价文本：每行形如 v_sh600000="1~浦发银行~600000~10.50~...";
+pub fn parse_quote_lines(text: &str) -> Vec<(String, Vec<String>)> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, value) = line.trim().strip_prefix("v_")?.split_once('=')?;
+            let value = value.trim().trim_end_matches(';').trim_matches('"');
+            let fields: Vec<String> = value.split('~').map(str::to_string).collect();
+            // 无效代码返回 v_pv_none_match="1"
+            if fields.len() < 50 {
+                return None;
+            }
+            Some((name.to_uppercase(), fields))
+        })
+        .collect()
+}
+
+fn number_at(fields: &[String], index: usize) -> Option<f64> {
+    fields.get(index)?.parse().ok()
+}
+
+// 报价字段转为股票详情（市值、成交额换算为元）
+fn quote_to_detail(symbol: String, fields: &[String]) -> StockDetail {
+    let num = |i: usize| number_at(fields, i);
+    StockDetail {
+        name: fields.get(1).cloned().unwrap_or_default(),
+        symbol,
+        current: num(3),
+        last_close: num(4),
+        open: num(5),
+        high: num(33),
+        low: num(34),
+        limit_up: num(47),
+        limit_down: num(48),
+        pe_lyr: num(53),
+        pe_ttm: num(39),
+        pe_forecast: num(52),
+        percent: num(32),
+        market_capital: num(45).map(|v| v * 100_000_000.0),
+        volume_ratio: num(49),
+        turnover_rate: num(38),
+        amount: num(37).map(|v| v * 10_000.0),
+    }
+}
+
+/// 解析分时数据：每条形如 "0930 10.50 1234 129500.00"（时间 价格 累计成交量(手) 累计成交额）
+pub fn parse_minute_rows(date: &str, rows: &[Value], last_close: f64) -> Vec<MinuteChartItem> {
+    let mut items = Vec::new();
+    let mut last_volume = 0i64;
+    for row in rows {
+        let Some(row) = row.as_str() else { continue };
+        let parts: Vec<&str> = row.split_whitespace().collect();
+        let (Some(time), Some(price), Some(volume)) = (parts.first(), parts.get(1), parts.get(2))
+        else {
+            continue;
+        };
+        let (Some(timestamp), Ok(price), Ok(volume)) = (
+            beijing_millis(&format!("{}{}", date, time)),
+            price.parse::<f64>(),
+            volume.parse::<i64>(),
+        ) else {
+            continue;
+        };
+        items.push(MinuteChartItem {
+            percent: if last_close > 0.0 {
+                (price - last_close) / last_close * 100.0
+            } else {
+                0.0
+            },
+            timestamp,
+            // 累计成交量（手）转为每分钟成交量（股）
+            volume: (volume - last_volume).max(0) * 100,
+        });
+        last_volume = volume;
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_kline_rows_with_percent_from_previous_close() {
+        let rows = json!([
+            ["2024-01-02", "10.00", "10.00", "10.10", "9.90", "1000.000"],
+            [
+                "2024-01-03",
+                "10.00",
+                "11.00",
+                "11.00",
+                "9.90",
+                "2000.000",
+                {},
+                "0.50",
+                "2200.00"
+            ],
+            ["bad", "1", "1", "1", "1", "1"]
+        ]);
+        let bars = parse_kline_rows(rows.as_array().unwrap());
+        assert_eq!(bars.len(), 1);
+        // 2024-01-03 00:00 北京时间
+        assert_eq!(bars[0].date, 1_704_211_200_000);
+        assert!((bars[0].percent - 10.0).abs() < 1e-9);
+        assert_eq!(bars[0].volume, 22_000_000.0);
+        assert_eq!(bars[0].turnoverrate, 0.5);
+    }
+
+    #[test]
+    fn parses_quote_lines_and_skips_unknown_codes() {
+        let mut fields = vec!["0"; 60];
+        fields[1] = "浦发银行";
+        fields[2] = "600000";
+        fields[3] = "10.50";
+        fields[32] = "1.25";
+        fields[45] = "3000";
+        let text = format!(
+            "v_sh600000=\"{}\";\nv_pv_none_match=\"1\";\n",
+            fields.join("~")
+        );
+        let quotes = parse_quote_lines(&text);
+        assert_eq!(quotes.len(), 1);
+        let (symbol, fields) = quotes.into_iter().next().unwrap();
+        assert_eq!(symbol, "SH600000");
+        let detail = quote_to_detail(symbol, &fields);
+        assert_eq!(detail.name, "浦发银行");
+        assert_eq!(detail.current, Some(10.5));
+        assert_eq!(detail.percent, Some(1.25));
+        assert_eq!(detail.market_capital, Some(300_000_000_000.0));
+    }
+
+    #[test]
+    fn parses_minute_rows_into_per_minute_volume() {
+        let rows = json!(["0930 10.10 100 101000.00", "0931 9.90 250 249500.00"]);
+        let items = parse_minute_rows("20240102", rows.as_array().unwrap(), 10.0);
+        assert_eq!(items.len(), 2);
+        // 2024-01-02 09:30 北京时间
+        assert_eq!(items[0].timestamp, 1_704_159_000_000);
+        assert!((items[0].percent - 1.0).abs() < 1e-9);
+        assert_eq!((items[0].volume, items[1].volume), (10_000, 15_000));
+    }
+}
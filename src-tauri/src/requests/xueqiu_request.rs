@@ -1,178 +1,298 @@
-use crate::requests::common::create_xueqiu_http_client;
+use crate::db::app_config_db::get_kline_adjust;
+use crate::requests::common::{
+    build_xueqiu_http_client, create_xueqiu_http_client, read_xueqiu_cookie,
+};
+use crate::requests::provider::{MarketDataProvider, ProviderError};
+use crate::structs::corporate_action_structs::{CorporateAction, KlineAdjust};
 use crate::structs::xueqiu_structs::{
-    MinuteChartResponse, RawBatchQuoteResponse, RawKlineResponse, RawStockDetailResponse,
-    SetSelectionResponse,
+    MinuteChartItem, MinuteChartResponse, RawBatchQuoteResponse, RawBonusResponse, RawKlineData,
+    RawKlineResponse, RawStockDetailResponse, SetSelectionResponse, StockDetail, StockKlineItem,
+    StockQuote,
 };
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use reqwest::Client;
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
 use tauri::AppHandle;
+use tauri_plugin_log::log;
 
-pub async fn fetch_raw_kline_data(
-    app: &AppHandle,
-    code: &str,
-    period: &str,
-    timestamp: &str,
-    limit: i32,
-) -> Result<RawKlineResponse, String> {
-    // 构建请求URL
-    let url = format!(
-        "https://stock.xueqiu.com/v5/stock/chart/kline.json?symbol={}&begin={}&period={}&type=before&count=-{}&indicator=kline,pe,pb,ps,pcf,market_capital,agt,ggt,balance",
-        code, timestamp, period, limit
-    );
-    println!("K线请求URL: {}", url);
+/// 雪球行情数据源
+pub struct XueqiuProvider {
+    client: Client,
+    adjust: KlineAdjust, // K线复权方式
+}
 
-    // 创建HTTP客户端
-    let client =
-        create_xueqiu_http_client(app).map_err(|e| format!("HTTP客户端创建失败: {}", e))?;
+impl XueqiuProvider {
+    /// 使用数据库中保存的Cookie创建数据源
+    pub fn from_db(conn: &Connection) -> Result<Self, ProviderError> {
+        let cookie = read_xueqiu_cookie(conn).map_err(ProviderError::Other)?;
+        let client = build_xueqiu_http_client(&cookie).map_err(ProviderError::Other)?;
+        let adjust = get_kline_adjust(conn).map_err(|e| ProviderError::Other(e.to_string()))?;
+        Ok(Self { client, adjust })
+    }
+}
 
-    // 发送请求
+#[async_trait]
+impl MarketDataProvider for XueqiuProvider {
+    fn id(&self) -> &'static str {
+        "xueqiu"
+    }
+
+    async fn fetch_kline(
+        &self,
+        code: &str,
+        period: &str,
+        timestamp: i64,
+        limit: i32,
+    ) -> Result<Vec<StockKlineItem>, ProviderError> {
+        let raw =
+            fetch_raw_kline_data(&self.client, code, period, timestamp, limit, self.adjust).await?;
+        Ok(parse_kline_items(raw.data))
+    }
+
+    async fn fetch_batch_quote(
+        &self,
+        symbols: &[String],
+    ) -> Result<Vec<StockQuote>, ProviderError> {
+        let raw = fetch_raw_batch_quote(&self.client, &symbols.join(",")).await?;
+        // 只保留有数据的 quote
+        Ok(raw
+            .data
+            .items
+            .into_iter()
+            .filter_map(|item| item.quote)
+            .collect())
+    }
+
+    async fn fetch_stock_detail(&self, code: &str) -> Result<Option<StockDetail>, ProviderError> {
+        let raw = fetch_raw_stock_detail(&self.client, code).await?;
+        Ok(raw.data.quote)
+    }
+
+    async fn fetch_minute_chart(&self, code: &str) -> Result<Vec<MinuteChartItem>, ProviderError> {
+        let raw = fetch_minute_chart(&self.client, code).await?;
+        Ok(raw.data.items)
+    }
+
+    async fn fetch_corporate_actions(
+        &self,
+        code: &str,
+    ) -> Result<Vec<CorporateAction>, ProviderError> {
+        let raw = fetch_raw_bonus(&self.client, code).await?;
+        Ok(raw
+            .data
+            .items
+            .into_iter()
+            .filter_map(|item| {
+                let plan = item.plan_explain?;
+                let ex_date = item.ashare_ex_dividend_date.or(item.ex_dividend_date)?;
+                let (cash_dividend, bonus_shares, transfer_shares) = parse_bonus_plan(&plan)?;
+                Some(CorporateAction {
+                    id: 0,
+                    code: code.to_uppercase(),
+                    ex_date: millis_to_beijing_date(ex_date)?,
+                    cash_dividend,
+                    bonus_shares,
+                    transfer_shares,
+                    split_ratio: 1.0,
+                    source: self.id().to_string(),
+                    note: plan,
+                })
+            })
+            .collect())
+    }
+}
+
+// 发送 GET 请求并解析 JSON（401/403 与解析失败会触发备用数据源）
+async fn get_json<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    label: &str,
+) -> Result<T, ProviderError> {
     let response = client
-        .get(&url)
+        .get(url)
         .send()
         .await
-        .map_err(|e| format!("请求发送失败: {}", e))?;
+        .map_err(|e| ProviderError::Network(format!("{}请求发送失败: {}", label, e)))?;
 
-    // 检查响应状态
     if !response.status().is_success() {
-        return Err(format!("API请求失败，状态码: {}", response.status()));
+        return Err(ProviderError::from_status(response.status().as_u16()));
     }
 
-    // 解析原始响应
-    let raw_response = response
-        .json::<RawKlineResponse>()
+    response
+        .json::<T>()
         .await
-        .map_err(|e| format!("JSON解析失败: {}", e))?;
+        .map_err(|e| ProviderError::Parse(format!("{}JSON解析失败: {}", label, e)))
+}
 
-    Ok(raw_response)
+pub async fn fetch_raw_kline_data(
+    client: &Client,
+    code: &str,
+    period: &str,
+    timestamp: i64,
+    limit: i32,
+    adjust: KlineAdjust,
+) -> Result<RawKlineResponse, ProviderError> {
+    // 构建请求URL（type 为复权方式）
+    let url = format!(
+        "https://stock.xueqiu.com/v5/stock/chart/kline.json?symbol={}&begin={}&period={}&type={}&count=-{}&indicator=kline,pe,pb,ps,pcf,market_capital,agt,ggt,balance",
+        code, timestamp, period, adjust.as_str(), limit
+    );
+    log::debug!("K线请求URL: {}", url);
+
+    get_json(client, &url, "K线").await
+}
+
+/// 将雪球按列返回的K线数据解析为业务结构体
+pub fn parse_kline_items(raw: RawKlineData) -> Vec<StockKlineItem> {
+    let RawKlineData { column, item } = raw;
+
+    let mut parsed_kline = Vec::new();
+    for kline_item in item {
+        // 构建字段映射
+        let data_map: std::collections::HashMap<&str, &serde_json::Value> = column
+            .iter()
+            .zip(kline_item.iter())
+            .map(|(col_name, val)| (col_name.as_str(), val))
+            .collect();
+        // 解析时间戳
+        let timestamp = data_map
+            .get("timestamp")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0);
+
+        // 解析数值字段
+        let parse_num = |key: &str| -> f64 {
+            data_map
+                .get(key)
+                .and_then(|v| match v {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::Number(n) => n.as_f64(),
+                    serde_json::Value::String(s) => s.parse().ok(),
+                    _ => None,
+                })
+                .unwrap_or(0.0)
+        };
+
+        parsed_kline.push(StockKlineItem {
+            date: timestamp,
+            open: parse_num("open"),
+            high: parse_num("high"),
+            low: parse_num("low"),
+            close: parse_num("close"),
+            volume: parse_num("amount"),
+            percent: parse_num("percent"),
+            turnoverrate: parse_num("turnoverrate"),
+        });
+    }
+
+    parsed_kline
 }
 
 /// 爬取批量股票报价原始数据（对应 Python 的 get_selection_details）
 /// 返回：RawBatchQuoteResponse（原始接口响应）
 pub async fn fetch_raw_batch_quote(
-    app: &AppHandle,
+    client: &Client,
     symbols: &str, // 逗号分隔的股票代码（如 "SH600000,SZ000001"）
-) -> Result<RawBatchQuoteResponse, String> {
-    // 1. 构建请求 URL（匹配 Python 的批量报价接口）
+) -> Result<RawBatchQuoteResponse, ProviderError> {
     let url = format!(
         "https://stock.xueqiu.com/v5/stock/batch/quote.json?symbol={}",
         symbols
     );
-    println!("批量报价请求URL: {}", url);
-
-    // 2. 创建 HTTP 客户端（复用统一的 Cookie 逻辑）
-    let client =
-        create_xueqiu_http_client(app).map_err(|e| format!("HTTP客户端创建失败: {}", e))?;
-
-    // 3. 发送请求（超时逻辑继承自 create_xueqiu_http_client）
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("批量报价请求发送失败: {}", e))?;
-
-    // 4. 检查响应状态（处理 401/403 等 Cookie 相关错误）
-    if !response.status().is_success() {
-        let status = response.status();
-        let err_msg = if status.as_u16() == 401 || status.as_u16() == 403 {
-            "Cookie已过期或无效".to_string()
-        } else {
-            format!("批量报价API请求失败，状态码: {}", status)
-        };
-        return Err(err_msg);
-    }
-
-    // 5. 解析原始响应（匹配 RawBatchQuoteResponse 结构体）
-    let raw_response = response
-        .json::<RawBatchQuoteResponse>()
-        .await
-        .map_err(|e| format!("批量报价JSON解析失败: {}", e))?;
+    log::debug!("批量报价请求URL: {}", url);
 
-    Ok(raw_response)
+    get_json(client, &url, "批量报价").await
 }
 
 /// 爬取单只股票详情原始数据
 pub async fn fetch_raw_stock_detail(
-    app: &AppHandle,
+    client: &Client,
     code: &str, // 单个股票代码（如 "SH600000"）
-) -> Result<RawStockDetailResponse, String> {
-    // 1. 构建请求 URL（匹配 Python 的单只详情接口，带 extend=detail 参数）
+) -> Result<RawStockDetailResponse, ProviderError> {
+    // 带 extend=detail 参数获取完整详情
     let url = format!(
         "https://stock.xueqiu.com/v5/stock/quote.json?symbol={}&extend=detail",
         code
     );
-    println!("单只股票详情请求URL: {}", url);
-
-    // 2. 创建 HTTP 客户端
-    let client =
-        create_xueqiu_http_client(app).map_err(|e| format!("HTTP客户端创建失败: {}", e))?;
+    log::debug!("单只股票详情请求URL: {}", url);
 
-    // 3. 发送请求
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("单只股票详情请求发送失败: {}", e))?;
-
-    // 4. 检查响应状态（优先处理 Cookie 错误）
-    if !response.status().is_success() {
-        let status = response.status();
-        let err_msg = match status.as_u16() {
-            401 | 403 => "Cookie已过期或无效".to_string(),
-            404 => format!("股票代码 {} 不存在（接口404）", code),
-            _ => format!("单只股票详情API请求失败，状态码: {}", status),
-        };
-        return Err(err_msg);
-    }
-
-    // 5. 解析原始响应（匹配 RawStockDetailResponse 结构体）
-    let raw_response = response
-        .json::<RawStockDetailResponse>()
-        .await
-        .map_err(|e| format!("单只股票详情JSON解析失败: {}", e))?;
-
-    Ok(raw_response)
+    get_json(client, &url, "单只股票详情").await
 }
 
 pub async fn fetch_minute_chart(
-    app: &AppHandle,
+    client: &Client,
     code: &str,
-) -> Result<MinuteChartResponse, String> {
-    // 1. 构建请求 URL
+) -> Result<MinuteChartResponse, ProviderError> {
     // 注意：URL中的 `period=1d` 是固定的，表示获取一天的数据
     let url = format!(
         "https://stock.xueqiu.com/v5/stock/chart/minute.json?symbol={}&period=1d",
         code
     );
-    println!("分时图数据请求URL: {}", url);
+    log::debug!("分时图数据请求URL: {}", url);
 
-    // 2. 创建 HTTP 客户端（复用统一的 Cookie 和超时逻辑）
-    let client =
-        create_xueqiu_http_client(app).map_err(|e| format!("HTTP客户端创建失败: {}", e))?;
+    get_json(client, &url, "分时图").await
+}
 
-    // 3. 发送请求
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("分时图数据请求发送失败: {}", e))?;
+/// 爬取分红送转原始数据（最近 100 次）
+pub async fn fetch_raw_bonus(
+    client: &Client,
+    code: &str,
+) -> Result<RawBonusResponse, ProviderError> {
+    let url = format!(
+        "https://stock.xueqiu.com/v5/stock/f10/cn/bonus.json?symbol={}&size=100&page=1",
+        code
+    );
+    log::debug!("分红送转请求URL: {}", url);
 
-    // 4. 检查响应状态
-    if !response.status().is_success() {
-        let status = response.status();
-        let err_msg = if status.as_u16() == 401 || status.as_u16() == 403 {
-            "Cookie已过期或无效，请重新登录。".to_string()
-        } else {
-            format!("分时图API请求失败，状态码: {}", status)
-        };
-        return Err(err_msg);
-    }
+    get_json(client, &url, "分红送转").await
+}
 
-    // 5. 解析JSON响应
-    // 使用 .json() 方法直接将响应体解析为我们定义的 MinuteChartResponse 结构体
-    let chart_data = response
-        .json::<MinuteChartResponse>()
-        .await
-        .map_err(|e| format!("分时图JSON解析失败: {}", e))?;
+// 毫秒时间戳转为北京时间日期（YYYY-MM-DD）
+fn millis_to_beijing_date(millis: i64) -> Option<String> {
+    let beijing = FixedOffset::east_opt(8 * 3600)?;
+    let time = DateTime::from_timestamp_millis(millis)?.with_timezone(&beijing);
+    Some(time.format("%Y-%m-%d").to_string())
+}
 
-    Ok(chart_data)
+// 读取关键字后面紧跟的数字（如 "送3" 中的 3）
+fn number_after(text: &str, keyword: &str) -> Option<f64> {
+    let start = text.find(keyword)? + keyword.len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    digits.parse().ok()
+}
+
+/// 解析分配方案，返回每股的（派息, 送股, 转增）
+/// 如 "10送3转2派1元" 为 (0.1, 0.3, 0.2)；方案有扣税后金额时派息按扣税后计算
+/// 不分配或无法识别时返回 None
+pub fn parse_bonus_plan(plan: &str) -> Option<(f64, f64, f64)> {
+    let plan = plan.trim();
+    // 方案以每多少股开头，缺省为每10股
+    let base_digits: String = plan.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let base: f64 = base_digits.parse().unwrap_or(10.0);
+    if base <= 0.0 {
+        return None;
+    }
+    let (main, remark) = match plan.find(['(', '（']) {
+        Some(index) => plan.split_at(index),
+        None => (plan, ""),
+    };
+
+    let cash = number_after(remark, "扣税后")
+        .or_else(|| number_after(main, "派"))
+        .unwrap_or(0.0);
+    let bonus = number_after(main, "送").unwrap_or(0.0);
+    let transfer = number_after(main, "转增")
+        .or_else(|| number_after(main, "转"))
+        .unwrap_or(0.0);
+
+    if cash == 0.0 && bonus == 0.0 && transfer == 0.0 {
+        return None;
+    }
+    Some((cash / base, bonus / base, transfer / base))
 }
 
 /// 添加股票到自选股组合（雪球）
@@ -181,7 +301,7 @@ pub async fn add_to_watchlist(
     symbols: &str, // 逗号分隔的股票代码，如 "SH600000,SZ000001"
 ) -> Result<SetSelectionResponse, String> {
     let url = "https://stock.xueqiu.com/v5/stock/portfolio/stock/add.json";
-    println!("添加自选请求URL: {}", url);
+    log::debug!("添加自选请求URL: {}", url);
 
     let client =
         create_xueqiu_http_client(app).map_err(|e| format!("HTTP客户端创建失败: {}", e))?;
@@ -228,7 +348,7 @@ pub async fn remove_from_watchlist(
     symbols: &str, // 逗号分隔的股票代码
 ) -> Result<SetSelectionResponse, String> {
     let url = "https://stock.xueqiu.com/v5/stock/portfolio/stock/cancel.json";
-    println!("删除自选请求URL: {}", url);
+    log::debug!("删除自选请求URL: {}", url);
 
     let client =
         create_xueqiu_http_client(app).map_err(|e| format!("HTTP客户端创建失败: {}", e))?;
@@ -266,3 +386,37 @@ pub async fn remove_from_watchlist(
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(actual: Option<(f64, f64, f64)>, expected: (f64, f64, f64)) {
+        let (cash, bonus, transfer) = actual.unwrap();
+        assert!((cash - expected.0).abs() < 1e-9);
+        assert!((bonus - expected.1).abs() < 1e-9);
+        assert!((transfer - expected.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parses_bonus_plans() {
+        approx(parse_bonus_plan("10送3转2派1元"), (0.1, 0.3, 0.2));
+        approx(parse_bonus_plan("10转增4股派2.5元(含税)"), (0.25, 0.0, 0.4));
+        approx(
+            parse_bonus_plan("10派5.1元(含税,扣税后4.59元)"),
+            (0.459, 0.0, 0.0),
+        );
+        approx(parse_bonus_plan("派1元"), (0.1, 0.0, 0.0));
+        assert!(parse_bonus_plan("不分配不转增").is_none());
+        assert!(parse_bonus_plan("").is_none());
+    }
+
+    #[test]
+    fn converts_ex_date_to_beijing_time() {
+        // 2024-06-19 16:00:00 UTC 即北京时间 2024-06-20 00:00:00
+        assert_eq!(
+            millis_to_beijing_date(1_718_812_800_000).as_deref(),
+            Some("2024-06-20")
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use crate::db::app_config_db::{
+    encrypt_plain_secrets, get_passphrase_params, reencrypt_secrets, set_passphrase_params,
+};
+use crate::db::common::AppDb;
+use crate::structs::secret_structs::{PassphraseParams, SecretStatus};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rusqlite::Connection;
+use sha2::Sha256;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+
+/// 加密后的配置值前缀（没有前缀的是旧版明文）
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+// 密钥文件放在用户目录下，不在应用数据目录中，也不会随备份导出
+const KEY_FILE_DIR: &str = ".candle-shadow";
+const KEY_FILE_NAME: &str = "secret.key";
+
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+// 用于校验密码的固定明文
+const CHECK_PLAINTEXT: &str = "candle-shadow-secret-check";
+
+pub type SecretKey = [u8; 32];
+
+// 当前会话使用的密钥（密码模式下解锁前为空）
+static ACTIVE_KEY: RwLock<Option<SecretKey>> = RwLock::new(None);
+
+// 启动时加载密钥失败的原因（通过加密状态告知用户，加载成功或重新设置密钥后清除）
+static LOAD_ERROR: RwLock<Option<String>> = RwLock::new(None);
+
+pub fn active_key() -> Option<SecretKey> {
+    ACTIVE_KEY.read().ok().and_then(|key| *key)
+}
+
+fn set_active_key(key: Option<SecretKey>) {
+    if key.is_some() {
+        set_load_error(None);
+    }
+    if let Ok(mut active) = ACTIVE_KEY.write() {
+        *active = key;
+    }
+}
+
+fn set_load_error(error: Option<String>) {
+    if let Ok(mut load_error) = LOAD_ERROR.write() {
+        *load_error = error;
+    }
+}
+
+fn load_error() -> Option<String> {
+    LOAD_ERROR.read().ok().and_then(|error| error.clone())
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// 加密：enc:v1: + base64(随机 nonce + 密文)
+pub fn encrypt_with(key: &SecretKey, plaintext: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| "加密失败".to_string())?;
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(payload)))
+}
+
+/// 解密 encrypt_with 生成的值（密钥不对或数据被修改时返回错误）
+pub fn decrypt_with(key: &SecretKey, value: &str) -> Result<String, String> {
+    let encoded = value
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .ok_or_else(|| "不是加密的配置值".to_string())?;
+    let payload = BASE64
+        .decode(encoded)
+        .map_err(|_| "加密数据格式错误".to_string())?;
+    if payload.len() <= NONCE_LEN {
+        return Err("加密数据格式错误".to_string());
+    }
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "解密失败，密钥不正确或数据已损坏".to_string())?;
+    String::from_utf8(plaintext).map_err(|_| "解密结果不是有效文本".to_string())
+}
+
+/// 用当前会话密钥加密（未解锁时拒绝，避免以明文保存）
+pub fn encrypt(plaintext: &str) -> Result<String, String> {
+    let key = active_key().ok_or_else(|| "敏感配置尚未解锁，请先输入密码".to_string())?;
+    encrypt_with(&key, plaintext)
+}
+
+/// 用当前会话密钥解密（旧版明文原样返回）
+pub fn decrypt(value: &str) -> Result<String, String> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    let key = active_key().ok_or_else(|| "敏感配置尚未解锁，请先输入密码".to_string())?;
+    decrypt_with(&key, value)
+}
+
+fn random_key() -> SecretKey {
+    Aes256Gcm::generate_key(&mut OsRng).into()
+}
+
+/// 由用户密码派生密钥（PBKDF2-SHA256）
+pub fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> SecretKey {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+/// 为新密码生成派生参数，返回参数和派生出的密钥
+pub fn new_passphrase(passphrase: &str) -> Result<(PassphraseParams, SecretKey), String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS);
+    let params = PassphraseParams {
+        salt: BASE64.encode(salt),
+        iterations: PBKDF2_ITERATIONS,
+        check: encrypt_with(&key, CHECK_PLAINTEXT)?,
+    };
+    Ok((params, key))
+}
+
+/// 校验密码并返回派生出的密钥
+pub fn verify_passphrase(passphrase: &str, params: &PassphraseParams) -> Result<SecretKey, String> {
+    let salt = BASE64
+        .decode(&params.salt)
+        .map_err(|_| "密钥派生参数已损坏".to_string())?;
+    let key = derive_key(passphrase, &salt, params.iterations);
+    match decrypt_with(&key, &params.check) {
+        Ok(check) if check == CHECK_PLAINTEXT => Ok(key),
+        _ => Err("密码不正确".to_string()),
+    }
+}
+
+/// 密钥文件路径：用户目录下的 .candle-shadow/secret.key
+pub fn key_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("无法获取用户目录: {}", e))?;
+    Ok(home.join(KEY_FILE_DIR).join(KEY_FILE_NAME))
+}
+
+fn read_key_file(path: &Path) -> Result<SecretKey, String> {
+    let encoded = std::fs::read_to_string(path).map_err(|e| format!("读取密钥文件失败: {}", e))?;
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|_| "密钥文件格式错误".to_string())?;
+    SecretKey::try_from(bytes.as_slice()).map_err(|_| "密钥文件长度错误".to_string())
+}
+
+/// 读取密钥文件，不存在时生成新的随机密钥
+pub fn read_or_create_key_file(path: &Path) -> Result<SecretKey, String> {
+    if path.exists() {
+        return read_key_file(path);
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("无法创建密钥目录: {}", e))?;
+    }
+    // 创建时即设为仅当前用户可读写；create_new 保证不会覆盖同时启动的另一个进程写入的密钥
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = match options.open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return read_key_file(path),
+        Err(e) => return Err(format!("创建密钥文件失败: {}", e)),
+    };
+    let key = random_key();
+    file.write_all(BASE64.encode(key).as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("写入密钥文件失败: {}", e))?;
+    Ok(key)
+}
+
+/// 启动时加载密钥：未设置密码时使用密钥文件，并加密旧版明文保存的敏感配置
+/// 设置了密码时保持锁定，等待用户输入密码
+/// 加载失败时敏感配置保持锁定，失败原因记录在加密状态中供界面提示
+pub fn init_secrets(app: &AppHandle, db: &AppDb) -> Result<(), String> {
+    load_secrets(app, db).inspect_err(|e| set_load_error(Some(e.clone())))
+}
+
+fn load_secrets(app: &AppHandle, db: &AppDb) -> Result<(), String> {
+    let params = db
+        .with_conn(|conn| get_passphrase_params(conn))
+        .map_err(|e| e.to_string())?;
+    if params.is_some() {
+        set_active_key(None);
+        return Ok(());
+    }
+
+    let key = read_or_create_key_file(&key_file_path(app)?)?;
+    set_active_key(Some(key));
+    db.with_conn(|conn| encrypt_plain_secrets(conn))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 当前的加密状态
+pub fn secret_status(app: &AppHandle, conn: &Connection) -> Result<SecretStatus, String> {
+    let passphrase = get_passphrase_params(conn)
+        .map_err(|e| e.to_string())?
+        .is_some();
+    Ok(SecretStatus {
+        mode: if passphrase { "passphrase" } else { "key_file" }.to_string(),
+        unlocked: active_key().is_some(),
+        load_error: load_error(),
+        key_file: if passphrase {
+            None
+        } else {
+            Some(key_file_path(app)?.to_string_lossy().to_string())
+        },
+    })
+}
+
+/// 输入密码解锁（仅密码模式）
+pub fn unlock(conn: &Connection, passphrase: &str) -> Result<(), String> {
+    let params = get_passphrase_params(conn)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "未设置密码，无需解锁".to_string())?;
+    let key = verify_passphrase(passphrase, &params)?;
+    set_active_key(Some(key));
+    encrypt_plain_secrets(conn).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 切换密钥来源：传入密码时改为密码模式，传入 None 时改回密钥文件模式
+/// 已保存的敏感配置在同一事务中用新密钥重新加密
+pub fn change_key_source(
+    app: &AppHandle,
+    conn: &mut Connection,
+    passphrase: Option<&str>,
+) -> Result<(), String> {
+    let old_key = active_key().ok_or_else(|| "敏感配置尚未解锁，请先输入密码".to_string())?;
+    let (params, new_key) = match passphrase {
+        Some(passphrase) if passphrase.chars().count() < 8 => {
+            return Err("密码至少需要 8 个字符".to_string())
+        }
+        Some(passphrase) => {
+            let (params, key) = new_passphrase(passphrase)?;
+            (Some(params), key)
+        }
+        None => (None, read_or_create_key_file(&key_file_path(app)?)?),
+    };
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+    reencrypt_secrets(&tx, &old_key, &new_key).map_err(|e| e.to_string())?;
+    set_passphrase_params(&tx, params.as_ref()).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+    set_active_key(Some(new_key));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_values_round_trip_only_with_the_same_key() {
+        let key = random_key();
+        let encrypted = encrypt_with(&key, "xq_a_token=abc").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("xq_a_token"));
+        assert_eq!(decrypt_with(&key, &encrypted).unwrap(), "xq_a_token=abc");
+        assert!(decrypt_with(&random_key(), &encrypted).is_err());
+    }
+
+    #[test]
+    fn passphrase_is_verified_against_stored_params() {
+        let (params, key) = new_passphrase("correct horse").unwrap();
+        assert_eq!(verify_passphrase("correct horse", &params).unwrap(), key);
+        assert!(verify_passphrase("wrong horse", &params).is_err());
+    }
+
+    #[test]
+    fn secrets_are_reencrypted_with_the_new_key() {
+        let conn = crate::db::common::open_test_connection();
+        // 旧版明文保存的 Cookie
+        conn.execute(
+            "INSERT INTO app_config (key, value) VALUES ('xueqiu_cookie', 'xq_a_token=abc')",
+            [],
+        )
+        .unwrap();
+        let (old_key, new_key) = (random_key(), random_key());
+        assert_eq!(reencrypt_secrets(&conn, &old_key, &new_key).unwrap(), 1);
+        assert_eq!(reencrypt_secrets(&conn, &new_key, &old_key).unwrap(), 1);
+
+        let stored: String = conn
+            .query_row(
+                "SELECT value FROM app_config WHERE key = 'xueqiu_cookie'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(is_encrypted(&stored));
+        assert_eq!(decrypt_with(&old_key, &stored).unwrap(), "xq_a_token=abc");
+        assert!(reencrypt_secrets(&conn, &new_key, &old_key).is_err());
+    }
+
+    #[test]
+    fn key_file_is_created_once_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join(KEY_FILE_NAME);
+        let created = read_or_create_key_file(&path).unwrap();
+        assert_eq!(read_or_create_key_file(&path).unwrap(), created);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::structs::statement_structs::{BrokerFormat, StatementColumns};
+
+/// 自定义列映射的格式 id
+pub const CUSTOM_BROKER: &str = "custom";
+
+fn names(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+// 华泰证券（涨乐财富通）交割单
+fn huatai() -> StatementColumns {
+    StatementColumns {
+        date: names(&["成交日期", "交收日期"]),
+        time: names(&["成交时间"]),
+        code: names(&["证券代码"]),
+        name: names(&["证券名称"]),
+        side: names(&["买卖标志", "操作", "业务名称"]),
+        quantity: names(&["成交数量", "成交股数"]),
+        price: names(&["成交均价", "成交价格"]),
+        market: names(&["交易市场", "市场名称"]),
+    }
+}
+
+// 东方财富证券交割单
+fn eastmoney() -> StatementColumns {
+    StatementColumns {
+        date: names(&["成交日期", "发生日期", "交割日期"]),
+        time: names(&["成交时间"]),
+        code: names(&["证券代码"]),
+        name: names(&["证券名称"]),
+        side: names(&["业务名称", "买卖方向", "委托方向"]),
+        quantity: names(&["成交数量", "发生数量"]),
+        price: names(&["成交价格", "成交均价"]),
+        market: names(&["市场", "交易市场"]),
+    }
+}
+
+// 同花顺交割单（导出的 .xls 实际是 GBK 编码的制表符文本）
+fn tonghuashun() -> StatementColumns {
+    StatementColumns {
+        date: names(&["成交日期"]),
+        time: names(&["成交时间"]),
+        code: names(&["证券代码"]),
+        name: names(&["证券名称"]),
+        side: names(&["操作", "买卖标志"]),
+        quantity: names(&["成交数量"]),
+        price: names(&["成交均价", "成交价格"]),
+        market: names(&["交易市场"]),
+    }
+}
+
+/// 内置的券商交割单格式
+pub fn builtin_brokers() -> Vec<BrokerFormat> {
+    [
+        ("huatai", "华泰证券", huatai()),
+        ("eastmoney", "东方财富", eastmoney()),
+        ("tonghuashun", "同花顺", tonghuashun()),
+    ]
+    .into_iter()
+    .map(|(id, name, columns)| BrokerFormat {
+        id: id.to_string(),
+        name: name.to_string(),
+        columns,
+    })
+    .collect()
+}
+
+/// 按 id 查找内置格式的列映射
+pub fn builtin_columns(id: &str) -> Option<StatementColumns> {
+    builtin_brokers()
+        .into_iter()
+        .find(|broker| broker.id == id)
+        .map(|broker| broker.columns)
+}
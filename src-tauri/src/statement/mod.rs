@@ -0,0 +1,429 @@
+pub mod brokers;
+
+use crate::db::position_db::{BUY_ACTION, SELL_ACTION};
+use crate::structs::statement_structs::{SkippedRow, StatementColumns, StatementOrder};
+use crate::structs::StockError;
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use chrono::{NaiveDate, NaiveTime};
+use std::io::Cursor;
+
+// 在前多少行中查找表头（交割单开头可能有标题、账户信息等）
+const HEADER_SEARCH_ROWS: usize = 20;
+
+// 没有成交时间时使用开盘时间，同一天的成交按文件中的先后顺序回放
+const DEFAULT_TRADE_TIME: &str = "09:30:00";
+
+/// 读取交割单为字符串表格：Excel 文件读取第一个工作表，其余按 CSV / 制表符文本读取
+/// 同花顺等导出的 .xls 实际是文本文件，无法按 Excel 打开时也按文本读取
+pub fn read_table(file_name: &str, data: &[u8]) -> Result<Vec<Vec<String>>, StockError> {
+    let extension = file_name
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    if matches!(extension.as_str(), "xlsx" | "xlsm" | "xlsb" | "xls" | "ods") {
+        match read_workbook(data) {
+            Ok(table) => return Ok(table),
+            Err(e) if extension != "xls" => return Err(e),
+            Err(_) => {}
+        }
+    }
+    read_text(data)
+}
+
+fn read_workbook(data: &[u8]) -> Result<Vec<Vec<String>>, StockError> {
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(data.to_vec()))
+        .map_err(|e| StockError::BusinessError(format!("打开 Excel 文件失败: {}", e)))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| StockError::BusinessError("Excel 文件中没有工作表".to_string()))?
+        .map_err(|e| StockError::BusinessError(format!("读取工作表失败: {}", e)))?;
+
+    Ok(range
+        .rows()
+        .map(|row| {
+            row.iter()
+                .map(|cell| clean_cell(&cell_text(cell)))
+                .collect()
+        })
+        .collect())
+}
+
+// Excel 单元格转为文本：整数不带小数点（避免代码变成 600000.0），日期转为 YYYY-MM-DD
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty | Data::Error(_) => String::new(),
+        Data::String(value) | Data::DateTimeIso(value) | Data::DurationIso(value) => value.clone(),
+        Data::Int(value) => value.to_string(),
+        Data::Float(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+            (*value as i64).to_string()
+        }
+        Data::Float(value) => value.to_string(),
+        Data::Bool(value) => value.to_string(),
+        Data::DateTime(value) => {
+            let serial = value.as_f64();
+            let Some(datetime) = value.as_datetime() else {
+                return serial.to_string();
+            };
+            if serial < 1.0 {
+                datetime.format("%H:%M:%S").to_string()
+            } else if serial.fract() == 0.0 {
+                datetime.format("%Y-%m-%d").to_string()
+            } else {
+                datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+            }
+        }
+    }
+}
+
+// 文本交割单：优先按 UTF-8 解码，失败时按 GBK（券商导出的文件大多是 GBK）
+fn read_text(data: &[u8]) -> Result<Vec<Vec<String>>, StockError> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::GBK.decode(data).0.into_owned(),
+    };
+    let first_line = text.lines().find(|line| !line.trim().is_empty());
+    let delimiter = match first_line {
+        Some(line) if line.contains('\t') => b'\t',
+        _ => b',',
+    };
+
+    // 逐行解析（交割单没有跨行的字段），空行保留，使表格行号与文件行号一致
+    let mut table = Vec::new();
+    for line in text.lines() {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .from_reader(line.as_bytes());
+        let row = match reader.records().next() {
+            Some(record) => record
+                .map_err(|e| StockError::BusinessError(format!("读取交割单失败: {}", e)))?
+                .iter()
+                .map(clean_cell)
+                .collect(),
+            None => Vec::new(),
+        };
+        table.push(row);
+    }
+    Ok(table)
+}
+
+// 去掉 Excel 文本格式的包裹（="600000" 或 '600000）和首尾空白
+fn clean_cell(value: &str) -> String {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("=\"")
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(value);
+    value.trim_start_matches('\'').trim().to_string()
+}
+
+// 表头中各列的位置
+struct ColumnIndex {
+    date: usize,
+    time: Option<usize>,
+    code: usize,
+    name: Option<usize>,
+    side: usize,
+    quantity: usize,
+    price: usize,
+    market: Option<usize>,
+}
+
+fn find_column(headers: &[String], candidates: &[String]) -> Option<usize> {
+    candidates
+        .iter()
+        .find_map(|name| headers.iter().position(|header| header == name.trim()))
+}
+
+// 在前几行中找到包含全部必需列的表头行
+fn find_header(
+    table: &[Vec<String>],
+    columns: &StatementColumns,
+) -> Result<(usize, ColumnIndex), StockError> {
+    for (row, headers) in table.iter().enumerate().take(HEADER_SEARCH_ROWS) {
+        let required = (
+            find_column(headers, &columns.date),
+            find_column(headers, &columns.code),
+            find_column(headers, &columns.side),
+            find_column(headers, &columns.quantity),
+            find_column(headers, &columns.price),
+        );
+        if let (Some(date), Some(code), Some(side), Some(quantity), Some(price)) = required {
+            let index = ColumnIndex {
+                date,
+                time: find_column(headers, &columns.time),
+                code,
+                name: find_column(headers, &columns.name),
+                side,
+                quantity,
+                price,
+                market: find_column(headers, &columns.market),
+            };
+            return Ok((row, index));
+        }
+    }
+
+    let expected: Vec<&str> = [
+        &columns.date,
+        &columns.code,
+        &columns.side,
+        &columns.quantity,
+        &columns.price,
+    ]
+    .iter()
+    .filter_map(|names| names.first().map(|name| name.as_str()))
+    .collect();
+    Err(StockError::BusinessError(format!(
+        "未找到交割单表头，请检查券商格式或列映射（需要包含：{}）",
+        expected.join("、")
+    )))
+}
+
+// 交易市场名称对应的代码前缀
+fn market_prefix(market: &str) -> Option<&'static str> {
+    let market = market.to_uppercase();
+    if market.contains("沪") || market.contains("上海") || market.contains("SH") {
+        Some("SH")
+    } else if market.contains("深") || market.contains("SZ") {
+        Some("SZ")
+    } else if market.contains("北") || market.contains("BJ") {
+        Some("BJ")
+    } else {
+        None
+    }
+}
+
+/// 统一证券代码为 SH600000 / SZ000001 形式
+/// 支持 600000、600000.SH、sh600000 等写法，纯数字代码优先按交易市场列补全前缀，否则按代码段判断
+/// Excel 中丢失的前导零会补齐为 6 位
+pub fn normalize_stock_code(code: &str, market: Option<&str>) -> Option<String> {
+    let code = code.trim().to_uppercase();
+    let (digits, prefix) = if let Some((digits, suffix)) = code.split_once('.') {
+        let prefix = match suffix {
+            "SH" | "SS" => "SH",
+            "SZ" => "SZ",
+            "BJ" => "BJ",
+            _ => return None,
+        };
+        (digits.to_string(), Some(prefix))
+    } else if let Some(prefix) = ["SH", "SZ", "BJ"].iter().find(|p| code.starts_with(**p)) {
+        (code[2..].to_string(), Some(*prefix))
+    } else {
+        (code.clone(), None)
+    };
+
+    if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let digits = format!("{:0>6}", digits);
+    let prefix = prefix
+        .or_else(|| market.and_then(market_prefix))
+        .or_else(|| match digits.as_bytes()[0] {
+            _ if digits.starts_with("92") => Some("BJ"),
+            b'5' | b'6' | b'9' => Some("SH"),
+            b'0' | b'1' | b'2' | b'3' => Some("SZ"),
+            b'4' | b'8' => Some("BJ"),
+            _ => None,
+        })?;
+    Some(format!("{}{}", prefix, digits))
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    ["%Y%m%d", "%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        // 93105 表示 09:31:05
+        return NaiveTime::parse_from_str(&format!("{:0>6}", value), "%H%M%S").ok();
+    }
+    ["%H:%M:%S", "%H:%M"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+}
+
+// 合并成交日期和成交时间为 YYYY-MM-DD HH:MM:SS，日期列中可以带时间
+fn parse_trade_time(date: &str, time: &str) -> Option<String> {
+    let (date, embedded) = match date.split_once([' ', 'T']) {
+        Some((date, rest)) => (date, rest.trim()),
+        None => (date, ""),
+    };
+    let date = parse_date(date.trim())?;
+    let time = [time.trim(), embedded]
+        .into_iter()
+        .find(|value| !value.is_empty())
+        .unwrap_or(DEFAULT_TRADE_TIME);
+    let time = parse_time(time)?;
+    Some(date.and_time(time).format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+// 数值列可能带千分位逗号或负号（卖出数量为负）
+fn parse_number(value: &str) -> Option<f64> {
+    value
+        .replace(',', "")
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(f64::abs)
+}
+
+// 买卖方向：包含“买”为买入，包含“卖”为卖出，其余（分红入账、转账等）不是成交
+fn parse_side(value: &str) -> Option<&'static str> {
+    if value.contains('买') {
+        Some(BUY_ACTION)
+    } else if value.contains('卖') {
+        Some(SELL_ACTION)
+    } else {
+        None
+    }
+}
+
+/// 按列映射解析交割单表格，返回成交记录和跳过的行（行号从1开始）
+pub fn parse_statement(
+    table: &[Vec<String>],
+    columns: &StatementColumns,
+) -> Result<(Vec<StatementOrder>, Vec<SkippedRow>), StockError> {
+    let (header_row, index) = find_header(table, columns)?;
+    let mut orders = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (row_index, row) in table.iter().enumerate().skip(header_row + 1) {
+        if row.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        let line = row_index + 1;
+        let cell = |column: usize| row.get(column).map(String::as_str).unwrap_or_default();
+        let optional = |column: Option<usize>| column.map(cell).unwrap_or_default();
+        let mut skip = |reason: String| skipped.push(SkippedRow { line, reason });
+
+        let side = cell(index.side);
+        let Some(action) = parse_side(side) else {
+            skip(format!("不是买卖成交: {}", side));
+            continue;
+        };
+        let market = Some(optional(index.market)).filter(|market| !market.is_empty());
+        let Some(code) = normalize_stock_code(cell(index.code), market) else {
+            skip(format!("无法识别的证券代码: {}", cell(index.code)));
+            continue;
+        };
+        let quantity = match parse_number(cell(index.quantity)) {
+            Some(quantity) if quantity >= 1.0 && quantity.fract() == 0.0 => quantity as i32,
+            _ => {
+                skip(format!("成交数量无效: {}", cell(index.quantity)));
+                continue;
+            }
+        };
+        let cost = match parse_number(cell(index.price)) {
+            Some(price) if price > 0.0 => price,
+            _ => {
+                skip(format!("成交价格无效: {}", cell(index.price)));
+                continue;
+            }
+        };
+        let Some(time) = parse_trade_time(cell(index.date), optional(index.time)) else {
+            skip(format!(
+                "无法识别的成交时间: {} {}",
+                cell(index.date),
+                optional(index.time)
+            ));
+            continue;
+        };
+
+        let name = optional(index.name);
+        orders.push(StatementOrder {
+            line,
+            name: if name.is_empty() {
+                code.clone()
+            } else {
+                name.to_string()
+            },
+            code,
+            time,
+            quantity,
+            cost,
+            action: action.to_string(),
+        });
+    }
+
+    Ok((orders, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::brokers::builtin_columns;
+    use super::*;
+
+    #[test]
+    fn normalizes_stock_codes() {
+        assert_eq!(normalize_stock_code("600000", None).unwrap(), "SH600000");
+        assert_eq!(normalize_stock_code("1", None).unwrap(), "SZ000001");
+        assert_eq!(normalize_stock_code("300750", None).unwrap(), "SZ300750");
+        assert_eq!(normalize_stock_code("600000.SS", None).unwrap(), "SH600000");
+        assert_eq!(normalize_stock_code("sz000001", None).unwrap(), "SZ000001");
+        assert_eq!(normalize_stock_code("430047", None).unwrap(), "BJ430047");
+        // 交易市场列优先于代码段判断
+        assert_eq!(
+            normalize_stock_code("510300", Some("深圳A股")).unwrap(),
+            "SZ510300"
+        );
+        assert!(normalize_stock_code("abc", None).is_none());
+        assert!(normalize_stock_code("6000001", None).is_none());
+    }
+
+    #[test]
+    fn parses_csv_statement() {
+        let csv = "华泰证券交割单\n\
+            成交日期,成交时间,证券代码,证券名称,买卖标志,成交数量,成交均价,交易市场\n\
+            20240102,93105,=\"600000\",浦发银行,证券买入,\"1,000\",10.5,上海A股\n\
+            20240103,14:55:00,'000001,平安银行,证券卖出,-200,12,深圳A股\n\
+            20240104,,600000,浦发银行,股息入账,0,0,上海A股\n\
+            \n\
+            2024-01-05,,600000,浦发银行,买入,0,10,上海A股\n";
+        let table = read_table("statement.csv", csv.as_bytes()).unwrap();
+        let (orders, skipped) =
+            parse_statement(&table, &builtin_columns("huatai").unwrap()).unwrap();
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].line, 3);
+        assert_eq!(orders[0].code, "SH600000");
+        assert_eq!(orders[0].time, "2024-01-02 09:31:05");
+        assert_eq!(orders[0].quantity, 1000);
+        assert_eq!(orders[0].action, BUY_ACTION);
+        assert_eq!(orders[1].code, "SZ000001");
+        assert_eq!(orders[1].quantity, 200);
+        assert_eq!(orders[1].action, SELL_ACTION);
+
+        // 非成交记录和数量为 0 的行被跳过，空行忽略
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].line, 5);
+        assert_eq!(skipped[1].line, 7);
+    }
+
+    #[test]
+    fn parses_gbk_tab_separated_statement() {
+        let text = "成交日期\t成交时间\t证券代码\t证券名称\t操作\t成交数量\t成交均价\n\
+            2024/01/02\t09:30:01\t300750\t宁德时代\t买入\t100\t180.5\n";
+        let (data, _, _) = encoding_rs::GBK.encode(text);
+        // 同花顺导出的 .xls 不是真正的 Excel 文件
+        let table = read_table("table.xls", &data).unwrap();
+        let (orders, skipped) =
+            parse_statement(&table, &builtin_columns("tonghuashun").unwrap()).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(orders[0].code, "SZ300750");
+        assert_eq!(orders[0].name, "宁德时代");
+        assert_eq!(orders[0].time, "2024-01-02 09:30:01");
+    }
+
+    #[test]
+    fn reports_missing_header() {
+        let table = vec![vec!["日期".to_string(), "代码".to_string()]];
+        let result = parse_statement(&table, &builtin_columns("eastmoney").unwrap());
+        assert!(matches!(result, Err(StockError::BusinessError(_))));
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// 默认账户ID（升级前的委托、持仓和资金流水都归入默认账户）
+pub const DEFAULT_ACCOUNT_ID: i32 = 1;
+
+/// 请求中未指定账户时使用默认账户
+pub fn default_account_id() -> i32 {
+    DEFAULT_ACCOUNT_ID
+}
+
+/// 券商账户 / 组合
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: i32,
+    pub name: String,
+    pub archived: bool, // 是否已归档（归档后不能再录入委托和资金流水）
+    pub created_at: String,
+}
+
+/// 持仓划转请求参数（按成本价从一个账户转到另一个账户）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferPositionParams {
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    pub code: String,
+    pub quantity: i32,
+    pub time: Option<String>, // 划转时间，默认当前时间
+}
+
+/// 一条持仓划转记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionTransfer {
+    pub id: i32,
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    pub code: String,
+    pub name: String,
+    pub quantity: i32,
+    pub cost: f64, // 划转时的每股成本（含买入费用）
+    pub time: String,
+}
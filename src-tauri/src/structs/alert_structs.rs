@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+/// 提醒条件类型（阈值单位见各项说明）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+    PriceAbove,       // 价格上穿：现价 ≥ 阈值（元）
+    PriceBelow,       // 价格下穿：现价 ≤ 阈值（元）
+    PercentAbove,     // 涨幅达到：涨跌幅 ≥ 阈值（%）
+    PercentBelow,     // 跌幅达到：涨跌幅 ≤ -阈值（%）
+    NearLimitUp,      // 接近涨停：距涨停价 ≤ 阈值（%）
+    NearLimitDown,    // 接近跌停：距跌停价 ≤ 阈值（%）
+    VolumeRatioAbove, // 量比放大：量比 ≥ 阈值
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertKind::PriceAbove => "price_above",
+            AlertKind::PriceBelow => "price_below",
+            AlertKind::PercentAbove => "percent_above",
+            AlertKind::PercentBelow => "percent_below",
+            AlertKind::NearLimitUp => "near_limit_up",
+            AlertKind::NearLimitDown => "near_limit_down",
+            AlertKind::VolumeRatioAbove => "volume_ratio_above",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "price_above" => Some(AlertKind::PriceAbove),
+            "price_below" => Some(AlertKind::PriceBelow),
+            "percent_above" => Some(AlertKind::PercentAbove),
+            "percent_below" => Some(AlertKind::PercentBelow),
+            "near_limit_up" => Some(AlertKind::NearLimitUp),
+            "near_limit_down" => Some(AlertKind::NearLimitDown),
+            "volume_ratio_above" => Some(AlertKind::VolumeRatioAbove),
+            _ => None,
+        }
+    }
+
+    /// 提醒消息中使用的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlertKind::PriceAbove => "价格上穿",
+            AlertKind::PriceBelow => "价格下穿",
+            AlertKind::PercentAbove => "涨幅达到",
+            AlertKind::PercentBelow => "跌幅达到",
+            AlertKind::NearLimitUp => "接近涨停",
+            AlertKind::NearLimitDown => "接近跌停",
+            AlertKind::VolumeRatioAbove => "量比放大",
+        }
+    }
+}
+
+/// 价格提醒规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: i32,
+    pub code: String,
+    pub kind: String, // AlertKind::as_str
+    pub threshold: f64,
+    pub enabled: bool,
+    pub cooldown_minutes: i64, // 触发后至少间隔多久才能再次触发
+    pub note: String,
+    pub created_at: String,
+    pub last_triggered_at: Option<String>,
+}
+
+fn default_cooldown_minutes() -> i64 {
+    30
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 新增/修改提醒规则请求参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertRuleParams {
+    pub code: String,
+    pub kind: String,
+    pub threshold: f64,
+    #[serde(default = "default_cooldown_minutes")]
+    pub cooldown_minutes: i64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub note: Option<String>,
+}
+
+/// 已触发的提醒记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub id: i32,
+    pub rule_id: i32,
+    pub code: String,
+    pub name: String,
+    pub kind: String,
+    pub threshold: f64,
+    pub value: f64,         // 触发时的观测值（价格、涨跌幅、距涨跌停百分比或量比）
+    pub price: Option<f64>, // 触发时的现价
+    pub message: String,
+    pub triggered_at: String,
+}
+
+/// 后台行情轮询与提醒设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertSettings {
+    pub enabled: bool,
+    pub poll_interval_secs: u64,
+    pub symbols: Vec<String>, // 额外轮询的股票（规则中的股票总会轮询），行情写入本地快照
+    pub notify: bool,         // 是否弹出系统通知
+    pub trading_hours_only: bool, // 只在交易时段轮询
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        AlertSettings {
+            enabled: true,
+            poll_interval_secs: 15,
+            symbols: Vec::new(),
+            notify: true,
+            trading_hours_only: true,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::structs::holdings_structs::HoldingExit;
+use serde::{Deserialize, Serialize};
+
+/// 组合分析请求参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct PortfolioAnalyticsParams {
+    pub start: Option<String>, // 开始日期 YYYY-MM-DD（为空表示从第一笔委托开始）
+    pub end: Option<String>,   // 结束日期 YYYY-MM-DD（为空表示到最新行情）
+    pub group: Option<String>, // 分组：day / week / month / year，默认 day
+    #[serde(default)]
+    pub risk_free_rate: f64, // 年化无风险利率，用于夏普/索提诺比率
+    #[serde(default)]
+    pub account_id: Option<i32>, // 账户ID，不传时汇总全部账户
+}
+
+/// 净值曲线上的一个点（按分组取每组最后一个交易日）
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPoint {
+    pub date: String,
+    pub market_value: f64, // 持仓市值
+    pub net_invested: f64, // 累计净投入（买入金额含费用 - 卖出净额）
+    pub pnl: f64,          // 累计盈亏 = 持仓市值 - 累计净投入
+    pub nav: f64,          // 时间加权净值，区间起点为 1
+    pub drawdown: f64,     // 相对此前最高净值的回撤（<= 0）
+}
+
+/// 每个分组区间的表现
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodPerformance {
+    pub period: String, // 分组标识，如 2024-03-01、2024-W10、2024-03、2024
+    pub start: String,
+    pub end: String,
+    pub return_rate: f64,     // 时间加权收益率
+    pub pnl: f64,             // 区间盈亏（含浮动盈亏）
+    pub realized_profit: f64, // 区间内卖出的已实现盈亏
+    pub trade_count: i32,     // 区间内卖出次数
+    pub win_count: i32,
+}
+
+/// 区间汇总指标
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PerformanceSummary {
+    pub total_return: f64,                  // 时间加权收益率
+    pub annualized_return: Option<f64>,     // 年化时间加权收益率
+    pub money_weighted_return: Option<f64>, // 资金加权年化收益率（XIRR）
+    pub total_pnl: f64,                     // 区间盈亏（含浮动盈亏）
+    pub realized_profit: f64,               // 区间内已实现盈亏
+    pub max_drawdown: f64,                  // 最大回撤（正数，0.2 表示 20%）
+    pub max_drawdown_days: i64,             // 最长回撤持续天数（自然日）
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    pub trade_count: i32,
+    pub win_rate: f64,
+    pub profit_factor: Option<f64>, // 总盈利 / 总亏损，没有亏损时为空
+    pub average_win: Option<f64>,
+    pub average_loss: Option<f64>,
+    pub average_holding_days: Option<f64>,
+    pub average_holding_trading_days: Option<f64>, // 平均持有交易日数（按交易日历）
+}
+
+/// 组合分析结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioAnalytics {
+    pub start: String,
+    pub end: String,
+    pub group: String,
+    pub summary: PerformanceSummary,
+    pub equity_curve: Vec<EquityPoint>,
+    pub periods: Vec<PeriodPerformance>,
+    pub best_trade: Option<HoldingExit>,
+    pub worst_trade: Option<HoldingExit>,
+    pub missing_prices: Vec<String>, // 缺少日线收盘价、按最近成交价估值的股票
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 备份压缩包中的文件及校验信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub path: String,   // 压缩包内路径（如 databases/app.db）
+    pub size: u64,      // 文件大小（字节）
+    pub sha256: String, // SHA-256 校验和（小写十六进制）
+}
+
+/// 备份清单（manifest.json），导入前用它校验压缩包是否完整、是否兼容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,               // 清单格式版本
+    pub app_version: String,               // 导出时的应用版本
+    pub created_at: String,                // 导出时间
+    pub schemas: BTreeMap<String, i64>,    // 各数据库的结构版本
+    pub row_counts: BTreeMap<String, i64>, // 各表的记录数
+    pub files: Vec<BackupFile>,
+}
+
+/// 自动备份设置（保存在 app_config 中）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    pub directory: Option<String>, // 备份目录，为空时使用应用数据目录下的 backups
+    pub interval_hours: u32,       // 两次自动备份的间隔（小时）
+    pub keep_daily: usize,         // 保留最近 N 天每天最新的一份
+    pub keep_weekly: usize,        // 保留最近 N 周每周最新的一份
+    pub keep_monthly: usize,       // 保留最近 N 个月每月最新的一份
+}
+
+impl Default for BackupSettings {
+    // 每天备份一次，保留 7 天、4 周、6 个月
+    fn default() -> Self {
+        BackupSettings {
+            enabled: true,
+            directory: None,
+            interval_hours: 24,
+            keep_daily: 7,
+            keep_weekly: 4,
+            keep_monthly: 6,
+        }
+    }
+}
+
+/// 备份目录中的一份快照
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackupSnapshot {
+    pub file_name: String,
+    pub created_at: String, // YYYY-MM-DD HH:MM:SS（取自文件名）
+    pub size: u64,
+}
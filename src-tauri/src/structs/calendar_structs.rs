@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+/// 当前市场状态（北京时间）
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketStatus {
+    pub now: String,  // 北京时间 YYYY-MM-DD HH:MM:SS
+    pub date: String, // YYYY-MM-DD
+    pub is_trading_day: bool,
+    pub session: String,       // MarketSession::as_str
+    pub session_label: String, // 如 “午间休市”
+    pub is_open: bool,         // 是否处于交易时段（含集合竞价）
+    pub previous_trading_day: String,
+    pub next_trading_day: String,
+    pub next_open: String, // 下一次开始交易的时间（集合竞价或午后开盘）
+    pub holiday_data_available: bool, // 是否有今年的休市安排，没有时只按周末判断
+}
+
+/// 某年（或某月）的交易日历
+#[derive(Debug, Clone, Serialize)]
+pub struct TradingCalendarInfo {
+    pub year: i32,
+    pub month: Option<u32>,
+    pub trading_days: Vec<String>,
+    pub holidays: Vec<String>, // 工作日休市的日期（周末不列出）
+    pub holiday_data_available: bool,
+    pub customized: bool, // 休市日期是否为用户自定义（覆盖随应用发布的休市表）
+}
@@ -0,0 +1,88 @@
+use crate::structs::account_structs::default_account_id;
+use serde::{Deserialize, Serialize};
+
+/// 手动记录的资金流水类型（买入/卖出由委托自动产生）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CashKind {
+    Deposit,  // 转入
+    Withdraw, // 转出
+    Dividend, // 现金分红（旧版本手动录入，现由公司行动产生）
+    Fee,      // 其他费用（如账户管理费）
+}
+
+impl CashKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CashKind::Deposit => "deposit",
+            CashKind::Withdraw => "withdraw",
+            CashKind::Dividend => "dividend",
+            CashKind::Fee => "fee",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "deposit" => Some(CashKind::Deposit),
+            "withdraw" => Some(CashKind::Withdraw),
+            "dividend" => Some(CashKind::Dividend),
+            "fee" => Some(CashKind::Fee),
+            _ => None,
+        }
+    }
+
+    /// 入账为 1，出账为 -1
+    pub fn sign(&self) -> f64 {
+        match self {
+            CashKind::Deposit | CashKind::Dividend => 1.0,
+            CashKind::Withdraw | CashKind::Fee => -1.0,
+        }
+    }
+}
+
+/// 添加资金流水请求参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddCashEntryParams {
+    pub time: String,
+    pub kind: String,         // deposit / withdraw / fee（分红请录入公司行动）
+    pub amount: f64,          // 金额（正数，方向由类型决定）
+    pub code: Option<String>, // 相关股票代码（可选）
+    pub note: Option<String>,
+    #[serde(default = "default_account_id")]
+    pub account_id: i32, // 所属账户，默认账户可不传
+}
+
+/// 一条资金流水（手动记录或委托产生）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CashFlow {
+    pub id: Option<i32>,       // 手动记录的ID，委托产生的流水为空
+    pub order_id: Option<i32>, // 对应的委托ID，手动记录为空
+    pub time: String,
+    pub kind: String, // deposit / withdraw / dividend / fee / buy / sell / duplicate_dividend
+    pub amount: f64,  // 入账为正，出账为负
+    pub code: Option<String>,
+    pub note: String,
+    pub account_id: i32,
+}
+
+/// 单只持仓的市值
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionValue {
+    pub code: String,
+    pub name: String,
+    pub quantity: i32,
+    pub cost: f64,
+    pub price: f64, // 最新价（没有行情时为成本价）
+    pub market_value: f64,
+    pub unrealized_profit: f64, // 浮动盈亏
+    pub priced: bool,           // 是否取到了行情价格
+}
+
+/// 账户总览：现金、持仓市值与总资产
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountSummary {
+    pub cash: f64,
+    pub market_value: f64,
+    pub total_equity: f64,
+    pub positions: Vec<PositionValue>,
+    pub strict_mode: bool,
+}
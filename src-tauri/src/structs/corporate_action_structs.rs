@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// 手动录入的公司行动来源标识（数据源获取的使用数据源 id）
+pub const MANUAL_SOURCE: &str = "manual";
+
+/// K线复权方式（保存在 app_config 中，决定向数据源请求的K线类型）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KlineAdjust {
+    None,   // 不复权
+    Before, // 前复权：历史价格按最新股本调整，最新价格与实际成交一致
+    After,  // 后复权：最新价格按上市时股本调整
+}
+
+impl KlineAdjust {
+    /// 与雪球 K线接口的 type 参数一致
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KlineAdjust::None => "normal",
+            KlineAdjust::Before => "before",
+            KlineAdjust::After => "after",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "normal" | "none" => Some(KlineAdjust::None),
+            "before" => Some(KlineAdjust::Before),
+            "after" => Some(KlineAdjust::After),
+            _ => None,
+        }
+    }
+}
+
+/// 公司行动（除权除息）：现金分红、送股、转增和拆合股，均按每股计
+/// 例如 “10送3转2派1元” 为 cash_dividend 0.1、bonus_shares 0.3、transfer_shares 0.2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorporateAction {
+    pub id: i32,
+    pub code: String,
+    pub ex_date: String, // 除权除息日 YYYY-MM-DD（前一交易日收盘时的持仓享有权益）
+    pub cash_dividend: f64, // 每股派息（元，实际到账金额）
+    pub bonus_shares: f64, // 每股送股
+    pub transfer_shares: f64, // 每股转增
+    pub split_ratio: f64, // 拆合股比例：2 表示 1 拆 2，0.5 表示 2 合 1，没有时为 1
+    pub source: String,  // 来源：manual 或数据源 id
+    pub note: String,
+}
+
+impl CorporateAction {
+    /// 除权后每股变为多少股
+    pub fn share_factor(&self) -> f64 {
+        self.split_ratio * (1.0 + self.bonus_shares + self.transfer_shares)
+    }
+}
+
+fn default_split_ratio() -> f64 {
+    1.0
+}
+
+/// 手动录入公司行动请求参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddCorporateActionParams {
+    pub code: String,
+    pub ex_date: String,
+    #[serde(default)]
+    pub cash_dividend: f64,
+    #[serde(default)]
+    pub bonus_shares: f64,
+    #[serde(default)]
+    pub transfer_shares: f64,
+    #[serde(default = "default_split_ratio")]
+    pub split_ratio: f64,
+    pub note: Option<String>,
+}
@@ -0,0 +1,126 @@
+use crate::structs::trend_lines_structs::TrendLine;
+
+/// 画线类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawingKind {
+    TrendLine,       // 两点线段（趋势线）
+    HorizontalLine,  // 水平支撑/压力线
+    Ray,             // 射线：从第一点经过第二点向右延伸
+    ExtendedLine,    // 直线：经过两点向两端延伸
+    ParallelChannel, // 平行通道：前两点为基准线，第三点确定平行线的位置
+    Fibonacci,       // 斐波那契回撤：起点、终点
+    Rectangle,       // 矩形区域：对角两点
+    Text,            // 文字标注：锚定在某根K线上
+}
+
+impl DrawingKind {
+    /// 可以开启突破提醒的直线类画线
+    pub const ALERTABLE: [DrawingKind; 4] = [
+        DrawingKind::TrendLine,
+        DrawingKind::HorizontalLine,
+        DrawingKind::Ray,
+        DrawingKind::ExtendedLine,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DrawingKind::TrendLine => "trend_line",
+            DrawingKind::HorizontalLine => "horizontal_line",
+            DrawingKind::Ray => "ray",
+            DrawingKind::ExtendedLine => "extended_line",
+            DrawingKind::ParallelChannel => "parallel_channel",
+            DrawingKind::Fibonacci => "fibonacci",
+            DrawingKind::Rectangle => "rectangle",
+            DrawingKind::Text => "text",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "trend_line" => Some(DrawingKind::TrendLine),
+            "horizontal_line" => Some(DrawingKind::HorizontalLine),
+            "ray" => Some(DrawingKind::Ray),
+            "extended_line" => Some(DrawingKind::ExtendedLine),
+            "parallel_channel" => Some(DrawingKind::ParallelChannel),
+            "fibonacci" => Some(DrawingKind::Fibonacci),
+            "rectangle" => Some(DrawingKind::Rectangle),
+            "text" => Some(DrawingKind::Text),
+            _ => None,
+        }
+    }
+
+    /// 该类型需要的锚点数量
+    pub fn point_count(&self) -> usize {
+        match self {
+            DrawingKind::HorizontalLine | DrawingKind::Text => 1,
+            DrawingKind::ParallelChannel => 3,
+            _ => 2,
+        }
+    }
+
+    pub fn is_alertable(&self) -> bool {
+        DrawingKind::ALERTABLE.contains(self)
+    }
+}
+
+/// 锚点：K线时间 + 价格
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DrawingPoint {
+    pub time: i64, // 13位时间戳
+    pub price: f64,
+}
+
+/// 图表上的画线对象
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Drawing {
+    pub id: i32,
+    pub code: String,
+    pub period: String,
+    pub kind: String, // DrawingKind::as_str
+    pub points: Vec<DrawingPoint>,
+    pub style: serde_json::Value, // 颜色、线宽、线型等，由前端解释
+    pub label: String,            // 文字标注内容或画线备注
+    pub alert_enabled: bool,      // 是否开启突破提醒（仅直线类画线）
+    pub alert_mode: String,       // TrendLineAlertMode::as_str
+    pub tolerance: f64,           // 触及提醒的容差（%）
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Drawing {
+    /// 直线类画线转换为趋势线（水平线的起止两点相同），用于突破提醒和旧版趋势线接口
+    pub fn to_trend_line(&self) -> Option<TrendLine> {
+        let kind = DrawingKind::parse(&self.kind).filter(DrawingKind::is_alertable)?;
+        let start = *self.points.first()?;
+        let end = if kind == DrawingKind::HorizontalLine {
+            start
+        } else {
+            *self.points.get(1)?
+        };
+        Some(TrendLine {
+            id: self.id,
+            code: self.code.clone(),
+            period: self.period.clone(),
+            start_time: start.time,
+            start_price: start.price,
+            end_time: end.time,
+            end_price: end.price,
+            alert_enabled: self.alert_enabled,
+            alert_mode: self.alert_mode.clone(),
+            tolerance: self.tolerance,
+        })
+    }
+}
+
+/// 新增/修改画线请求参数（修改时保留提醒设置）
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DrawingParams {
+    pub code: String,
+    pub period: String,
+    pub kind: String,
+    pub points: Vec<DrawingPoint>,
+    #[serde(default)]
+    pub style: serde_json::Value, // 缺省为 {}
+    #[serde(default)]
+    pub label: String,
+}
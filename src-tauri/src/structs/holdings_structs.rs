@@ -1,5 +1,6 @@
 // holdings_structs.rs
 
+use crate::structs::account_structs::default_account_id;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,33 +14,93 @@ pub struct Holding {
     pub status: i32,               // 状态：1-当前持仓，0-历史记录
     pub sell_time: Option<String>, // 卖出时间（可为空）
     pub sell_price: Option<f64>,   // 卖出价格（可为空）
-    pub profit: Option<f64>,       // 盈利（可为空）
+    pub profit: Option<f64>,       // 盈利：清仓时为本轮总盈利，持仓中为已实现部分（可为空）
+    #[serde(default = "default_account_id")]
+    pub account_id: i32, // 所属账户
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AddHoldingReq {
+/// 持仓批次（每笔买入为一个批次）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingLot {
+    pub id: i32,
+    pub holding_id: i32,
+    pub order_id: Option<i32>,
+    pub code: String,
+    pub buy_time: String,
+    pub price: f64,
+    pub quantity: i32,
+    pub remaining: i32, // 剩余未卖出数量
+    pub fee: f64,       // 买入费用
+}
+
+/// 卖出记录（每笔卖出一条，部分卖出也单独记录）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingExit {
+    pub id: Option<i32>, // 旧版清仓记录没有卖出明细，此时为空
+    pub holding_id: i32,
+    pub order_id: Option<i32>,
     pub code: String,
     pub name: String,
-    pub cost: f64,
+    pub hold_time: String,
+    pub sell_time: String,
+    pub sell_price: f64,
     pub quantity: i32,
-    pub hold_time: String,   // 必须传入持仓时间
-    pub status: Option<i32>, // 可选，默认为1
+    pub cost: f64,    // 卖出部分的每股成本（含买入费用）
+    pub profit: f64,  // 本次卖出的已实现盈亏（扣除买卖费用后）
+    pub closed: bool, // 本次卖出后是否清仓
+    pub fee: f64,     // 卖出费用
+    #[serde(default = "default_account_id")]
+    pub account_id: i32, // 所属账户
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateHoldingReq {
+/// 现金分红记录（按除息日前的持仓数量计算，计入已实现盈亏）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingDividend {
     pub id: i32,
-    pub cost: Option<f64>,
-    pub quantity: Option<i32>,
-    pub status: Option<i32>,       // 可更新状态
-    pub sell_time: Option<String>, // 可更新卖出时间
-    pub sell_price: Option<f64>,   // 可更新卖出价格
-    pub profit: Option<f64>,       // 可更新盈利
+    pub holding_id: i32,
+    pub action_id: i32, // 对应的公司行动
+    pub code: String,
+    pub ex_date: String,
+    pub quantity: i32,  // 享有分红的持仓数量
+    pub per_share: f64, // 每股派息
+    pub amount: f64,    // 分红金额
+    pub account_id: i32,
+}
+
+/// 卖出时的成本计算方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostMethod {
+    Fifo,    // 先进先出：按买入顺序消耗批次
+    Average, // 平均成本：按当前持仓均价结转
+}
+
+impl CostMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostMethod::Fifo => "fifo",
+            CostMethod::Average => "average",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "fifo" => Some(CostMethod::Fifo),
+            "average" => Some(CostMethod::Average),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct DeleteHoldingReq {
-    pub id: i32,
+pub struct AddHoldingReq {
+    pub code: String,
+    pub name: String,
+    pub cost: f64,
+    pub quantity: i32,
+    pub hold_time: String,   // 必须传入持仓时间
+    pub status: Option<i32>, // 可选，默认为1
+    #[serde(default = "default_account_id")]
+    pub account_id: i32, // 所属账户，默认账户可不传
 }
 
 // 用于前端查询的参数
@@ -68,19 +129,25 @@ pub struct PagedResult<T> {
 pub struct QueryHistoryParams {
     pub page: i32,      // 页码，从1开始
     pub page_size: i32, // 每页大小
+    #[serde(default)]
+    pub account_id: Option<i32>, // 账户ID，不传时汇总全部账户
 }
 
 #[derive(serde::Deserialize)]
 pub struct MonthlyStatsParams {
     pub year: i32,
     pub month: i32,
+    #[serde(default)]
+    pub account_id: Option<i32>, // 账户ID，不传时汇总全部账户
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct MonthlyStats {
     pub year: i32,
     pub month: i32,
-    pub operation_count: i32, // 操作次数
-    pub win_rate: f64,        // 胜率（0.0 ~ 1.0）
-    pub total_profit: f64,    // 总盈利金额
+    pub operation_count: i32,                      // 操作次数
+    pub win_rate: f64,                             // 胜率（0.0 ~ 1.0）
+    pub total_profit: f64,                         // 总盈利金额
+    pub trading_days: i32,                         // 当月交易日数（按交易日历）
+    pub average_holding_trading_days: Option<f64>, // 当月卖出记录的平均持有交易日数
 }
@@ -0,0 +1,59 @@
+use crate::structs::backup_structs::BackupManifest;
+use serde::Serialize;
+
+/// 导入备份时的合并策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportStrategy {
+    Replace, // 清空本地对应的表后写入备份数据
+    #[default]
+    KeepLocal, // 合并：与本地记录冲突（主键或自然主键相同）时保留本地记录
+    KeepBackup, // 合并：冲突时使用备份记录覆盖本地
+    Append,  // 追加：自增 id 的表总是重新分配 id（引用这些 id 的记录随之更新），不覆盖任何本地记录
+}
+
+impl ImportStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportStrategy::Replace => "replace",
+            ImportStrategy::KeepLocal => "keep_local",
+            ImportStrategy::KeepBackup => "keep_backup",
+            ImportStrategy::Append => "append",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "replace" => Some(ImportStrategy::Replace),
+            "keep_local" => Some(ImportStrategy::KeepLocal),
+            "keep_backup" => Some(ImportStrategy::KeepBackup),
+            "append" => Some(ImportStrategy::Append),
+            _ => None,
+        }
+    }
+}
+
+/// 单张表的导入统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TableImportStats {
+    pub database: String,
+    pub table: String,
+    pub inserted: usize,     // 新增记录数
+    pub updated: usize,      // 被备份覆盖的本地记录数
+    pub conflicts: usize,    // 与本地记录冲突（主键或自然主键相同）的记录数
+    pub rekeyed: usize,      // id 已被本地另一条记录占用、重新分配 id 后写入的记录数
+    pub deleted: usize,      // 替换模式下清空的本地记录数
+    pub skipped: usize,      // 数据无法转换而跳过的行数
+    pub failed: usize,       // 写入失败的行数
+    pub errors: Vec<String>, // 跳过和失败的原因（最多保留前若干条）
+}
+
+/// 导入结果（预演模式下为将要发生的变更，数据库不会被修改）
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub strategy: String,
+    pub dry_run: bool,
+    pub manifest: Option<BackupManifest>, // 旧版备份没有清单
+    pub files: Vec<String>,               // 压缩包中使用的数据库文件
+    pub missing: Vec<String>,             // 备份中没有找到的数据库
+    pub tables: Vec<TableImportStats>,
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 技术指标请求（随K线请求一起传入）
+/// name：ma / ema / macd / kdj / rsi / boll / atr / obv / vwap
+/// params：指标参数，如 MACD 为 [12, 26, 9]；为空时使用默认参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndicatorSpec {
+    pub name: String,
+    #[serde(default)]
+    pub params: Vec<f64>,
+}
+
+/// 技术指标计算结果
+/// series 中的每条序列与返回的K线一一对齐，数据不足的位置为 null
+#[derive(Debug, Clone, Serialize)]
+pub struct IndicatorResult {
+    pub name: String,
+    pub params: Vec<f64>,
+    pub series: BTreeMap<String, Vec<Option<f64>>>,
+}
@@ -14,11 +14,23 @@ pub enum StockError {
     #[error("业务错误: {0}")]
     BusinessError(String),
 }
+pub mod account_structs;
+pub mod alert_structs;
+pub mod analytics_structs;
+pub mod backup_structs;
+pub mod calendar_structs;
+pub mod cash_structs;
+pub mod corporate_action_structs;
+pub mod drawings_structs;
 pub mod holdings_structs;
+pub mod import_structs;
+pub mod indicator_structs;
 pub mod market_analysis_structs;
 pub mod orders_structs;
+pub mod secret_structs;
 pub mod selection_structs;
 pub mod self_reflect_structs;
+pub mod statement_structs;
 pub mod stock_review_structs;
 pub mod stock_structs;
 pub mod trend_lines_structs;
@@ -1,3 +1,4 @@
+use crate::structs::account_structs::default_account_id;
 use serde::{Deserialize, Serialize};
 
 /// 委托结构体
@@ -10,6 +11,21 @@ pub struct Order {
     pub quantity: i32,
     pub cost: f64,
     pub action: String, // "买入" 或 "卖出"
+    #[serde(default)]
+    pub commission: f64, // 佣金
+    #[serde(default)]
+    pub stamp_duty: f64, // 印花税
+    #[serde(default)]
+    pub transfer_fee: f64, // 过户费
+    #[serde(default = "default_account_id")]
+    pub account_id: i32, // 所属账户
+}
+
+impl Order {
+    /// 委托的全部交易费用
+    pub fn total_fee(&self) -> f64 {
+        self.commission + self.stamp_duty + self.transfer_fee
+    }
 }
 
 /// 添加委托请求参数
@@ -21,6 +37,8 @@ pub struct AddOrderParams {
     pub quantity: i32,
     pub cost: f64,
     pub action: String,
+    #[serde(default = "default_account_id")]
+    pub account_id: i32, // 所属账户，默认账户可不传
 }
 
 /// 分页查询参数
@@ -28,6 +46,8 @@ pub struct AddOrderParams {
 pub struct QueryOrdersParams {
     pub page: i32,      // 页码，从1开始
     pub page_size: i32, // 每页大小
+    #[serde(default)]
+    pub account_id: Option<i32>, // 账户ID，不传时汇总全部账户
 }
 
 /// 分页查询结果
@@ -39,3 +59,120 @@ pub struct PaginatedOrders {
     pub page_size: i32,   // 每页大小
     pub total_pages: i32, // 总页数
 }
+
+/// A 股交易费用模型（保存在 app_config 中）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeModel {
+    pub commission_rate: f64,   // 佣金费率（买卖双向）
+    pub min_commission: f64,    // 单笔最低佣金（元）
+    pub stamp_duty_rate: f64,   // 印花税率（仅卖出）
+    pub transfer_fee_rate: f64, // 过户费率（沪市，买卖双向）
+}
+
+impl Default for FeeModel {
+    // 万 2.5 佣金最低 5 元，印花税 0.05%，过户费 0.001%
+    fn default() -> Self {
+        FeeModel {
+            commission_rate: 0.00025,
+            min_commission: 5.0,
+            stamp_duty_rate: 0.0005,
+            transfer_fee_rate: 0.00001,
+        }
+    }
+}
+
+/// 单笔委托的费用明细
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderFees {
+    pub commission: f64,
+    pub stamp_duty: f64,
+    pub transfer_fee: f64,
+}
+
+// 费用按分四舍五入
+fn round_cent(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+impl FeeModel {
+    /// 计算一笔委托的费用：佣金不足最低值按最低值收取，印花税只在卖出时收取，过户费只对沪市收取
+    pub fn calculate(&self, code: &str, is_buy: bool, price: f64, quantity: i32) -> OrderFees {
+        let amount = price * quantity as f64;
+        if amount <= 0.0 {
+            return OrderFees::default();
+        }
+
+        let commission = round_cent((amount * self.commission_rate).max(self.min_commission));
+        let stamp_duty = if is_buy {
+            0.0
+        } else {
+            round_cent(amount * self.stamp_duty_rate)
+        };
+        let transfer_fee = if code.trim().to_uppercase().starts_with("SH") {
+            round_cent(amount * self.transfer_fee_rate)
+        } else {
+            0.0
+        };
+
+        OrderFees {
+            commission,
+            stamp_duty,
+            transfer_fee,
+        }
+    }
+
+    /// 费率不能为负数
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [
+            ("佣金费率", self.commission_rate),
+            ("最低佣金", self.min_commission),
+            ("印花税率", self.stamp_duty_rate),
+            ("过户费率", self.transfer_fee_rate),
+        ];
+        for (label, value) in values {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{}无效: {}", label, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_share_fees() {
+        let model = FeeModel::default();
+
+        // 小额买入按最低佣金收取，深市没有过户费
+        let buy = model.calculate("SZ000001", true, 10.0, 100);
+        assert_eq!(
+            buy,
+            OrderFees {
+                commission: 5.0,
+                stamp_duty: 0.0,
+                transfer_fee: 0.0
+            }
+        );
+
+        // 沪市卖出 10 万元：佣金 25，印花税 50，过户费 1
+        let sell = model.calculate("sh600000", false, 10.0, 10_000);
+        assert_eq!(
+            sell,
+            OrderFees {
+                commission: 25.0,
+                stamp_duty: 50.0,
+                transfer_fee: 1.0
+            }
+        );
+
+        let invalid = FeeModel {
+            commission_rate: -0.1,
+            ..FeeModel::default()
+        };
+        assert!(invalid.validate().is_err());
+        assert!(model.validate().is_ok());
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// 密码模式的密钥派生参数（保存在 app_config 中，不包含密码本身）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassphraseParams {
+    pub salt: String,    // 随机盐（base64）
+    pub iterations: u32, // PBKDF2 迭代次数
+    pub check: String,   // 用派生密钥加密的固定文本，用于校验密码是否正确
+}
+
+/// 敏感配置的加密状态
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretStatus {
+    pub mode: String,               // key_file：本机密钥文件；passphrase：用户密码
+    pub unlocked: bool,             // 密码模式下输入密码前为 false，此时无法读写敏感配置
+    pub load_error: Option<String>, // 启动时密钥加载失败的原因（此时敏感配置保持锁定）
+    pub key_file: Option<String>,   // 密钥文件路径（密钥文件模式）
+}
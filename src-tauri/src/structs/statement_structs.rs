@@ -0,0 +1,64 @@
+use crate::structs::account_structs::default_account_id;
+use serde::{Deserialize, Serialize};
+
+/// 交割单列映射：每项为可能的表头名称，按顺序取第一个存在的列
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatementColumns {
+    pub date: Vec<String>, // 成交日期（可以包含时间）
+    #[serde(default)]
+    pub time: Vec<String>, // 成交时间（可选）
+    pub code: Vec<String>, // 证券代码
+    #[serde(default)]
+    pub name: Vec<String>, // 证券名称（可选）
+    pub side: Vec<String>, // 买卖方向（如 “证券买入”、“卖出”）
+    pub quantity: Vec<String>, // 成交数量
+    pub price: Vec<String>, // 成交均价
+    #[serde(default)]
+    pub market: Vec<String>, // 交易市场（可选，用于补全代码前缀）
+}
+
+/// 券商交割单格式（内置券商或自定义）
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokerFormat {
+    pub id: String,
+    pub name: String,
+    pub columns: StatementColumns,
+}
+
+/// 交割单导入请求参数（预览和确认导入使用同一份参数）
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatementImportParams {
+    pub file_name: String,                 // 文件名，按扩展名识别 CSV / Excel
+    pub data: Vec<u8>,                     // 文件内容
+    pub broker: String,                    // 券商格式 id，custom 表示自定义列映射
+    pub columns: Option<StatementColumns>, // 自定义列映射，不传时使用已保存的映射
+    #[serde(default = "default_account_id")]
+    pub account_id: i32, // 导入到的账户，默认账户可不传
+}
+
+/// 交割单中的一笔成交
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementOrder {
+    pub line: usize, // 文件中的行号（从1开始）
+    pub code: String,
+    pub name: String,
+    pub time: String, // YYYY-MM-DD HH:MM:SS
+    pub quantity: i32,
+    pub cost: f64,
+    pub action: String, // 与手动录入一致："1" 买入，"0" 卖出
+}
+
+/// 未导入的行及原因
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedRow {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// 导入预览：将新增的委托、已存在的委托和跳过的行
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementPreview {
+    pub orders: Vec<StatementOrder>,
+    pub duplicates: Vec<StatementOrder>,
+    pub skipped: Vec<SkippedRow>,
+}
@@ -8,6 +8,16 @@ pub struct AddTrendLineReq {
     pub end_price: f64,
 }
 
+/// 修改趋势线请求参数（拖动后保存新的起止点，ID 和提醒设置不变）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UpdateTrendLineReq {
+    pub id: i32,
+    pub start_time: i64, // 13位时间戳
+    pub start_price: f64,
+    pub end_time: i64, // 13位时间戳
+    pub end_price: f64,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DeleteTrendLineReq {
     pub id: i32,
@@ -22,4 +32,97 @@ pub struct TrendLine {
     pub start_price: f64,
     pub end_time: i64,
     pub end_price: f64,
+    #[serde(default)]
+    pub alert_enabled: bool, // 是否开启突破提醒
+    #[serde(default = "default_alert_mode")]
+    pub alert_mode: String, // TrendLineAlertMode::as_str
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64, // 触及提醒的容差（%，相对趋势线价格）
+}
+
+fn default_alert_mode() -> String {
+    TrendLineAlertMode::Cross.as_str().to_string()
+}
+
+fn default_tolerance() -> f64 {
+    0.5
+}
+
+impl TrendLine {
+    /// 按起止两点把趋势线延伸到指定时间（13位时间戳），返回该时间的趋势线价格
+    /// 起止时间相同时只有水平线有意义，否则返回 None
+    pub fn price_at(&self, time: i64) -> Option<f64> {
+        if self.end_time == self.start_time {
+            return (self.end_price == self.start_price).then_some(self.start_price);
+        }
+        let slope = (self.end_price - self.start_price) / (self.end_time - self.start_time) as f64;
+        Some(self.start_price + slope * (time - self.start_time) as f64)
+    }
+
+    /// 把起止点移到新的时间（如另一周期K线的时间），价格按原趋势线计算，线的位置不变
+    /// 原本不同的起止点被移到同一时间时无法确定斜率，返回 None
+    pub fn reanchored(&self, start_time: i64, end_time: i64) -> Option<TrendLine> {
+        if start_time == end_time && self.start_time != self.end_time {
+            return None;
+        }
+        Some(TrendLine {
+            start_time,
+            start_price: self.price_at(start_time)?,
+            end_time,
+            end_price: self.price_at(end_time)?,
+            ..self.clone()
+        })
+    }
+}
+
+/// 趋势线突破提醒方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrendLineAlertMode {
+    Cross, // 实时价格穿越趋势线
+    Touch, // 实时价格进入趋势线容差范围
+    Close, // 该周期最新一根已收盘K线收在趋势线另一侧
+}
+
+impl TrendLineAlertMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrendLineAlertMode::Cross => "cross",
+            TrendLineAlertMode::Touch => "touch",
+            TrendLineAlertMode::Close => "close",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "cross" => Some(TrendLineAlertMode::Cross),
+            "touch" => Some(TrendLineAlertMode::Touch),
+            "close" => Some(TrendLineAlertMode::Close),
+            _ => None,
+        }
+    }
+}
+
+/// 设置趋势线突破提醒请求参数
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TrendLineAlertReq {
+    pub id: i32,
+    pub enabled: bool,
+    pub mode: Option<String>,   // 默认 cross
+    pub tolerance: Option<f64>, // 默认 0.5（%）
+}
+
+/// 趋势线突破记录
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TrendLineBreak {
+    pub id: i32,
+    pub line_id: i32,
+    pub code: String,
+    pub period: String,
+    pub mode: String,          // 触发方式
+    pub direction: String,     // up：向上突破；down：向下跌破；touch：触及
+    pub line_price: f64,       // 触发时趋势线的价格
+    pub price: f64,            // 触发时的价格（收盘模式为收盘价）
+    pub bar_time: Option<i64>, // 收盘模式下触发的K线时间
+    pub message: String,
+    pub triggered_at: String,
 }
@@ -1,3 +1,4 @@
+use crate::structs::indicator_structs::IndicatorSpec;
 use serde::{Deserialize, Serialize};
 
 /// K线数据接口的原始返回结构
@@ -39,6 +40,25 @@ pub struct RawStockDetailData {
     pub quote: Option<StockDetail>, // 股票详情数据
 }
 
+/// 分红送转接口的原始返回结构
+#[derive(Debug, Deserialize)]
+pub struct RawBonusResponse {
+    pub data: RawBonusData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawBonusData {
+    #[serde(default)]
+    pub items: Vec<RawBonusItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawBonusItem {
+    pub plan_explain: Option<String>,  // 分配方案（如 "10送3转2派1元"）
+    pub ex_dividend_date: Option<i64>, // 除权除息日（毫秒时间戳）
+    pub ashare_ex_dividend_date: Option<i64>, // A股除权除息日（毫秒时间戳）
+}
+
 // --------------------------
 // 2. 业务层数据结构（解析后的目标格式）
 // --------------------------
@@ -59,6 +79,8 @@ pub struct StockKlineItem {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StockQuote {
     // 根据实际接口字段补充，例如：
+    #[serde(default)]
+    pub symbol: String, // 带市场前缀的代码（如 SH600000），离线快照以此为键
     pub code: String,
     pub name: String,
     pub current: f64, // 当前价
@@ -105,6 +127,8 @@ pub struct GetStockDataParams {
     pub period: String,            // 周期（daily/weekly/monthly）
     pub timestamp: Option<String>, // 起始时间戳（可选）
     pub limit: i32,                // 数据条数
+    #[serde(default)]
+    pub indicators: Vec<IndicatorSpec>, // 需要一并计算的技术指标（可选）
 }
 /// 分时图请求参数
 #[derive(Debug, Deserialize)]
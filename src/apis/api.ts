@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import * as responseType from '@/types/response';
 export const queryStockByWordApi = (w: string) =>
   invoke<responseType.SearchStocksInvokeReturn>('search_stocks_by_keyword', {
@@ -10,6 +11,7 @@ export const getKlineDataApi = (
   period: string,
   timestamp: string,
   limit: number = 100,
+  indicators: responseType.IndicatorSpec[] = [],
 ) =>
   invoke<responseType.KlineDataInvokeReturn>('get_kline_data', {
     params: {
@@ -17,6 +19,7 @@ export const getKlineDataApi = (
       period,
       timestamp,
       limit,
+      indicators,
     },
   });
 
@@ -157,16 +160,25 @@ export const deleteStockLineApi = (id: number) =>
     req: { id },
   });
 
-// 查询趋势线
-export const getTrendLinesApi = (code: string, period: string) =>
+// 查询趋势线（project 为 true 时包含其他周期画的趋势线，投影到当前周期）
+export const getTrendLinesApi = (
+  code: string,
+  period: string,
+  project?: boolean,
+) =>
   invoke<responseType.GetTrendLinesInvokeReturn>('query_trend_lines_cmd', {
     code,
     period,
+    project,
   });
 
 // 添加趋势线（批量）
 export const addTrendLinesApi = (
-  lines: Omit<responseType.StockTrendLineType, 'id'>[], // 不需要传 id
+  // 不需要传 id 和提醒设置
+  lines: Omit<
+    responseType.StockTrendLineType,
+    'id' | 'alert_enabled' | 'alert_mode' | 'tolerance'
+  >[],
 ) =>
   invoke<responseType.InvokeBooleanReturn>('add_trend_lines_cmd', {
     reqs: lines,
@@ -177,6 +189,65 @@ export const deleteTrendLineApi = (id: number) =>
   invoke<responseType.InvokeBooleanReturn>('delete_trend_line_cmd', {
     req: { id },
   });
+
+// 修改趋势线（拖动后保存新的起止点，ID 不变）
+export const updateTrendLineApi = (
+  line: Omit<
+    responseType.StockTrendLineType,
+    'code' | 'period' | 'alert_enabled' | 'alert_mode' | 'tolerance'
+  >,
+) =>
+  invoke<responseType.InvokeBooleanReturn>('update_trend_line_cmd', {
+    req: line,
+  });
+
+// 设置趋势线突破提醒
+export const setTrendLineAlertApi = (req: responseType.TrendLineAlertReq) =>
+  invoke<responseType.InvokeBooleanReturn>('set_trend_line_alert_cmd', {
+    req,
+  });
+
+// 画线（趋势线、水平线、通道、斐波那契、矩形、文字标注等）
+export const getDrawingsApi = (
+  code: string,
+  period: string,
+  kind?: responseType.DrawingKind,
+) =>
+  invoke<responseType.DrawingsInvokeReturn>('get_drawings_cmd', {
+    code,
+    period,
+    kind,
+  });
+
+export const addDrawingApi = (params: responseType.DrawingParams) =>
+  invoke<responseType.AddDrawingInvokeReturn>('add_drawing_cmd', { params });
+
+export const updateDrawingApi = (
+  id: number,
+  params: responseType.DrawingParams,
+) =>
+  invoke<responseType.InvokeBooleanReturn>('update_drawing_cmd', {
+    id,
+    params,
+  });
+
+export const deleteDrawingApi = (id: number) =>
+  invoke<responseType.InvokeBooleanReturn>('delete_drawing_cmd', { id });
+
+// 查询趋势线突破记录
+export const getTrendLineBreaksApi = (
+  code?: string,
+  lineId?: number,
+  limit?: number,
+) =>
+  invoke<responseType.TrendLineBreaksInvokeReturn>(
+    'get_trend_line_breaks_cmd',
+    { code, lineId, limit },
+  );
+
+// 迁移到画线时未能转换、原样保留的旧版趋势线条数
+export const getLegacyTrendLineCountApi = () =>
+  invoke<responseType.InvokeNumberReturn>('get_legacy_trend_line_count_cmd');
 // 操作反省
 
 export const getSelfReflectApi = () =>
@@ -204,18 +275,24 @@ export const deleteSelfReflectApi = (id: number) =>
   });
 
 /**
- * 获取所有持仓列表
+ * 获取所有持仓列表（不传账户时汇总全部账户）
  */
-export const getAllHoldingsApi = () =>
-  invoke<responseType.GetAllHoldingsInvokeReturn>('get_all_holdings_cmd');
+export const getAllHoldingsApi = (accountId?: number) =>
+  invoke<responseType.GetAllHoldingsInvokeReturn>('get_all_holdings_cmd', {
+    accountId,
+  });
 
 export const getHistoryHoldingsApi = (params: QueryOrdersParams) =>
-  invoke<responseType.GetAllHoldingsInvokeReturn>('get_history_holdings_cmd', {
-    params: {
-      page: params.current,
-      page_size: params.pageSize,
+  invoke<responseType.GetHistoryHoldingsInvokeReturn>(
+    'get_history_holdings_cmd',
+    {
+      params: {
+        page: params.current,
+        page_size: params.pageSize,
+        account_id: params.accountId,
+      },
     },
-  });
+  );
 /**
  * 添加持仓
  */
@@ -233,52 +310,399 @@ export const queryHoldingByCodeApi = (code: string) =>
       code,
     },
   );
-/**
- * 更新持仓
- */
-export const updateHoldingApi = (params: responseType.UpdateHoldingParams) =>
-  invoke<responseType.InvokeBooleanReturn>('update_holding_cmd', {
-    params,
-  });
-
-export const deleteHoldingApi = (id: number) =>
-  invoke<responseType.InvokeBooleanReturn>('delete_holding_cmd', {
-    id,
-  });
 /**
  * 获取所有委托列表
  */
 export interface QueryOrdersParams {
   current: number; // 页码，从1开始
   pageSize: number; // 每页大小
+  accountId?: number; // 账户，为空时汇总全部账户
 }
 export const getAllOrdersApi = (params: QueryOrdersParams) =>
   invoke<responseType.GetAllOrdersInvokeReturn>('get_all_orders_cmd', {
     params: {
       page: params.current,
       page_size: params.pageSize,
+      account_id: params.accountId,
     },
   });
 
-export const getOrdersByCodeApi = (code: string) =>
+export const getOrdersByCodeApi = (code: string, accountId?: number) =>
   invoke<responseType.GetOrdersByCodeInvokeReturn>('get_orders_by_code_cmd', {
     code,
+    accountId,
   });
 
-export const getMonthlyStatsApi = (params: { year: number; month: number }) =>
+export const getMonthlyStatsApi = (params: {
+  year: number;
+  month: number;
+  account_id?: number;
+}) =>
   invoke<responseType.GetMonthlyStatsInvokeReturn>('get_monthly_stats_cmd', {
     params,
   });
 /**
  * 添加委托
  */
-export const addOrderApi = (params: Omit<responseType.OrderItem, 'id'>) =>
+export const addOrderApi = (params: responseType.AddOrderParams) =>
   invoke<responseType.InvokeBooleanReturn>('add_order_cmd', { params });
 
 export const deleteOrderApi = (id: number) =>
   invoke<responseType.InvokeBooleanReturn>('delete_order_cmd', { id });
 
-export const importDatabaseApi = (zipData: Uint8Array) =>
-  invoke<string>('import_database', {
+// 添加/删除委托并在后端同步更新持仓（同一事务）
+export const addOrderWithHoldingApi = (
+  params: responseType.AddOrderParams,
+) =>
+  invoke<responseType.InvokeBooleanReturn>('add_order_with_holding_cmd', {
+    params,
+  });
+
+export const deleteOrderWithHoldingApi = (id: number) =>
+  invoke<responseType.InvokeBooleanReturn>('delete_order_with_holding_cmd', {
+    id,
+  });
+
+// 按全部委托记录重建持仓
+export const rebuildHoldingsApi = () =>
+  invoke<responseType.InvokeBooleanReturn>('rebuild_holdings_cmd');
+
+export const importDatabaseApi = (
+  zipData: Uint8Array,
+  strategy: responseType.ImportStrategy = 'keep_local',
+  dryRun = false,
+) =>
+  invoke<responseType.ImportReport>('import_database', {
     zipData: Array.from(zipData),
+    strategy,
+    dryRun,
+  });
+
+// 导出全部数据（ZIP，含 manifest.json），可用 importDatabaseApi 导入
+export const exportDatabaseApi = () =>
+  invoke<number[]>('export_database').then((data) => new Uint8Array(data));
+
+// 自动备份
+export const getBackupSettingsApi = () =>
+  invoke<responseType.GetBackupSettingsInvokeReturn>(
+    'get_backup_settings_cmd',
+  );
+
+export const setBackupSettingsApi = (settings: responseType.BackupSettings) =>
+  invoke<responseType.InvokeBooleanReturn>('set_backup_settings_cmd', {
+    settings,
+  });
+
+export const listBackupSnapshotsApi = () =>
+  invoke<responseType.BackupSnapshotsInvokeReturn>(
+    'list_backup_snapshots_cmd',
+  );
+
+export const createBackupSnapshotApi = () =>
+  invoke<responseType.BackupSnapshotInvokeReturn>(
+    'create_backup_snapshot_cmd',
+  );
+
+// 从本地快照恢复，默认替换现有数据
+export const restoreBackupSnapshotApi = (
+  fileName: string,
+  strategy: responseType.ImportStrategy = 'replace',
+  dryRun = false,
+) =>
+  invoke<responseType.ImportReportInvokeReturn>(
+    'restore_backup_snapshot_cmd',
+    { fileName, strategy, dryRun },
+  );
+
+// 敏感配置加密
+export const getSecretStatusApi = () =>
+  invoke<responseType.SecretStatusInvokeReturn>('get_secret_status_cmd');
+
+export const unlockSecretsApi = (passphrase: string) =>
+  invoke<responseType.InvokeBooleanReturn>('unlock_secrets_cmd', {
+    passphrase,
+  });
+
+// 传入 null 时改回本机密钥文件
+export const setSecretPassphraseApi = (passphrase: string | null) =>
+  invoke<responseType.InvokeBooleanReturn>('set_secret_passphrase_cmd', {
+    passphrase,
   });
+
+// 行情数据源
+export const getMarketDataProviderApi = () =>
+  invoke<responseType.GetMarketDataProviderInvokeReturn>(
+    'get_market_data_provider_cmd',
+  );
+
+export const setMarketDataProviderApi = (provider: string) =>
+  invoke<responseType.InvokeBooleanReturn>('set_market_data_provider_cmd', {
+    provider,
+  });
+
+// 离线模式
+export const getOfflineModeApi = () =>
+  invoke<responseType.InvokeBooleanReturn>('get_offline_mode_cmd');
+
+export const setOfflineModeApi = (enabled: boolean) =>
+  invoke<responseType.InvokeBooleanReturn>('set_offline_mode_cmd', {
+    enabled,
+  });
+
+// 持仓批次
+export const getHoldingLotsApi = (holdingId: number) =>
+  invoke<responseType.GetHoldingLotsInvokeReturn>('get_holding_lots_cmd', {
+    holdingId,
+  });
+
+// 卖出成本计算方式（fifo / average）
+export const getCostMethodApi = () =>
+  invoke<responseType.GetCostMethodInvokeReturn>('get_cost_method_cmd');
+
+export const setCostMethodApi = (method: 'fifo' | 'average') =>
+  invoke<responseType.InvokeBooleanReturn>('set_cost_method_cmd', { method });
+
+// 交易费用模型
+export const getFeeModelApi = () =>
+  invoke<responseType.GetFeeModelInvokeReturn>('get_fee_model_cmd');
+
+export const setFeeModelApi = (model: responseType.FeeModel) =>
+  invoke<responseType.InvokeBooleanReturn>('set_fee_model_cmd', { model });
+
+// 组合分析
+export const getPortfolioAnalyticsApi = (
+  params: responseType.PortfolioAnalyticsParams,
+) =>
+  invoke<responseType.GetPortfolioAnalyticsInvokeReturn>(
+    'get_portfolio_analytics_cmd',
+    { params },
+  );
+
+export const getEquityCurveApi = (
+  params: responseType.PortfolioAnalyticsParams,
+) =>
+  invoke<responseType.GetEquityCurveInvokeReturn>('get_equity_curve_cmd', {
+    params,
+  });
+
+// 资金流水与账户总览
+export const addCashEntryApi = (params: responseType.AddCashEntryParams) =>
+  invoke<responseType.AddCashEntryInvokeReturn>('add_cash_entry_cmd', {
+    params,
+  });
+
+export const deleteCashEntryApi = (id: number) =>
+  invoke<responseType.InvokeBooleanReturn>('delete_cash_entry_cmd', { id });
+
+export const getCashFlowsApi = (accountId?: number) =>
+  invoke<responseType.GetCashFlowsInvokeReturn>('get_cash_flows_cmd', {
+    accountId,
+  });
+
+export const getAccountSummaryApi = (accountId?: number) =>
+  invoke<responseType.GetAccountSummaryInvokeReturn>(
+    'get_account_summary_cmd',
+    { accountId },
+  );
+
+export const getCashStrictModeApi = () =>
+  invoke<responseType.InvokeBooleanReturn>('get_cash_strict_mode_cmd');
+
+export const setCashStrictModeApi = (enabled: boolean) =>
+  invoke<responseType.InvokeBooleanReturn>('set_cash_strict_mode_cmd', {
+    enabled,
+  });
+
+// 账户 / 组合
+export const getAccountsApi = (includeArchived = false) =>
+  invoke<responseType.GetAccountsInvokeReturn>('get_accounts_cmd', {
+    includeArchived,
+  });
+
+export const createAccountApi = (name: string) =>
+  invoke<responseType.CreateAccountInvokeReturn>('create_account_cmd', {
+    name,
+  });
+
+export const renameAccountApi = (id: number, name: string) =>
+  invoke<responseType.InvokeBooleanReturn>('rename_account_cmd', {
+    id,
+    name,
+  });
+
+export const archiveAccountApi = (id: number, archived: boolean) =>
+  invoke<responseType.InvokeBooleanReturn>('archive_account_cmd', {
+    id,
+    archived,
+  });
+
+// 按成本价在账户之间划转持仓
+export const transferPositionApi = (
+  params: responseType.TransferPositionParams,
+) =>
+  invoke<responseType.CreateAccountInvokeReturn>('transfer_position_cmd', {
+    params,
+  });
+
+export const getPositionTransfersApi = (accountId?: number, code?: string) =>
+  invoke<responseType.GetPositionTransfersInvokeReturn>(
+    'get_position_transfers_cmd',
+    { accountId, code },
+  );
+
+export const deletePositionTransferApi = (id: number) =>
+  invoke<responseType.InvokeBooleanReturn>('delete_position_transfer_cmd', {
+    id,
+  });
+
+// 公司行动（分红送转、拆合股）
+export const getCorporateActionsApi = (code?: string) =>
+  invoke<responseType.GetCorporateActionsInvokeReturn>(
+    'get_corporate_actions_cmd',
+    { code },
+  );
+
+export const addCorporateActionApi = (
+  params: responseType.AddCorporateActionParams,
+) =>
+  invoke<responseType.CorporateActionCountInvokeReturn>(
+    'add_corporate_action_cmd',
+    { params },
+  );
+
+export const deleteCorporateActionApi = (id: number) =>
+  invoke<responseType.InvokeBooleanReturn>('delete_corporate_action_cmd', {
+    id,
+  });
+
+// 从行情数据源获取分红送转，返回新增条数
+export const fetchCorporateActionsApi = (code: string) =>
+  invoke<responseType.CorporateActionCountInvokeReturn>(
+    'fetch_corporate_actions_cmd',
+    { code },
+  );
+
+export const getHoldingDividendsApi = (holdingId: number) =>
+  invoke<responseType.GetHoldingDividendsInvokeReturn>(
+    'get_holding_dividends_cmd',
+    { holdingId },
+  );
+
+// K线复权方式，切换后清空K线缓存
+export const getKlineAdjustApi = () =>
+  invoke<responseType.GetKlineAdjustInvokeReturn>('get_kline_adjust_cmd');
+
+export const setKlineAdjustApi = (adjust: responseType.KlineAdjust) =>
+  invoke<responseType.InvokeBooleanReturn>('set_kline_adjust_cmd', {
+    adjust,
+  });
+
+// 券商交割单导入
+export const getStatementBrokersApi = () =>
+  invoke<responseType.GetStatementBrokersInvokeReturn>(
+    'get_statement_brokers_cmd',
+  );
+
+export const saveStatementColumnsApi = (
+  columns: responseType.StatementColumns,
+) =>
+  invoke<responseType.InvokeBooleanReturn>('save_statement_columns_cmd', {
+    columns,
+  });
+
+// 预览不写入数据库，确认后用同一份参数调用导入
+export const previewStatementImportApi = (
+  params: responseType.StatementImportParams,
+) =>
+  invoke<responseType.StatementPreviewInvokeReturn>(
+    'preview_statement_import_cmd',
+    { params },
+  );
+
+export const importStatementApi = (
+  params: responseType.StatementImportParams,
+) =>
+  invoke<responseType.StatementPreviewInvokeReturn>('import_statement_cmd', {
+    params,
+  });
+
+// 价格提醒
+export const getAlertRulesApi = (code?: string) =>
+  invoke<responseType.AlertRulesInvokeReturn>('get_alert_rules_cmd', { code });
+
+export const addAlertRuleApi = (params: responseType.AlertRuleParams) =>
+  invoke<responseType.AddAlertRuleInvokeReturn>('add_alert_rule_cmd', {
+    params,
+  });
+
+export const updateAlertRuleApi = (
+  id: number,
+  params: responseType.AlertRuleParams,
+) =>
+  invoke<responseType.InvokeBooleanReturn>('update_alert_rule_cmd', {
+    id,
+    params,
+  });
+
+export const setAlertRuleEnabledApi = (id: number, enabled: boolean) =>
+  invoke<responseType.InvokeBooleanReturn>('set_alert_rule_enabled_cmd', {
+    id,
+    enabled,
+  });
+
+export const deleteAlertRuleApi = (id: number) =>
+  invoke<responseType.InvokeBooleanReturn>('delete_alert_rule_cmd', { id });
+
+export const getAlertEventsApi = (code?: string, limit?: number) =>
+  invoke<responseType.AlertEventsInvokeReturn>('get_alert_events_cmd', {
+    code,
+    limit,
+  });
+
+export const clearAlertEventsApi = () =>
+  invoke<responseType.InvokeBooleanReturn>('clear_alert_events_cmd');
+
+export const getAlertSettingsApi = () =>
+  invoke<responseType.AlertSettingsInvokeReturn>('get_alert_settings_cmd');
+
+export const setAlertSettingsApi = (settings: responseType.AlertSettings) =>
+  invoke<responseType.InvokeBooleanReturn>('set_alert_settings_cmd', {
+    settings,
+  });
+
+// 监听后台触发的价格提醒，返回取消监听的函数
+export const onPriceAlert = (
+  handler: (event: responseType.AlertEvent) => void,
+) =>
+  listen<responseType.AlertEvent>('price-alert', (e) => handler(e.payload));
+
+// 监听后台检测到的趋势线突破，返回取消监听的函数
+export const onTrendLineBreak = (
+  handler: (event: responseType.TrendLineBreak) => void,
+) =>
+  listen<responseType.TrendLineBreak>('trend-line-break', (e) =>
+    handler(e.payload),
+  );
+
+// 交易日历
+export const getMarketStatusApi = () =>
+  invoke<responseType.MarketStatusInvokeReturn>('get_market_status_cmd');
+
+export const getTradingCalendarApi = (year: number, month?: number) =>
+  invoke<responseType.TradingCalendarInvokeReturn>(
+    'get_trading_calendar_cmd',
+    { year, month },
+  );
+
+export const countTradingDaysApi = (start: string, end: string) =>
+  invoke<responseType.CountTradingDaysInvokeReturn>(
+    'count_trading_days_cmd',
+    { start, end },
+  );
+
+// dates 不传时恢复内置休市安排
+export const setTradingHolidaysApi = (year: number, dates?: string[]) =>
+  invoke<responseType.TradingCalendarInvokeReturn>(
+    'set_trading_holidays_cmd',
+    { year, dates },
+  );
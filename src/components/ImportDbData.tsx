@@ -7,14 +7,60 @@ import {
   Alert,
   Typography,
   Collapse,
+  Radio,
+  Table,
 } from 'antd';
 import { InboxOutlined, QuestionCircleOutlined } from '@ant-design/icons';
 import { importDatabaseApi } from '@/apis/api';
+import type {
+  ImportReport,
+  ImportStrategy,
+  TableImportStats,
+} from '@/types/response';
 
 const { Dragger } = Upload;
 const { Text, Paragraph } = Typography;
 const { Panel } = Collapse;
 
+const strategyOptions: { label: string; value: ImportStrategy }[] = [
+  { label: '合并（冲突保留本地）', value: 'keep_local' },
+  { label: '合并（冲突使用备份）', value: 'keep_backup' },
+  { label: '追加（重新分配编号）', value: 'append' },
+  { label: '全部替换', value: 'replace' },
+];
+
+const statsColumns = [
+  { title: '数据表', dataIndex: 'table', key: 'table' },
+  { title: '新增', dataIndex: 'inserted', key: 'inserted' },
+  { title: '覆盖', dataIndex: 'updated', key: 'updated' },
+  { title: '冲突', dataIndex: 'conflicts', key: 'conflicts' },
+  { title: '编号重分配', dataIndex: 'rekeyed', key: 'rekeyed' },
+  { title: '清空', dataIndex: 'deleted', key: 'deleted' },
+  { title: '跳过', dataIndex: 'skipped', key: 'skipped' },
+  { title: '失败', dataIndex: 'failed', key: 'failed' },
+];
+
+const summarize = (report: ImportReport) => {
+  const sum = (key: keyof Omit<TableImportStats, 'table'>) =>
+    report.tables.reduce((total, item) => total + item[key], 0);
+  const lines = [
+    `新增 ${sum('inserted')} 条，覆盖 ${sum('updated')} 条，冲突 ${sum(
+      'conflicts',
+    )} 条，编号重分配 ${sum('rekeyed')} 条，清空 ${sum('deleted')} 条，跳过 ${sum('skipped')} 条，失败 ${sum(
+      'failed',
+    )} 条`,
+  ];
+  if (report.manifest) {
+    lines.push(
+      `备份来自 ${report.manifest.app_version} 版，导出于 ${report.manifest.created_at}`,
+    );
+  }
+  if (report.missing.length > 0) {
+    lines.push(`备份中没有：${report.missing.join('、')}`);
+  }
+  return lines.join('\n');
+};
+
 interface DatabaseImportProps {
   open: boolean;
   onClose: () => void;
@@ -28,16 +74,22 @@ export default function DatabaseImport({
 }: DatabaseImportProps) {
   const [importing, setImporting] = useState(false);
   const [progress, setProgress] = useState(0);
+  const [strategy, setStrategy] = useState<ImportStrategy>('keep_local');
+  // 已预演的备份文件，确认后才真正导入
+  const [pendingData, setPendingData] = useState<Uint8Array | null>(null);
+  const [preview, setPreview] = useState<ImportReport | null>(null);
   const [importResult, setImportResult] = useState<{
     type: 'success' | 'error' | 'info';
     message: string;
     details?: string;
   } | null>(null);
 
-  const handleImport = async (file: File) => {
+  // 预演导入：统计各表变更，不写入数据库
+  const handlePreview = async (file: File) => {
     setImporting(true);
     setProgress(0);
     setImportResult(null);
+    setPreview(null);
 
     try {
       // 检查文件类型
@@ -49,20 +101,48 @@ export default function DatabaseImport({
       const arrayBuffer = await file.arrayBuffer();
       const uint8Array = new Uint8Array(arrayBuffer);
 
-      // 调用后端导入命令
-      const result = await importDatabaseApi(uint8Array);
+      const report = await importDatabaseApi(uint8Array, strategy, true);
+      setPendingData(uint8Array);
+      setPreview(report);
+      setImportResult({
+        type: 'info',
+        message: '导入预览（尚未写入）',
+        details: summarize(report),
+      });
+    } catch (error) {
+      console.error('预览失败:', error);
+      setImportResult({
+        type: 'error',
+        message: `备份文件校验失败`,
+        details: error instanceof Error ? error.message : String(error),
+      });
+    } finally {
+      setImporting(false);
+    }
+  };
+
+  const handleImport = async () => {
+    if (!pendingData) return;
+    setImporting(true);
+    setProgress(0);
 
+    try {
+      // 调用后端导入命令（全部成功才会写入）
+      const report = await importDatabaseApi(pendingData, strategy);
+
+      setPendingData(null);
+      setPreview(report);
       setImportResult({
         type: 'success',
         message: `数据库导入完成`,
-        details: result,
+        details: summarize(report),
       });
       setProgress(100);
 
       // 延迟执行成功回调，让用户看到成功消息
       setTimeout(() => {
         onImportSuccess();
-        onClose();
+        handleClose();
       }, 2000);
     } catch (error) {
       console.error('导入失败:', error);
@@ -79,6 +159,8 @@ export default function DatabaseImport({
   const handleClose = () => {
     setImportResult(null);
     setProgress(0);
+    setPendingData(null);
+    setPreview(null);
     onClose();
   };
 
@@ -88,7 +170,7 @@ export default function DatabaseImport({
     accept: '.zip',
     showUploadList: false,
     beforeUpload: (file: File) => {
-      handleImport(file);
+      handlePreview(file);
       return false; // 阻止默认上传行为
     },
   };
@@ -102,10 +184,32 @@ export default function DatabaseImport({
         <Button key="close" onClick={handleClose}>
           关闭
         </Button>,
+        <Button
+          key="import"
+          type="primary"
+          disabled={!pendingData}
+          loading={importing}
+          onClick={handleImport}
+        >
+          确认导入
+        </Button>,
       ]}
       width={700}
     >
       <div style={{ padding: '20px 0' }}>
+        <Radio.Group
+          style={{ marginBottom: 16 }}
+          options={strategyOptions}
+          value={strategy}
+          disabled={importing}
+          onChange={(e) => {
+            // 切换策略后需要重新预览
+            setStrategy(e.target.value);
+            setPendingData(null);
+            setPreview(null);
+            setImportResult(null);
+          }}
+        />
         <Dragger {...uploadProps} disabled={importing}>
           <p className="ant-upload-drag-icon">
             <InboxOutlined />
@@ -130,7 +234,10 @@ export default function DatabaseImport({
               message={importResult.message}
               description={
                 importResult.details && (
-                  <Text type="secondary" style={{ fontSize: '12px' }}>
+                  <Text
+                    type="secondary"
+                    style={{ fontSize: '12px', whiteSpace: 'pre-line' }}
+                  >
                     {importResult.details}
                   </Text>
                 )
@@ -143,6 +250,28 @@ export default function DatabaseImport({
           </div>
         )}
 
+        {preview && (
+          <Table
+            style={{ marginTop: 16 }}
+            size="small"
+            rowKey="table"
+            pagination={false}
+            columns={statsColumns}
+            dataSource={preview.tables}
+            expandable={{
+              // 展开查看跳过和失败的原因
+              rowExpandable: (record) => record.errors.length > 0,
+              expandedRowRender: (record) => (
+                <ul style={{ margin: 0, paddingLeft: '20px' }}>
+                  {record.errors.map((error) => (
+                    <li key={error}>{error}</li>
+                  ))}
+                </ul>
+              ),
+            }}
+          />
+        )}
+
         <Collapse
           style={{ marginTop: 20 }}
           expandIcon={({ isActive }) => (
@@ -157,7 +286,8 @@ export default function DatabaseImport({
               </Paragraph>
               <ul style={{ margin: 0, paddingLeft: '20px' }}>
                 <li>请确保备份文件包含完整的数据库文件</li>
-                <li>导入过程将覆盖现有的数据</li>
+                <li>上传后先预览各表的变更，确认后才会写入</li>
+                <li>导入失败时不会修改现有数据</li>
                 <li>建议在导入前备份当前数据</li>
                 <li>支持子目录结构（如 databases/ 目录）</li>
                 <li>自动忽略 macOS 系统文件</li>
@@ -167,13 +297,15 @@ export default function DatabaseImport({
                 <strong>需要的数据库文件：</strong>
               </Paragraph>
               <ul style={{ margin: 0, paddingLeft: '20px' }}>
+                <li>app.db（新版备份，包含全部数据）</li>
+                <li>或旧版按功能拆分的文件：</li>
                 <li>app_config.db</li>
                 <li>all_stocks.db</li>
                 <li>my_selection.db</li>
                 <li>stock_review.db</li>
                 <li>self_reflect.db</li>
                 <li>market_analysis.db</li>
-                <li>stock_lines.db</li>
+                <li>trend_lines.db</li>
                 <li>holdings.db</li>
                 <li>orders.db</li>
               </ul>
@@ -1,11 +1,18 @@
 import { SettingOutlined } from '@ant-design/icons';
 import type { MenuProps } from 'antd';
-import { Dropdown, Input, Modal } from 'antd';
+import { Dropdown, Input, Modal, notification } from 'antd';
 import { invoke } from '@tauri-apps/api/core';
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
 import { useNavigate } from 'react-router-dom';
 import HeaderSearch, { StockValue } from './HeaderSearch';
 import DatabaseImport from '@/components/ImportDbData';
+import {
+  getLegacyTrendLineCountApi,
+  getMarketStatusApi,
+  getOfflineModeApi,
+  getSecretStatusApi,
+  setOfflineModeApi,
+} from '@/apis/api';
 import { Button } from 'antd';
 import {
   ArrowLeftOutlined,
@@ -71,6 +78,45 @@ export default function HeaderNav() {
 function RightDropdown() {
   const [isModalOpen, setIsModalOpen] = useState(false);
   const [importModalOpen, setImportModalOpen] = useState(false);
+  const [offline, setOffline] = useState(false);
+  useEffect(() => {
+    getOfflineModeApi().then((res) => setOffline(res.success && res.data));
+    // 启动时密钥加载失败，敏感配置（雪球 Cookie 等）处于锁定状态
+    getSecretStatusApi().then((res) => {
+      if (res.success && res.data.load_error) {
+        notification.warning({
+          message: '敏感配置已锁定',
+          description: `密钥加载失败：${res.data.load_error}，雪球 Cookie 等敏感配置暂时无法读写`,
+          duration: 0,
+        });
+      }
+    });
+    // 内置休市表没有今年的安排：节假日会被当作交易日
+    getMarketStatusApi().then((res) => {
+      if (res.success && !res.data.holiday_data_available) {
+        notification.warning({
+          message: '缺少今年的休市安排',
+          description: `没有 ${res.data.date.slice(0, 4)} 年的交易所休市安排，节假日会被当作交易日，价格提醒和交易时段判断可能不准确，请升级应用`,
+          duration: 0,
+        });
+      }
+    });
+    // 升级时时间或价格无效的旧版趋势线没有转换为画线，单独保留
+    getLegacyTrendLineCountApi().then((res) => {
+      if (res.success && res.data > 0) {
+        notification.warning({
+          message: '部分旧版趋势线未能转换',
+          description: `${res.data} 条趋势线的时间或价格无效，未显示在图表上，原数据保留在 trend_lines_legacy 表中`,
+          duration: 0,
+        });
+      }
+    });
+  }, []);
+  const toggleOffline = () => {
+    setOfflineModeApi(!offline).then((res) => {
+      if (res.success) setOffline(!offline);
+    });
+  };
   const items: MenuProps['items'] = [
     {
       key: '1',
@@ -80,6 +126,14 @@ function RightDropdown() {
       key: '2',
       label: <a onClick={() => setImportModalOpen(true)}>导入数据库</a>,
     },
+    {
+      key: '3',
+      label: (
+        <a onClick={toggleOffline}>
+          {offline ? '关闭离线模式' : '开启离线模式'}
+        </a>
+      ),
+    },
   ];
   return (
     <>
@@ -1,9 +1,9 @@
 import { Table, Tag, Empty } from 'antd';
 import type { TableColumnsType, TablePaginationConfig } from 'antd';
-import type { HoldingItem } from '@/types/response';
+import type { HoldingExit } from '@/types/response';
 
 interface HistoryTableProps {
-  historyList: HoldingItem[];
+  historyList: HoldingExit[];
   pagination: any;
   onChange: (pagination: TablePaginationConfig) => void;
 }
@@ -13,7 +13,7 @@ export default function HistoryTable({
   pagination,
   onChange,
 }: HistoryTableProps) {
-  const columns: TableColumnsType<HoldingItem> = [
+  const columns: TableColumnsType<HoldingExit> = [
     {
       title: '股票名称',
       dataIndex: 'name',
@@ -26,11 +26,16 @@ export default function HistoryTable({
       ),
     },
     {
-      title: '持仓数量',
+      title: '卖出数量',
       dataIndex: 'quantity',
       width: 100,
-      render: (value) => (
-        <span className="font-semibold">{value.toLocaleString()} 股</span>
+      render: (value, record) => (
+        <div>
+          <span className="font-semibold">{value.toLocaleString()} 股</span>
+          <div className="text-gray-500 text-sm">
+            {record.closed ? '清仓' : '部分卖出'}
+          </div>
+        </div>
       ),
     },
     {
@@ -54,6 +59,14 @@ export default function HistoryTable({
           '-'
         ),
     },
+    {
+      title: '卖出费用',
+      dataIndex: 'fee',
+      width: 100,
+      render: (value) => (
+        <span className="text-gray-600">¥{(value ?? 0).toFixed(2)}</span>
+      ),
+    },
     {
       title: '盈利情况',
       dataIndex: 'profit',
@@ -139,12 +152,12 @@ export default function HistoryTable({
       <div className="mb-4">
         <h3 className="text-lg font-semibold text-gray-800">交易历史</h3>
         <p className="text-gray-600 text-sm">
-          共 {pagination.total} 条历史持仓记录
+          共 {pagination.total} 条卖出记录
         </p>
       </div>
 
       {/* 表格 */}
-      <Table<HoldingItem>
+      <Table<HoldingExit>
         columns={columns}
         dataSource={historyList}
         size="middle"
@@ -159,8 +172,8 @@ export default function HistoryTable({
           pageSizeOptions: ['10', '20', '50', '100'],
         }}
         onChange={onChange}
-        rowKey="id"
-        scroll={{ x: 900 }}
+        rowKey={(record) => `${record.holding_id}-${record.id ?? 0}`}
+        scroll={{ x: 1000 }}
         className="shadow-sm"
       />
     </div>
@@ -96,6 +96,20 @@ export default function OrdersTable({
         </span>
       ),
     },
+    {
+      title: '费用',
+      width: 100,
+      render: (_, record) => {
+        const fee = record.commission + record.stamp_duty + record.transfer_fee;
+        return (
+          <Tooltip
+            title={`佣金 ${record.commission.toFixed(2)} / 印花税 ${record.stamp_duty.toFixed(2)} / 过户费 ${record.transfer_fee.toFixed(2)}`}
+          >
+            <span className="text-gray-600">¥{fee.toFixed(2)}</span>
+          </Tooltip>
+        );
+      },
+    },
     {
       title: '委托时间',
       dataIndex: 'time',
@@ -188,7 +202,7 @@ export default function OrdersTable({
             : 'bg-green-50 hover:bg-green-100'
         }
         rowKey="id"
-        scroll={{ x: 800 }}
+        scroll={{ x: 900 }}
         className="shadow-sm"
       />
     </div>
@@ -17,7 +17,12 @@ import {
   getHistoryHoldingsApi,
   getMonthlyStatsApi,
 } from '@/apis/api';
-import type { HoldingItem, OrderItem, MonthlyStats } from '@/types/response';
+import type {
+  HoldingItem,
+  HoldingExit,
+  OrderItem,
+  MonthlyStats,
+} from '@/types/response';
 import { useRealTimeData } from '@/hooks/useRealTimeData';
 import HoldingsCard from '@/components/myHolding/HoldingsCard';
 import OrdersTable from '@/components/myHolding/OrdersTable';
@@ -35,7 +40,7 @@ export default function MyHolding() {
   const [activeTab, setActiveTab] = useState('holdings');
   const [modalOpen, setModalOpen] = useState(false);
   const [holdingList, setHoldingList] = useState<HoldingItem[]>([]);
-  const [historyHoldingList, setHistoryHoldingList] = useState<HoldingItem[]>(
+  const [historyHoldingList, setHistoryHoldingList] = useState<HoldingExit[]>(
     [],
   );
   const [orderList, setOrderList] = useState<OrderItem[]>([]);
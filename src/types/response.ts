@@ -3,9 +3,13 @@ type ResponseBaseType<T> = {
   message: string;
   data: T;
   count?: number;
+  provider?: string; // 行情数据来源（cache 表示本地数据）
+  stale?: boolean; // 是否为离线/过期数据
+  as_of?: string; // 数据时间（YYYY-MM-DD HH:mm:ss）
 };
 
 export type InvokeBooleanReturn = Promise<ResponseBaseType<boolean>>;
+export type InvokeNumberReturn = Promise<ResponseBaseType<number>>;
 
 export type SearchStocksResponse = {
   symbol: string;
@@ -26,8 +30,19 @@ export type KlineDataResponse = {
   percent: number; // 涨跌幅
   turnoverrate: number; // 换手率
 };
+// 技术指标请求：name 为 ma/ema/macd/kdj/rsi/boll/atr/obv/vwap，params 为空时使用默认参数
+export type IndicatorSpec = {
+  name: string;
+  params?: number[];
+};
+// 技术指标结果：series 中每条序列与K线一一对齐，数据不足处为 null
+export type IndicatorResult = {
+  name: string;
+  params: number[];
+  series: Record<string, (number | null)[]>;
+};
 export type KlineDataInvokeReturn = Promise<
-  ResponseBaseType<KlineDataResponse[]>
+  ResponseBaseType<KlineDataResponse[]> & { indicators?: IndicatorResult[] }
 >;
 
 export interface SelectionItem {
@@ -107,12 +122,86 @@ export interface StockTrendLineType {
   start_price: number; // 起始价格
   end_time: number; // 结束K线时间戳（13位毫秒）
   end_price: number; // 结束价格
+  alert_enabled: boolean; // 是否开启突破提醒
+  alert_mode: TrendLineAlertMode;
+  tolerance: number; // 触及模式的容差（%）
 }
 
 export type GetTrendLinesInvokeReturn = Promise<
   ResponseBaseType<StockTrendLineType[]>
 >;
 
+// 突破提醒模式：cross 实时价穿越、touch 触及容差范围、close K线收盘价突破
+export type TrendLineAlertMode = 'cross' | 'touch' | 'close';
+
+export interface TrendLineAlertReq {
+  id: number;
+  enabled: boolean;
+  mode?: TrendLineAlertMode;
+  tolerance?: number;
+}
+
+export interface TrendLineBreak {
+  id: number;
+  line_id: number;
+  code: string;
+  period: string;
+  mode: TrendLineAlertMode;
+  direction: 'up' | 'down' | 'touch';
+  line_price: number; // 触发时趋势线的价格
+  price: number; // 触发价（收盘模式为收盘价）
+  bar_time: number | null; // 收盘模式对应的K线时间戳
+  message: string;
+  triggered_at: string;
+}
+
+export type TrendLineBreaksInvokeReturn = Promise<
+  ResponseBaseType<TrendLineBreak[]>
+>;
+
+// 画线类型：趋势线、水平线、射线、直线、平行通道、斐波那契回撤、矩形、文字标注
+export type DrawingKind =
+  | 'trend_line'
+  | 'horizontal_line'
+  | 'ray'
+  | 'extended_line'
+  | 'parallel_channel'
+  | 'fibonacci'
+  | 'rectangle'
+  | 'text';
+
+export interface DrawingPoint {
+  time: number; // K线时间戳（13位毫秒）
+  price: number;
+}
+
+export interface Drawing {
+  id: number;
+  code: string;
+  period: string;
+  kind: DrawingKind;
+  points: DrawingPoint[]; // 水平线、文字 1 个，平行通道 3 个，其余 2 个
+  style: Record<string, unknown>; // 颜色、线宽等
+  label: string;
+  alert_enabled: boolean; // 仅直线类画线支持突破提醒
+  alert_mode: TrendLineAlertMode;
+  tolerance: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface DrawingParams {
+  code: string;
+  period: string;
+  kind: DrawingKind;
+  points: DrawingPoint[];
+  style?: Record<string, unknown>;
+  label?: string;
+}
+
+export type DrawingsInvokeReturn = Promise<ResponseBaseType<Drawing[]>>;
+export type AddDrawingInvokeReturn = Promise<ResponseBaseType<number>>;
+
 export interface StockReviewItem {
   id: number; // 唯一标识符
   code: string; // 股票名称
@@ -170,9 +259,63 @@ export interface HoldingItem {
   status: number; // 1-当前持仓，0-历史记录
   sell_time?: string; // 卖出时间
   sell_price?: number; // 卖出价格
-  profit?: number; // 盈利
+  profit?: number; // 盈利（持仓中为已实现部分）
+  account_id: number; // 所属账户
+}
+
+// 持仓买入批次
+export interface HoldingLot {
+  id: number;
+  holding_id: number;
+  order_id?: number;
+  code: string;
+  buy_time: string;
+  price: number;
+  quantity: number;
+  remaining: number; // 剩余未卖出数量
+  fee: number; // 买入费用
+}
+
+// 卖出记录（部分卖出也单独一条）
+export interface HoldingExit {
+  id?: number; // 旧版清仓记录没有卖出明细时为空
+  holding_id: number;
+  order_id?: number;
+  code: string;
+  name: string;
+  hold_time: string;
+  sell_time: string;
+  sell_price: number;
+  quantity: number; // 卖出数量
+  cost: number; // 卖出部分的每股成本（含买入费用）
+  profit: number; // 本次已实现盈亏（已扣除费用）
+  closed: boolean; // 本次卖出后是否清仓
+  fee: number; // 卖出费用
+  account_id: number; // 所属账户
+}
+
+export type GetHistoryHoldingsInvokeReturn = Promise<
+  ResponseBaseType<HoldingExit[]>
+>;
+
+export type GetHoldingLotsInvokeReturn = Promise<
+  ResponseBaseType<HoldingLot[]>
+>;
+
+export type GetCostMethodInvokeReturn = Promise<
+  ResponseBaseType<'fifo' | 'average'>
+>;
+
+// 交易费用模型
+export interface FeeModel {
+  commission_rate: number; // 佣金费率
+  min_commission: number; // 最低佣金（元）
+  stamp_duty_rate: number; // 印花税率（仅卖出）
+  transfer_fee_rate: number; // 过户费率（仅沪市）
 }
 
+export type GetFeeModelInvokeReturn = Promise<ResponseBaseType<FeeModel>>;
+
 export interface AddHoldingParams {
   code: string;
   name: string;
@@ -180,16 +323,7 @@ export interface AddHoldingParams {
   quantity: number;
   hold_time: string;
   status?: number; // 可选，默认为1
-}
-
-export interface UpdateHoldingParams {
-  id: number;
-  cost?: number;
-  quantity?: number;
-  status?: number;
-  sell_time?: string;
-  sell_price?: number;
-  profit?: number;
+  account_id?: number; // 可选，默认账户
 }
 
 export type GetAllHoldingsInvokeReturn = Promise<
@@ -206,6 +340,8 @@ export interface MonthlyStats {
   operation_count: number;
   win_rate: number;
   total_profit: number;
+  trading_days: number; // 当月交易日数
+  average_holding_trading_days: number | null;
 }
 
 export type GetMonthlyStatsInvokeReturn = Promise<
@@ -221,8 +357,18 @@ export interface OrderItem {
   quantity: number; // 委托数量
   cost: number; // 委托价格/成本
   action: string; // 操作类型：买入/卖出
+  commission: number; // 佣金
+  stamp_duty: number; // 印花税
+  transfer_fee: number; // 过户费
+  account_id: number; // 所属账户
 }
 
+// 添加委托的参数，费用由后端按费用模型计算，不传账户时记入默认账户
+export type AddOrderParams = Omit<
+  OrderItem,
+  'id' | 'commission' | 'stamp_duty' | 'transfer_fee' | 'account_id'
+> & { account_id?: number };
+
 export interface PaginatedOrders<T> {
   orders: T[];
   total: number; // 总记录数
@@ -239,3 +385,461 @@ export type GetAllOrdersInvokeReturn = Promise<
 export type GetOrdersByCodeInvokeReturn = Promise<
   ResponseBaseType<OrderItem[]>
 >;
+
+// 行情数据源
+export interface MarketDataProviderInfo {
+  active: string; // 当前数据源 id
+  providers: { id: string; name: string }[]; // 可选数据源
+}
+export type GetMarketDataProviderInvokeReturn = Promise<
+  ResponseBaseType<MarketDataProviderInfo>
+>;
+
+// 组合分析
+export type AnalyticsGroup = 'day' | 'week' | 'month' | 'year';
+
+export interface PortfolioAnalyticsParams {
+  start?: string; // YYYY-MM-DD，为空表示从第一笔委托开始
+  end?: string; // YYYY-MM-DD，为空表示到最新行情
+  group?: AnalyticsGroup;
+  risk_free_rate?: number; // 年化无风险利率
+  account_id?: number; // 为空时汇总全部账户
+}
+
+export interface EquityPoint {
+  date: string;
+  market_value: number; // 持仓市值
+  net_invested: number; // 累计净投入
+  pnl: number; // 累计盈亏
+  nav: number; // 时间加权净值，区间起点为 1
+  drawdown: number; // 回撤（<= 0）
+}
+
+export interface PeriodPerformance {
+  period: string;
+  start: string;
+  end: string;
+  return_rate: number;
+  pnl: number;
+  realized_profit: number;
+  trade_count: number;
+  win_count: number;
+}
+
+export interface PerformanceSummary {
+  total_return: number;
+  annualized_return: number | null;
+  money_weighted_return: number | null;
+  total_pnl: number;
+  realized_profit: number;
+  max_drawdown: number;
+  max_drawdown_days: number;
+  sharpe_ratio: number | null;
+  sortino_ratio: number | null;
+  trade_count: number;
+  win_rate: number;
+  profit_factor: number | null;
+  average_win: number | null;
+  average_loss: number | null;
+  average_holding_days: number | null;
+  average_holding_trading_days: number | null;
+}
+
+export interface PortfolioAnalytics {
+  start: string;
+  end: string;
+  group: AnalyticsGroup;
+  summary: PerformanceSummary;
+  equity_curve: EquityPoint[];
+  periods: PeriodPerformance[];
+  best_trade: HoldingExit | null;
+  worst_trade: HoldingExit | null;
+  missing_prices: string[]; // 缺少日线收盘价的股票
+}
+
+export type GetPortfolioAnalyticsInvokeReturn = Promise<
+  ResponseBaseType<PortfolioAnalytics>
+>;
+
+export type GetEquityCurveInvokeReturn = Promise<
+  ResponseBaseType<EquityPoint[]>
+>;
+
+// 资金流水
+// 现金分红由公司行动自动产生，不能手动录入
+export type CashKind = 'deposit' | 'withdraw' | 'fee';
+
+export interface AddCashEntryParams {
+  time: string;
+  kind: CashKind;
+  amount: number; // 正数，方向由类型决定
+  code?: string; // 相关股票代码
+  note?: string;
+  account_id?: number; // 可选，默认账户
+}
+
+export interface CashFlow {
+  id: number | null; // 委托产生的流水为空
+  order_id: number | null; // 手动记录为空
+  time: string;
+  kind: CashKind | 'dividend' | 'duplicate_dividend' | 'buy' | 'sell'; // duplicate_dividend：已由公司行动计入的旧手动分红，按 0 计
+  amount: number; // 入账为正，出账为负
+  code: string | null;
+  note: string;
+  account_id: number;
+}
+
+export interface PositionValue {
+  code: string;
+  name: string;
+  quantity: number;
+  cost: number;
+  price: number; // 最新价（没有行情时为成本价）
+  market_value: number;
+  unrealized_profit: number;
+  priced: boolean;
+}
+
+export interface AccountSummary {
+  cash: number;
+  market_value: number;
+  total_equity: number;
+  positions: PositionValue[];
+  strict_mode: boolean;
+}
+
+export type AddCashEntryInvokeReturn = Promise<ResponseBaseType<number>>;
+
+export type GetCashFlowsInvokeReturn = Promise<ResponseBaseType<CashFlow[]>>;
+
+export type GetAccountSummaryInvokeReturn = Promise<
+  ResponseBaseType<AccountSummary>
+>;
+
+// 账户 / 组合
+export interface Account {
+  id: number;
+  name: string;
+  archived: boolean; // 归档后不能再录入委托和资金流水
+  created_at: string;
+}
+
+export interface TransferPositionParams {
+  from_account_id: number;
+  to_account_id: number;
+  code: string;
+  quantity: number;
+  time?: string; // 默认当前时间
+}
+
+export interface PositionTransfer {
+  id: number;
+  from_account_id: number;
+  to_account_id: number;
+  code: string;
+  name: string;
+  quantity: number;
+  cost: number; // 划转时的每股成本（含买入费用）
+  time: string;
+}
+
+export type GetAccountsInvokeReturn = Promise<ResponseBaseType<Account[]>>;
+
+export type CreateAccountInvokeReturn = Promise<ResponseBaseType<number>>;
+
+export type GetPositionTransfersInvokeReturn = Promise<
+  ResponseBaseType<PositionTransfer[]>
+>;
+
+// 公司行动（除权除息），数值均为每股
+export interface CorporateAction {
+  id: number;
+  code: string;
+  ex_date: string; // 除权除息日 YYYY-MM-DD
+  cash_dividend: number; // 每股派息
+  bonus_shares: number; // 每股送股
+  transfer_shares: number; // 每股转增
+  split_ratio: number; // 拆合股比例，没有时为 1
+  source: string; // manual 或数据源 id
+  note: string;
+}
+
+export interface AddCorporateActionParams {
+  code: string;
+  ex_date: string;
+  cash_dividend?: number;
+  bonus_shares?: number;
+  transfer_shares?: number;
+  split_ratio?: number;
+  note?: string;
+}
+
+// 持仓在除息日获得的现金分红
+export interface HoldingDividend {
+  id: number;
+  holding_id: number;
+  action_id: number;
+  code: string;
+  ex_date: string;
+  quantity: number; // 除息时的持仓数量
+  per_share: number;
+  amount: number;
+  account_id: number;
+}
+
+export type KlineAdjust = 'normal' | 'before' | 'after';
+
+export type GetCorporateActionsInvokeReturn = Promise<
+  ResponseBaseType<CorporateAction[]>
+>;
+
+export type GetHoldingDividendsInvokeReturn = Promise<
+  ResponseBaseType<HoldingDividend[]>
+>;
+
+export type GetKlineAdjustInvokeReturn = Promise<
+  ResponseBaseType<KlineAdjust>
+>;
+
+export type CorporateActionCountInvokeReturn = Promise<
+  ResponseBaseType<number>
+>;
+
+// 交割单列映射：每项为可能的表头名称
+export interface StatementColumns {
+  date: string[]; // 成交日期
+  time?: string[]; // 成交时间
+  code: string[]; // 证券代码
+  name?: string[]; // 证券名称
+  side: string[]; // 买卖方向
+  quantity: string[]; // 成交数量
+  price: string[]; // 成交均价
+  market?: string[]; // 交易市场
+}
+
+export interface BrokerFormat {
+  id: string; // huatai / eastmoney / tonghuashun / custom
+  name: string;
+  columns: StatementColumns;
+}
+
+export interface StatementImportParams {
+  file_name: string;
+  data: number[]; // 文件内容
+  broker: string;
+  columns?: StatementColumns; // 自定义列映射，不传时使用已保存的映射
+  account_id?: number;
+}
+
+// 交割单中的一笔成交
+export interface StatementOrder {
+  line: number; // 文件中的行号
+  code: string;
+  name: string;
+  time: string;
+  quantity: number;
+  cost: number;
+  action: '1' | '0';
+}
+
+export interface SkippedRow {
+  line: number;
+  reason: string;
+}
+
+export interface StatementPreview {
+  orders: StatementOrder[]; // 将新增的委托
+  duplicates: StatementOrder[]; // 已存在的委托
+  skipped: SkippedRow[];
+}
+
+export type GetStatementBrokersInvokeReturn = Promise<
+  ResponseBaseType<BrokerFormat[]>
+>;
+
+export type StatementPreviewInvokeReturn = Promise<
+  ResponseBaseType<StatementPreview>
+>;
+
+// 备份导入策略
+export type ImportStrategy =
+  | 'replace' // 清空本地对应的表后写入备份
+  | 'keep_local' // 合并，冲突时保留本地
+  | 'keep_backup' // 合并，冲突时使用备份
+  | 'append'; // 追加，自增 id 重新分配
+
+export interface TableImportStats {
+  database: string;
+  table: string;
+  inserted: number;
+  updated: number;
+  conflicts: number;
+  rekeyed: number; // 编号被本地其他记录占用、重新分配编号后写入的行数
+  deleted: number;
+  skipped: number; // 数据无法转换而跳过的行数
+  failed: number; // 写入失败的行数
+  errors: string[];
+}
+
+// 备份清单（manifest.json）
+export interface BackupManifest {
+  format_version: number;
+  app_version: string;
+  created_at: string;
+  schemas: Record<string, number>; // 各数据库的结构版本
+  row_counts: Record<string, number>; // 各表的记录数
+  files: { path: string; size: number; sha256: string }[];
+}
+
+export interface ImportReport {
+  strategy: ImportStrategy;
+  dry_run: boolean; // 预演时数据库不会被修改
+  manifest: BackupManifest | null; // 旧版备份没有清单
+  files: string[];
+  missing: string[]; // 备份中没有找到的数据库
+  tables: TableImportStats[];
+}
+
+// 自动备份设置
+export interface BackupSettings {
+  enabled: boolean;
+  directory: string | null; // 为空时使用应用数据目录下的 backups
+  interval_hours: number;
+  keep_daily: number;
+  keep_weekly: number;
+  keep_monthly: number;
+}
+
+export interface BackupSnapshot {
+  file_name: string;
+  created_at: string;
+  size: number;
+}
+
+export type GetBackupSettingsInvokeReturn = Promise<
+  ResponseBaseType<BackupSettings>
+>;
+
+export type BackupSnapshotsInvokeReturn = Promise<
+  ResponseBaseType<BackupSnapshot[]>
+>;
+
+export type BackupSnapshotInvokeReturn = Promise<
+  ResponseBaseType<BackupSnapshot>
+>;
+
+export type ImportReportInvokeReturn = Promise<ResponseBaseType<ImportReport>>;
+
+// 敏感配置（雪球 Cookie 等）的加密状态
+export interface SecretStatus {
+  mode: 'key_file' | 'passphrase'; // 本机密钥文件 / 用户密码
+  unlocked: boolean; // 密码模式下输入密码前为 false
+  load_error: string | null; // 启动时密钥加载失败的原因（此时敏感配置保持锁定）
+  key_file: string | null;
+}
+
+export type SecretStatusInvokeReturn = Promise<ResponseBaseType<SecretStatus>>;
+
+// 价格提醒
+export type AlertKind =
+  | 'price_above'
+  | 'price_below'
+  | 'percent_above'
+  | 'percent_below'
+  | 'near_limit_up'
+  | 'near_limit_down'
+  | 'volume_ratio_above';
+
+export interface AlertRule {
+  id: number;
+  code: string;
+  kind: AlertKind;
+  threshold: number; // 价格（元）、涨跌幅 / 距涨跌停（%）或量比
+  enabled: boolean;
+  cooldown_minutes: number;
+  note: string;
+  created_at: string;
+  last_triggered_at: string | null;
+}
+
+export interface AlertRuleParams {
+  code: string;
+  kind: AlertKind;
+  threshold: number;
+  cooldown_minutes?: number;
+  enabled?: boolean;
+  note?: string;
+}
+
+export interface AlertEvent {
+  id: number;
+  rule_id: number;
+  code: string;
+  name: string;
+  kind: AlertKind;
+  threshold: number;
+  value: number;
+  price: number | null;
+  message: string;
+  triggered_at: string;
+}
+
+export interface AlertSettings {
+  enabled: boolean;
+  poll_interval_secs: number;
+  symbols: string[]; // 规则之外额外轮询的股票
+  notify: boolean;
+  trading_hours_only: boolean;
+}
+
+export type AlertRulesInvokeReturn = Promise<ResponseBaseType<AlertRule[]>>;
+
+export type AddAlertRuleInvokeReturn = Promise<ResponseBaseType<number>>;
+
+export type AlertEventsInvokeReturn = Promise<ResponseBaseType<AlertEvent[]>>;
+
+export type AlertSettingsInvokeReturn = Promise<
+  ResponseBaseType<AlertSettings>
+>;
+
+// 交易日历相关类型
+export type MarketSession =
+  | 'closed'
+  | 'pre_open'
+  | 'opening_auction'
+  | 'auction_break'
+  | 'morning'
+  | 'lunch_break'
+  | 'afternoon'
+  | 'closing_auction'
+  | 'after_close';
+
+export interface MarketStatus {
+  now: string; // 北京时间
+  date: string;
+  is_trading_day: boolean;
+  session: MarketSession;
+  session_label: string;
+  is_open: boolean;
+  previous_trading_day: string;
+  next_trading_day: string;
+  next_open: string;
+  holiday_data_available: boolean;
+}
+
+export interface TradingCalendarInfo {
+  year: number;
+  month: number | null;
+  trading_days: string[];
+  holidays: string[]; // 工作日休市的日期
+  holiday_data_available: boolean;
+  customized: boolean;
+}
+
+export type MarketStatusInvokeReturn = Promise<ResponseBaseType<MarketStatus>>;
+
+export type TradingCalendarInvokeReturn = Promise<
+  ResponseBaseType<TradingCalendarInfo>
+>;
+
+export type CountTradingDaysInvokeReturn = Promise<ResponseBaseType<number>>;